@@ -0,0 +1,299 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::metadata::FileMetadata;
+use crate::temp::write_via_temp;
+use crate::utils::{read_file_content, sanitize_relative_path};
+
+/// Magic prefix of a multi-file capsule bundle
+///
+/// Only checked once the capsule's envelope says its content is a bundle, to
+/// catch a malformed payload; it is never used to detect bundles.
+pub const ARCHIVE_MAGIC: &[u8; 8] = b"CAPSARC1";
+
+/// Current bundle manifest version
+pub const ARCHIVE_VERSION: u8 = 1;
+
+/// Manifest describing the files stored in a bundle, in order
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ArchiveManifest {
+    pub version: u8,
+    pub entries: Vec<ArchiveEntryInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ArchiveEntryInfo {
+    pub name: String,
+    pub size: u64,
+    /// Modification time in milliseconds, when metadata was preserved
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mtime: Option<u64>,
+    /// Unix permission bits, when metadata was preserved
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<u32>,
+}
+
+/// A file stored in a bundle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    pub name: String,
+    pub data: Vec<u8>,
+    pub mtime: Option<u64>,
+    pub mode: Option<u32>,
+}
+
+/// Bundle several files into a single payload
+///
+/// Layout: magic, u32 LE manifest length, JSON manifest, then each file's
+/// bytes back to back in manifest order. Only the file name of each path is
+/// kept, so names must be unique. With `preserve_metadata` each entry also
+/// records its modification time and permissions.
+pub fn build_archive(paths: &[PathBuf], preserve_metadata: bool) -> Result<Vec<u8>> {
+    let mut entries = Vec::with_capacity(paths.len());
+    for path in paths {
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| anyhow::anyhow!("Invalid file name: {}", path.display()))?
+            .to_string();
+        if entries.iter().any(|e: &ArchiveEntry| e.name == name) {
+            anyhow::bail!("Duplicate file name in bundle: {name}");
+        }
+        let metadata = if preserve_metadata {
+            Some(FileMetadata::from_path(path)?)
+        } else {
+            None
+        };
+        entries.push(ArchiveEntry {
+            name,
+            data: read_file_content(path)?,
+            mtime: metadata.as_ref().and_then(|m| m.mtime),
+            mode: metadata.as_ref().and_then(|m| m.mode),
+        });
+    }
+    encode_archive(&entries)
+}
+
+/// Encode in-memory entries into the bundle layout
+pub fn encode_archive(entries: &[ArchiveEntry]) -> Result<Vec<u8>> {
+    let manifest = ArchiveManifest {
+        version: ARCHIVE_VERSION,
+        entries: entries
+            .iter()
+            .map(|e| ArchiveEntryInfo {
+                name: e.name.clone(),
+                size: e.data.len() as u64,
+                mtime: e.mtime,
+                mode: e.mode,
+            })
+            .collect(),
+    };
+    let manifest_bytes = serde_json::to_vec(&manifest).context("Failed to serialize manifest")?;
+
+    let data_len: usize = entries.iter().map(|e| e.data.len()).sum();
+    let mut out = Vec::with_capacity(ARCHIVE_MAGIC.len() + 4 + manifest_bytes.len() + data_len);
+    out.extend_from_slice(ARCHIVE_MAGIC);
+    out.extend_from_slice(&(manifest_bytes.len() as u32).to_le_bytes());
+    out.extend_from_slice(&manifest_bytes);
+    for entry in entries {
+        out.extend_from_slice(&entry.data);
+    }
+    Ok(out)
+}
+
+/// Decode a bundle back into its entries
+pub fn read_archive(data: &[u8]) -> Result<Vec<ArchiveEntry>> {
+    if !data.starts_with(ARCHIVE_MAGIC) {
+        anyhow::bail!("Content is not a capsule bundle");
+    }
+
+    let header_len = ARCHIVE_MAGIC.len() + 4;
+    if data.len() < header_len {
+        anyhow::bail!("Bundle is truncated");
+    }
+    let manifest_len =
+        u32::from_le_bytes(data[ARCHIVE_MAGIC.len()..header_len].try_into().unwrap()) as usize;
+    let manifest_end = header_len
+        .checked_add(manifest_len)
+        .filter(|end| *end <= data.len())
+        .ok_or_else(|| anyhow::anyhow!("Bundle is truncated"))?;
+
+    let manifest: ArchiveManifest = serde_json::from_slice(&data[header_len..manifest_end])
+        .context("Failed to parse bundle manifest")?;
+    if manifest.version != ARCHIVE_VERSION {
+        anyhow::bail!("Unsupported bundle version: {}", manifest.version);
+    }
+
+    let mut offset = manifest_end;
+    let mut entries = Vec::with_capacity(manifest.entries.len());
+    for info in manifest.entries {
+        let end = usize::try_from(info.size)
+            .ok()
+            .and_then(|size| offset.checked_add(size))
+            .filter(|end| *end <= data.len())
+            .ok_or_else(|| anyhow::anyhow!("Bundle is truncated at entry: {}", info.name))?;
+        entries.push(ArchiveEntry {
+            name: info.name,
+            data: data[offset..end].to_vec(),
+            mtime: info.mtime,
+            mode: info.mode,
+        });
+        offset = end;
+    }
+
+    Ok(entries)
+}
+
+/// Extract a bundle into a directory, returning the written paths
+///
+/// Existing files are only replaced when `overwrite` is set; the check runs
+/// before anything is written so a refused extraction leaves no partial output.
+/// Each file is staged in `temp_dir` before being moved into place, and any
+/// preserved modification time and permissions are restored.
+pub fn extract_archive(
+    data: &[u8],
+    output_dir: &Path,
+    overwrite: bool,
+    temp_dir: &Path,
+) -> Result<Vec<PathBuf>> {
+    let entries = read_archive(data)?;
+
+    let mut targets = Vec::with_capacity(entries.len());
+    for entry in &entries {
+        // Never let a stored name place files outside the output directory
+        let path = sanitize_relative_path(output_dir, &entry.name)
+            .with_context(|| format!("Invalid entry name in bundle: {}", entry.name))?;
+        if path.exists() && !overwrite {
+            anyhow::bail!(
+                "Output file already exists: {}. Use --force to overwrite.",
+                path.display()
+            );
+        }
+        targets.push(path);
+    }
+
+    for (entry, path) in entries.iter().zip(&targets) {
+        write_via_temp(temp_dir, path, &entry.data)?;
+        FileMetadata {
+            name: entry.name.clone(),
+            mtime: entry.mtime,
+            mode: entry.mode,
+        }
+        .apply(path)?;
+    }
+
+    Ok(targets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::write_file_content;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_bundle_and_extract_two_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let notes = temp_dir.path().join("notes.txt");
+        let image = temp_dir.path().join("image.png");
+        fs::write(&notes, b"remember the milk").unwrap();
+        fs::write(&image, [0x89, b'P', b'N', b'G', 0, 1, 2, 3]).unwrap();
+
+        let bundle = build_archive(&[notes.clone(), image.clone()], false).unwrap();
+        assert_eq!(read_archive(&bundle).unwrap().len(), 2);
+
+        let out_dir = temp_dir.path().join("out");
+        let staging = temp_dir.path().join("staging");
+        let written = extract_archive(&bundle, &out_dir, false, &staging).unwrap();
+
+        assert_eq!(
+            written,
+            vec![out_dir.join("notes.txt"), out_dir.join("image.png")]
+        );
+        assert_eq!(fs::read(&written[0]).unwrap(), fs::read(&notes).unwrap());
+        assert_eq!(fs::read(&written[1]).unwrap(), fs::read(&image).unwrap());
+        assert_eq!(fs::read_dir(&staging).unwrap().count(), 0);
+
+        // A second extraction refuses to clobber the files unless forced
+        assert!(extract_archive(&bundle, &out_dir, false, &staging).is_err());
+        assert!(extract_archive(&bundle, &out_dir, true, &staging).is_ok());
+    }
+
+    #[test]
+    fn test_preserved_mtime_restored_on_extract() {
+        let temp_dir = TempDir::new().unwrap();
+        let notes = temp_dir.path().join("notes.txt");
+        fs::write(&notes, b"remember the milk").unwrap();
+        let mtime = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_600_000_000);
+        fs::File::options()
+            .write(true)
+            .open(&notes)
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+
+        let bundle = build_archive(&[notes], true).unwrap();
+        let out_dir = temp_dir.path().join("out");
+        let written = extract_archive(&bundle, &out_dir, false, temp_dir.path()).unwrap();
+
+        assert_eq!(
+            fs::metadata(&written[0]).unwrap().modified().unwrap(),
+            mtime
+        );
+    }
+
+    #[test]
+    fn test_duplicate_names_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let a = temp_dir.path().join("a").join("same.txt");
+        let b = temp_dir.path().join("b").join("same.txt");
+        write_file_content(&a, b"one").unwrap();
+        write_file_content(&b, b"two").unwrap();
+
+        assert!(build_archive(&[a, b], false).is_err());
+    }
+
+    #[test]
+    fn test_truncated_bundle_rejected() {
+        let bundle = encode_archive(&[ArchiveEntry {
+            name: "a.txt".to_string(),
+            data: b"hello".to_vec(),
+            mtime: None,
+            mode: None,
+        }])
+        .unwrap();
+
+        assert!(read_archive(&bundle[..bundle.len() - 1]).is_err());
+        assert!(read_archive(&bundle[..10]).is_err());
+        assert!(read_archive(b"not a bundle").is_err());
+    }
+
+    #[test]
+    fn test_traversal_entry_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let out_dir = temp_dir.path().join("out");
+
+        for name in ["../../etc/passwd", "/etc/passwd"] {
+            let bundle = encode_archive(&[
+                ArchiveEntry {
+                    name: "ok.txt".to_string(),
+                    data: b"fine".to_vec(),
+                    mtime: None,
+                    mode: None,
+                },
+                ArchiveEntry {
+                    name: name.to_string(),
+                    data: b"pwned".to_vec(),
+                    mtime: None,
+                    mode: None,
+                },
+            ])
+            .unwrap();
+
+            assert!(extract_archive(&bundle, &out_dir, true, temp_dir.path()).is_err());
+            assert!(!out_dir.join("ok.txt").exists());
+        }
+    }
+}
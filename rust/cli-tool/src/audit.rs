@@ -0,0 +1,244 @@
+use anyhow::{Context, Result};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::config::default_config_dir;
+use crate::utils::current_timestamp_ms;
+
+/// Kind of mutating operation recorded in the audit log
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditOperation {
+    Create,
+    Approve,
+    Unlock,
+    Payment,
+    Revoke,
+    Reshare,
+    #[serde(rename = "revoke-approval")]
+    RevokeApproval,
+    Cancel,
+}
+
+impl fmt::Display for AuditOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AuditOperation::Create => "create",
+            AuditOperation::Approve => "approve",
+            AuditOperation::Unlock => "unlock",
+            AuditOperation::Payment => "payment",
+            AuditOperation::Revoke => "revoke",
+            AuditOperation::Reshare => "reshare",
+            AuditOperation::RevokeApproval => "revoke-approval",
+            AuditOperation::Cancel => "cancel",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for AuditOperation {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "create" => Ok(AuditOperation::Create),
+            "approve" => Ok(AuditOperation::Approve),
+            "unlock" => Ok(AuditOperation::Unlock),
+            "payment" => Ok(AuditOperation::Payment),
+            "revoke" => Ok(AuditOperation::Revoke),
+            "reshare" => Ok(AuditOperation::Reshare),
+            "revoke-approval" => Ok(AuditOperation::RevokeApproval),
+            "cancel" => Ok(AuditOperation::Cancel),
+            other => anyhow::bail!(
+                "Invalid operation type: {other}. Use create, approve, unlock, payment, revoke, reshare, revoke-approval, or cancel"
+            ),
+        }
+    }
+}
+
+/// A single audit log entry; never holds keys or content
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AuditRecord {
+    pub timestamp: u64,
+    pub operation: AuditOperation,
+    pub capsule_id: String,
+    pub transaction_digest: Option<String>,
+    pub network: String,
+}
+
+impl AuditRecord {
+    pub fn new(
+        operation: AuditOperation,
+        capsule_id: &str,
+        transaction_digest: Option<&str>,
+        network: &str,
+    ) -> Self {
+        Self {
+            timestamp: current_timestamp_ms(),
+            operation,
+            capsule_id: capsule_id.to_string(),
+            transaction_digest: transaction_digest.map(str::to_string),
+            network: network.to_string(),
+        }
+    }
+}
+
+/// Filters applied when reading the audit log
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    pub since: Option<u64>,
+    pub operation: Option<AuditOperation>,
+}
+
+impl AuditFilter {
+    pub fn matches(&self, record: &AuditRecord) -> bool {
+        if let Some(since) = self.since {
+            if record.timestamp < since {
+                return false;
+            }
+        }
+        if let Some(operation) = self.operation {
+            if record.operation != operation {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Append-only JSON lines audit log
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append one record as a single JSON line
+    pub fn append(&self, record: &AuditRecord) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create audit log directory: {}", parent.display())
+            })?;
+        }
+
+        let mut line = serde_json::to_string(record).context("Failed to serialize audit record")?;
+        line.push('\n');
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open audit log: {}", self.path.display()))?;
+        file.write_all(line.as_bytes())
+            .with_context(|| format!("Failed to write audit log: {}", self.path.display()))?;
+
+        Ok(())
+    }
+
+    /// Read all records matching the filter, oldest first
+    ///
+    /// A missing log is treated as empty; malformed lines are skipped.
+    pub fn read(&self, filter: &AuditFilter) -> Result<Vec<AuditRecord>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read audit log: {}", self.path.display()))?;
+
+        let mut records = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<AuditRecord>(line) {
+                Ok(record) if filter.matches(&record) => records.push(record),
+                Ok(_) => {}
+                Err(e) => warn!("Skipping malformed audit log line {}: {}", index + 1, e),
+            }
+        }
+
+        Ok(records)
+    }
+}
+
+/// Name of the audit log inside the config directory
+pub const AUDIT_LOG_FILE_NAME: &str = "audit.jsonl";
+
+/// Get the default audit log path
+pub fn default_audit_log_path() -> PathBuf {
+    default_config_dir().join(AUDIT_LOG_FILE_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_append_and_filter() {
+        let temp_dir = TempDir::new().unwrap();
+        let log = AuditLog::new(temp_dir.path().join("nested").join("audit.jsonl"));
+
+        let mut old = AuditRecord::new(AuditOperation::Create, "0x1", Some("0xaa"), "devnet");
+        old.timestamp = 1_000;
+        log.append(&old).unwrap();
+        log.append(&AuditRecord::new(
+            AuditOperation::Approve,
+            "0x1",
+            Some("0xbb"),
+            "devnet",
+        ))
+        .unwrap();
+
+        assert_eq!(log.read(&AuditFilter::default()).unwrap().len(), 2);
+
+        let approvals = log
+            .read(&AuditFilter {
+                operation: Some(AuditOperation::Approve),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(approvals.len(), 1);
+        assert_eq!(approvals[0].transaction_digest.as_deref(), Some("0xbb"));
+
+        let recent = log
+            .read(&AuditFilter {
+                since: Some(2_000),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].operation, AuditOperation::Approve);
+    }
+
+    #[test]
+    fn test_missing_log_is_empty() {
+        let temp_dir = TempDir::new().unwrap();
+        let log = AuditLog::new(temp_dir.path().join("audit.jsonl"));
+
+        assert!(log.read(&AuditFilter::default()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_operation_from_str() {
+        assert_eq!(
+            "Payment".parse::<AuditOperation>().unwrap(),
+            AuditOperation::Payment
+        );
+        assert!("delete".parse::<AuditOperation>().is_err());
+    }
+}
@@ -1,13 +1,28 @@
-﻿use crate::config::Config;
-use crate::file_processor::{BatchProcessor, FileInfo, FileProcessor};
-use crate::sdk::CapsuleSDK;
-use crate::utils::{future_timestamp, init_sdk, parse_duration, read_file_content};
+﻿use crate::commands::unlock::output_file_name;
+use crate::config::Config;
+use crate::file_processor::{BatchProcessor, FileHash, FileHasher, FileInfo, FileProcessor};
+use crate::inspect::inspect_content;
+use crate::manifest::{canonical_path, BatchManifest, ManifestEntry};
+use crate::sdk::{CapsuleSDK, CreateCapsuleResult, RetryPolicy};
+use crate::temp::write_via_temp;
+use crate::theme::{style, theme};
+use crate::utils::{
+    format_file_size, future_timestamp, init_sdk, key_fingerprint, parse_duration,
+    read_file_content,
+};
 use anyhow::{Context, Result};
-use console::style;
 use indicatif::ProgressBar;
-use log::info;
-use std::path::PathBuf;
+use log::{info, warn};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
+
+/// How long a webhook notification may take before it is abandoned
+pub const WEBHOOK_TIMEOUT_SECS: u64 = 10;
+
+/// (path, error message) for each input that could not be processed
+type InputFailures = Vec<(String, String)>;
 
 /// Batch operation types
 #[derive(Debug, Clone)]
@@ -23,10 +38,22 @@ pub enum BatchOperationType {
         price: u64,
     },
     Unlock {
-        encryption_keys: Vec<String>,
+        keys: UnlockKeys,
+        /// Directory decrypted content is written to; without one, capsules are
+        /// only reported as unlocked
+        output_dir: Option<PathBuf>,
     },
 }
 
+/// Encryption keys for an unlock batch, whose input files each hold a capsule ID
+#[derive(Debug, Clone)]
+pub enum UnlockKeys {
+    /// One key per input file, in input order
+    Positional(Vec<String>),
+    /// Keys looked up by the capsule ID in each file
+    ByCapsuleId(BTreeMap<String, String>),
+}
+
 /// Batch operation configuration
 #[derive(Debug, Clone)]
 pub struct BatchConfig {
@@ -49,25 +76,137 @@ impl Default for BatchConfig {
     }
 }
 
+impl BatchConfig {
+    /// Retry settings for each item of the batch
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            attempts: self.retry_attempts,
+            delay_ms: self.retry_delay_ms,
+        }
+    }
+}
+
 /// Batch operation executor
 pub struct BatchExecutor {
     config: BatchConfig,
     sdk: Arc<CapsuleSDK>,
     file_processor: FileProcessor,
+    /// Hashing threads when content is hashed in parallel (0 = one per core)
+    hash_threads: Option<usize>,
+    /// Record of completed files, for resuming an interrupted batch
+    manifest: Option<BatchManifest>,
+    /// Where unlocked content is staged before it is moved into place
+    temp_dir: PathBuf,
 }
 
 impl BatchExecutor {
     pub async fn new(config: BatchConfig, cli_config: &Config) -> Result<Self> {
-        let sdk = Arc::new(init_sdk(cli_config).await?);
+        let sdk = Arc::new(
+            init_sdk(cli_config)
+                .await?
+                .with_retry_policy(config.retry_policy()),
+        );
         let file_processor = FileProcessor::new();
 
         Ok(Self {
             config,
             sdk,
             file_processor,
+            hash_threads: None,
+            manifest: None,
+            temp_dir: cli_config.temp_dir(),
         })
     }
 
+    /// Run operations through `sdk` instead of one built from the CLI config
+    ///
+    /// Its network calls are retried according to the batch's retry settings.
+    pub fn with_sdk(mut self, sdk: CapsuleSDK) -> Self {
+        self.sdk = Arc::new(sdk.with_retry_policy(self.config.retry_policy()));
+        self
+    }
+
+    /// Use a custom file processor for input discovery and validation
+    pub fn with_file_processor(mut self, file_processor: FileProcessor) -> Self {
+        self.file_processor = file_processor;
+        self
+    }
+
+    /// Hash file content on `threads` threads instead of serially (0 = one per core)
+    pub fn with_parallel_hash(mut self, threads: usize) -> Self {
+        self.hash_threads = Some(threads);
+        self
+    }
+
+    /// Record each created capsule in `manifest` and skip files it already lists
+    pub fn with_manifest(mut self, manifest: BatchManifest) -> Self {
+        self.manifest = Some(manifest);
+        self
+    }
+
+    /// Drop files the manifest lists as completed by an earlier run
+    fn skip_completed(&self, files: Vec<FileInfo>) -> Result<Vec<FileInfo>> {
+        let Some(ref manifest) = self.manifest else {
+            return Ok(files);
+        };
+        let completed = manifest.completed()?;
+        let (done, remaining): (Vec<_>, Vec<_>) = files
+            .into_iter()
+            .partition(|file| completed.contains(&canonical_path(&file.path)));
+        if !done.is_empty() {
+            println!(
+                "Skipping {} files already completed in {}",
+                style(done.len()).bold(),
+                manifest.path().display()
+            );
+        }
+        Ok(remaining)
+    }
+
+    /// Hash every file's content, in parallel when configured
+    fn hash_files(&self, files: &[FileInfo]) -> Result<Vec<FileHash>> {
+        match self.hash_threads {
+            Some(threads) => FileHasher::hash_parallel(files, threads),
+            None => FileHasher::hash_serial(files),
+        }
+    }
+
+    /// Enumerate input paths into files
+    ///
+    /// An unreadable input is a per-item failure unless the batch should stop
+    /// on the first error.
+    fn collect_inputs(&self, input_paths: Vec<PathBuf>) -> Result<(Vec<FileInfo>, InputFailures)> {
+        let mut all_files = Vec::new();
+        let mut input_failures = Vec::new();
+        for path in input_paths {
+            match self.file_processor.process_path(&path) {
+                Ok(files) => all_files.extend(files),
+                Err(e) if self.config.continue_on_error => {
+                    info!("Skipping input {}: {:#}", path.display(), e);
+                    input_failures.push((path.display().to_string(), format!("{e:#}")));
+                }
+                Err(e) => {
+                    return Err(e)
+                        .with_context(|| format!("Failed to process path: {}", path.display()))
+                }
+            }
+        }
+        Ok((all_files, input_failures))
+    }
+
+    /// Report what a batch would process without uploading anything
+    ///
+    /// Files are hashed so that inputs with identical content can be reported.
+    pub fn estimate(&self, input_paths: Vec<PathBuf>) -> Result<BatchEstimate> {
+        let (files, skipped) = self.collect_inputs(input_paths)?;
+        if !files.is_empty() {
+            self.file_processor.validate_files(&files)?;
+        }
+        let mut estimate = BatchEstimate::from_files(&files, skipped);
+        estimate.duplicates = duplicate_groups(&self.hash_files(&files)?);
+        Ok(estimate)
+    }
+
     /// Execute batch operation on files
     pub async fn execute_batch(&self, input_paths: Vec<PathBuf>) -> Result<BatchOperationResult> {
         info!(
@@ -75,18 +214,13 @@ impl BatchExecutor {
             input_paths.len()
         );
 
-        // Process all input paths to get file list
-        let mut all_files = Vec::new();
-        for path in input_paths {
-            let files = self
-                .file_processor
-                .process_path(&path)
-                .with_context(|| format!("Failed to process path: {}", path.display()))?;
-            all_files.extend(files);
-        }
+        let (all_files, input_failures) = self.collect_inputs(input_paths)?;
+        let all_files = self.skip_completed(all_files)?;
 
         if all_files.is_empty() {
-            return Ok(BatchOperationResult::empty());
+            let mut result = BatchOperationResult::empty();
+            result.record_failures(input_failures);
+            return Ok(result);
         }
 
         // Validate files
@@ -94,7 +228,7 @@ impl BatchExecutor {
 
         println!(
             "\n{} Starting batch operation on {} files",
-            style("").cyan(),
+            theme().id(""),
             style(all_files.len()).bold()
         );
 
@@ -102,7 +236,7 @@ impl BatchExecutor {
         let (_multi_progress, main_pb) = self.file_processor.create_batch_progress(all_files.len());
 
         // Execute based on operation type
-        let result = match &self.config.operation_type {
+        let mut result = match &self.config.operation_type {
             BatchOperationType::CreateTime { unlock_time } => {
                 self.execute_create_time_batch(all_files, *unlock_time, &main_pb)
                     .await?
@@ -123,12 +257,14 @@ impl BatchExecutor {
                 self.execute_create_payment_batch(all_files, *price, &main_pb)
                     .await?
             }
-            BatchOperationType::Unlock { encryption_keys } => {
-                self.execute_unlock_batch(all_files, encryption_keys.clone(), &main_pb)
+            BatchOperationType::Unlock { keys, output_dir } => {
+                self.execute_unlock_batch(all_files, keys, output_dir.as_deref(), &main_pb)
                     .await?
             }
         };
 
+        result.record_failures(input_failures);
+
         main_pb.finish_with_message(format!(
             "Batch complete: {} successful, {} failed",
             result.successful.len(),
@@ -145,21 +281,21 @@ impl BatchExecutor {
         progress_bar: &ProgressBar,
     ) -> Result<BatchOperationResult> {
         let sdk = self.sdk.clone();
+        let manifest = self.manifest.clone();
 
         let batch_result = BatchProcessor::process_files(
             files,
             move |file_info| {
                 let sdk = sdk.clone();
+                let manifest = manifest.clone();
                 async move {
                     let content = read_file_content(&file_info.path)?;
                     let result = sdk.create_time_capsule(content, unlock_time, None).await?;
-                    Ok(format!(
-                        "{} -> {}",
-                        file_info.path.display(),
-                        result.capsule_id
-                    ))
+                    finish_created(manifest.as_ref(), &file_info, &result)
                 }
             },
+            self.config.max_concurrent,
+            !self.config.continue_on_error,
             Some(progress_bar),
         )
         .await;
@@ -175,24 +311,24 @@ impl BatchExecutor {
         progress_bar: &ProgressBar,
     ) -> Result<BatchOperationResult> {
         let sdk = self.sdk.clone();
+        let manifest = self.manifest.clone();
 
         let batch_result = BatchProcessor::process_files(
             files,
             move |file_info| {
                 let sdk = sdk.clone();
+                let manifest = manifest.clone();
                 let approvers = approvers.clone();
                 async move {
                     let content = read_file_content(&file_info.path)?;
                     let result = sdk
                         .create_multisig_capsule(content, threshold, approvers, None)
                         .await?;
-                    Ok(format!(
-                        "{} -> {}",
-                        file_info.path.display(),
-                        result.capsule_id
-                    ))
+                    finish_created(manifest.as_ref(), &file_info, &result)
                 }
             },
+            self.config.max_concurrent,
+            !self.config.continue_on_error,
             Some(progress_bar),
         )
         .await;
@@ -207,21 +343,21 @@ impl BatchExecutor {
         progress_bar: &ProgressBar,
     ) -> Result<BatchOperationResult> {
         let sdk = self.sdk.clone();
+        let manifest = self.manifest.clone();
 
         let batch_result = BatchProcessor::process_files(
             files,
             move |file_info| {
                 let sdk = sdk.clone();
+                let manifest = manifest.clone();
                 async move {
                     let content = read_file_content(&file_info.path)?;
                     let result = sdk.create_payment_capsule(content, price, None).await?;
-                    Ok(format!(
-                        "{} -> {}",
-                        file_info.path.display(),
-                        result.capsule_id
-                    ))
+                    finish_created(manifest.as_ref(), &file_info, &result)
                 }
             },
+            self.config.max_concurrent,
+            !self.config.continue_on_error,
             Some(progress_bar),
         )
         .await;
@@ -232,23 +368,19 @@ impl BatchExecutor {
     async fn execute_unlock_batch(
         &self,
         files: Vec<FileInfo>,
-        encryption_keys: Vec<String>,
+        keys: &UnlockKeys,
+        output_dir: Option<&Path>,
         progress_bar: &ProgressBar,
     ) -> Result<BatchOperationResult> {
-        if files.len() != encryption_keys.len() {
-            anyhow::bail!(
-                "Number of files ({}) must match number of encryption keys ({})",
-                files.len(),
-                encryption_keys.len()
-            );
-        }
-
         let mut successful = Vec::new();
-        let mut failed = Vec::new();
         let total_size: u64 = files.iter().map(|f| f.size).sum();
+        let file_count = files.len();
+
+        let (pairs, mut failed) = self.pair_unlock_keys(files, keys)?;
+        progress_bar.inc((file_count - pairs.len()) as u64);
 
         // Process files sequentially for unlock operations
-        for (file_info, encryption_key) in files.into_iter().zip(encryption_keys.into_iter()) {
+        for (file_info, encryption_key) in pairs {
             progress_bar.set_message(format!(
                 "Unlocking: {}",
                 file_info
@@ -258,7 +390,10 @@ impl BatchExecutor {
                     .to_string_lossy()
             ));
 
-            match self.unlock_single_file(&file_info, &encryption_key).await {
+            match self
+                .unlock_single_file(&file_info, &encryption_key, output_dir)
+                .await
+            {
                 Ok(result) => {
                     successful.push(result);
                     info!("Successfully unlocked: {}", file_info.path.display());
@@ -271,6 +406,9 @@ impl BatchExecutor {
                         file_info.path.display(),
                         error_msg
                     );
+                    if !self.config.continue_on_error {
+                        break;
+                    }
                 }
             }
 
@@ -287,6 +425,7 @@ impl BatchExecutor {
 
         Ok(BatchOperationResult {
             successful,
+            outcomes: Vec::new(),
             failed,
             total_processed,
             total_size,
@@ -294,31 +433,189 @@ impl BatchExecutor {
         })
     }
 
+    /// Pair each input file with the key for its capsule
+    ///
+    /// Files or keys left without a partner abort the batch when it should
+    /// stop on errors; otherwise the pairs found still run and the rest are
+    /// returned as failures.
+    fn pair_unlock_keys(
+        &self,
+        files: Vec<FileInfo>,
+        keys: &UnlockKeys,
+    ) -> Result<(Vec<(FileInfo, String)>, InputFailures)> {
+        let mut failed = Vec::new();
+        let encryption_keys = match keys {
+            UnlockKeys::Positional(encryption_keys) => encryption_keys,
+            UnlockKeys::ByCapsuleId(keys) => {
+                let mut pairs = Vec::new();
+                for file_info in files {
+                    let capsule_id = match read_capsule_id(&file_info.path) {
+                        Ok(capsule_id) => capsule_id,
+                        Err(e) if self.config.continue_on_error => {
+                            failed.push((file_info.path.display().to_string(), e.to_string()));
+                            continue;
+                        }
+                        Err(e) => return Err(e),
+                    };
+                    match keys.get(&capsule_id) {
+                        Some(key) => pairs.push((file_info, key.clone())),
+                        None if self.config.continue_on_error => failed.push((
+                            file_info.path.display().to_string(),
+                            format!("No encryption key for capsule {capsule_id} in the manifest"),
+                        )),
+                        None => anyhow::bail!(
+                            "No encryption key for capsule {} (from {}) in the manifest",
+                            capsule_id,
+                            file_info.path.display()
+                        ),
+                    }
+                }
+                return Ok((pairs, failed));
+            }
+        };
+
+        if files.len() != encryption_keys.len() {
+            if !self.config.continue_on_error {
+                anyhow::bail!(
+                    "Number of files ({}) must match number of encryption keys ({}); \
+                     use a manifest to match keys to capsule IDs instead of by position",
+                    files.len(),
+                    encryption_keys.len()
+                );
+            }
+            for file_info in files.iter().skip(encryption_keys.len()) {
+                failed.push((
+                    file_info.path.display().to_string(),
+                    "No encryption key provided for this file".to_string(),
+                ));
+            }
+            for index in files.len()..encryption_keys.len() {
+                failed.push((
+                    format!("encryption key #{}", index + 1),
+                    "No file provided for this key".to_string(),
+                ));
+            }
+        }
+
+        let pairs = files
+            .into_iter()
+            .zip(encryption_keys.iter().cloned())
+            .collect();
+        Ok((pairs, failed))
+    }
+
     async fn unlock_single_file(
         &self,
         file_info: &FileInfo,
         encryption_key: &str,
+        output_dir: Option<&Path>,
     ) -> Result<String> {
         // For unlock operations, we assume the file contains a capsule ID
-        let capsule_id_content = read_file_content(&file_info.path)?;
-        let capsule_id = String::from_utf8(capsule_id_content)
-            .context("File does not contain valid UTF-8 capsule ID")?
-            .trim()
-            .to_string();
+        let capsule_id = read_capsule_id(&file_info.path)?;
 
         let result = self
             .sdk
             .unlock_and_decrypt(&capsule_id, encryption_key, None, None)
             .await?;
 
-        if result.success {
-            Ok(format!("{} -> unlocked", file_info.path.display()))
-        } else {
+        if !result.success {
             anyhow::bail!(
                 "Failed to unlock: {}",
                 result.error.unwrap_or_else(|| "Unknown error".to_string())
             )
         }
+        let Some(output_dir) = output_dir else {
+            return Ok(format!("{} -> unlocked", file_info.path.display()));
+        };
+
+        // Metadata stored with `create --preserve-metadata` names the output file
+        let (metadata, content) = result.content_and_metadata()?;
+        let content_type = result
+            .content_type
+            .clone()
+            .unwrap_or_else(|| inspect_content(content, result.bundle).mime_type);
+        let output_path = output_dir.join(output_file_name(
+            &capsule_id,
+            metadata.as_ref().map(|metadata| metadata.name.as_str()),
+            &content_type,
+        ));
+        if output_path.exists() {
+            anyhow::bail!("Output file already exists: {}", output_path.display());
+        }
+        write_via_temp(&self.temp_dir, &output_path, content)
+            .with_context(|| format!("Failed to write {}", output_path.display()))?;
+        if let Some(ref metadata) = metadata {
+            metadata
+                .apply(&output_path)
+                .context("Failed to restore file metadata")?;
+        }
+        Ok(format!(
+            "{} -> {}",
+            file_info.path.display(),
+            output_path.display()
+        ))
+    }
+}
+
+/// The capsule ID an unlock input file holds
+fn read_capsule_id(path: &Path) -> Result<String> {
+    let content = read_file_content(path)?;
+    Ok(String::from_utf8(content)
+        .context("File does not contain valid UTF-8 capsule ID")?
+        .trim()
+        .to_string())
+}
+
+/// Outcome for a created capsule, also appended to the manifest when there is one
+///
+/// The capsule exists either way, so a failed manifest write only warns.
+fn finish_created(
+    manifest: Option<&BatchManifest>,
+    file_info: &FileInfo,
+    result: &CreateCapsuleResult,
+) -> Result<BatchItemOutcome> {
+    if let Some(manifest) = manifest {
+        let entry = ManifestEntry::new(
+            &file_info.path,
+            &result.capsule_id,
+            &result.cid,
+            &result.encryption_key,
+        );
+        if let Err(e) = manifest.append(&entry) {
+            warn!(
+                "Failed to record {} in the manifest: {e:#}",
+                file_info.path.display()
+            );
+        }
+    }
+    BatchItemOutcome::created(file_info, &result.capsule_id, &result.encryption_key)
+}
+
+/// One capsule created by a batch, with the fingerprint of its key
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct BatchItemOutcome {
+    pub file: String,
+    pub capsule_id: String,
+    pub key_fingerprint: String,
+}
+
+impl BatchItemOutcome {
+    fn created(file_info: &FileInfo, capsule_id: &str, encryption_key: &str) -> Result<Self> {
+        Ok(Self {
+            file: file_info.path.display().to_string(),
+            capsule_id: capsule_id.to_string(),
+            key_fingerprint: key_fingerprint(encryption_key)?,
+        })
+    }
+}
+
+impl std::fmt::Display for BatchItemOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} -> {} (key {})",
+            self.file, self.capsule_id, self.key_fingerprint
+        )
     }
 }
 
@@ -326,6 +623,8 @@ impl BatchExecutor {
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct BatchOperationResult {
     pub successful: Vec<String>,
+    /// Created capsules; empty for unlock batches
+    pub outcomes: Vec<BatchItemOutcome>,
     pub failed: Vec<(String, String)>,
     pub total_processed: usize,
     pub total_size: u64,
@@ -336,6 +635,7 @@ impl BatchOperationResult {
     pub fn empty() -> Self {
         Self {
             successful: Vec::new(),
+            outcomes: Vec::new(),
             failed: Vec::new(),
             total_processed: 0,
             total_size: 0,
@@ -343,9 +643,22 @@ impl BatchOperationResult {
         }
     }
 
-    pub fn from_batch_result(batch_result: crate::file_processor::BatchResult) -> Self {
+    /// Add failures that happened outside the per-file processing
+    pub fn record_failures(&mut self, failures: Vec<(String, String)>) {
+        self.total_processed += failures.len();
+        self.failed.extend(failures);
+    }
+
+    pub fn from_batch_result(
+        batch_result: crate::file_processor::BatchResult<BatchItemOutcome>,
+    ) -> Self {
         Self {
-            successful: batch_result.successful,
+            successful: batch_result
+                .successful
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            outcomes: batch_result.successful,
             failed: batch_result.failed,
             total_processed: batch_result.total_processed,
             total_size: batch_result.total_size,
@@ -354,31 +667,184 @@ impl BatchOperationResult {
     }
 
     pub fn display_summary(&self) {
-        println!("\n{}", style("Batch Operation Summary").bold().cyan());
+        println!("\n{}", theme().heading("Batch Operation Summary"));
         println!("{}", "=".repeat(50));
 
         println!("Operation type: {}", self.operation_type);
         println!("Total processed: {}", self.total_processed);
-        println!("Successful: {}", style(self.successful.len()).green());
-        println!("Failed: {}", style(self.failed.len()).red());
+        println!("Successful: {}", theme().success(self.successful.len()));
+        println!("Failed: {}", theme().error(self.failed.len()));
         println!("Total size: {} bytes", self.total_size);
 
-        if !self.successful.is_empty() {
-            println!("\n{} Successful operations:", style("").green());
+        if !self.outcomes.is_empty() {
+            display_outcomes(&self.outcomes);
+        } else if !self.successful.is_empty() {
+            println!("\n{} Successful operations:", theme().success(""));
             for success in &self.successful {
                 println!("   {success}");
             }
         }
 
         if !self.failed.is_empty() {
-            println!("\n{} Failed operations:", style("").red());
+            println!("\n{} Failed operations:", theme().error(""));
             for (item, error) in &self.failed {
-                println!("   {}: {}", style(item).dim(), style(error).red());
+                println!("   {}: {}", theme().dim(item), theme().error(error));
             }
         }
     }
 }
 
+/// Table of created capsules, so each stored key can be matched by fingerprint
+fn display_outcomes(outcomes: &[BatchItemOutcome]) {
+    let id_width = outcomes
+        .iter()
+        .map(|outcome| outcome.capsule_id.len())
+        .max()
+        .unwrap_or(0)
+        .max("Capsule ID".len());
+
+    println!("\n{} Created capsules:", theme().success(""));
+    println!(
+        "   {:<id_width$}  {:<16}  {}",
+        style("Capsule ID").bold(),
+        style("Key").bold(),
+        style("File").bold()
+    );
+    for outcome in outcomes {
+        println!(
+            "   {:<id_width$}  {:<16}  {}",
+            theme().id(&outcome.capsule_id),
+            theme().dim(&outcome.key_fingerprint),
+            outcome.file
+        );
+    }
+}
+
+/// Size breakdown for one MIME type in a batch estimate
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct MimeBreakdown {
+    pub count: usize,
+    pub total_size: u64,
+}
+
+/// Preview of a batch: what would be processed and how much data it is
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BatchEstimate {
+    pub file_count: usize,
+    pub total_size: u64,
+    pub by_mime_type: std::collections::BTreeMap<String, MimeBreakdown>,
+    pub skipped: Vec<(String, String)>,
+    /// Paths of files sharing identical content, one group per distinct hash
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub duplicates: Vec<Vec<String>>,
+}
+
+impl BatchEstimate {
+    pub fn from_files(files: &[FileInfo], skipped: Vec<(String, String)>) -> Self {
+        let mut by_mime_type = std::collections::BTreeMap::new();
+        for file in files {
+            let entry: &mut MimeBreakdown = by_mime_type.entry(file.mime_type.clone()).or_default();
+            entry.count += 1;
+            entry.total_size += file.size;
+        }
+
+        Self {
+            file_count: files.len(),
+            total_size: files.iter().map(|f| f.size).sum(),
+            by_mime_type,
+            skipped,
+            duplicates: Vec::new(),
+        }
+    }
+
+    pub fn display_summary(&self) {
+        println!("\n{}", theme().heading("Batch Estimate"));
+        println!("{}", "=".repeat(50));
+
+        println!("Files: {}", style(self.file_count).bold());
+        println!("Total size: {}", format_file_size(self.total_size));
+
+        if !self.by_mime_type.is_empty() {
+            println!(
+                "\n{:<30} {:>8} {:>12}",
+                style("Type").bold(),
+                style("Files").bold(),
+                style("Size").bold()
+            );
+            println!("{}", "-".repeat(52));
+            for (mime_type, breakdown) in &self.by_mime_type {
+                println!(
+                    "{:<30} {:>8} {:>12}",
+                    mime_type,
+                    breakdown.count,
+                    format_file_size(breakdown.total_size)
+                );
+            }
+        }
+
+        if !self.duplicates.is_empty() {
+            println!("\n{} Identical content:", theme().warning(""));
+            for group in &self.duplicates {
+                println!("   {}", group.join(", "));
+            }
+        }
+
+        if !self.skipped.is_empty() {
+            println!("\n{} Skipped inputs:", theme().warning(""));
+            for (item, error) in &self.skipped {
+                println!("   {}: {}", theme().dim(item), theme().warning(error));
+            }
+        }
+
+        println!("\n{}", theme().dim("Estimate only: nothing was uploaded."));
+    }
+}
+
+/// Group paths whose content hashes match, keeping only groups of two or more
+fn duplicate_groups(hashes: &[FileHash]) -> Vec<Vec<String>> {
+    let mut by_hash: std::collections::BTreeMap<&str, Vec<String>> =
+        std::collections::BTreeMap::new();
+    for file in hashes {
+        by_hash
+            .entry(file.hash.as_str())
+            .or_default()
+            .push(file.path.display().to_string());
+    }
+    let mut groups: Vec<Vec<String>> = by_hash
+        .into_values()
+        .filter(|paths| paths.len() > 1)
+        .collect();
+    for group in &mut groups {
+        group.sort();
+    }
+    groups
+}
+
+/// POST a finished batch result as JSON to a webhook
+///
+/// The payload is the serialized `BatchOperationResult`, which only carries
+/// paths, capsule IDs and error messages, never encryption keys.
+pub async fn notify_webhook(url: &str, result: &BatchOperationResult) -> Result<()> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(WEBHOOK_TIMEOUT_SECS))
+        .build()
+        .context("Failed to create HTTP client")?;
+
+    let response = client
+        .post(url)
+        .json(result)
+        .send()
+        .await
+        .with_context(|| format!("Failed to POST batch result to {url}"))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        anyhow::bail!("Webhook {} responded with {}", url, status);
+    }
+
+    Ok(())
+}
+
 /// Batch operation builder for easier configuration
 pub struct BatchOperationBuilder {
     operation_type: Option<BatchOperationType>,
@@ -386,6 +852,7 @@ pub struct BatchOperationBuilder {
     retry_attempts: u32,
     retry_delay_ms: u64,
     continue_on_error: bool,
+    output_dir: Option<PathBuf>,
 }
 
 impl Default for BatchOperationBuilder {
@@ -396,6 +863,7 @@ impl Default for BatchOperationBuilder {
             retry_attempts: 3,
             retry_delay_ms: 1000,
             continue_on_error: true,
+            output_dir: None,
         }
     }
 }
@@ -426,7 +894,25 @@ impl BatchOperationBuilder {
     }
 
     pub fn unlock_capsules(mut self, encryption_keys: Vec<String>) -> Self {
-        self.operation_type = Some(BatchOperationType::Unlock { encryption_keys });
+        self.operation_type = Some(BatchOperationType::Unlock {
+            keys: UnlockKeys::Positional(encryption_keys),
+            output_dir: None,
+        });
+        self
+    }
+
+    /// Unlock the capsules named by the input files with keys looked up by capsule ID
+    pub fn unlock_capsules_by_id(mut self, keys: BTreeMap<String, String>) -> Self {
+        self.operation_type = Some(BatchOperationType::Unlock {
+            keys: UnlockKeys::ByCapsuleId(keys),
+            output_dir: None,
+        });
+        self
+    }
+
+    /// Write unlocked content to `dir`, named by stored file name or capsule ID
+    pub fn output_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.output_dir = Some(dir.into());
         self
     }
 
@@ -451,9 +937,17 @@ impl BatchOperationBuilder {
     }
 
     pub fn build(self) -> Result<BatchConfig> {
-        let operation_type = self
+        let mut operation_type = self
             .operation_type
             .ok_or_else(|| anyhow::anyhow!("Operation type must be specified"))?;
+        if let Some(dir) = self.output_dir {
+            match operation_type {
+                BatchOperationType::Unlock {
+                    ref mut output_dir, ..
+                } => *output_dir = Some(dir),
+                _ => anyhow::bail!("An output directory only applies to unlock operations"),
+            }
+        }
 
         Ok(BatchConfig {
             operation_type,
@@ -468,6 +962,8 @@ impl BatchOperationBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::storage::{ContentStore, MemoryStore, StoreRequestFailed};
+    use std::sync::atomic::{AtomicU32, Ordering};
 
     #[test]
     fn test_batch_operation_builder() {
@@ -494,6 +990,7 @@ mod tests {
     fn test_batch_operation_result() {
         let result = BatchOperationResult {
             successful: vec!["file1.txt -> 0x123".to_string()],
+            outcomes: Vec::new(),
             failed: vec![("file2.txt".to_string(), "Permission denied".to_string())],
             total_processed: 2,
             total_size: 1024,
@@ -504,4 +1001,173 @@ mod tests {
         assert_eq!(result.failed.len(), 1);
         assert_eq!(result.total_processed, 2);
     }
+
+    #[test]
+    fn test_created_outcome_carries_key_fingerprint() {
+        let file_info = FileInfo {
+            path: PathBuf::from("notes/letter.txt"),
+            size: 12,
+            mime_type: "text/plain".to_string(),
+            is_binary: false,
+        };
+        let key = "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=";
+        let outcome = BatchItemOutcome::created(&file_info, "0xabc", key).unwrap();
+
+        assert_eq!(outcome.capsule_id, "0xabc");
+        assert!(!outcome.key_fingerprint.is_empty());
+        assert_eq!(outcome.key_fingerprint, key_fingerprint(key).unwrap());
+        assert!(!outcome.to_string().contains(key));
+
+        let result = BatchOperationResult::from_batch_result(crate::file_processor::BatchResult {
+            successful: vec![outcome.clone()],
+            failed: Vec::new(),
+            total_processed: 1,
+            total_size: 12,
+        });
+        assert_eq!(result.outcomes, vec![outcome]);
+        let json = serde_json::to_value(&result).unwrap();
+        assert!(json["outcomes"][0]["key_fingerprint"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_notify_webhook_posts_counts() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .and(body_partial_json(serde_json::json!({
+                "total_processed": 3,
+                "successful": ["a.txt -> 0x1", "b.txt -> 0x2"],
+                "failed": [["c.txt", "Permission denied"]],
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let result = BatchOperationResult {
+            successful: vec!["a.txt -> 0x1".to_string(), "b.txt -> 0x2".to_string()],
+            outcomes: Vec::new(),
+            failed: vec![("c.txt".to_string(), "Permission denied".to_string())],
+            total_processed: 3,
+            total_size: 42,
+            operation_type: "batch".to_string(),
+        };
+
+        notify_webhook(&format!("{}/hook", server.uri()), &result)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_notify_webhook_reports_error_status() {
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(wiremock::matchers::any())
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+
+        assert!(
+            notify_webhook(&server.uri(), &BatchOperationResult::empty())
+                .await
+                .is_err()
+        );
+    }
+
+    /// Store whose first `failures` uploads fail as if the node were unreachable
+    struct FlakyStore {
+        inner: MemoryStore,
+        failures: u32,
+        puts: AtomicU32,
+    }
+
+    #[async_trait::async_trait]
+    impl ContentStore for FlakyStore {
+        async fn put(&self, data: &[u8]) -> Result<String> {
+            if self.puts.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return Err(StoreRequestFailed {
+                    message: "IPFS node unreachable".to_string(),
+                }
+                .into());
+            }
+            self.inner.put(data).await
+        }
+
+        async fn get(&self, cid: &str) -> Result<Vec<u8>> {
+            self.inner.get(cid).await
+        }
+
+        async fn contains(&self, cid: &str) -> Result<bool> {
+            self.inner.contains(cid).await
+        }
+
+        async fn unpin(&self, cid: &str) -> Result<()> {
+            self.inner.unpin(cid).await
+        }
+    }
+
+    async fn flaky_batch(failures: u32, retry_attempts: u32) -> (BatchOperationResult, u32) {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("flaky.txt");
+        std::fs::write(&path, b"hello").unwrap();
+
+        let config = Config::default();
+        let batch_config = BatchOperationBuilder::new()
+            .create_time_capsules("1h")
+            .unwrap()
+            .retry_attempts(retry_attempts)
+            .retry_delay(1)
+            .build()
+            .unwrap();
+        let store = Arc::new(FlakyStore {
+            inner: MemoryStore::new(),
+            failures,
+            puts: AtomicU32::new(0),
+        });
+        let sdk = CapsuleSDK::new(config.clone())
+            .await
+            .unwrap()
+            .with_store(store.clone());
+        let executor = BatchExecutor::new(batch_config, &config)
+            .await
+            .unwrap()
+            .with_sdk(sdk);
+
+        let result = executor.execute_batch(vec![path]).await.unwrap();
+        (result, store.puts.load(Ordering::SeqCst))
+    }
+
+    #[tokio::test]
+    async fn test_transient_failures_are_retried() {
+        // Retried inside the SDK with the batch's policy: one capsule, three uploads
+        let (result, puts) = flaky_batch(2, 3).await;
+        assert_eq!(result.successful.len(), 1);
+        assert!(result.failed.is_empty());
+        assert_eq!(puts, 3);
+
+        // The create itself is not retried on top, so attempts are not multiplied
+        let (result, puts) = flaky_batch(u32::MAX, 1).await;
+        assert!(result.successful.is_empty());
+        assert_eq!(result.failed.len(), 1);
+        assert_eq!(puts, 2);
+
+        let attempts = AtomicU32::new(0);
+        let retry = RetryPolicy {
+            attempts: 3,
+            delay_ms: 1,
+        };
+        // Errors that would only repeat are not retried
+        let invalid: Result<()> = retry
+            .run(|| async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                anyhow::bail!("Invalid encryption key format")
+            })
+            .await;
+        assert!(invalid.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
 }
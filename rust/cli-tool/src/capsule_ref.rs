@@ -0,0 +1,155 @@
+use anyhow::Result;
+use log::info;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::config::{Config, Network};
+
+/// URI scheme for sharing capsules together with the network they live on
+pub const CAPSULE_URI_SCHEME: &str = "capsule://";
+
+/// A capsule given either as a bare ID or as `capsule://<network>/<id>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapsuleRef {
+    pub capsule_id: String,
+    pub network: Option<Network>,
+}
+
+impl FromStr for CapsuleRef {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let Some(rest) = s.strip_prefix(CAPSULE_URI_SCHEME) else {
+            return Ok(Self {
+                capsule_id: s.to_string(),
+                network: None,
+            });
+        };
+
+        let (network, capsule_id) = match rest.split_once('/') {
+            Some((network, capsule_id)) => (Some(network.parse::<Network>()?), capsule_id),
+            None => (None, rest),
+        };
+        if capsule_id.is_empty() || capsule_id.contains('/') {
+            anyhow::bail!("Invalid capsule URI: {s}. Use capsule://<network>/<capsule-id>");
+        }
+
+        Ok(Self {
+            capsule_id: capsule_id.to_string(),
+            network,
+        })
+    }
+}
+
+impl fmt::Display for CapsuleRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.network {
+            Some(network) => write!(f, "{CAPSULE_URI_SCHEME}{network}/{}", self.capsule_id),
+            None => write!(f, "{}", self.capsule_id),
+        }
+    }
+}
+
+/// Turn capsule references into bare IDs, following any network they embed
+///
+/// Unless the endpoint was chosen explicitly on the command line, `config`
+/// is switched to the embedded network for this invocation, so a capsule
+/// shared from testnet is looked up on testnet. References that disagree
+/// about the network are rejected.
+pub fn resolve_capsule_refs(
+    config: &mut Config,
+    references: &[String],
+    endpoint_explicit: bool,
+) -> Result<Vec<String>> {
+    let refs = references
+        .iter()
+        .map(|reference| reference.parse::<CapsuleRef>())
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut embedded: Option<Network> = None;
+    for network in refs.iter().filter_map(|r| r.network) {
+        match embedded {
+            Some(seen) if seen != network => {
+                anyhow::bail!("Capsule references name different networks: {seen} and {network}")
+            }
+            _ => embedded = Some(network),
+        }
+    }
+
+    if let Some(network) = embedded {
+        if endpoint_explicit {
+            info!(
+                "Keeping the network given on the command line over {network} from the capsule URI"
+            );
+        } else if config.network != network.to_string() {
+            info!(
+                "Capsule URI names {network}; using it instead of {}",
+                config.network
+            );
+            config.switch_network(network);
+        }
+    }
+
+    Ok(refs.into_iter().map(|r| r.capsule_id).collect())
+}
+
+/// `resolve_capsule_refs` for a single reference
+pub fn resolve_capsule_ref(
+    config: &mut Config,
+    reference: &str,
+    endpoint_explicit: bool,
+) -> Result<String> {
+    let mut ids = resolve_capsule_refs(config, &[reference.to_string()], endpoint_explicit)?;
+    Ok(ids.remove(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_references() {
+        let uri: CapsuleRef = "capsule://testnet/0xabc".parse().unwrap();
+        assert_eq!(uri.capsule_id, "0xabc");
+        assert_eq!(uri.network, Some(Network::Testnet));
+        assert_eq!(uri.to_string(), "capsule://testnet/0xabc");
+
+        let bare: CapsuleRef = "0xabc".parse().unwrap();
+        assert_eq!(bare.network, None);
+
+        assert!("capsule://nowhere/0xabc".parse::<CapsuleRef>().is_err());
+        assert!("capsule://testnet/".parse::<CapsuleRef>().is_err());
+    }
+
+    #[test]
+    fn test_testnet_uri_switches_devnet_config() {
+        let mut config = Config::default();
+        let ids =
+            resolve_capsule_refs(&mut config, &["capsule://testnet/0xabc".to_string()], false)
+                .unwrap();
+
+        assert_eq!(ids, vec!["0xabc".to_string()]);
+        assert_eq!(config.network, "testnet");
+        assert_eq!(config.get_rpc_url(), "https://fullnode.testnet.sui.io:443");
+    }
+
+    #[test]
+    fn test_explicit_network_wins() {
+        let mut config = Config::default();
+        resolve_capsule_refs(&mut config, &["capsule://testnet/0xabc".to_string()], true).unwrap();
+
+        assert_eq!(config.network, "devnet");
+        assert_eq!(config.get_rpc_url(), "https://fullnode.devnet.sui.io:443");
+    }
+
+    #[test]
+    fn test_conflicting_networks_rejected() {
+        let mut config = Config::default();
+        let refs = [
+            "capsule://testnet/0xabc".to_string(),
+            "capsule://mainnet/0xdef".to_string(),
+        ];
+        assert!(resolve_capsule_refs(&mut config, &refs, false).is_err());
+    }
+}
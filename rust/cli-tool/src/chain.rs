@@ -0,0 +1,294 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+/// Read-only view of the chain used for pre-flight checks
+#[async_trait]
+pub trait ChainClient: Send + Sync {
+    /// Gas the create transaction for a capsule would use, from a dry run
+    async fn estimate_create_gas(&self, capsule_type: &str, storage_bytes: u64) -> Result<u64>;
+
+    /// Timestamp of the latest checkpoint, which is the clock the contract sees
+    async fn latest_timestamp_ms(&self) -> Result<u64>;
+
+    /// What the chain records about a capsule object
+    async fn capsule_state(&self, capsule_id: &str) -> Result<CapsuleChainState>;
+
+    /// Submit the owner-only transaction marking a capsule revoked, returning its digest
+    async fn revoke_capsule(&self, capsule_id: &str, sender: &str) -> Result<String>;
+
+    /// Submit the owner-only transaction deleting a capsule object, returning its digest
+    async fn destroy_capsule(&self, capsule_id: &str, sender: &str) -> Result<String>;
+
+    /// Submit the transaction withdrawing `sender`'s approval of a multisig
+    /// capsule, returning its digest
+    async fn revoke_approval(&self, capsule_id: &str, sender: &str) -> Result<String>;
+}
+
+/// What the chain records about a capsule
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapsuleChainState {
+    pub owner: String,
+    pub revoked: bool,
+    /// IPFS CID of the encrypted content
+    pub cid: Option<String>,
+    /// Hex BLAKE3 hash of the plaintext
+    pub content_hash: Option<String>,
+    /// Unlock time the contract checks, which is 0 for capsules that are not
+    /// time-locked; the ciphertext is bound to it
+    pub unlock_time_ms: Option<u64>,
+    pub unlocked: bool,
+    /// Addresses whose approval of a multisig capsule is recorded
+    pub approvals: Vec<String>,
+    /// Approvals needed to unlock, for multisig capsules
+    pub required_approvals: Option<u64>,
+}
+
+/// Chain client talking to a Sui full node over JSON-RPC
+///
+/// Capsule creation is still mocked, so there is no transaction to dry-run
+/// and gas estimates fail with an explanation instead of a made-up figure;
+/// the same goes for submitting a revocation, a destruction or withdrawing
+/// an approval.
+pub struct SuiRpcChain {
+    client: Client,
+    rpc_url: String,
+}
+
+#[derive(Deserialize)]
+struct RpcResponse {
+    result: Option<Value>,
+    error: Option<Value>,
+}
+
+impl SuiRpcChain {
+    pub fn new(client: Client, rpc_url: impl Into<String>) -> Self {
+        Self {
+            client,
+            rpc_url: rpc_url.into(),
+        }
+    }
+
+    async fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+        let response: RpcResponse = self
+            .client
+            .post(&self.rpc_url)
+            .json(&request)
+            .send()
+            .await
+            .with_context(|| format!("{method} request to {} failed", self.rpc_url))?
+            .error_for_status()
+            .with_context(|| format!("{method} request to {} failed", self.rpc_url))?
+            .json()
+            .await
+            .with_context(|| format!("Invalid {method} response"))?;
+
+        if let Some(error) = response.error {
+            anyhow::bail!("{method} returned an error: {error}");
+        }
+        response
+            .result
+            .ok_or_else(|| anyhow::anyhow!("{method} returned no result"))
+    }
+}
+
+/// Sui encodes u64 values as decimal strings
+fn parse_u64(value: &Value, what: &str) -> Result<u64> {
+    value
+        .as_str()
+        .and_then(|s| s.parse().ok())
+        .or_else(|| value.as_u64())
+        .ok_or_else(|| anyhow::anyhow!("Invalid {what}: {value}"))
+}
+
+/// Sui encodes `vector<u8>` values as arrays of numbers
+fn parse_hex_bytes(value: &Value) -> Option<String> {
+    value
+        .as_array()?
+        .iter()
+        .map(|byte| {
+            byte.as_u64()
+                .filter(|byte| *byte <= u8::MAX as u64)
+                .map(|byte| format!("{byte:02x}"))
+        })
+        .collect()
+}
+
+#[async_trait]
+impl ChainClient for SuiRpcChain {
+    async fn estimate_create_gas(&self, _capsule_type: &str, _storage_bytes: u64) -> Result<u64> {
+        anyhow::bail!(
+            "transactions are not submitted to the network yet, so there is nothing to dry-run"
+        )
+    }
+
+    async fn latest_timestamp_ms(&self) -> Result<u64> {
+        let sequence = self
+            .call("sui_getLatestCheckpointSequenceNumber", json!([]))
+            .await?;
+        let sequence = parse_u64(&sequence, "checkpoint sequence number")?;
+
+        let checkpoint = self
+            .call("sui_getCheckpoint", json!([sequence.to_string()]))
+            .await?;
+        parse_u64(&checkpoint["timestampMs"], "checkpoint timestamp")
+    }
+
+    async fn capsule_state(&self, capsule_id: &str) -> Result<CapsuleChainState> {
+        let object = self
+            .call(
+                "sui_getObject",
+                json!([capsule_id, {"showOwner": true, "showContent": true}]),
+            )
+            .await?;
+        if let Some(error) = object.get("error") {
+            anyhow::bail!("Capsule {capsule_id} not found on chain: {error}");
+        }
+
+        let data = &object["data"];
+        let owner = data["owner"]["AddressOwner"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Capsule {capsule_id} has no address owner"))?
+            .to_string();
+        let fields = &data["content"]["fields"];
+        // Capsules created before revocation existed have no such field
+        let revoked = fields["revoked"].as_bool().unwrap_or(false);
+        let cid = fields["cid"].as_str().map(str::to_string);
+        let content_hash = parse_hex_bytes(&fields["content_hash"]);
+        let unlocked = fields["unlocked"].as_bool().unwrap_or(false);
+        let approvals = fields["approvals"]
+            .as_array()
+            .map(|approvals| {
+                approvals
+                    .iter()
+                    .filter_map(|approver| approver.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let required_approvals = match fields.get("threshold") {
+            Some(value) => Some(parse_u64(value, "approval threshold")?),
+            None => None,
+        };
+        let unlock_time_ms = match fields.get("unlock_time_ms") {
+            Some(value) => Some(parse_u64(value, "unlock time")?),
+            None => None,
+        };
+
+        Ok(CapsuleChainState {
+            owner,
+            revoked,
+            cid,
+            content_hash,
+            unlock_time_ms,
+            unlocked,
+            approvals,
+            required_approvals,
+        })
+    }
+
+    async fn revoke_capsule(&self, _capsule_id: &str, _sender: &str) -> Result<String> {
+        anyhow::bail!(
+            "transactions are not submitted to the network yet, so revocation is unavailable"
+        )
+    }
+
+    async fn destroy_capsule(&self, _capsule_id: &str, _sender: &str) -> Result<String> {
+        anyhow::bail!(
+            "transactions are not submitted to the network yet, so capsules cannot be destroyed"
+        )
+    }
+
+    async fn revoke_approval(&self, _capsule_id: &str, _sender: &str) -> Result<String> {
+        anyhow::bail!(
+            "transactions are not submitted to the network yet, so approvals cannot be withdrawn"
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_latest_timestamp_from_checkpoint() {
+        let node = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(
+                json!({"method": "sui_getLatestCheckpointSequenceNumber"}),
+            ))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({"jsonrpc": "2.0", "id": 1, "result": "4242"})),
+            )
+            .mount(&node)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(
+                json!({"method": "sui_getCheckpoint", "params": ["4242"]}),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {"sequenceNumber": "4242", "timestampMs": "1700000000123"}
+            })))
+            .mount(&node)
+            .await;
+
+        let chain = SuiRpcChain::new(Client::new(), node.uri());
+        assert_eq!(
+            chain.latest_timestamp_ms().await.unwrap(),
+            1_700_000_000_123
+        );
+    }
+
+    #[tokio::test]
+    async fn test_capsule_state_reads_capsule_fields() {
+        let node = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({"method": "sui_getObject"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {"data": {
+                    "objectId": "0xcap",
+                    "owner": {"AddressOwner": "0xowner"},
+                    "content": {"dataType": "moveObject", "fields": {
+                        "revoked": true,
+                        "cid": "QmCapsule",
+                        "content_hash": [0, 15, 171, 255],
+                        "unlock_time_ms": "1700000000000",
+                        "unlocked": true,
+                        "approvals": ["0xapprover"],
+                        "threshold": "2"
+                    }}
+                }}
+            })))
+            .mount(&node)
+            .await;
+
+        let chain = SuiRpcChain::new(Client::new(), node.uri());
+        assert_eq!(
+            chain.capsule_state("0xcap").await.unwrap(),
+            CapsuleChainState {
+                owner: "0xowner".to_string(),
+                revoked: true,
+                cid: Some("QmCapsule".to_string()),
+                content_hash: Some("000fabff".to_string()),
+                unlock_time_ms: Some(1_700_000_000_000),
+                unlocked: true,
+                approvals: vec!["0xapprover".to_string()],
+                required_approvals: Some(2),
+            }
+        );
+    }
+}
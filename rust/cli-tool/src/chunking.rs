@@ -0,0 +1,167 @@
+use anyhow::{Context, Result};
+use encryptor_wasi::{
+    build_merkle_root, hash_content_bytes, hash_from_hex, hash_to_hex, merkle_proof,
+    verify_merkle_proof,
+};
+use serde::{Deserialize, Serialize};
+
+/// Associated data binding an encrypted chunk to its position
+pub fn chunk_associated_data(index: usize) -> Vec<u8> {
+    format!("capsule-chunk:{index}").into_bytes()
+}
+
+/// One encrypted piece of a chunked capsule
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ChunkRef {
+    pub cid: String,
+    /// Base64 key the chunk was encrypted with, fresh for every chunk
+    pub key: String,
+    /// Hex BLAKE3 hash of the chunk's plaintext
+    pub content_hash: String,
+    pub size: u64,
+    /// Hex sibling hashes from the chunk up to the manifest's Merkle root
+    pub proof: Vec<String>,
+}
+
+/// Ordered list of the chunks a large file was split into
+///
+/// This is what the capsule itself encrypts, so the chunk keys are only
+/// readable by whoever can unlock it. The capsule's envelope records that its
+/// plaintext is a manifest.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ChunkManifest {
+    pub total_size: u64,
+    /// Hex Merkle root over the chunks' plaintext, in order
+    pub merkle_root: String,
+    pub chunks: Vec<ChunkRef>,
+}
+
+impl ChunkManifest {
+    /// List `chunks`, the uploaded form of `pieces`, filling in each one's
+    /// proof against the Merkle root of the pieces
+    pub fn new(pieces: &[&[u8]], mut chunks: Vec<ChunkRef>) -> Result<Self> {
+        if pieces.len() != chunks.len() {
+            anyhow::bail!(
+                "Expected {} chunk references but got {}",
+                pieces.len(),
+                chunks.len()
+            );
+        }
+        for (index, chunk) in chunks.iter_mut().enumerate() {
+            chunk.proof = merkle_proof(pieces, index)
+                .iter()
+                .map(hash_to_hex)
+                .collect();
+        }
+
+        Ok(Self {
+            total_size: chunks.iter().map(|chunk| chunk.size).sum(),
+            merkle_root: hash_to_hex(&build_merkle_root(pieces)),
+            chunks,
+        })
+    }
+
+    /// Encode as capsule plaintext, which is the manifest as JSON
+    pub fn to_plaintext(&self) -> Result<Vec<u8>> {
+        serde_json::to_vec(self).context("Failed to serialize chunk manifest")
+    }
+
+    /// Read a manifest from the decrypted content of a chunked capsule
+    pub fn from_plaintext(data: &[u8]) -> Result<Self> {
+        serde_json::from_slice(data).context("Failed to parse chunk manifest")
+    }
+
+    /// Join decrypted chunks back into the original content
+    ///
+    /// Every chunk must match its recorded hash and prove its position under
+    /// the Merkle root, so a missing, altered or reordered chunk is rejected.
+    pub fn reassemble(&self, chunks: Vec<Vec<u8>>) -> Result<Vec<u8>> {
+        if chunks.len() != self.chunks.len() {
+            anyhow::bail!(
+                "Expected {} chunks but got {}",
+                self.chunks.len(),
+                chunks.len()
+            );
+        }
+
+        let root = hash_from_hex(&self.merkle_root).context("Invalid Merkle root format")?;
+        let mut content = Vec::with_capacity(self.total_size as usize);
+        for (index, (chunk, chunk_ref)) in chunks.iter().zip(&self.chunks).enumerate() {
+            let hash =
+                hash_from_hex(&chunk_ref.content_hash).context("Invalid chunk hash format")?;
+            if hash_content_bytes(chunk) != hash {
+                anyhow::bail!("Chunk {index} does not match its content hash");
+            }
+            let proof = chunk_ref
+                .proof
+                .iter()
+                .map(|sibling| hash_from_hex(sibling))
+                .collect::<Result<Vec<_>, _>>()
+                .context("Invalid Merkle proof format")?;
+            if !verify_merkle_proof(chunk, &proof, index, &root) {
+                anyhow::bail!("Chunk {index} is not at its position under the Merkle root");
+            }
+            content.extend_from_slice(chunk);
+        }
+        if content.len() as u64 != self.total_size {
+            anyhow::bail!(
+                "Reassembled {} bytes but the manifest records {}",
+                content.len(),
+                self.total_size
+            );
+        }
+
+        Ok(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk_ref(content: &[u8]) -> ChunkRef {
+        ChunkRef {
+            cid: crate::storage::MemoryStore::cid_for(content),
+            key: String::new(),
+            content_hash: hash_to_hex(&hash_content_bytes(content)),
+            size: content.len() as u64,
+            proof: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_reassembly_rejects_reordered_chunks() {
+        let chunks = vec![b"first".to_vec(), b"second".to_vec(), b"third".to_vec()];
+        let pieces: Vec<&[u8]> = chunks.iter().map(Vec::as_slice).collect();
+        let manifest =
+            ChunkManifest::new(&pieces, pieces.iter().map(|c| chunk_ref(c)).collect()).unwrap();
+        assert_eq!(manifest.total_size, 16);
+        assert_eq!(
+            manifest.merkle_root,
+            hash_to_hex(&build_merkle_root(&pieces))
+        );
+
+        let plaintext = manifest.to_plaintext().unwrap();
+        let parsed = ChunkManifest::from_plaintext(&plaintext).unwrap();
+        assert_eq!(parsed, manifest);
+        assert_eq!(
+            parsed.reassemble(chunks.clone()).unwrap(),
+            b"firstsecondthird"
+        );
+
+        let mut reordered = chunks.clone();
+        reordered.swap(0, 1);
+        assert!(parsed.reassemble(reordered).is_err());
+        assert!(parsed.reassemble(chunks[..2].to_vec()).is_err());
+
+        let mut tampered = parsed.clone();
+        tampered.chunks.swap(0, 2);
+        assert!(tampered.reassemble(chunks.clone()).is_err());
+
+        let mut tampered = parsed.clone();
+        tampered.chunks[1].proof.clear();
+        assert!(tampered.reassemble(chunks).is_err());
+
+        assert!(ChunkManifest::from_plaintext(b"plain content").is_err());
+    }
+}
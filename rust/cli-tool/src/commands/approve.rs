@@ -1,23 +1,36 @@
 use crate::config::Config;
-use crate::sdk::{create_progress_bar, create_spinner};
+use crate::sdk::{create_progress_bar, create_spinner, RevokeApprovalResult};
+use crate::theme::{style, theme};
 use crate::utils::init_sdk;
 use anyhow::Result;
 use clap::Args;
-use console::style;
 
 #[derive(Args)]
 pub struct ApproveArgs {
     /// Capsule ID to approve
     #[arg(short, long)]
     pub capsule_id: String,
-    /// Output format
-    #[arg(long, default_value = "human")]
-    pub format: String,
+    /// Output format (defaults to the configured format for this command)
+    #[arg(long)]
+    pub format: Option<String>,
+}
+
+#[derive(Args)]
+pub struct RevokeApprovalArgs {
+    /// Capsule ID whose approval to withdraw
+    #[arg(short, long)]
+    pub capsule_id: String,
+    /// Output format (defaults to the configured format for this command)
+    #[arg(long)]
+    pub format: Option<String>,
 }
 
 pub async fn handle_approve(args: ApproveArgs, config: &Config) -> Result<()> {
-    println!("{}", style("Approving Multisig Capsule").bold().cyan());
-    println!("{}", "=".repeat(50));
+    let format = config.output_format_for("approve", args.format.as_deref());
+    if !config.quiet {
+        println!("{}", theme().heading("Approving Multisig Capsule"));
+        println!("{}", "=".repeat(50));
+    }
 
     // Initialize SDK
     let spinner = create_spinner("Initializing SDK...");
@@ -29,7 +42,7 @@ pub async fn handle_approve(args: ApproveArgs, config: &Config) -> Result<()> {
 
     println!(
         "\n{} Approving capsule: {}",
-        style("✅").cyan(),
+        theme().id("✅"),
         style(&args.capsule_id).bold()
     );
 
@@ -42,7 +55,7 @@ pub async fn handle_approve(args: ApproveArgs, config: &Config) -> Result<()> {
         .await?;
 
     // Display result
-    display_approve_result(&result, &args.format)?;
+    display_approve_result(&result, &format, config.quiet)?;
 
     Ok(())
 }
@@ -59,13 +72,19 @@ fn validate_approve_args(args: &ApproveArgs) -> Result<()> {
     Ok(())
 }
 
-fn display_approve_result(result: &crate::sdk::ApprovalResult, format: &str) -> Result<()> {
+fn display_approve_result(
+    result: &crate::sdk::ApprovalResult,
+    format: &str,
+    quiet: bool,
+) -> Result<()> {
     if result.success {
-        println!(
-            "\n{}",
-            style("Approval Submitted Successfully!").bold().green()
-        );
-        println!("{}", "=".repeat(50));
+        if !quiet {
+            println!(
+                "\n{}",
+                theme().success("Approval Submitted Successfully!").bold()
+            );
+            println!("{}", "=".repeat(50));
+        }
 
         match format {
             "json" => {
@@ -76,27 +95,28 @@ fn display_approve_result(result: &crate::sdk::ApprovalResult, format: &str) ->
                 println!(
                     "{} {}",
                     style("Transaction:").bold(),
-                    style(&result.transaction_digest).cyan()
+                    theme().id(&result.transaction_digest)
                 );
+                println!("{} {} MIST", style("Gas Used:").bold(), result.gas_used);
                 println!(
                     "{} {}/{}",
                     style("Current Approvals:").bold(),
-                    style(result.current_approvals).cyan(),
-                    style(result.required_approvals).cyan()
+                    theme().id(result.current_approvals),
+                    theme().id(result.required_approvals)
                 );
 
                 if result.current_approvals >= result.required_approvals {
                     println!(
                         "\n{}",
-                        style("🎉 Capsule is now ready to be unlocked!")
+                        theme()
+                            .success("🎉 Capsule is now ready to be unlocked!")
                             .bold()
-                            .green()
                     );
                 } else {
                     let remaining = result.required_approvals - result.current_approvals;
                     println!(
                         "\n{} {} more approval{} needed",
-                        style("⏳").yellow(),
+                        theme().warning("⏳"),
                         style(remaining).bold(),
                         if remaining == 1 { "" } else { "s" }
                     );
@@ -104,14 +124,14 @@ fn display_approve_result(result: &crate::sdk::ApprovalResult, format: &str) ->
             }
         }
     } else {
-        println!("\n{}", style("Failed to Submit Approval").bold().red());
+        println!("\n{}", theme().error("Failed to Submit Approval").bold());
         println!("{}", "=".repeat(50));
 
         if let Some(ref error) = result.error {
-            println!("{} {}", style("Error:").bold().red(), error);
+            println!("{} {}", theme().error("Error:").bold(), error);
         }
 
-        println!("\n{}", style("Possible reasons:").bold().yellow());
+        println!("\n{}", theme().warning("Possible reasons:").bold());
         println!("• You are not an authorized approver for this capsule");
         println!("• You have already approved this capsule");
         println!("• Capsule does not exist or is not a multisig capsule");
@@ -121,11 +141,60 @@ fn display_approve_result(result: &crate::sdk::ApprovalResult, format: &str) ->
     Ok(())
 }
 
+/// Withdraw the configured address's approval of a multisig capsule
+pub async fn handle_revoke_approval(args: RevokeApprovalArgs, config: &Config) -> Result<()> {
+    let format = config.output_format_for("revoke-approval", args.format.as_deref());
+    if !config.quiet {
+        println!("{}", theme().heading("Withdrawing Approval"));
+        println!("{}", "=".repeat(50));
+    }
+
+    if !args.capsule_id.starts_with("0x") {
+        anyhow::bail!("Capsule ID must start with '0x'");
+    }
+
+    let spinner = create_spinner("Submitting withdrawal...");
+    let sdk = init_sdk(config).await?;
+    let result = sdk.revoke_approval(&args.capsule_id).await;
+    spinner.finish_and_clear();
+    let result = result?;
+
+    display_revoke_approval_result(&result, &format)
+}
+
+fn display_revoke_approval_result(result: &RevokeApprovalResult, format: &str) -> Result<()> {
+    match format {
+        "json" => {
+            println!("{}", serde_json::to_string_pretty(result)?);
+        }
+        _ => {
+            println!("\n{}", theme().success("Approval Withdrawn").bold());
+            println!(
+                "{} {}",
+                style("Capsule ID:").bold(),
+                theme().id(&result.capsule_id)
+            );
+            println!(
+                "{} {}",
+                style("Transaction:").bold(),
+                theme().id(&result.transaction_digest)
+            );
+            println!(
+                "{} {}/{}",
+                style("Current Approvals:").bold(),
+                theme().id(result.current_approvals),
+                theme().id(result.required_approvals)
+            );
+        }
+    }
+    Ok(())
+}
+
 /// Interactive approve command that guides the user through the process
 pub async fn handle_approve_interactive(config: &Config) -> Result<()> {
     use dialoguer::{Confirm, Input};
 
-    println!("{}", style("Interactive Capsule Approval").bold().cyan());
+    println!("{}", theme().heading("Interactive Capsule Approval"));
     println!("{}", "=".repeat(50));
 
     // Get capsule ID
@@ -183,7 +252,7 @@ pub async fn handle_approve_interactive(config: &Config) -> Result<()> {
     // Create approve args and proceed
     let args = ApproveArgs {
         capsule_id,
-        format: "human".to_string(),
+        format: Some("human".to_string()),
     };
 
     handle_approve(args, config).await
@@ -191,7 +260,7 @@ pub async fn handle_approve_interactive(config: &Config) -> Result<()> {
 
 /// List pending approvals for the current user
 pub async fn handle_list_pending_approvals(config: &Config) -> Result<()> {
-    println!("{}", style("Pending Approvals").bold().cyan());
+    println!("{}", theme().heading("Pending Approvals"));
     println!("{}", "=".repeat(50));
 
     // Initialize SDK
@@ -202,7 +271,7 @@ pub async fn handle_list_pending_approvals(config: &Config) -> Result<()> {
     spinner.finish_with_message("Pending approvals retrieved ✓");
 
     if pending.is_empty() {
-        println!("\n{}", style("No pending approvals found.").dim());
+        println!("\n{}", theme().dim("No pending approvals found."));
         return Ok(());
     }
 
@@ -216,7 +285,7 @@ pub async fn handle_list_pending_approvals(config: &Config) -> Result<()> {
         println!(
             "\n{}. {}",
             style(i + 1).bold(),
-            style(&capsule.capsule_id).cyan()
+            theme().id(&capsule.capsule_id)
         );
         println!(
             "   Created: {}",
@@ -234,7 +303,7 @@ pub async fn handle_list_pending_approvals(config: &Config) -> Result<()> {
 
     println!(
         "\n{}",
-        style("Use 'approve --capsule-id <ID>' to approve a capsule").dim()
+        theme().dim("Use 'approve --capsule-id <ID>' to approve a capsule")
     );
 
     Ok(())
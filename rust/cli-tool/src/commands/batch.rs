@@ -1,9 +1,12 @@
-﻿use crate::batch::{BatchExecutor, BatchOperationBuilder};
+﻿use crate::batch::{notify_webhook, BatchExecutor, BatchOperationBuilder};
 use crate::config::Config;
-use crate::sdk::create_spinner;
+use crate::file_processor::FileProcessor;
+use crate::manifest::BatchManifest;
+use crate::sdk::{create_spinner, BoundCondition};
+use crate::theme::theme;
+use crate::utils::{merge_approvers, read_approvers_file};
 use anyhow::{Context, Result};
 use clap::Args;
-use console::style;
 use std::path::PathBuf;
 
 #[derive(Args)]
@@ -28,14 +31,22 @@ pub struct BatchArgs {
     #[arg(long, value_delimiter = ',')]
     pub approvers: Vec<String>,
     
+    /// File of approver addresses (newline- or comma-separated), merged with --approvers
+    #[arg(long)]
+    pub approvers_file: Option<PathBuf>,
+    
     /// Price for payment capsules (in MIST)
     #[arg(short, long)]
     pub price: Option<u64>,
     
-    /// Encryption keys for unlock operations (comma-separated)
+    /// Encryption keys for unlock operations, one per input file in order (comma-separated)
     #[arg(short, long, value_delimiter = ',')]
     pub encryption_keys: Vec<String>,
     
+    /// Write unlocked content to this directory, named by stored file name or capsule ID
+    #[arg(long)]
+    pub output_dir: Option<PathBuf>,
+    
     /// Maximum concurrent operations
     #[arg(long, default_value = "4")]
     pub max_concurrent: usize,
@@ -52,6 +63,14 @@ pub struct BatchArgs {
     #[arg(short, long)]
     pub recursive: bool,
     
+    /// Include hidden files and directories (names starting with '.')
+    #[arg(long)]
+    pub include_hidden: bool,
+    
+    /// Follow symlinks that stay inside the directory instead of skipping them
+    #[arg(long)]
+    pub follow_symlinks: bool,
+    
     /// Maximum file size to process (in bytes)
     #[arg(long, default_value = "104857600")] // 100MB
     pub max_size: u64,
@@ -60,26 +79,95 @@ pub struct BatchArgs {
     #[arg(long, value_delimiter = ',')]
     pub extensions: Vec<String>,
     
-    /// Output format
-    #[arg(long, default_value = "human")]
-    pub format: String,
+    /// Only process files matching these globs, relative to the directory (comma-separated)
+    #[arg(long, value_delimiter = ',')]
+    pub include: Vec<String>,
+    
+    /// Skip files and directories matching these globs, relative to the directory (comma-separated)
+    #[arg(long, value_delimiter = ',')]
+    pub exclude: Vec<String>,
+    
+    /// Output format (defaults to the configured format for this command)
+    #[arg(long)]
+    pub format: Option<String>,
+    
+    /// POST the final batch result as JSON to this URL
+    #[arg(long)]
+    pub webhook: Option<String>,
+    
+    /// Only enumerate and validate inputs, print a size summary, and exit
+    #[arg(long)]
+    pub estimate_only: bool,
+    
+    /// Hash file content on multiple cores when looking for duplicates
+    #[arg(long)]
+    pub parallel_hash: bool,
+    
+    /// Threads used by --parallel-hash (0 = one per core)
+    #[arg(long, default_value = "0", requires = "parallel_hash")]
+    pub hash_threads: usize,
+    
+    /// JSON lines file recording each created capsule; files it lists are skipped on re-runs,
+    /// and unlock operations look up each capsule's key in it
+    #[arg(long)]
+    pub manifest: Option<PathBuf>,
 }
 
-pub async fn handle_batch(args: BatchArgs, config: &Config) -> Result<()> {
-    println!("{}", style("Batch Capsule Operations").bold().cyan());
-    println!("{}", "=".repeat(50));
+pub async fn handle_batch(mut args: BatchArgs, config: &Config) -> Result<()> {
+    let format = config.output_format_for("batch", args.format.as_deref());
+    if !config.quiet {
+        println!("{}", theme().heading("Batch Capsule Operations"));
+        println!("{}", "=".repeat(50));
+    }
+
+    // Merge approvers from --approvers-file
+    if let Some(ref approvers_file) = args.approvers_file {
+        let from_file = read_approvers_file(approvers_file)?;
+        args.approvers = merge_approvers(&args.approvers, &from_file)?;
+    }
 
     // Validate arguments
-    validate_batch_args(&args)?;
+    validate_batch_args(&args, config.max_price())?;
 
     // Build batch configuration
     let batch_config = build_batch_config(&args)?;
 
     // Create batch executor
     let spinner = create_spinner("Initializing batch executor...");
-    let executor = BatchExecutor::new(batch_config, config).await?;
+    let mut file_processor = FileProcessor::new()
+        .with_max_size(args.max_size)
+        .recursive(args.recursive)
+        .include_hidden(args.include_hidden)
+        .follow_symlinks(args.follow_symlinks)
+        .with_include_globs(args.include.clone())?
+        .with_exclude_globs(args.exclude.clone())?;
+    if !args.extensions.is_empty() {
+        file_processor = file_processor.with_extensions(args.extensions.clone());
+    }
+    let mut executor = BatchExecutor::new(batch_config, config)
+        .await?
+        .with_file_processor(file_processor);
+    if args.parallel_hash {
+        executor = executor.with_parallel_hash(args.hash_threads);
+    }
+    // An unlock batch only reads keys from the manifest
+    if let Some(ref manifest) = args.manifest {
+        if args.operation != "unlock" {
+            executor = executor.with_manifest(BatchManifest::new(manifest));
+        }
+    }
     spinner.finish_with_message("Batch executor initialized ");
 
+    // Preview without executing
+    if args.estimate_only {
+        let estimate = executor.estimate(args.inputs.clone())?;
+        match format.as_str() {
+            "json" => println!("{}", serde_json::to_string_pretty(&estimate)?),
+            _ => estimate.display_summary(),
+        }
+        return Ok(());
+    }
+
     // Display operation summary
     display_operation_summary(&args);
 
@@ -88,7 +176,7 @@ pub async fn handle_batch(args: BatchArgs, config: &Config) -> Result<()> {
         .context("Failed to execute batch operation")?;
 
     // Display results
-    match args.format.as_str() {
+    match format.as_str() {
         "json" => {
             let json = serde_json::to_value(&result)?;
             println!("{}", serde_json::to_string_pretty(&json)?);
@@ -98,6 +186,22 @@ pub async fn handle_batch(args: BatchArgs, config: &Config) -> Result<()> {
         }
     }
 
+    // Notify webhook; a failed POST never fails the batch
+    if let Some(ref webhook) = args.webhook {
+        let spinner = create_spinner("Notifying webhook...");
+        match notify_webhook(webhook, &result).await {
+            Ok(()) => spinner.finish_with_message("Webhook notified ✓"),
+            Err(e) => {
+                spinner.finish_and_clear();
+                println!(
+                    "{} {}",
+                    theme().warning("⚠️"),
+                    theme().warning(format!("Webhook notification failed: {e:#}"))
+                );
+            }
+        }
+    }
+
     // Exit with error code if any operations failed
     if !result.failed.is_empty() && !args.continue_on_error {
         std::process::exit(1);
@@ -106,7 +210,7 @@ pub async fn handle_batch(args: BatchArgs, config: &Config) -> Result<()> {
     Ok(())
 }
 
-fn validate_batch_args(args: &BatchArgs) -> Result<()> {
+fn validate_batch_args(args: &BatchArgs, max_price: u64) -> Result<()> {
     // Validate inputs exist
     for input in &args.inputs {
         if !input.exists() {
@@ -126,27 +230,32 @@ fn validate_batch_args(args: &BatchArgs) -> Result<()> {
                 anyhow::bail!("--threshold is required for create-multisig operations");
             }
             if args.approvers.is_empty() {
-                anyhow::bail!("--approvers is required for create-multisig operations");
-            }
-            let threshold = args.threshold.unwrap();
-            if threshold == 0 {
-                anyhow::bail!("Threshold must be greater than 0");
+                anyhow::bail!("--approvers or --approvers-file is required for create-multisig operations");
             }
-            if threshold > args.approvers.len() as u64 {
-                anyhow::bail!("Threshold cannot be greater than number of approvers");
+            BoundCondition::Multisig {
+                threshold: args.threshold.unwrap(),
+                approvers: args.approvers.clone(),
             }
+            .validate(max_price)?;
         }
         "create-payment" => {
             if args.price.is_none() {
                 anyhow::bail!("--price is required for create-payment operations");
             }
-            if args.price.unwrap() == 0 {
-                anyhow::bail!("Price must be greater than 0");
+            BoundCondition::Payment {
+                price: args.price.unwrap(),
             }
+            .validate(max_price)?;
         }
         "unlock" => {
-            if args.encryption_keys.is_empty() {
-                anyhow::bail!("--encryption-keys is required for unlock operations");
+            match (args.encryption_keys.is_empty(), &args.manifest) {
+                (true, None) => {
+                    anyhow::bail!("--encryption-keys or --manifest is required for unlock operations");
+                }
+                (false, Some(_)) => {
+                    anyhow::bail!("Use either --encryption-keys or --manifest for unlock operations, not both");
+                }
+                _ => {}
             }
         }
         _ => {
@@ -154,6 +263,19 @@ fn validate_batch_args(args: &BatchArgs) -> Result<()> {
         }
     }
 
+    if args.output_dir.is_some() && args.operation != "unlock" {
+        anyhow::bail!("--output-dir only applies to unlock operations");
+    }
+
+    // Validate webhook URL
+    if let Some(ref webhook) = args.webhook {
+        let url = reqwest::Url::parse(webhook)
+            .with_context(|| format!("Invalid webhook URL: {webhook}"))?;
+        if url.scheme() != "http" && url.scheme() != "https" {
+            anyhow::bail!("Webhook URL must use http or https: {}", webhook);
+        }
+    }
+
     // Validate concurrent operations limit
     if args.max_concurrent == 0 {
         anyhow::bail!("Max concurrent operations must be greater than 0");
@@ -182,7 +304,14 @@ fn build_batch_config(args: &BatchArgs) -> Result<crate::batch::BatchConfig> {
             builder.create_payment_capsules(args.price.unwrap())
         }
         "unlock" => {
-            builder.unlock_capsules(args.encryption_keys.clone())
+            let builder = match args.manifest {
+                Some(ref manifest) => builder.unlock_capsules_by_id(BatchManifest::new(manifest).keys()?),
+                None => builder.unlock_capsules(args.encryption_keys.clone()),
+            };
+            match args.output_dir {
+                Some(ref output_dir) => builder.output_dir(output_dir),
+                None => builder,
+            }
         }
         _ => unreachable!(), // Already validated
     };
@@ -191,10 +320,10 @@ fn build_batch_config(args: &BatchArgs) -> Result<crate::batch::BatchConfig> {
 }
 
 fn display_operation_summary(args: &BatchArgs) {
-    println!("\n{}", style("Operation Summary").bold().yellow());
+    println!("\n{}", theme().warning("Operation Summary").bold());
     println!("{}", "-".repeat(30));
     
-    println!("Operation type: {}", style(&args.operation).cyan());
+    println!("Operation type: {}", theme().id(&args.operation));
     println!("Input paths: {}", args.inputs.len());
     for input in &args.inputs {
         println!("   {}", input.display());
@@ -212,7 +341,13 @@ fn display_operation_summary(args: &BatchArgs) {
             println!("Price: {} MIST", args.price.unwrap());
         }
         "unlock" => {
-            println!("Encryption keys: {} provided", args.encryption_keys.len());
+            match args.manifest {
+                Some(ref manifest) => println!("Encryption keys: from {}", manifest.display()),
+                None => println!("Encryption keys: {} provided", args.encryption_keys.len()),
+            }
+            if let Some(ref output_dir) = args.output_dir {
+                println!("Output directory: {}", output_dir.display());
+            }
         }
         _ => {}
     }
@@ -220,6 +355,11 @@ fn display_operation_summary(args: &BatchArgs) {
     println!("Max concurrent: {}", args.max_concurrent);
     println!("Retry attempts: {}", args.retry_attempts);
     println!("Continue on error: {}", args.continue_on_error);
+    if let Some(ref manifest) = args.manifest {
+        if args.operation != "unlock" {
+            println!("Manifest: {}", manifest.display());
+        }
+    }
     println!();
 }
 
@@ -227,7 +367,7 @@ fn display_operation_summary(args: &BatchArgs) {
 pub async fn handle_batch_interactive(config: &Config) -> Result<()> {
     use dialoguer::{Confirm, Input, Select};
 
-    println!("{}", style("Interactive Batch Operations").bold().cyan());
+    println!("{}", theme().heading("Interactive Batch Operations"));
     println!("{}", "=".repeat(50));
 
     // Get input paths
@@ -244,7 +384,7 @@ pub async fn handle_batch_interactive(config: &Config) -> Result<()> {
         
         let path = PathBuf::from(input);
         if !path.exists() {
-            println!("{} Path does not exist, skipping.", style("").yellow());
+            println!("{} Path does not exist, skipping.", theme().warning(""));
             continue;
         }
         
@@ -319,15 +459,26 @@ pub async fn handle_batch_interactive(config: &Config) -> Result<()> {
         unlock_time,
         threshold,
         approvers,
+        approvers_file: None,
         price,
         encryption_keys,
+        output_dir: None,
         max_concurrent,
         retry_attempts: 3,
         continue_on_error,
         recursive: false,
+        include_hidden: false,
+        follow_symlinks: false,
         max_size: 104857600,
         extensions: Vec::new(),
-        format: "human".to_string(),
+        include: Vec::new(),
+        exclude: Vec::new(),
+        format: Some("human".to_string()),
+        webhook: None,
+        estimate_only: false,
+        parallel_hash: false,
+        hash_threads: 0,
+        manifest: None,
     };
 
     handle_batch(args, config).await
@@ -0,0 +1,172 @@
+use crate::config::Config;
+use crate::theme::{style, theme};
+use anyhow::Result;
+use clap::Args;
+use encryptor_wasi::{decrypt_content, encrypt_content, generate_key, hash_content_bytes};
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// Cipher every capsule is encrypted with
+const CAPSULE_CIPHER: &str = "xchacha20poly1305";
+
+#[derive(Args)]
+pub struct BenchArgs {
+    /// Buffer size in MiB
+    #[arg(long, default_value_t = 16)]
+    pub size: usize,
+    /// Timed iterations per operation, after one untimed warm-up
+    #[arg(long, default_value_t = 5)]
+    pub iterations: u32,
+    /// Output format (defaults to the configured format for this command)
+    #[arg(long)]
+    pub format: Option<String>,
+}
+
+/// Throughput of one operation over the benchmark buffer
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchResult {
+    pub algorithm: &'static str,
+    pub operation: &'static str,
+    pub bytes: usize,
+    pub iterations: u32,
+    pub total_ms: f64,
+    pub mb_per_sec: f64,
+}
+
+/// Measure local encryption and hashing throughput without touching the network
+pub fn handle_bench(args: BenchArgs, config: &Config) -> Result<()> {
+    let format = config.output_format_for("bench", args.format.as_deref());
+    let results = run_bench(args.size * 1024 * 1024, args.iterations)?;
+
+    match format.as_str() {
+        "json" => {
+            println!("{}", serde_json::to_string_pretty(&results)?);
+        }
+        _ => display_results(&results, config.quiet),
+    }
+
+    Ok(())
+}
+
+/// Time each available algorithm over an in-memory buffer of `bytes` bytes
+pub fn run_bench(bytes: usize, iterations: u32) -> Result<Vec<BenchResult>> {
+    if bytes == 0 {
+        anyhow::bail!("Benchmark size must be greater than zero");
+    }
+    if iterations == 0 {
+        anyhow::bail!("Benchmark iterations must be greater than zero");
+    }
+
+    let buffer: Vec<u8> = (0..bytes).map(|i| i as u8).collect();
+    let key = generate_key().map_err(|e| anyhow::anyhow!("Failed to generate key: {}", e))?;
+    let encrypted = encrypt_content(&buffer, &key)
+        .map_err(|e| anyhow::anyhow!("Failed to encrypt benchmark buffer: {}", e))?;
+
+    let results = vec![
+        measure(CAPSULE_CIPHER, "encrypt", bytes, iterations, || {
+            encrypt_content(&buffer, &key)
+                .map(drop)
+                .map_err(|e| anyhow::anyhow!("Encryption failed: {}", e))
+        })?,
+        measure(CAPSULE_CIPHER, "decrypt", bytes, iterations, || {
+            decrypt_content(&encrypted.ciphertext, &encrypted.nonce, &key)
+                .map(drop)
+                .map_err(|e| anyhow::anyhow!("Decryption failed: {}", e))
+        })?,
+        measure("blake3", "hash", bytes, iterations, || {
+            std::hint::black_box(hash_content_bytes(&buffer));
+            Ok(())
+        })?,
+    ];
+
+    Ok(results)
+}
+
+fn measure(
+    algorithm: &'static str,
+    operation: &'static str,
+    bytes: usize,
+    iterations: u32,
+    mut op: impl FnMut() -> Result<()>,
+) -> Result<BenchResult> {
+    // Warm caches and the allocator before timing
+    op()?;
+
+    let mut elapsed = Duration::ZERO;
+    for _ in 0..iterations {
+        let start = Instant::now();
+        op()?;
+        elapsed += start.elapsed();
+    }
+
+    let secs = elapsed.as_secs_f64().max(f64::EPSILON);
+    Ok(BenchResult {
+        algorithm,
+        operation,
+        bytes,
+        iterations,
+        total_ms: elapsed.as_secs_f64() * 1000.0,
+        mb_per_sec: (bytes as f64 * iterations as f64) / (1024.0 * 1024.0) / secs,
+    })
+}
+
+fn display_results(results: &[BenchResult], quiet: bool) {
+    if !quiet {
+        println!("{}", theme().heading("Encryption Benchmark"));
+        println!("{}", "=".repeat(50));
+    }
+
+    if let Some(first) = results.first() {
+        println!(
+            "Buffer: {:.1} MiB, {} iterations per operation\n",
+            first.bytes as f64 / (1024.0 * 1024.0),
+            first.iterations
+        );
+    }
+
+    println!(
+        "{:<20} {:<10} {:>12} {:>12}",
+        style("Algorithm").bold(),
+        style("Operation").bold(),
+        style("MB/s").bold(),
+        style("Total ms").bold()
+    );
+    println!("{}", "-".repeat(57));
+
+    for result in results {
+        let algorithm = if result.algorithm == CAPSULE_CIPHER {
+            theme().success(format!("{}*", result.algorithm))
+        } else {
+            style(result.algorithm.to_string())
+        };
+        println!(
+            "{:<20} {:<10} {:>12.1} {:>12.1}",
+            algorithm, result.operation, result.mb_per_sec, result.total_ms
+        );
+    }
+
+    println!("\n* cipher used for capsules");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bench_reports_positive_throughput() {
+        let results = run_bench(64 * 1024, 2).unwrap();
+
+        assert_eq!(results.len(), 3);
+        for result in &results {
+            assert_eq!(result.bytes, 64 * 1024);
+            assert_eq!(result.iterations, 2);
+            assert!(result.mb_per_sec > 0.0, "{result:?}");
+        }
+    }
+
+    #[test]
+    fn test_empty_bench_rejected() {
+        assert!(run_bench(0, 1).is_err());
+        assert!(run_bench(1024, 0).is_err());
+    }
+}
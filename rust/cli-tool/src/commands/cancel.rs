@@ -0,0 +1,96 @@
+use crate::config::{Config, Network};
+use crate::sdk::{create_spinner, CancelResult};
+use crate::theme::{style, theme};
+use crate::utils::init_sdk;
+use anyhow::Result;
+use clap::Args;
+use dialoguer::Confirm;
+
+#[derive(Args)]
+pub struct CancelArgs {
+    /// Capsule ID to cancel
+    #[arg(short, long)]
+    pub capsule_id: String,
+    /// Also ask the IPFS node or pinning service to stop pinning the content
+    #[arg(long)]
+    pub unpin: bool,
+    /// Cancel on mainnet without asking for confirmation
+    #[arg(short, long)]
+    pub yes: bool,
+    /// Output format (defaults to the configured format for this command)
+    #[arg(long)]
+    pub format: Option<String>,
+}
+
+pub async fn handle_cancel(args: CancelArgs, config: &Config) -> Result<()> {
+    let format = config.output_format_for("cancel", args.format.as_deref());
+    if !config.quiet {
+        println!("{}", theme().heading("Cancelling Capsule"));
+        println!("{}", "=".repeat(50));
+    }
+
+    if !args.capsule_id.starts_with("0x") {
+        anyhow::bail!("Capsule ID must start with '0x'");
+    }
+
+    // Destroying is permanent, so real funds and data get a second chance
+    if !args.yes && config.network.parse::<Network>().ok() == Some(Network::Mainnet) {
+        let confirmed = Confirm::new()
+            .with_prompt(format!(
+                "Destroy {} on mainnet? It can never be unlocked afterwards",
+                args.capsule_id
+            ))
+            .default(false)
+            .interact()?;
+        if !confirmed {
+            println!("Cancellation aborted by user");
+            return Ok(());
+        }
+    }
+
+    let spinner = create_spinner("Submitting cancellation...");
+    let sdk = init_sdk(config).await?;
+    let result = sdk.cancel_capsule(&args.capsule_id, args.unpin).await;
+    spinner.finish_and_clear();
+    let result = result?;
+
+    display_cancel_result(&result, &format)
+}
+
+fn display_cancel_result(result: &CancelResult, format: &str) -> Result<()> {
+    match format {
+        "json" => {
+            println!("{}", serde_json::to_string_pretty(result)?);
+        }
+        _ => {
+            println!(
+                "\n{}",
+                theme().success("Capsule Cancelled Successfully!").bold()
+            );
+            println!(
+                "{} {}",
+                style("Capsule ID:").bold(),
+                theme().id(&result.capsule_id)
+            );
+            println!(
+                "{} {}",
+                style("Transaction:").bold(),
+                theme().id(&result.transaction_digest)
+            );
+            if let Some(ref cid) = result.unpinned_cid {
+                println!("{} {}", style("Unpinned:").bold(), theme().id(cid));
+            }
+        }
+    }
+
+    eprintln!(
+        "{} Content may still be cached by gateways or other IPFS nodes{}",
+        theme().warning("⚠"),
+        if result.unpinned_cid.is_some() {
+            ""
+        } else {
+            "; use --unpin to stop pinning it"
+        }
+    );
+    Ok(())
+}
@@ -1,50 +1,137 @@
+use crate::archive::build_archive;
+use crate::compression::CompressionAlgo;
 use crate::config::Config;
-use crate::file_processor::{BatchProcessor, FileProcessor};
-use crate::sdk::{create_progress_bar, create_spinner};
+use crate::file_processor::{BatchProcessor, FileInfo, FileProcessor};
+use crate::metadata::{attach_metadata, FileMetadata};
+use crate::qr;
+use crate::sdk::{
+    create_progress_bar, create_spinner, normalize_approvers, parse_x25519_key, BoundCondition,
+    CapsuleSDK, Combinator, CostEstimate, CreateCapsuleResult, OperationMetrics,
+};
+use crate::temp::write_via_temp;
+use crate::theme::{style, theme};
 use crate::utils::{
-    future_timestamp, init_sdk, parse_duration, read_file_content, validate_sui_address,
+    init_sdk, is_key_reused, merge_approvers, parse_unlock_time, read_approvers_file,
+    read_file_content, record_key_fingerprint, validate_sui_address, KEY_FINGERPRINTS_FILE_NAME,
 };
 use anyhow::{Context, Result};
+use base64::Engine;
 use clap::Args;
-use console::style;
-use std::path::PathBuf;
+use log::warn;
+use serde::Serialize;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 
 #[derive(Args)]
 pub struct CreateArgs {
     /// File or directory to create capsule from
-    #[arg(short, long)]
-    pub file: PathBuf,
+    #[arg(short, long, required_unless_present = "files")]
+    pub file: Option<PathBuf>,
+    /// Bundle exactly these files into a single capsule (repeatable)
+    #[arg(long = "files", conflicts_with = "file")]
+    pub files: Vec<PathBuf>,
     /// Type of capsule to create
     #[arg(long, value_enum)]
     pub capsule_type: CapsuleType,
-    /// Unlock time for time-based capsules (e.g., "1h", "30m", "2d")
+    /// Unlock time for time-based and composite capsules: a duration from now
+    /// (e.g., "1h", "30m", "2d") or an RFC 3339 datetime (e.g., "2030-12-31T23:59:59Z")
     #[arg(short = 't', long, required_if_eq("capsule_type", "time"))]
     pub unlock_time: Option<String>,
-    /// Threshold for multisig capsules
+    /// Threshold for multisig and composite capsules
     #[arg(long, required_if_eq("capsule_type", "multisig"))]
     pub threshold: Option<u64>,
     /// Approvers for multisig capsules (comma-separated addresses)
-    #[arg(
-        long,
-        required_if_eq("capsule_type", "multisig"),
-        value_delimiter = ','
-    )]
+    #[arg(long, value_delimiter = ',')]
     pub approvers: Vec<String>,
-    /// Price for payment capsules (in MIST)
+    /// File of approver addresses (newline- or comma-separated), merged with --approvers
+    #[arg(long)]
+    pub approvers_file: Option<PathBuf>,
+    /// Price for payment and composite capsules (in MIST)
     #[arg(short, long, required_if_eq("capsule_type", "payment"))]
     pub price: Option<u64>,
+    /// Whether a composite capsule needs all of its conditions (and) or any one (or)
+    #[arg(long, default_value = "and")]
+    pub combinator: Combinator,
     /// Process directory recursively
     #[arg(short, long)]
     pub recursive: bool,
+    /// Include hidden files and directories (names starting with '.')
+    #[arg(long)]
+    pub include_hidden: bool,
+    /// Follow symlinks that stay inside the directory instead of skipping them
+    #[arg(long)]
+    pub follow_symlinks: bool,
     /// Maximum file size to process (in bytes)
     #[arg(long, default_value = "104857600")] // 100MB
     pub max_size: u64,
     /// Allowed file extensions (comma-separated)
     #[arg(long, value_delimiter = ',')]
     pub extensions: Vec<String>,
-    /// Output format
-    #[arg(long, default_value = "human")]
-    pub format: String,
+    /// Only process files matching these globs, relative to the directory (comma-separated)
+    #[arg(long, value_delimiter = ',')]
+    pub include: Vec<String>,
+    /// Skip files and directories matching these globs, relative to the directory (comma-separated)
+    #[arg(long, value_delimiter = ',')]
+    pub exclude: Vec<String>,
+    /// Output format (defaults to the configured format for this command)
+    #[arg(long)]
+    pub format: Option<String>,
+    /// Download and decrypt the capsule after upload to confirm it is retrievable
+    #[arg(long)]
+    pub verify_after: bool,
+    /// Print the estimated storage and gas cost without creating anything
+    #[arg(long)]
+    pub estimate_only: bool,
+    /// Encrypt the original file name, modification time and permissions with the content
+    #[arg(long)]
+    pub preserve_metadata: bool,
+    /// Compress content before encrypting it (none, gzip or zstd)
+    #[arg(long, default_value = "none")]
+    pub compress: CompressionAlgo,
+    /// Split content larger than this many bytes across several uploads
+    /// (combine with --max-size to store files over 100MB)
+    #[arg(long)]
+    pub chunk_size: Option<usize>,
+    /// Also wrap the content key to this base64 X25519 public key, so its
+    /// holder can recover the content if the encryption key is lost
+    #[arg(long)]
+    pub escrow_pubkey: Option<String>,
+    /// Print how long each stage took and how many bytes were uploaded
+    #[arg(long)]
+    pub timings: bool,
+    /// Encrypt with this base64 key instead of a fresh one per capsule
+    #[arg(long)]
+    pub key: Option<String>,
+    /// Fail instead of warning when --key was already used for another capsule
+    #[arg(long, requires = "key")]
+    pub strict: bool,
+    /// Derive the encryption key from this passphrase with Argon2id
+    #[arg(long, conflicts_with = "key")]
+    pub password: Option<String>,
+    /// Also show the encryption key as a QR code (not with --format json)
+    #[arg(long)]
+    pub qr: bool,
+    /// Write the encryption key as a QR code PNG to this file
+    #[arg(long)]
+    pub qr_output: Option<PathBuf>,
+    /// Put the capsule ID in the QR code along with the key
+    #[arg(long)]
+    pub qr_include_id: bool,
+    /// Write the encryption key, capsule ID and CID to this file (readable
+    /// only by you) and print just the capsule ID
+    #[arg(long, conflicts_with = "qr")]
+    pub key_out: Option<PathBuf>,
+    /// Replace an existing --key-out file
+    #[arg(long, requires = "key_out")]
+    pub force: bool,
+}
+
+/// Contents of a `--key-out` file
+#[derive(Serialize)]
+struct KeyFile<'a> {
+    capsule_id: &'a str,
+    cid: &'a str,
+    encryption_key: &'a str,
 }
 
 #[derive(clap::ValueEnum, Clone, Debug)]
@@ -52,24 +139,95 @@ pub enum CapsuleType {
     Time,
     Multisig,
     Payment,
+    /// Two or more of the conditions above, combined with --combinator
+    Composite,
+}
+
+impl CapsuleType {
+    /// Name used for the capsule type on chain
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CapsuleType::Time => "time",
+            CapsuleType::Multisig => "multisig",
+            CapsuleType::Payment => "payment",
+            CapsuleType::Composite => "composite",
+        }
+    }
 }
 
-pub async fn handle_create(args: CreateArgs, config: &Config) -> Result<()> {
-    println!("{}", style("Creating Time Capsule").bold().cyan());
-    println!("{}", "=".repeat(50));
+pub async fn handle_create(mut args: CreateArgs, config: &Config) -> Result<()> {
+    let format = config.output_format_for("create", args.format.as_deref());
+    if !config.quiet {
+        println!("{}", theme().heading("Creating Time Capsule"));
+        println!("{}", "=".repeat(50));
+    }
 
     // Initialize SDK
     let spinner = create_spinner("Initializing SDK...");
-    let sdk = init_sdk(config).await?;
+    let mut sdk = init_sdk(config)
+        .await?
+        .with_compression(args.compress)
+        .with_file_metadata(args.preserve_metadata && args.files.is_empty())
+        .with_bundle(!args.files.is_empty());
     spinner.finish_with_message("SDK initialized ✓");
 
+    if let Some(chunk_size) = args.chunk_size {
+        if chunk_size == 0 {
+            anyhow::bail!("Chunk size must be greater than 0");
+        }
+        sdk = sdk.with_chunk_size(chunk_size);
+    }
+
+    let key_store = config.config_dir().join(KEY_FINGERPRINTS_FILE_NAME);
+    if let Some(ref key) = args.key {
+        sdk = sdk.with_encryption_key(parse_encryption_key(key)?);
+        if check_key_reuse(&key_store, key, args.strict)? {
+            println!(
+                "{}",
+                theme().warning(
+                    "⚠️  This key was already used for another capsule; one leak would expose both"
+                )
+            );
+        }
+    }
+
+    if let Some(password) = args.password.take() {
+        if password.is_empty() {
+            anyhow::bail!("Password cannot be empty");
+        }
+        sdk = sdk.with_password(password);
+    }
+
+    if let Some(ref escrow_pubkey) = args.escrow_pubkey {
+        let public_key = parse_x25519_key(escrow_pubkey).context("Invalid --escrow-pubkey")?;
+        sdk = sdk.with_escrow_key(public_key);
+        println!(
+            "{}",
+            theme()
+                .warning("🔑 Key escrow enabled: the escrow key holder can recover this content")
+        );
+    }
+
+    // Merge approvers from --approvers-file
+    if let Some(ref approvers_file) = args.approvers_file {
+        let from_file = read_approvers_file(approvers_file)?;
+        args.approvers = merge_approvers(&args.approvers, &from_file)?;
+    }
+
     // Validate arguments based on capsule type
-    validate_create_args(&args)?;
+    validate_create_args(&args, config.max_price())?;
+    if let Some(ref key_out) = args.key_out {
+        check_key_out(key_out, args.force)?;
+    }
 
     // Set up file processor
     let mut file_processor = FileProcessor::new()
         .with_max_size(args.max_size)
-        .recursive(args.recursive);
+        .recursive(args.recursive)
+        .include_hidden(args.include_hidden)
+        .follow_symlinks(args.follow_symlinks)
+        .with_include_globs(args.include.clone())?
+        .with_exclude_globs(args.exclude.clone())?;
 
     if !args.extensions.is_empty() {
         file_processor = file_processor.with_extensions(args.extensions.clone());
@@ -77,68 +235,98 @@ pub async fn handle_create(args: CreateArgs, config: &Config) -> Result<()> {
 
     // Process files
     let spinner = create_spinner("Analyzing files...");
-    let files = file_processor
-        .process_path(&args.file)
-        .context("Failed to process input path")?;
+    let files = match args.file {
+        Some(ref path) if args.files.is_empty() => file_processor
+            .process_path(path)
+            .context("Failed to process input path")?,
+        _ => collect_bundle_files(&file_processor, &args.files)?,
+    };
     spinner.finish_with_message(format!("Found {} files ✓", files.len()));
 
     // Validate files
     file_processor.validate_files(&files)?;
 
-    // Create capsules
-    if files.len() == 1 {
-        // Single file
-        let file_info = &files[0];
-        let content = read_file_content(&file_info.path)?;
-
-        println!(
-            "\n{} Creating capsule for: {}",
-            style("📦").cyan(),
-            style(file_info.path.display()).bold()
-        );
-        println!(
-            "File size: {}",
-            crate::utils::format_file_size(file_info.size)
-        );
-        println!("MIME type: {}", file_info.mime_type);
-
-        let pb = create_progress_bar(4, "Creating capsule...");
+    if args.estimate_only {
+        let sources = if args.files.is_empty() {
+            files
+                .iter()
+                .map(|f| (f.path.display().to_string(), f.size))
+                .collect()
+        } else {
+            let paths: Vec<PathBuf> = files.iter().map(|f| f.path.clone()).collect();
+            let bundle = build_archive(&paths, args.preserve_metadata)?;
+            vec![("bundle".to_string(), bundle.len() as u64)]
+        };
+        return display_cost_estimates(&sdk, &args.capsule_type, sources, &format).await;
+    }
 
-        let result = match args.capsule_type {
-            CapsuleType::Time => {
-                let duration = parse_duration(args.unlock_time.as_ref().unwrap())?;
-                let unlock_time = future_timestamp(duration);
+    // Create capsules
+    if !args.files.is_empty() {
+        // Explicit file list bundled into one capsule
+        if !config.quiet {
+            println!(
+                "\n{} Creating bundled capsule for {} files:",
+                theme().id("📦"),
+                style(files.len()).bold()
+            );
+            for file_info in &files {
                 println!(
-                    "Unlock time: {}",
-                    crate::utils::format_timestamp(unlock_time)
+                    "  • {} ({})",
+                    file_info.path.display(),
+                    crate::utils::format_file_size(file_info.size)
                 );
-                sdk.create_time_capsule(content, unlock_time, Some(&pb))
-                    .await?
             }
-            CapsuleType::Multisig => {
-                let threshold = args.threshold.unwrap();
-                let approvers = args.approvers.clone();
-                println!("Threshold: {}/{}", threshold, approvers.len());
-                println!("Approvers: {}", approvers.join(", "));
-                sdk.create_multisig_capsule(content, threshold, approvers, Some(&pb))
-                    .await?
-            }
-            CapsuleType::Payment => {
-                let price = args.price.unwrap();
-                println!("Price: {price} MIST");
-                sdk.create_payment_capsule(content, price, Some(&pb))
-                    .await?
-            }
-        };
+        }
+
+        let paths: Vec<PathBuf> = files.iter().map(|f| f.path.clone()).collect();
+        let content = build_archive(&paths, args.preserve_metadata)?;
+        if !config.quiet {
+            println!(
+                "Bundle size: {}",
+                crate::utils::format_file_size(content.len() as u64)
+            );
+        }
+
+        let result =
+            create_single_capsule(&sdk, &args, content, &format, &key_store, config).await?;
+        display_key_qr(&result, &args, &format, config)?;
+    } else if files.len() == 1 {
+        // Single file
+        let file_info = &files[0];
+        let content = read_capsule_content(&file_info.path, args.preserve_metadata)?;
+
+        if !config.quiet {
+            println!(
+                "\n{} Creating capsule for: {}",
+                theme().id("📦"),
+                style(file_info.path.display()).bold()
+            );
+            println!(
+                "File size: {}",
+                crate::utils::format_file_size(file_info.size)
+            );
+            println!("MIME type: {}", file_info.mime_type);
+        }
 
-        display_create_result(&result, &args.format)?;
+        let result =
+            create_single_capsule(&sdk, &args, content, &format, &key_store, config).await?;
+        display_key_qr(&result, &args, &format, config)?;
     } else {
+        if args.qr || args.qr_output.is_some() {
+            anyhow::bail!("--qr and --qr-output only apply when creating a single capsule");
+        }
+        if args.key_out.is_some() {
+            anyhow::bail!("--key-out only applies when creating a single capsule");
+        }
+
         // Batch processing
-        println!(
-            "\n{} Processing {} files in batch mode",
-            style("📦").cyan(),
-            style(files.len()).bold()
-        );
+        if !config.quiet {
+            println!(
+                "\n{} Processing {} files in batch mode",
+                theme().id("📦"),
+                style(files.len()).bold()
+            );
+        }
 
         let (_multi_progress, main_pb) = file_processor.create_batch_progress(files.len());
 
@@ -147,12 +335,13 @@ pub async fn handle_create(args: CreateArgs, config: &Config) -> Result<()> {
             |file_info| {
                 let sdk = &sdk;
                 let args = &args;
+                let key_store = &key_store;
                 async move {
-                    let content = read_file_content(&file_info.path)?;
+                    let content = read_capsule_content(&file_info.path, args.preserve_metadata)?;
                     let result = match args.capsule_type {
                         CapsuleType::Time => {
-                            let duration = parse_duration(args.unlock_time.as_ref().unwrap())?;
-                            let unlock_time = future_timestamp(duration);
+                            let unlock_time =
+                                parse_unlock_time(args.unlock_time.as_ref().unwrap())?;
                             sdk.create_time_capsule(content, unlock_time, None).await?
                         }
                         CapsuleType::Multisig => {
@@ -165,7 +354,18 @@ pub async fn handle_create(args: CreateArgs, config: &Config) -> Result<()> {
                             let price = args.price.unwrap();
                             sdk.create_payment_capsule(content, price, None).await?
                         }
+                        CapsuleType::Composite => {
+                            let conditions = composite_conditions(args)?;
+                            sdk.create_composite_capsule(content, args.combinator, conditions, None)
+                                .await?
+                        }
                     };
+                    if args.verify_after {
+                        sdk.verify_upload(&result).await.with_context(|| {
+                            format!("Verification failed for capsule {}", result.capsule_id)
+                        })?;
+                    }
+                    record_key_use(key_store, args, &result.capsule_id);
                     Ok(format!(
                         "{} -> {}",
                         file_info.path.display(),
@@ -183,13 +383,13 @@ pub async fn handle_create(args: CreateArgs, config: &Config) -> Result<()> {
         if !batch_result.failed.is_empty() {
             use crate::file_processor::ErrorReporter;
 
-            println!("\n{}", style("Error Summary").bold().red());
+            println!("\n{}", theme().error("Error Summary").bold());
             println!("{}", "=".repeat(50));
             println!("{}", ErrorReporter::generate_error_summary(&batch_result));
 
             let suggestions = ErrorReporter::suggest_recovery_actions(&batch_result.failed);
             if !suggestions.is_empty() {
-                println!("\n{}", style("Recovery Suggestions:").bold().yellow());
+                println!("\n{}", theme().warning("Recovery Suggestions:").bold());
                 for suggestion in suggestions {
                     println!("  • {suggestion}");
                 }
@@ -200,10 +400,277 @@ pub async fn handle_create(args: CreateArgs, config: &Config) -> Result<()> {
     Ok(())
 }
 
-fn validate_create_args(args: &CreateArgs) -> Result<()> {
+/// Read a file's content, prefixed with its metadata when it is to be preserved
+fn read_capsule_content(path: &Path, preserve_metadata: bool) -> Result<Vec<u8>> {
+    let content = read_file_content(path)?;
+    if !preserve_metadata {
+        return Ok(content);
+    }
+    attach_metadata(&FileMetadata::from_path(path)?, &content)
+}
+
+/// Create one capsule from the given content and display the result
+async fn create_single_capsule(
+    sdk: &CapsuleSDK,
+    args: &CreateArgs,
+    content: Vec<u8>,
+    format: &str,
+    key_store: &Path,
+    config: &Config,
+) -> Result<CreateCapsuleResult> {
+    let pb = create_progress_bar(4, "Creating capsule...");
+
+    let result = match args.capsule_type {
+        CapsuleType::Time => {
+            let unlock_time = parse_unlock_time(args.unlock_time.as_ref().unwrap())?;
+            if !config.quiet {
+                println!(
+                    "Unlock time: {}",
+                    crate::utils::format_timestamp(unlock_time)
+                );
+            }
+            sdk.create_time_capsule(content, unlock_time, Some(&pb))
+                .await?
+        }
+        CapsuleType::Multisig => {
+            let threshold = args.threshold.unwrap();
+            let approvers = args.approvers.clone();
+            if !config.quiet {
+                println!("Threshold: {}/{}", threshold, approvers.len());
+                println!("Approvers: {}", approvers.join(", "));
+            }
+            sdk.create_multisig_capsule(content, threshold, approvers, Some(&pb))
+                .await?
+        }
+        CapsuleType::Payment => {
+            let price = args.price.unwrap();
+            if !config.quiet {
+                println!("Price: {price} MIST");
+            }
+            sdk.create_payment_capsule(content, price, Some(&pb))
+                .await?
+        }
+        CapsuleType::Composite => {
+            let conditions = composite_conditions(args)?;
+            let parts: Vec<&str> = conditions.iter().map(|c| c.type_name()).collect();
+            if !config.quiet {
+                println!(
+                    "Requires: {}",
+                    parts.join(&format!(" {} ", args.combinator))
+                );
+            }
+            sdk.create_composite_capsule(content, args.combinator, conditions, Some(&pb))
+                .await?
+        }
+    };
+
+    if args.verify_after {
+        let spinner = create_spinner("Verifying uploaded content...");
+        sdk.verify_upload(&result)
+            .await
+            .with_context(|| format!("Verification failed for capsule {}", result.capsule_id))?;
+        spinner.finish_with_message("Upload verified ✓");
+    }
+    record_key_use(key_store, args, &result.capsule_id);
+
+    if let Some(ref key_out) = args.key_out {
+        write_key_file(&config.temp_dir(), key_out, &result)?;
+    }
+    write_create_result(
+        &mut io::stdout().lock(),
+        &result,
+        format,
+        config.quiet,
+        args.key_out.as_deref(),
+    )?;
+    if args.timings && format != "json" {
+        if let Some(ref metrics) = result.metrics {
+            display_timings(metrics);
+        }
+    }
+    Ok(result)
+}
+
+/// Show or write the encryption key as a QR code when asked to
+fn display_key_qr(
+    result: &CreateCapsuleResult,
+    args: &CreateArgs,
+    format: &str,
+    config: &Config,
+) -> Result<()> {
+    if !args.qr && args.qr_output.is_none() {
+        return Ok(());
+    }
+    let capsule_id = args.qr_include_id.then_some(result.capsule_id.as_str());
+    let payload = qr::key_payload(&result.encryption_key, capsule_id)?;
+
+    // JSON output keeps only the text key so it stays parseable
+    if args.qr && format != "json" {
+        println!("\n{}", style("Encryption Key QR Code:").bold());
+        println!("{}", qr::render_terminal(&payload)?);
+    }
+    if let Some(ref path) = args.qr_output {
+        let png = qr::render_png(&payload)?;
+        write_via_temp(&config.temp_dir(), path, &png)
+            .with_context(|| format!("Failed to write QR code: {}", path.display()))?;
+        if format != "json" {
+            println!(
+                "{} QR code written to {}",
+                theme().success("✓"),
+                path.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Decode a base64 encryption key given with `--key`
+fn parse_encryption_key(encoded: &str) -> Result<[u8; 32]> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim())
+        .context("Encryption key must be base64")?;
+    bytes.try_into().map_err(|bytes: Vec<u8>| {
+        anyhow::anyhow!("Encryption key must be 32 bytes, got {}", bytes.len())
+    })
+}
+
+/// Whether a supplied key was used before; with `strict`, reuse is an error
+fn check_key_reuse(key_store: &Path, encryption_key: &str, strict: bool) -> Result<bool> {
+    let reused = is_key_reused(key_store, encryption_key)?;
+    if reused && strict {
+        anyhow::bail!(
+            "This encryption key was already used for another capsule. Use a fresh key or drop --strict"
+        );
+    }
+    Ok(reused)
+}
+
+/// Remember a supplied key's fingerprint so later reuse is noticed
+fn record_key_use(key_store: &Path, args: &CreateArgs, capsule_id: &str) {
+    if let Some(ref key) = args.key {
+        if let Err(e) = record_key_fingerprint(key_store, key, capsule_id) {
+            warn!("Failed to record key fingerprint: {e:#}");
+        }
+    }
+}
+
+/// Print the stage timings and byte counts an SDK operation reported
+pub(crate) fn display_timings(metrics: &OperationMetrics) {
+    println!("\n{}", style("Timings:").bold());
+    for stage in &metrics.stage_durations {
+        println!("  {:<14} {:>10.1} ms", stage.stage, stage.duration_ms);
+    }
+    println!("  {:<14} {:>10.1} ms", "total", metrics.total_ms());
+    if metrics.bytes_uploaded > 0 {
+        println!(
+            "  Uploaded:   {}",
+            crate::utils::format_file_size(metrics.bytes_uploaded)
+        );
+    }
+    if metrics.bytes_downloaded > 0 {
+        println!(
+            "  Downloaded: {}",
+            crate::utils::format_file_size(metrics.bytes_downloaded)
+        );
+    }
+    if metrics.retries > 0 {
+        println!("  Retries:    {}", metrics.retries);
+    }
+}
+
+/// A cost estimate for one capsule that `create` would make
+#[derive(Serialize)]
+struct SourceCostEstimate {
+    source: String,
+    #[serde(flatten)]
+    estimate: CostEstimate,
+}
+
+/// Estimate and print the cost of each capsule that would be created
+async fn display_cost_estimates(
+    sdk: &CapsuleSDK,
+    capsule_type: &CapsuleType,
+    sources: Vec<(String, u64)>,
+    format: &str,
+) -> Result<()> {
+    let mut estimates = Vec::with_capacity(sources.len());
+    for (source, content_len) in sources {
+        let estimate = sdk
+            .estimate_cost(content_len, capsule_type.as_str())
+            .await?;
+        estimates.push(SourceCostEstimate { source, estimate });
+    }
+
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(&estimates)?),
+        _ => {
+            println!("\n{}", theme().heading("Cost Estimate"));
+            println!("{}", "=".repeat(50));
+            for SourceCostEstimate { source, estimate } in &estimates {
+                let gas = match estimate.estimated_gas {
+                    Some(gas) => format!("{gas} MIST"),
+                    None => theme().dim("unavailable").to_string(),
+                };
+                println!(
+                    "{}: {} stored, gas {}",
+                    style(source).bold(),
+                    crate::utils::format_file_size(estimate.storage_bytes),
+                    gas
+                );
+            }
+
+            // Notes are usually the same for every capsule
+            let mut notes: Vec<&String> = Vec::new();
+            for note in estimates.iter().flat_map(|e| &e.estimate.notes) {
+                if !notes.contains(&note) {
+                    notes.push(note);
+                }
+            }
+            for note in notes {
+                println!("  • {}", theme().dim(note));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Validate each explicitly listed file individually
+fn collect_bundle_files(
+    file_processor: &FileProcessor,
+    paths: &[PathBuf],
+) -> Result<Vec<FileInfo>> {
+    let mut files = Vec::with_capacity(paths.len());
+    for path in paths {
+        if !path.is_file() {
+            anyhow::bail!("--files only accepts regular files: {}", path.display());
+        }
+        let mut info = file_processor
+            .process_path(path)
+            .with_context(|| format!("Invalid bundle input: {}", path.display()))?;
+        files.append(&mut info);
+    }
+    Ok(files)
+}
+
+fn validate_create_args(args: &CreateArgs, max_price: u64) -> Result<()> {
     // Validate file/directory exists
-    if !args.file.exists() {
-        anyhow::bail!("File or directory does not exist: {}", args.file.display());
+    match args.file {
+        Some(ref file) => {
+            if !file.exists() {
+                anyhow::bail!("File or directory does not exist: {}", file.display());
+            }
+        }
+        None => {
+            if args.files.is_empty() {
+                anyhow::bail!("Either --file or --files is required");
+            }
+            for file in &args.files {
+                if !file.exists() {
+                    anyhow::bail!("File does not exist: {}", file.display());
+                }
+            }
+        }
     }
 
     // Validate capsule type specific arguments
@@ -218,72 +685,389 @@ fn validate_create_args(args: &CreateArgs) -> Result<()> {
                 anyhow::bail!("Threshold is required for multisig capsules");
             }
             if args.approvers.is_empty() {
-                anyhow::bail!("Approvers are required for multisig capsules");
-            }
-            let threshold = args.threshold.unwrap();
-            if threshold == 0 {
-                anyhow::bail!("Threshold must be greater than 0");
-            }
-            if threshold > args.approvers.len() as u64 {
-                anyhow::bail!("Threshold cannot be greater than number of approvers");
+                anyhow::bail!(
+                    "Approvers are required for multisig capsules (--approvers or --approvers-file)"
+                );
             }
             // Validate approver addresses
             for approver in &args.approvers {
                 validate_sui_address(approver)
                     .with_context(|| format!("Invalid approver address: {approver}"))?;
             }
+            BoundCondition::Multisig {
+                threshold: args.threshold.unwrap(),
+                approvers: normalize_approvers(&args.approvers)?,
+            }
+            .validate(max_price)?;
         }
         CapsuleType::Payment => {
             if args.price.is_none() {
                 anyhow::bail!("Price is required for payment capsules");
             }
-            if args.price.unwrap() == 0 {
-                anyhow::bail!("Price must be greater than 0");
+            BoundCondition::Payment {
+                price: args.price.unwrap(),
             }
+            .validate(max_price)?;
+        }
+        CapsuleType::Composite => {
+            BoundCondition::Composite {
+                combinator: args.combinator,
+                conditions: composite_conditions(args)?,
+            }
+            .validate(max_price)?;
         }
     }
 
     Ok(())
 }
 
-fn display_create_result(result: &crate::sdk::CreateCapsuleResult, format: &str) -> Result<()> {
-    println!(
-        "\n{}",
-        style("Capsule Created Successfully!").bold().green()
-    );
-    println!("{}", "=".repeat(50));
+/// The parts of a composite capsule, from whichever condition flags were given
+fn composite_conditions(args: &CreateArgs) -> Result<Vec<BoundCondition>> {
+    let mut conditions = Vec::new();
+    if let Some(ref unlock_time) = args.unlock_time {
+        let unlock_time = parse_unlock_time(unlock_time)?;
+        conditions.push(BoundCondition::Time { unlock_time });
+    }
+    if args.threshold.is_some() || !args.approvers.is_empty() {
+        let Some(threshold) = args.threshold else {
+            anyhow::bail!("--threshold is required when a composite capsule has approvers");
+        };
+        if args.approvers.is_empty() {
+            anyhow::bail!(
+                "Approvers are required for a multisig condition (--approvers or --approvers-file)"
+            );
+        }
+        for approver in &args.approvers {
+            validate_sui_address(approver)
+                .with_context(|| format!("Invalid approver address: {approver}"))?;
+        }
+        conditions.push(BoundCondition::Multisig {
+            threshold,
+            approvers: normalize_approvers(&args.approvers)?,
+        });
+    }
+    if let Some(price) = args.price {
+        conditions.push(BoundCondition::Payment { price });
+    }
+    Ok(conditions)
+}
+
+/// Refuse to replace an existing key file unless forced, before anything is created
+fn check_key_out(path: &Path, force: bool) -> Result<()> {
+    if path.exists() && !force {
+        anyhow::bail!(
+            "Key file already exists: {}. Use --force to replace it.",
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+/// Save the encryption key where only the owner can read it
+fn write_key_file(temp_dir: &Path, path: &Path, result: &CreateCapsuleResult) -> Result<()> {
+    let data = serde_json::to_vec_pretty(&KeyFile {
+        capsule_id: &result.capsule_id,
+        cid: &result.cid,
+        encryption_key: &result.encryption_key,
+    })?;
+    write_via_temp(temp_dir, path, &data)
+        .with_context(|| format!("Failed to write key file: {}", path.display()))
+}
+
+/// Write the created capsule to `out`, without the heading and key warning
+/// when `quiet`, or only its ID when the key went to `key_out`
+fn write_create_result(
+    out: &mut impl Write,
+    result: &CreateCapsuleResult,
+    format: &str,
+    quiet: bool,
+    key_out: Option<&Path>,
+) -> Result<()> {
+    if key_out.is_some() {
+        match format {
+            "json" => writeln!(
+                out,
+                "{}",
+                serde_json::json!({ "capsule_id": result.capsule_id })
+            )?,
+            _ => writeln!(out, "{}", result.capsule_id)?,
+        }
+        return Ok(());
+    }
+
+    if !quiet {
+        writeln!(
+            out,
+            "\n{}",
+            theme().success("Capsule Created Successfully!").bold()
+        )?;
+        writeln!(out, "{}", "=".repeat(50))?;
+    }
 
     match format {
         "json" => {
             let json = serde_json::to_value(result)?;
-            println!("{}", serde_json::to_string_pretty(&json)?);
+            writeln!(out, "{}", serde_json::to_string_pretty(&json)?)?;
+        }
+        "toml" => {
+            write!(out, "{}", toml::to_string_pretty(result)?)?;
+        }
+        "yaml" => {
+            write!(out, "{}", serde_yaml::to_string(result)?)?;
         }
         _ => {
-            println!(
+            writeln!(
+                out,
                 "{} {}",
                 style("Capsule ID:").bold(),
-                style(&result.capsule_id).cyan()
-            );
-            println!(
+                theme().id(&result.capsule_id)
+            )?;
+            writeln!(
+                out,
                 "{} {}",
                 style("Transaction:").bold(),
-                style(&result.transaction_digest).cyan()
-            );
-            println!(
+                theme().id(&result.transaction_digest)
+            )?;
+            writeln!(
+                out,
+                "{} {} MIST",
+                style("Gas Used:").bold(),
+                result.gas_used
+            )?;
+            writeln!(
+                out,
                 "{} {}",
                 style("IPFS CID:").bold(),
-                style(&result.cid).cyan()
-            );
-            println!(
+                theme().id(&result.cid)
+            )?;
+            writeln!(
+                out,
                 "{} {}",
                 style("Encryption Key:").bold(),
-                style(&result.encryption_key).yellow()
-            );
-            println!("\n{}", style("⚠️  Important:").bold().yellow());
-            println!("Save the encryption key securely. You will need it to unlock the capsule.");
-            println!("The encryption key is not stored anywhere else and cannot be recovered.");
+                theme().warning(&result.encryption_key)
+            )?;
+            if let Some(ref compression) = result.compression {
+                let summary = if compression.applied == CompressionAlgo::None {
+                    "none (content stored uncompressed)".to_string()
+                } else {
+                    format!(
+                        "{} ({} -> {}, {:.1}% of original)",
+                        compression.applied,
+                        crate::utils::format_file_size(compression.original_size),
+                        crate::utils::format_file_size(compression.stored_size),
+                        compression.ratio * 100.0
+                    )
+                };
+                writeln!(out, "{} {}", style("Compression:").bold(), summary)?;
+            }
+            if let Some(ref escrow) = result.escrow {
+                writeln!(
+                    out,
+                    "{} {}",
+                    style("Escrow Key:").bold(),
+                    theme().id(&escrow.public_key)
+                )?;
+                writeln!(
+                    out,
+                    "{} {}",
+                    style("Wrapped Key:").bold(),
+                    theme().warning(&escrow.wrapped_key)
+                )?;
+            }
+            if !quiet {
+                writeln!(out, "\n{}", theme().warning("⚠️  Important:").bold())?;
+                writeln!(
+                    out,
+                    "Save the encryption key securely. You will need it to unlock the capsule."
+                )?;
+                if result.escrow.is_some() {
+                    writeln!(
+                        out,
+                        "The escrow key holder can recover it from the wrapped key above."
+                    )?;
+                } else {
+                    writeln!(
+                        out,
+                        "The encryption key is not stored anywhere else and cannot be recovered."
+                    )?;
+                }
+            }
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compression::CompressionOutcome;
+    use tempfile::TempDir;
+
+    const KEY: &str = "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=";
+
+    #[test]
+    fn test_second_create_with_same_key_warns() {
+        let temp_dir = TempDir::new().unwrap();
+        let key_store = temp_dir.path().join(KEY_FINGERPRINTS_FILE_NAME);
+
+        // First create: the key is new
+        assert!(!check_key_reuse(&key_store, KEY, false).unwrap());
+        record_key_fingerprint(&key_store, KEY, "0xfirst").unwrap();
+
+        // Second create with the same key warns, or fails when strict
+        assert!(check_key_reuse(&key_store, KEY, false).unwrap());
+        assert!(check_key_reuse(&key_store, KEY, true).is_err());
+
+        let other = "HxwdHhscGRoXGBUWExQREg8QDQ4LDAkKBwgFBgMEAQI=";
+        assert!(!check_key_reuse(&key_store, other, true).unwrap());
+
+        // Only fingerprints are stored
+        let stored = std::fs::read_to_string(&key_store).unwrap();
+        assert!(!stored.contains(KEY));
+    }
+
+    #[test]
+    fn test_create_result_as_toml_and_yaml() {
+        let condition = BoundCondition::Composite {
+            combinator: Combinator::Or,
+            conditions: vec![
+                BoundCondition::Time {
+                    unlock_time: 1_700_000_000_000,
+                },
+                BoundCondition::Payment { price: 1_000 },
+            ],
+        };
+        let result = CreateCapsuleResult {
+            capsule_id: "0xcapsule".to_string(),
+            transaction_digest: "digest".to_string(),
+            cid: "QmT78zSuBmuS4z925WZfrqQ1qHaJ56DQaTfyMUF7F8ff5o".to_string(),
+            encryption_key: KEY.to_string(),
+            content_hash: "hash".to_string(),
+            condition: condition.clone(),
+            encoding: Default::default(),
+            compression: None,
+            escrow: None,
+            gas_used: 42,
+            metrics: None,
+        };
+
+        let toml_out: toml::Table =
+            toml::from_str(&toml::to_string_pretty(&result).unwrap()).unwrap();
+        assert_eq!(toml_out["capsule_id"].as_str(), Some("0xcapsule"));
+        let parsed: BoundCondition = toml_out["condition"].clone().try_into().unwrap();
+        assert_eq!(parsed, condition);
+
+        let yaml_out: serde_yaml::Value =
+            serde_yaml::from_str(&serde_yaml::to_string(&result).unwrap()).unwrap();
+        assert_eq!(yaml_out["gas_used"].as_u64(), Some(42));
+        let parsed: BoundCondition = serde_yaml::from_value(yaml_out["condition"].clone()).unwrap();
+        assert_eq!(parsed, condition);
+    }
+
+    fn time_capsule_result() -> CreateCapsuleResult {
+        CreateCapsuleResult {
+            capsule_id: "0xcapsule".to_string(),
+            transaction_digest: "digest".to_string(),
+            cid: "QmT78zSuBmuS4z925WZfrqQ1qHaJ56DQaTfyMUF7F8ff5o".to_string(),
+            encryption_key: KEY.to_string(),
+            content_hash: "hash".to_string(),
+            condition: BoundCondition::Time {
+                unlock_time: 1_700_000_000_000,
+            },
+            encoding: Default::default(),
+            compression: None,
+            escrow: None,
+            gas_used: 42,
+            metrics: None,
+        }
+    }
+
+    #[test]
+    fn test_quiet_create_prints_only_the_result() {
+        let result = time_capsule_result();
+        let written = |format: &str, quiet: bool| {
+            let mut out = Vec::new();
+            write_create_result(&mut out, &result, format, quiet, None).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        let loud = written("human", false);
+        assert!(
+            loud.contains("====") && loud.contains("Important"),
+            "{loud}"
+        );
+
+        let quiet = written("human", true);
+        assert!(!quiet.contains("===="), "{quiet}");
+        assert!(!quiet.contains("Successfully") && !quiet.contains("Important"));
+        assert!(
+            quiet.contains("0xcapsule") && quiet.contains(KEY),
+            "{quiet}"
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&written("json", true)).unwrap();
+        assert_eq!(json["capsule_id"], "0xcapsule");
+    }
+
+    #[test]
+    fn test_create_output_reports_compression() {
+        let written = |result: &CreateCapsuleResult, format: &str| {
+            let mut out = Vec::new();
+            write_create_result(&mut out, result, format, true, None).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        let mut result = time_capsule_result();
+        assert!(!written(&result, "human").contains("Compression:"));
+
+        result.compression = Some(CompressionOutcome::new(CompressionAlgo::Zstd, 4096, 512));
+        let human = written(&result, "human");
+        assert!(
+            human.contains("zstd") && human.contains("12.5% of original"),
+            "{human}"
+        );
+        let json: serde_json::Value = serde_json::from_str(&written(&result, "json")).unwrap();
+        assert_eq!(json["compression"]["applied"], "zstd");
+        assert_eq!(json["compression"]["ratio"], 0.125);
+
+        result.compression = Some(CompressionOutcome::new(CompressionAlgo::None, 4096, 4096));
+        let human = written(&result, "human");
+        assert!(human.contains("stored uncompressed"), "{human}");
+    }
+
+    #[test]
+    fn test_key_out_keeps_the_key_off_stdout() {
+        let temp_dir = TempDir::new().unwrap();
+        let key_out = temp_dir.path().join("capsule.key");
+        let result = time_capsule_result();
+
+        check_key_out(&key_out, false).unwrap();
+        write_key_file(temp_dir.path(), &key_out, &result).unwrap();
+
+        let stored: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&key_out).unwrap()).unwrap();
+        assert_eq!(stored["encryption_key"], KEY);
+        assert_eq!(stored["capsule_id"], "0xcapsule");
+        assert_eq!(stored["cid"], result.cid.as_str());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&key_out).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // An existing key file is only replaced with --force
+        assert!(check_key_out(&key_out, false).is_err());
+        check_key_out(&key_out, true).unwrap();
+
+        for (format, expected) in [
+            ("human", "0xcapsule\n"),
+            ("json", "{\"capsule_id\":\"0xcapsule\"}\n"),
+        ] {
+            let mut out = Vec::new();
+            write_create_result(&mut out, &result, format, false, Some(&key_out)).unwrap();
+            assert_eq!(String::from_utf8(out).unwrap(), expected);
+        }
+    }
+}
@@ -0,0 +1,471 @@
+use crate::commands::list::unlock_summary;
+use crate::config::Config;
+use crate::sdk::{CapsuleQuery, CapsuleSDK, CapsuleStatus};
+use crate::utils::{format_file_size, format_timestamp, init_sdk};
+use anyhow::Result;
+use clap::Args;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Clear, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+use std::io::IsTerminal;
+
+#[derive(Args)]
+pub struct DashboardArgs {
+    /// Filter by capsule type
+    #[arg(short = 't', long)]
+    pub capsule_type: Option<String>,
+    /// Show only capsules created by current user
+    #[arg(long)]
+    pub mine: bool,
+    /// Maximum number of capsules to load
+    #[arg(short, long, default_value = "200")]
+    pub limit: u32,
+}
+
+/// A state change, independent of how the dashboard is drawn
+#[derive(Debug, Clone)]
+pub enum Action {
+    Up,
+    Down,
+    Top,
+    Bottom,
+    StartFilter,
+    FilterInput(char),
+    FilterBackspace,
+    FinishFilter,
+    ClearFilter,
+    Loaded(Vec<CapsuleStatus>),
+    ShowDetail(Box<CapsuleStatus>),
+    CloseDetail,
+    Message(String),
+}
+
+/// What the event loop should do after a key press
+#[derive(Debug)]
+enum Command {
+    Apply(Action),
+    Refresh,
+    Details,
+    Approve,
+    Quit,
+}
+
+/// Everything the dashboard shows
+///
+/// `selected` indexes the filtered view, not `capsules`.
+#[derive(Debug, Default)]
+pub struct DashboardState {
+    pub capsules: Vec<CapsuleStatus>,
+    pub filter: String,
+    pub filtering: bool,
+    pub selected: usize,
+    pub detail: Option<Box<CapsuleStatus>>,
+    pub message: Option<String>,
+}
+
+impl DashboardState {
+    /// Capsules matching the filter, case-insensitively, on ID, type or status
+    pub fn visible(&self) -> Vec<&CapsuleStatus> {
+        let needle = self.filter.to_lowercase();
+        self.capsules
+            .iter()
+            .filter(|capsule| {
+                needle.is_empty()
+                    || capsule.capsule_id.to_lowercase().contains(&needle)
+                    || capsule.capsule_type.to_lowercase().contains(&needle)
+                    || capsule.status.to_lowercase().contains(&needle)
+            })
+            .collect()
+    }
+
+    pub fn selected_capsule(&self) -> Option<&CapsuleStatus> {
+        self.visible().get(self.selected).copied()
+    }
+
+    pub fn apply(&mut self, action: Action) {
+        // A status message only lasts until the next thing happens
+        if !matches!(action, Action::Message(_)) {
+            self.message = None;
+        }
+
+        match action {
+            Action::Up => self.selected = self.selected.saturating_sub(1),
+            Action::Down => self.selected += 1,
+            Action::Top => self.selected = 0,
+            Action::Bottom => self.selected = usize::MAX,
+            Action::StartFilter => self.filtering = true,
+            Action::FilterInput(c) => {
+                self.filter.push(c);
+                self.selected = 0;
+            }
+            Action::FilterBackspace => {
+                self.filter.pop();
+                self.selected = 0;
+            }
+            Action::FinishFilter => self.filtering = false,
+            Action::ClearFilter => {
+                self.filter.clear();
+                self.filtering = false;
+                self.selected = 0;
+            }
+            Action::Loaded(capsules) => {
+                // Keep the cursor on the same capsule across a refresh
+                let previous = self.selected_capsule().map(|c| c.capsule_id.clone());
+                self.capsules = capsules;
+                if let Some(id) = previous {
+                    self.selected = self
+                        .visible()
+                        .iter()
+                        .position(|c| c.capsule_id == id)
+                        .unwrap_or(self.selected);
+                }
+            }
+            Action::ShowDetail(capsule) => self.detail = Some(capsule),
+            Action::CloseDetail => self.detail = None,
+            Action::Message(message) => self.message = Some(message),
+        }
+
+        let visible = self.visible().len();
+        self.selected = self.selected.min(visible.saturating_sub(1));
+    }
+}
+
+pub async fn handle_dashboard(args: DashboardArgs, config: &Config) -> Result<()> {
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        anyhow::bail!(
+            "The dashboard needs an interactive terminal. Use `capsule list` or `capsule status` for scripted output."
+        );
+    }
+
+    let sdk = init_sdk(config).await?;
+    let mut query = CapsuleQuery::new().with_limit(args.limit);
+    if let Some(capsule_type) = &args.capsule_type {
+        query = query.with_type(capsule_type);
+    }
+    if args.mine {
+        query = query.mine_only();
+    }
+
+    let mut state = DashboardState::default();
+    state.apply(Action::Loaded(
+        sdk.list_capsules(query.clone()).await?.capsules,
+    ));
+
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, &sdk, &query, &mut state).await;
+    ratatui::restore();
+    result
+}
+
+async fn run(
+    terminal: &mut DefaultTerminal,
+    sdk: &CapsuleSDK,
+    query: &CapsuleQuery,
+    state: &mut DashboardState,
+) -> Result<()> {
+    let mut table_state = TableState::default();
+
+    loop {
+        table_state.select(Some(state.selected));
+        terminal.draw(|frame| render(frame, state, &mut table_state))?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        match key_command(state, key) {
+            Some(Command::Apply(action)) => state.apply(action),
+            Some(Command::Refresh) => match sdk.list_capsules(query.clone()).await {
+                Ok(page) => {
+                    let capsules = page.capsules;
+                    let count = capsules.len();
+                    state.apply(Action::Loaded(capsules));
+                    state.apply(Action::Message(format!("Loaded {count} capsules")));
+                }
+                Err(e) => state.apply(Action::Message(format!("Refresh failed: {e:#}"))),
+            },
+            Some(Command::Details) => {
+                if let Some(id) = state.selected_capsule().map(|c| c.capsule_id.clone()) {
+                    match sdk.get_capsule_status(&id).await {
+                        Ok(status) => state.apply(Action::ShowDetail(Box::new(status))),
+                        Err(e) => state.apply(Action::Message(format!("Status failed: {e:#}"))),
+                    }
+                }
+            }
+            Some(Command::Approve) => {
+                let message = match state.selected_capsule() {
+                    Some(capsule) if capsule.capsule_type != "multisig" => {
+                        "Only multisig capsules can be approved".to_string()
+                    }
+                    Some(capsule) => {
+                        let id = capsule.capsule_id.clone();
+                        match sdk.approve_multisig_capsule(&id, None).await {
+                            Ok(result) => format!(
+                                "Approved {} ({}/{} approvals)",
+                                id, result.current_approvals, result.required_approvals
+                            ),
+                            Err(e) => format!("Approval failed: {e:#}"),
+                        }
+                    }
+                    None => continue,
+                };
+                state.apply(Action::Message(message));
+            }
+            Some(Command::Quit) => return Ok(()),
+            None => {}
+        }
+    }
+}
+
+/// Map a key press to a command for the current mode
+fn key_command(state: &DashboardState, key: KeyEvent) -> Option<Command> {
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+        return Some(Command::Quit);
+    }
+
+    if state.filtering {
+        let action = match key.code {
+            KeyCode::Char(c) => Action::FilterInput(c),
+            KeyCode::Backspace => Action::FilterBackspace,
+            KeyCode::Enter => Action::FinishFilter,
+            KeyCode::Esc => Action::ClearFilter,
+            _ => return None,
+        };
+        return Some(Command::Apply(action));
+    }
+
+    if state.detail.is_some() {
+        return match key.code {
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => {
+                Some(Command::Apply(Action::CloseDetail))
+            }
+            _ => None,
+        };
+    }
+
+    let command = match key.code {
+        KeyCode::Up | KeyCode::Char('k') => Command::Apply(Action::Up),
+        KeyCode::Down | KeyCode::Char('j') => Command::Apply(Action::Down),
+        KeyCode::Home | KeyCode::Char('g') => Command::Apply(Action::Top),
+        KeyCode::End | KeyCode::Char('G') => Command::Apply(Action::Bottom),
+        KeyCode::Char('/') => Command::Apply(Action::StartFilter),
+        KeyCode::Esc => Command::Apply(Action::ClearFilter),
+        KeyCode::Enter => Command::Details,
+        KeyCode::Char('a') => Command::Approve,
+        KeyCode::Char('r') => Command::Refresh,
+        KeyCode::Char('q') => Command::Quit,
+        _ => return None,
+    };
+    Some(command)
+}
+
+fn render(frame: &mut Frame, state: &DashboardState, table_state: &mut TableState) {
+    let [table_area, footer_area] =
+        Layout::vertical([Constraint::Min(3), Constraint::Length(1)]).areas(frame.area());
+
+    let visible = state.visible();
+    let rows = visible.iter().map(|capsule| {
+        Row::new(vec![
+            capsule.capsule_id.clone(),
+            capsule.capsule_type.clone(),
+            capsule.status.clone(),
+            format_timestamp(capsule.created_at),
+            unlock_summary(capsule, false),
+        ])
+    });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(20),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(24),
+            Constraint::Min(16),
+        ],
+    )
+    .header(
+        Row::new(["Capsule ID", "Type", "Status", "Created", "Unlock Info"])
+            .style(Style::new().add_modifier(Modifier::BOLD)),
+    )
+    .block(Block::bordered().title(format!(
+        " Time Capsules ({}/{}) ",
+        visible.len(),
+        state.capsules.len()
+    )))
+    .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+    frame.render_stateful_widget(table, table_area, table_state);
+
+    let footer = if state.filtering {
+        format!("Filter: {}_  (Enter to keep, Esc to clear)", state.filter)
+    } else if let Some(message) = &state.message {
+        message.clone()
+    } else if !state.filter.is_empty() {
+        format!("Filter: {}  (/ to edit, Esc to clear)", state.filter)
+    } else {
+        "↑/↓ move  / filter  Enter details  a approve  r refresh  q quit".to_string()
+    };
+    frame.render_widget(Paragraph::new(footer), footer_area);
+
+    if let Some(capsule) = &state.detail {
+        let area = centered(frame.area(), 60, 14);
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(detail_lines(capsule)).block(Block::bordered().title(" Details ")),
+            area,
+        );
+    }
+}
+
+fn detail_lines(capsule: &CapsuleStatus) -> Vec<Line<'static>> {
+    let mut lines = vec![
+        Line::from(format!("ID:      {}", capsule.capsule_id)),
+        Line::from(format!("Type:    {}", capsule.capsule_type)),
+        Line::from(format!("Status:  {}", capsule.status)),
+        Line::from(format!("Created: {}", format_timestamp(capsule.created_at))),
+    ];
+    if let Some(creator) = &capsule.creator {
+        lines.push(Line::from(format!("Creator: {creator}")));
+    }
+    if let Some(size) = capsule.content_size {
+        lines.push(Line::from(format!("Size:    {}", format_file_size(size))));
+    }
+    if let Some(cid) = &capsule.cid {
+        lines.push(Line::from(format!("CID:     {cid}")));
+    }
+    lines.push(Line::from(format!(
+        "Unlock:  {}",
+        unlock_summary(capsule, false)
+    )));
+    if let Some(approvals) = &capsule.approvals {
+        lines.push(Line::from(format!(
+            "Signers: {}",
+            approvals.approvers.join(", ")
+        )));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from("Esc to close"));
+    lines
+}
+
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let [area] = Layout::horizontal([Constraint::Length(width)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::vertical([Constraint::Length(height)])
+        .flex(Flex::Center)
+        .areas(area);
+    area
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capsule(id: &str, capsule_type: &str, status: &str) -> CapsuleStatus {
+        CapsuleStatus {
+            capsule_type: capsule_type.to_string(),
+            status: status.to_string(),
+            ..CapsuleStatus::failed(id, String::new())
+        }
+    }
+
+    fn state() -> DashboardState {
+        let mut state = DashboardState::default();
+        state.apply(Action::Loaded(vec![
+            capsule("0xaaa", "time", "locked"),
+            capsule("0xbbb", "multisig", "locked"),
+            capsule("0xccc", "payment", "unlocked"),
+        ]));
+        state
+    }
+
+    #[test]
+    fn test_selection_stays_in_bounds() {
+        let mut state = state();
+        state.apply(Action::Up);
+        assert_eq!(state.selected, 0);
+
+        state.apply(Action::Bottom);
+        assert_eq!(state.selected, 2);
+        state.apply(Action::Down);
+        assert_eq!(state.selected_capsule().unwrap().capsule_id, "0xccc");
+
+        // A refresh keeps the same capsule selected even if it moved
+        state.apply(Action::Loaded(vec![
+            capsule("0xccc", "payment", "unlocked"),
+            capsule("0xaaa", "time", "locked"),
+        ]));
+        assert_eq!(state.selected, 0);
+
+        state.apply(Action::Loaded(vec![]));
+        assert_eq!(state.selected, 0);
+        assert!(state.selected_capsule().is_none());
+    }
+
+    #[test]
+    fn test_filter_narrows_and_resets_selection() {
+        let mut state = state();
+        state.apply(Action::Bottom);
+
+        state.apply(Action::StartFilter);
+        for c in "LOCK".chars() {
+            state.apply(Action::FilterInput(c));
+        }
+        // "unlocked" contains "lock" too
+        assert_eq!(state.visible().len(), 3);
+
+        for _ in 0..4 {
+            state.apply(Action::FilterBackspace);
+        }
+        for c in "multi".chars() {
+            state.apply(Action::FilterInput(c));
+        }
+        let ids: Vec<&str> = state
+            .visible()
+            .iter()
+            .map(|c| c.capsule_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["0xbbb"]);
+        assert_eq!(state.selected, 0);
+
+        state.apply(Action::FinishFilter);
+        assert!(!state.filtering);
+        assert_eq!(state.filter, "multi");
+
+        state.apply(Action::ClearFilter);
+        assert_eq!(state.visible().len(), 3);
+    }
+
+    #[test]
+    fn test_keys_depend_on_mode() {
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        let mut state = state();
+
+        assert!(matches!(
+            key_command(&state, key(KeyCode::Char('q'))),
+            Some(Command::Quit)
+        ));
+
+        state.apply(Action::StartFilter);
+        assert!(matches!(
+            key_command(&state, key(KeyCode::Char('q'))),
+            Some(Command::Apply(Action::FilterInput('q')))
+        ));
+
+        state.apply(Action::FinishFilter);
+        state.apply(Action::ShowDetail(Box::new(capsule(
+            "0xaaa", "time", "locked",
+        ))));
+        assert!(matches!(
+            key_command(&state, key(KeyCode::Esc)),
+            Some(Command::Apply(Action::CloseDetail))
+        ));
+        assert!(key_command(&state, key(KeyCode::Char('a'))).is_none());
+    }
+}
@@ -0,0 +1,217 @@
+use crate::config::Config;
+use crate::sdk::{create_spinner, CapsuleStatus};
+use crate::theme::{style, theme};
+use crate::utils::{format_timestamp, init_sdk};
+use anyhow::Result;
+use clap::Args;
+use serde::Serialize;
+
+#[derive(Args)]
+pub struct DiffArgs {
+    /// First capsule ID
+    pub first: String,
+    /// Second capsule ID
+    pub second: String,
+    /// Output format (defaults to the configured format for this command)
+    #[arg(long)]
+    pub format: Option<String>,
+}
+
+/// One compared field; a missing value on either side counts as a difference
+#[derive(Debug, Serialize)]
+pub struct FieldDiff {
+    pub field: &'static str,
+    pub first: Option<String>,
+    pub second: Option<String>,
+    pub same: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CapsuleDiff {
+    pub first: String,
+    pub second: String,
+    /// Both capsules hold the same plaintext, whatever their CIDs
+    pub same_content: bool,
+    pub fields: Vec<FieldDiff>,
+}
+
+pub async fn handle_diff(args: DiffArgs, config: &Config) -> Result<()> {
+    let format = config.output_format_for("diff", args.format.as_deref());
+
+    let spinner = create_spinner("Fetching capsule statuses...");
+    let sdk = init_sdk(config).await?;
+    let (first, second) = tokio::try_join!(
+        sdk.get_capsule_status(&args.first),
+        sdk.get_capsule_status(&args.second)
+    )?;
+    spinner.finish_with_message("Fetched both capsules ✓");
+
+    let diff = diff_capsules(&first, &second);
+    match format.as_str() {
+        "json" => {
+            println!("{}", serde_json::to_string_pretty(&diff)?);
+        }
+        _ => display_diff(&diff, config.quiet),
+    }
+
+    Ok(())
+}
+
+/// Compare the fields that identify a capsule's content and unlock condition
+pub fn diff_capsules(first: &CapsuleStatus, second: &CapsuleStatus) -> CapsuleDiff {
+    let fields = vec![
+        field(
+            "content_hash",
+            first.content_hash.clone(),
+            second.content_hash.clone(),
+        ),
+        field("cid", first.cid.clone(), second.cid.clone()),
+        field(
+            "type",
+            Some(first.capsule_type.clone()),
+            Some(second.capsule_type.clone()),
+        ),
+        field(
+            "unlock_condition",
+            unlock_condition(first),
+            unlock_condition(second),
+        ),
+        field(
+            "content_size",
+            first.content_size.map(|size| size.to_string()),
+            second.content_size.map(|size| size.to_string()),
+        ),
+        field(
+            "status",
+            Some(first.status.clone()),
+            Some(second.status.clone()),
+        ),
+    ];
+
+    CapsuleDiff {
+        first: first.capsule_id.clone(),
+        second: second.capsule_id.clone(),
+        same_content: fields[0].same,
+        fields,
+    }
+}
+
+fn field(name: &'static str, first: Option<String>, second: Option<String>) -> FieldDiff {
+    FieldDiff {
+        field: name,
+        same: first.is_some() && first == second,
+        first,
+        second,
+    }
+}
+
+/// The condition itself, independent of progress such as approvals so far
+fn unlock_condition(capsule: &CapsuleStatus) -> Option<String> {
+    match capsule.capsule_type.as_str() {
+        "time" => capsule
+            .unlock_time
+            .map(|unlock_time| format!("after {}", format_timestamp(unlock_time))),
+        "multisig" => capsule.approvals.as_ref().map(|approvals| {
+            format!(
+                "{} of [{}]",
+                approvals.required,
+                approvals.approvers.join(", ")
+            )
+        }),
+        "payment" => capsule.price.map(|price| format!("{price} MIST")),
+        _ => None,
+    }
+}
+
+fn display_diff(diff: &CapsuleDiff, quiet: bool) {
+    if !quiet {
+        println!("{}", theme().heading("Capsule Diff"));
+        println!("{}", "=".repeat(50));
+    }
+    println!("First:  {}", theme().id(&diff.first));
+    println!("Second: {}", theme().id(&diff.second));
+    println!();
+
+    for field in &diff.fields {
+        let first = field.first.as_deref().unwrap_or("-");
+        let second = field.second.as_deref().unwrap_or("-");
+        if field.same {
+            println!(
+                "{} {:<18} {}",
+                theme().success("="),
+                style(field.field).bold(),
+                first
+            );
+        } else {
+            println!(
+                "{} {:<18} {}",
+                theme().warning("≠"),
+                style(field.field).bold(),
+                theme().error(first)
+            );
+            println!("  {:<18} {}", "", theme().error(second));
+        }
+    }
+
+    println!();
+    if diff.same_content {
+        println!(
+            "{}",
+            theme().success("✅ Same content hash: both capsules hold identical plaintext")
+        );
+    } else {
+        println!(
+            "{}",
+            theme().warning("⚠️  Content hashes differ or are unknown")
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capsule(id: &str, cid: &str, content_hash: &str) -> CapsuleStatus {
+        CapsuleStatus {
+            capsule_type: "time".to_string(),
+            status: "locked".to_string(),
+            content_size: Some(1024),
+            cid: Some(cid.to_string()),
+            content_hash: Some(content_hash.to_string()),
+            unlock_time: Some(1_735_689_600_000),
+            error: None,
+            ..CapsuleStatus::failed(id, String::new())
+        }
+    }
+
+    fn same(diff: &CapsuleDiff, name: &str) -> bool {
+        diff.fields.iter().find(|f| f.field == name).unwrap().same
+    }
+
+    #[test]
+    fn test_same_hash_different_cid() {
+        let original = capsule("0xaaa", "QmOriginal123", "abc123");
+        let rekeyed = capsule("0xbbb", "QmRekeyed4567", "abc123");
+
+        let diff = diff_capsules(&original, &rekeyed);
+
+        assert!(diff.same_content);
+        assert!(same(&diff, "content_hash"));
+        assert!(!same(&diff, "cid"));
+        assert!(same(&diff, "type"));
+        assert!(same(&diff, "unlock_condition"));
+    }
+
+    #[test]
+    fn test_unknown_hash_is_not_same_content() {
+        let mut first = capsule("0xaaa", "QmSame1234567", "abc123");
+        let mut second = capsule("0xbbb", "QmSame1234567", "abc123");
+        first.content_hash = None;
+        second.content_hash = None;
+
+        let diff = diff_capsules(&first, &second);
+
+        assert!(!diff.same_content);
+        assert!(same(&diff, "cid"));
+    }
+}
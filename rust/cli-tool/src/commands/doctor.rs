@@ -0,0 +1,333 @@
+use crate::chain::{ChainClient, SuiRpcChain};
+use crate::config::{Config, IpfsBackend};
+use crate::sdk::{build_http_client, DEFAULT_CLOCK_SKEW_TOLERANCE_MS};
+use crate::storage::PinningStore;
+use crate::theme::theme;
+use crate::utils::{current_timestamp_ms, load_private_key};
+use anyhow::{Context, Result};
+use clap::Args;
+use reqwest::Client;
+use serde::Serialize;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+/// How long each network check may take before it counts as failed
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Args)]
+pub struct DoctorArgs {
+    /// Output format (defaults to the configured format for this command)
+    #[arg(long)]
+    pub format: Option<String>,
+}
+
+/// Outcome of one setup check
+#[derive(Debug, Serialize)]
+pub struct DoctorCheck {
+    pub name: &'static str,
+    pub passed: bool,
+    /// Whether a failure makes `doctor` exit with an error
+    pub critical: bool,
+    pub detail: String,
+}
+
+impl DoctorCheck {
+    fn new(name: &'static str, critical: bool, result: Result<String>) -> Self {
+        let (passed, detail) = match result {
+            Ok(detail) => (true, detail),
+            Err(e) => (false, format!("{e:#}")),
+        };
+        Self {
+            name,
+            passed,
+            critical,
+            detail,
+        }
+    }
+}
+
+/// Check the configuration and the services it points at, failing if any
+/// critical check fails
+pub async fn handle_doctor(args: DoctorArgs, config: &Config) -> Result<()> {
+    let format = config.output_format_for("doctor", args.format.as_deref());
+    let checks = run_checks(config).await?;
+
+    match format.as_str() {
+        "json" => {
+            println!("{}", serde_json::to_string_pretty(&checks)?);
+        }
+        _ => display_checks(&checks, config.quiet),
+    }
+
+    let failed = checks
+        .iter()
+        .filter(|check| check.critical && !check.passed)
+        .count();
+    if failed > 0 {
+        anyhow::bail!("{failed} critical doctor checks failed");
+    }
+    Ok(())
+}
+
+/// Run every check
+///
+/// Checking the private key prompts for the keystore passphrase when the key
+/// is kept there and `CAPSULE_KEY_PASSPHRASE` is not set.
+pub async fn run_checks(config: &Config) -> Result<Vec<DoctorCheck>> {
+    let client = build_http_client(config)?;
+    let (rpc, chain_now) = check_rpc(&client, config).await;
+    let tolerance_ms = config
+        .clock_skew_tolerance_ms
+        .unwrap_or(DEFAULT_CLOCK_SKEW_TOLERANCE_MS);
+
+    Ok(vec![
+        check_config_file(config),
+        rpc,
+        check_ipfs(&client, config).await,
+        DoctorCheck::new("Private key", false, check_private_key(config)),
+        DoctorCheck::new(
+            "Clock",
+            false,
+            check_clock(current_timestamp_ms(), chain_now, tolerance_ms),
+        ),
+    ])
+}
+
+fn check_config_file(config: &Config) -> DoctorCheck {
+    let result = match &config.config_file {
+        Some(path) => Config::load_from_file(path).map(|_| format!("{} parses", path.display())),
+        None => Ok("No config file found; using defaults".to_string()),
+    };
+    DoctorCheck::new("Config file", true, result)
+}
+
+/// Fetch the chain's time from the RPC endpoint, which also shows it is up
+async fn check_rpc(client: &Client, config: &Config) -> (DoctorCheck, Option<u64>) {
+    let rpc_url = config.get_rpc_url();
+    let chain = SuiRpcChain::new(client.clone(), &rpc_url);
+    let started = Instant::now();
+    let result = with_timeout(&rpc_url, chain.latest_timestamp_ms()).await;
+    let elapsed_ms = started.elapsed().as_millis();
+
+    let chain_now = result.as_ref().ok().copied();
+    let result = result.map(|_| format!("{rpc_url} answered in {elapsed_ms} ms"));
+    (DoctorCheck::new("RPC endpoint", true, result), chain_now)
+}
+
+async fn check_ipfs(client: &Client, config: &Config) -> DoctorCheck {
+    let result = match config.pinning_service {
+        IpfsBackend::ApiNode => with_timeout(
+            &config.ipfs_url,
+            ipfs_node_version(client, &config.ipfs_url),
+        )
+        .await
+        .map(|version| format!("{} runs IPFS {version}", config.ipfs_url)),
+        IpfsBackend::Pinata { ref jwt } => {
+            with_timeout("Pinata", PinningStore::pinata(client.clone(), jwt).ping())
+                .await
+                .map(|()| "Pinata accepted the JWT".to_string())
+        }
+        IpfsBackend::Web3Storage { ref token } => with_timeout(
+            "web3.storage",
+            PinningStore::web3_storage(client.clone(), token).ping(),
+        )
+        .await
+        .map(|()| "web3.storage answered".to_string()),
+    };
+    DoctorCheck::new("IPFS endpoint", true, result)
+}
+
+/// Ask an IPFS node's HTTP API for its version
+async fn ipfs_node_version(client: &Client, url: &str) -> Result<String> {
+    let response: serde_json::Value = client
+        .post(format!("{}/api/v0/version", url.trim_end_matches('/')))
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .with_context(|| format!("Failed to reach the IPFS API at {url}"))?
+        .json()
+        .await
+        .with_context(|| format!("Invalid version response from {url}"))?;
+    response["Version"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| anyhow::anyhow!("{url} did not report an IPFS version"))
+}
+
+fn check_private_key(config: &Config) -> Result<String> {
+    let source = if let Some(path) = &config.private_key_path {
+        format!("read from {}", path.display())
+    } else if config.private_key.is_some() {
+        "set inline".to_string()
+    } else {
+        format!("decrypted from {}", config.keystore_path().display())
+    };
+    if load_private_key(config)?.is_empty() {
+        anyhow::bail!("Private key is empty");
+    }
+    Ok(format!("Private key {source}"))
+}
+
+/// Compare the local clock with the time of the chain's latest checkpoint
+fn check_clock(local_now: u64, chain_now: Option<u64>, tolerance_ms: u64) -> Result<String> {
+    let chain_now =
+        chain_now.ok_or_else(|| anyhow::anyhow!("Cannot compare without the chain's time"))?;
+    let skew_ms = local_now.abs_diff(chain_now);
+    if skew_ms > tolerance_ms {
+        let (direction, judged) = if local_now > chain_now {
+            ("ahead of", "early")
+        } else {
+            ("behind", "late")
+        };
+        anyhow::bail!(
+            "Local clock is {:.1} s {direction} the chain's, so time capsules may be judged ready too {judged}",
+            skew_ms as f64 / 1000.0
+        );
+    }
+    Ok(format!("Within {skew_ms} ms of the chain"))
+}
+
+async fn with_timeout<T>(target: &str, check: impl Future<Output = Result<T>>) -> Result<T> {
+    tokio::time::timeout(CHECK_TIMEOUT, check)
+        .await
+        .map_err(|_| {
+            anyhow::anyhow!(
+                "{target} did not answer within {} s",
+                CHECK_TIMEOUT.as_secs()
+            )
+        })?
+}
+
+fn display_checks(checks: &[DoctorCheck], quiet: bool) {
+    if !quiet {
+        println!("{}", theme().heading("Capsule Doctor"));
+        println!("{}", "=".repeat(50));
+    }
+
+    for check in checks {
+        match (check.passed, check.critical) {
+            (true, _) => println!("{} {}: {}", theme().success("✓"), check.name, check.detail),
+            (false, true) => println!(
+                "{} {}: {}",
+                theme().error("✗"),
+                check.name,
+                theme().error(&check.detail)
+            ),
+            (false, false) => println!(
+                "{} {}: {}",
+                theme().warning("⚠"),
+                check.name,
+                theme().warning(&check.detail)
+            ),
+        }
+    }
+
+    let passed = checks.iter().filter(|check| check.passed).count();
+    println!("\n{}/{} checks passed", passed, checks.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn check<'a>(checks: &'a [DoctorCheck], name: &str) -> &'a DoctorCheck {
+        checks.iter().find(|check| check.name == name).unwrap()
+    }
+
+    /// An RPC node and IPFS API answering on the same mock server
+    async fn mock_services() -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(
+                json!({"method": "sui_getLatestCheckpointSequenceNumber"}),
+            ))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({"jsonrpc": "2.0", "id": 1, "result": "7"})),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({"method": "sui_getCheckpoint"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {"timestampMs": current_timestamp_ms().to_string()}
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v0/version"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "Version": "0.29.0",
+                "Commit": "",
+                "Repo": "16",
+                "System": "amd64/linux",
+                "Golang": "go1.22.4"
+            })))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    #[tokio::test]
+    async fn test_checks_follow_services_up_and_down() {
+        let temp_dir = TempDir::new().unwrap();
+        let server = mock_services().await;
+        let config = Config {
+            rpc_url: Some(server.uri()),
+            ipfs_url: server.uri(),
+            config_dir: Some(temp_dir.path().to_path_buf()),
+            ..Config::default()
+        };
+
+        let checks = run_checks(&config).await.unwrap();
+        assert!(check(&checks, "Config file").passed);
+        assert!(check(&checks, "RPC endpoint").passed);
+        let ipfs = check(&checks, "IPFS endpoint");
+        assert!(ipfs.passed && ipfs.detail.contains("0.29.0"), "{ipfs:?}");
+        assert!(check(&checks, "Clock").passed);
+        // No key anywhere is reported, but is not critical
+        let key = check(&checks, "Private key");
+        assert!(!key.passed && !key.critical);
+
+        // A port nothing listens on
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let down = format!("http://127.0.0.1:{port}");
+        let config = Config {
+            rpc_url: Some(down.clone()),
+            ipfs_url: down,
+            private_key: Some("suiprivkey1test".to_string()),
+            ..config
+        };
+
+        let checks = run_checks(&config).await.unwrap();
+        for name in ["RPC endpoint", "IPFS endpoint"] {
+            let failed = check(&checks, name);
+            assert!(!failed.passed && failed.critical, "{failed:?}");
+        }
+        assert!(!check(&checks, "Clock").passed);
+        assert!(check(&checks, "Private key").passed);
+    }
+
+    #[test]
+    fn test_clock_check() {
+        let now = 1_700_000_000_000;
+        assert!(check_clock(now, Some(now - 2_000), 30_000).is_ok());
+
+        let ahead = check_clock(now, Some(now - 60_000), 30_000).unwrap_err();
+        assert!(ahead.to_string().contains("60.0 s ahead of"), "{ahead}");
+        let behind = check_clock(now, Some(now + 60_000), 30_000).unwrap_err();
+        assert!(behind.to_string().contains("behind"), "{behind}");
+
+        assert!(check_clock(now, None, 30_000).is_err());
+    }
+}
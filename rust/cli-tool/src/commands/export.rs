@@ -0,0 +1,86 @@
+use crate::config::Config;
+use crate::records::{CapsuleRecords, ExportBundle};
+use crate::temp::write_via_temp;
+use crate::theme::theme;
+use anyhow::{Context, Result};
+use clap::Args;
+use dialoguer::Password;
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct ExportArgs {
+    /// File to write the bundle to
+    #[arg(short, long)]
+    pub output: PathBuf,
+    /// Encrypt the bundle with a password (prompted for unless --password is given)
+    #[arg(long)]
+    pub encrypt: bool,
+    /// Password for --encrypt
+    #[arg(long, requires = "encrypt")]
+    pub password: Option<String>,
+    /// Overwrite the output file if it exists
+    #[arg(long)]
+    pub force: bool,
+}
+
+pub async fn handle_export(args: ExportArgs, config: &Config) -> Result<()> {
+    let records = capsule_records(config)?;
+    if args.output.exists() && !args.force {
+        anyhow::bail!(
+            "Output file already exists: {}. Use --force to overwrite.",
+            args.output.display()
+        );
+    }
+
+    let password = match (args.encrypt, args.password) {
+        (false, _) => None,
+        (true, Some(password)) => Some(password),
+        (true, None) => Some(
+            Password::new()
+                .with_prompt("Bundle password")
+                .with_confirmation("Confirm password", "Passwords do not match")
+                .interact()?,
+        ),
+    };
+    if password.as_deref() == Some("") {
+        anyhow::bail!("Password cannot be empty");
+    }
+
+    let bundle = ExportBundle::new(records.read()?);
+    let encoded = bundle.encode(password.as_deref())?;
+    write_via_temp(&config.temp_dir(), &args.output, &encoded)
+        .with_context(|| format!("Failed to write bundle: {}", args.output.display()))?;
+
+    println!(
+        "{} Exported {} capsules to {}{}",
+        theme().success("✓"),
+        bundle.capsules.len(),
+        args.output.display(),
+        if password.is_some() {
+            " (encrypted)"
+        } else {
+            ""
+        }
+    );
+    let holds_keys = bundle
+        .capsules
+        .iter()
+        .any(|record| record.encryption_key.is_some());
+    if password.is_none() && holds_keys {
+        println!(
+            "{}",
+            theme()
+                .warning("⚠️  The bundle holds encryption keys in the clear; consider --encrypt")
+        );
+    }
+    Ok(())
+}
+
+/// The record store export and import work on
+pub(crate) fn capsule_records(config: &Config) -> Result<CapsuleRecords> {
+    config
+        .capsule_records_path
+        .clone()
+        .map(CapsuleRecords::new)
+        .ok_or_else(|| anyhow::anyhow!("No capsule record store is configured"))
+}
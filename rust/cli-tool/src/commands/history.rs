@@ -0,0 +1,107 @@
+use crate::audit::{default_audit_log_path, AuditFilter, AuditLog, AuditOperation, AuditRecord};
+use crate::config::Config;
+use crate::theme::{style, theme};
+use crate::utils::{current_timestamp_ms, format_timestamp, parse_duration, truncate_string};
+use anyhow::Result;
+use clap::Args;
+
+#[derive(Args)]
+pub struct HistoryArgs {
+    /// Only show operations from this recent period (e.g., "1h", "7d")
+    #[arg(long)]
+    pub since: Option<String>,
+    /// Filter by operation type (create, approve, unlock, payment, revoke, reshare, revoke-approval,
+    /// cancel)
+    #[arg(short = 't', long = "type")]
+    pub operation: Option<String>,
+    /// Output format (defaults to the configured format for this command)
+    #[arg(long)]
+    pub format: Option<String>,
+}
+
+pub async fn handle_history(args: HistoryArgs, config: &Config) -> Result<()> {
+    let format = config.output_format_for("history", args.format.as_deref());
+    let filter = AuditFilter {
+        since: match args.since {
+            Some(ref since) => Some(current_timestamp_ms().saturating_sub(parse_duration(since)?)),
+            None => None,
+        },
+        operation: args
+            .operation
+            .as_deref()
+            .map(str::parse::<AuditOperation>)
+            .transpose()?,
+    };
+
+    let audit_log = AuditLog::new(
+        config
+            .audit_log_path
+            .clone()
+            .unwrap_or_else(default_audit_log_path),
+    );
+    let records = audit_log.read(&filter)?;
+
+    match format.as_str() {
+        "json" => {
+            println!("{}", serde_json::to_string_pretty(&records)?);
+        }
+        "csv" => {
+            println!("timestamp,operation,capsule_id,transaction_digest,network");
+            for record in &records {
+                println!(
+                    "{},{},{},{},{}",
+                    record.timestamp,
+                    record.operation,
+                    record.capsule_id,
+                    record.transaction_digest.as_deref().unwrap_or_default(),
+                    record.network
+                );
+            }
+        }
+        _ => {
+            if !config.quiet {
+                println!("{}", theme().heading("Operation History"));
+                println!("{}", "=".repeat(50));
+                println!("Audit log: {}", audit_log.path().display());
+            }
+            display_table(&records);
+        }
+    }
+
+    Ok(())
+}
+
+fn display_table(records: &[AuditRecord]) {
+    if records.is_empty() {
+        println!("\n{}", theme().dim("No operations recorded."));
+        return;
+    }
+
+    println!(
+        "\n{:<25} {:<10} {:<20} {:<20} {:<10}",
+        style("Time").bold(),
+        style("Operation").bold(),
+        style("Capsule ID").bold(),
+        style("Transaction").bold(),
+        style("Network").bold()
+    );
+    println!("{}", "-".repeat(85));
+
+    for record in records {
+        let digest = record.transaction_digest.as_deref().unwrap_or("-");
+        println!(
+            "{:<25} {:<10} {:<20} {:<20} {:<10}",
+            format_timestamp(record.timestamp),
+            record.operation,
+            theme().id(truncate_string(&record.capsule_id, 18)),
+            truncate_string(digest, 18),
+            record.network
+        );
+    }
+
+    println!(
+        "\n{} operation{} found",
+        style(records.len()).bold(),
+        if records.len() == 1 { "" } else { "s" }
+    );
+}
@@ -0,0 +1,52 @@
+use crate::commands::export::capsule_records;
+use crate::config::Config;
+use crate::records::ExportBundle;
+use crate::theme::theme;
+use anyhow::{Context, Result};
+use clap::Args;
+use dialoguer::Password;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct ImportArgs {
+    /// Bundle written by `capsule export`
+    pub bundle: PathBuf,
+    /// Password for an encrypted bundle (prompted for when needed otherwise)
+    #[arg(long)]
+    pub password: Option<String>,
+}
+
+pub async fn handle_import(args: ImportArgs, config: &Config) -> Result<()> {
+    let records = capsule_records(config)?;
+    let data = fs::read(&args.bundle)
+        .with_context(|| format!("Failed to read bundle: {}", args.bundle.display()))?;
+
+    let password = match args.password {
+        Some(password) => Some(password),
+        None if ExportBundle::is_encrypted(&data) => {
+            Some(Password::new().with_prompt("Bundle password").interact()?)
+        }
+        None => None,
+    };
+    let bundle = ExportBundle::decode(&data, password.as_deref())?;
+
+    let added = records.merge(&bundle.capsules)?;
+    println!(
+        "{} Imported {} of {} capsules into {}",
+        theme().success("✓"),
+        added,
+        bundle.capsules.len(),
+        records.path().display()
+    );
+    if added < bundle.capsules.len() {
+        println!(
+            "{}",
+            theme().dim(format!(
+                "{} already known capsules kept their local records",
+                bundle.capsules.len() - added
+            ))
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use crate::config::Config;
+use crate::keystore::{keystore_passphrase, write_keystore};
+use crate::theme::theme;
+use anyhow::{Context, Result};
+use clap::{Args, Subcommand};
+use dialoguer::Password;
+use encryptor_wasi::Zeroizing;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct KeyArgs {
+    #[command(subcommand)]
+    pub command: KeyCommand,
+}
+
+#[derive(Subcommand)]
+pub enum KeyCommand {
+    /// Store a private key in the keystore, encrypted with a passphrase
+    Import(KeyImportArgs),
+}
+
+#[derive(Args)]
+pub struct KeyImportArgs {
+    /// Read the private key from this file instead of prompting for it
+    #[arg(short, long)]
+    pub file: Option<PathBuf>,
+    /// Replace an existing keystore
+    #[arg(long)]
+    pub force: bool,
+}
+
+pub fn handle_key(args: KeyArgs, config: &Config) -> Result<()> {
+    match args.command {
+        KeyCommand::Import(args) => import_key(args, config),
+    }
+}
+
+fn import_key(args: KeyImportArgs, config: &Config) -> Result<()> {
+    let keystore_path = config.keystore_path();
+    if keystore_path.exists() && !args.force {
+        anyhow::bail!(
+            "Keystore already exists: {}. Use --force to replace it.",
+            keystore_path.display()
+        );
+    }
+
+    let private_key = Zeroizing::new(match &args.file {
+        Some(path) => fs::read_to_string(path)
+            .with_context(|| format!("Failed to read private key from: {}", path.display()))?
+            .trim()
+            .to_string(),
+        None => Password::new().with_prompt("Private key").interact()?,
+    });
+    if private_key.is_empty() {
+        anyhow::bail!("Private key cannot be empty");
+    }
+    let passphrase = keystore_passphrase(true)?;
+    if passphrase.is_empty() {
+        anyhow::bail!("Passphrase cannot be empty");
+    }
+
+    write_keystore(
+        &config.temp_dir(),
+        &keystore_path,
+        &private_key,
+        &passphrase,
+    )?;
+
+    println!(
+        "{} Private key stored encrypted in {}",
+        theme().success("✓"),
+        keystore_path.display()
+    );
+    if let Some(path) = &args.file {
+        println!(
+            "{}",
+            theme().warning(format!(
+                "⚠️  {} still holds the key in the clear; delete it once the keystore works",
+                path.display()
+            ))
+        );
+    }
+    if config.private_key_path.is_some() || config.private_key.is_some() {
+        println!(
+            "{}",
+            theme().warning(
+                "⚠️  A configured private key takes precedence; remove it to use the keystore"
+            )
+        );
+    }
+    Ok(())
+}
@@ -1,9 +1,15 @@
 use crate::config::Config;
+use crate::records::CapsuleRecord;
 use crate::sdk::{create_spinner, CapsuleStatus};
-use crate::utils::{format_file_size, format_timestamp, init_sdk, truncate_string};
+use crate::theme::{style, theme};
+use crate::utils::{
+    current_timestamp_ms, format_file_size, format_timestamp_in, init_sdk, parse_duration,
+    truncate_string,
+};
 use anyhow::Result;
 use clap::Args;
-use console::style;
+use serde::Serialize;
+use std::io::Write;
 
 #[derive(Args)]
 pub struct ListArgs {
@@ -19,29 +25,86 @@ pub struct ListArgs {
     /// Maximum number of results to return
     #[arg(short, long, default_value = "50")]
     pub limit: u32,
-    /// Skip this many results (for pagination)
-    #[arg(long, default_value = "0")]
+    /// Skip this many results (deprecated: use --cursor, which does not skip or
+    /// repeat capsules created while paging)
+    #[arg(long, default_value = "0", conflicts_with = "cursor")]
     pub offset: u32,
-    /// Output format
-    #[arg(long, default_value = "human")]
-    pub format: String,
+    /// Continue from where a previous page ended
+    #[arg(long, conflicts_with = "local")]
+    pub cursor: Option<String>,
+    /// Output format: human, json, jsonl (one capsule per line), csv, toml
+    /// or yaml (defaults to the configured format for this command)
+    #[arg(long)]
+    pub format: Option<String>,
     /// Show detailed information
     #[arg(short, long)]
     pub detailed: bool,
+    /// Only show capsules older than this, or overdue for unlock by more than this (e.g., "30d")
+    #[arg(long)]
+    pub stale: Option<String>,
+    /// Show times in the local timezone instead of UTC (JSON and CSV keep epoch milliseconds)
+    #[arg(long)]
+    pub local_time: bool,
+    /// List capsules from the local index of created ones, without the network
+    #[arg(long)]
+    pub local: bool,
+}
+
+/// A capsule as printed by `list --format json`, with its computed age
+#[derive(Serialize)]
+struct ListedCapsule<'a> {
+    #[serde(flatten)]
+    capsule: &'a CapsuleStatus,
+    age_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    overdue_ms: Option<u64>,
+}
+
+impl<'a> ListedCapsule<'a> {
+    fn new(capsule: &'a CapsuleStatus, now: u64) -> Self {
+        Self {
+            capsule,
+            age_ms: capsule.age_ms(now),
+            overdue_ms: capsule.overdue_ms(now),
+        }
+    }
 }
 
 pub async fn handle_list(args: ListArgs, config: &Config) -> Result<()> {
-    println!("{}", style("Listing Time Capsules").bold().cyan());
-    println!("{}", "=".repeat(50));
+    let format = config.output_format_for("list", args.format.as_deref());
+    // JSON Lines output is only capsules, so every stdout line parses
+    if format != "jsonl" && !config.quiet {
+        println!("{}", theme().heading("Listing Time Capsules"));
+        println!("{}", "=".repeat(50));
+    }
+
+    let stale_after = args.stale.as_deref().map(parse_duration).transpose()?;
+
+    if args.local {
+        let sdk = init_sdk(config).await?;
+        let records = sdk.load_local_capsules()?;
+        let mut capsules = filter_local(records.iter().map(CapsuleRecord::to_status), &args);
+        if let Some(max_age_ms) = stale_after {
+            capsules = filter_stale(capsules, current_timestamp_ms(), max_age_ms);
+        }
+        let local_time = args.local_time || config.local_time;
+        return display_capsules(&capsules, &format, args.detailed, stale_after, local_time);
+    }
 
     // Initialize SDK
     let spinner = create_spinner("Fetching capsules...");
     let sdk = init_sdk(config).await?;
 
     // Build query parameters
-    let mut query = crate::sdk::CapsuleQuery::new()
-        .with_limit(args.limit)
-        .with_offset(args.offset);
+    let mut query = crate::sdk::CapsuleQuery::new().with_limit(args.limit);
+    if let Some(cursor) = &args.cursor {
+        query = query.with_cursor(cursor);
+    } else if args.offset > 0 {
+        #[allow(deprecated)]
+        {
+            query = query.with_offset(args.offset);
+        }
+    }
 
     if let Some(capsule_type) = &args.capsule_type {
         query = query.with_type(capsule_type);
@@ -56,34 +119,97 @@ pub async fn handle_list(args: ListArgs, config: &Config) -> Result<()> {
     }
 
     // Fetch capsules
-    let capsules = sdk.list_capsules(query).await?;
+    let page = sdk.list_capsules(query).await?;
+    let mut capsules = page.capsules;
     spinner.finish_with_message(format!("Found {} capsules ✓", capsules.len()));
 
+    if let Some(max_age_ms) = stale_after {
+        capsules = filter_stale(capsules, current_timestamp_ms(), max_age_ms);
+    }
+
     // Display results
-    display_capsules(&capsules, &args)?;
+    let local_time = args.local_time || config.local_time;
+    display_capsules(&capsules, &format, args.detailed, stale_after, local_time)?;
+
+    if let Some(next_cursor) = page.next_cursor {
+        // Keep stdout parseable for machine-readable formats
+        if matches!(format.as_str(), "json" | "jsonl" | "csv" | "toml" | "yaml") {
+            eprintln!("next_cursor: {next_cursor}");
+        } else {
+            println!(
+                "\n{}",
+                theme().dim(format!(
+                    "More capsules available: capsule list --cursor {next_cursor}"
+                ))
+            );
+        }
+    }
 
     Ok(())
 }
 
-fn display_capsules(capsules: &[CapsuleStatus], args: &ListArgs) -> Result<()> {
+/// Apply the type, status and paging options to locally recorded capsules
+fn filter_local(
+    capsules: impl Iterator<Item = CapsuleStatus>,
+    args: &ListArgs,
+) -> Vec<CapsuleStatus> {
+    capsules
+        .filter(|capsule| {
+            args.capsule_type
+                .as_ref()
+                .is_none_or(|capsule_type| &capsule.capsule_type == capsule_type)
+        })
+        .filter(|capsule| {
+            args.status
+                .as_ref()
+                .is_none_or(|status| &capsule.status == status)
+        })
+        .skip(args.offset as usize)
+        .take(args.limit as usize)
+        .collect()
+}
+
+/// Keep only capsules that are stale as of `now`
+fn filter_stale(capsules: Vec<CapsuleStatus>, now: u64, max_age_ms: u64) -> Vec<CapsuleStatus> {
+    capsules
+        .into_iter()
+        .filter(|capsule| capsule.is_stale(now, max_age_ms))
+        .collect()
+}
+
+fn display_capsules(
+    capsules: &[CapsuleStatus],
+    format: &str,
+    detailed: bool,
+    stale_after: Option<u64>,
+    local_time: bool,
+) -> Result<()> {
+    if format == "jsonl" {
+        return write_json_lines(&mut std::io::stdout(), capsules, current_timestamp_ms());
+    }
     if capsules.is_empty() {
-        println!("\n{}", style("No capsules found.").dim());
+        println!("\n{}", theme().dim("No capsules found."));
         return Ok(());
     }
 
-    match args.format.as_str() {
+    match format {
         "json" => {
-            let json = serde_json::to_value(capsules)?;
-            println!("{}", serde_json::to_string_pretty(&json)?);
+            println!("{}", json_array(capsules, current_timestamp_ms())?);
         }
         "csv" => {
             display_csv(capsules)?;
         }
+        "toml" => {
+            print!("{}", toml_document(capsules, current_timestamp_ms())?);
+        }
+        "yaml" => {
+            print!("{}", yaml_document(capsules, current_timestamp_ms())?);
+        }
         _ => {
-            if args.detailed {
-                display_detailed(capsules)?;
+            if detailed {
+                display_detailed(capsules, stale_after, local_time)?;
             } else {
-                display_table(capsules)?;
+                display_table(capsules, stale_after, local_time)?;
             }
         }
     }
@@ -91,7 +217,53 @@ fn display_capsules(capsules: &[CapsuleStatus], args: &ListArgs) -> Result<()> {
     Ok(())
 }
 
-fn display_table(capsules: &[CapsuleStatus]) -> Result<()> {
+fn listed(capsules: &[CapsuleStatus], now: u64) -> Vec<ListedCapsule<'_>> {
+    capsules
+        .iter()
+        .map(|capsule| ListedCapsule::new(capsule, now))
+        .collect()
+}
+
+/// Capsules as one pretty-printed JSON array
+fn json_array(capsules: &[CapsuleStatus], now: u64) -> Result<String> {
+    Ok(serde_json::to_string_pretty(&listed(capsules, now))?)
+}
+
+/// Capsules as a TOML document; TOML has no top-level arrays, so they are
+/// listed under a `capsules` key
+fn toml_document(capsules: &[CapsuleStatus], now: u64) -> Result<String> {
+    #[derive(Serialize)]
+    struct Document<'a> {
+        capsules: Vec<ListedCapsule<'a>>,
+    }
+
+    Ok(toml::to_string_pretty(&Document {
+        capsules: listed(capsules, now),
+    })?)
+}
+
+/// Capsules as a YAML sequence
+fn yaml_document(capsules: &[CapsuleStatus], now: u64) -> Result<String> {
+    Ok(serde_yaml::to_string(&listed(capsules, now))?)
+}
+
+/// Write each capsule as a compact JSON object on its own line, flushing
+/// after every line so consumers see capsules as they are written
+fn write_json_lines(out: &mut impl Write, capsules: &[CapsuleStatus], now: u64) -> Result<()> {
+    for capsule in capsules {
+        serde_json::to_writer(&mut *out, &ListedCapsule::new(capsule, now))?;
+        writeln!(out)?;
+        out.flush()?;
+    }
+    Ok(())
+}
+
+/// Print capsules as a table, marking stale rows when a threshold is given
+pub(crate) fn display_table(
+    capsules: &[CapsuleStatus],
+    stale_after: Option<u64>,
+    local_time: bool,
+) -> Result<()> {
     println!(
         "\n{:<20} {:<12} {:<10} {:<15} {:<20}",
         style("Capsule ID").bold(),
@@ -102,51 +274,43 @@ fn display_table(capsules: &[CapsuleStatus]) -> Result<()> {
     );
     println!("{}", "-".repeat(80));
 
+    let now = current_timestamp_ms();
     for capsule in capsules {
         let id_short = truncate_string(&capsule.capsule_id, 18);
-        let created = format_timestamp(capsule.created_at);
+        let created = if capsule.error.is_some() {
+            "-".to_string()
+        } else {
+            format_timestamp_in(capsule.created_at, local_time)
+        };
         let created_short = truncate_string(&created, 13);
 
-        let unlock_info = match capsule.capsule_type.as_str() {
-            "time" => {
-                if let Some(unlock_time) = capsule.unlock_time {
-                    let time_str = format_timestamp(unlock_time);
-                    truncate_string(&time_str, 18)
-                } else {
-                    "Unknown".to_string()
-                }
-            }
-            "multisig" => {
-                if let Some(ref approvals) = capsule.approvals {
-                    format!("{}/{} approvals", approvals.current, approvals.required)
-                } else {
-                    "Unknown".to_string()
-                }
-            }
-            "payment" => {
-                if let Some(price) = capsule.price {
-                    format!("{price} MIST")
-                } else {
-                    "Unknown".to_string()
-                }
-            }
-            _ => "Unknown".to_string(),
-        };
+        let unlock_info = unlock_summary(capsule, local_time);
 
         let status_colored = match capsule.status.as_str() {
-            "locked" => style(&capsule.status).red(),
-            "unlocked" => style(&capsule.status).green(),
-            "ready" => style(&capsule.status).yellow(),
-            _ => style(&capsule.status).dim(),
+            "locked" => theme().error(&capsule.status),
+            "unlocked" => theme().success(&capsule.status),
+            "ready" => theme().warning(&capsule.status),
+            "revoked" => theme().dim(&capsule.status).italic(),
+            "error" => theme().error(&capsule.status).bold(),
+            _ => theme().dim(&capsule.status),
+        };
+
+        let marker = if stale_after.is_some_and(|max_age_ms| capsule.is_stale(now, max_age_ms)) {
+            theme().warning("stale".to_string())
+        } else if let Some(overdue) = capsule.overdue_ms(now) {
+            theme().dim(format!("overdue {}", format_duration_ms(overdue)))
+        } else {
+            theme().dim(String::new())
         };
 
         println!(
-            "{:<20} {:<12} {:<10} {:<15} {:<20}",
-            style(id_short).cyan(),
+            "{:<20} {:<12} {:<10} {:<15} {:<20} {}",
+            theme().id(id_short),
             capsule.capsule_type,
             status_colored,
             created_short,
-            unlock_info
+            unlock_info,
+            marker
         );
     }
 
@@ -159,34 +323,90 @@ fn display_table(capsules: &[CapsuleStatus]) -> Result<()> {
     Ok(())
 }
 
-fn display_detailed(capsules: &[CapsuleStatus]) -> Result<()> {
+/// Short description of what a capsule is waiting on, as shown in the table
+pub(crate) fn unlock_summary(capsule: &CapsuleStatus, local_time: bool) -> String {
+    match capsule.capsule_type.as_str() {
+        _ if capsule.error.is_some() => {
+            truncate_string(capsule.error.as_deref().unwrap_or_default(), 30)
+        }
+        "time" => {
+            if let Some(unlock_time) = capsule.unlock_time {
+                let time_str = format_timestamp_in(unlock_time, local_time);
+                truncate_string(&time_str, 18)
+            } else {
+                "Unknown".to_string()
+            }
+        }
+        "multisig" => {
+            if let Some(ref approvals) = capsule.approvals {
+                format!("{}/{} approvals", approvals.current, approvals.required)
+            } else {
+                "Unknown".to_string()
+            }
+        }
+        "payment" => {
+            if let Some(price) = capsule.price {
+                format!("{price} MIST")
+            } else {
+                "Unknown".to_string()
+            }
+        }
+        _ => "Unknown".to_string(),
+    }
+}
+
+pub(crate) fn display_detailed(
+    capsules: &[CapsuleStatus],
+    stale_after: Option<u64>,
+    local_time: bool,
+) -> Result<()> {
+    let now = current_timestamp_ms();
     for (i, capsule) in capsules.iter().enumerate() {
         if i > 0 {
             println!();
         }
 
-        println!("{}", style(format!("Capsule #{}", i + 1)).bold().cyan());
+        if stale_after.is_some_and(|max_age_ms| capsule.is_stale(now, max_age_ms)) {
+            println!(
+                "{} {}",
+                theme().heading(format!("Capsule #{}", i + 1)),
+                theme().warning("(stale)")
+            );
+        } else {
+            println!("{}", theme().heading(format!("Capsule #{}", i + 1)));
+        }
         println!("{}", "-".repeat(40));
 
         println!(
             "{} {}",
             style("ID:").bold(),
-            style(&capsule.capsule_id).cyan()
+            theme().id(&capsule.capsule_id)
         );
+        if let Some(ref error) = capsule.error {
+            println!("{} {}", style("Error:").bold(), theme().error(error));
+            continue;
+        }
+
         println!("{} {}", style("Type:").bold(), capsule.capsule_type);
 
         let status_colored = match capsule.status.as_str() {
-            "locked" => style(&capsule.status).red(),
-            "unlocked" => style(&capsule.status).green(),
-            "ready" => style(&capsule.status).yellow(),
-            _ => style(&capsule.status).dim(),
+            "locked" => theme().error(&capsule.status),
+            "unlocked" => theme().success(&capsule.status),
+            "ready" => theme().warning(&capsule.status),
+            "revoked" => theme().dim(&capsule.status).italic(),
+            _ => theme().dim(&capsule.status),
         };
         println!("{} {}", style("Status:").bold(), status_colored);
 
         println!(
             "{} {}",
             style("Created:").bold(),
-            format_timestamp(capsule.created_at)
+            format_timestamp_in(capsule.created_at, local_time)
+        );
+        println!(
+            "{} {}",
+            style("Age:").bold(),
+            format_duration_ms(capsule.age_ms(now))
         );
 
         if let Some(ref creator) = capsule.creator {
@@ -212,23 +432,29 @@ fn display_detailed(capsules: &[CapsuleStatus]) -> Result<()> {
                     println!(
                         "{} {}",
                         style("Unlock Time:").bold(),
-                        format_timestamp(unlock_time)
+                        format_timestamp_in(unlock_time, local_time)
                     );
 
-                    let now = crate::utils::current_timestamp_ms();
                     if unlock_time > now {
                         let remaining = unlock_time - now;
                         let remaining_str = format_duration_ms(remaining);
                         println!(
                             "{} {}",
                             style("Time Remaining:").bold(),
-                            style(remaining_str).yellow()
+                            theme().warning(remaining_str)
                         );
                     } else {
                         println!(
                             "{} {}",
                             style("Time Remaining:").bold(),
-                            style("Ready to unlock").green()
+                            theme().success("Ready to unlock")
+                        );
+                    }
+                    if let Some(overdue) = capsule.overdue_ms(now) {
+                        println!(
+                            "{} {}",
+                            style("Overdue By:").bold(),
+                            theme().warning(format_duration_ms(overdue))
                         );
                     }
                 }
@@ -238,8 +464,8 @@ fn display_detailed(capsules: &[CapsuleStatus]) -> Result<()> {
                     println!(
                         "{} {}/{}",
                         style("Approvals:").bold(),
-                        style(approvals.current).cyan(),
-                        style(approvals.required).cyan()
+                        theme().id(approvals.current),
+                        theme().id(approvals.required)
                     );
 
                     if !approvals.approvers.is_empty() {
@@ -252,7 +478,7 @@ fn display_detailed(capsules: &[CapsuleStatus]) -> Result<()> {
             }
             "payment" => {
                 if let Some(price) = capsule.price {
-                    println!("{} {} MIST", style("Price:").bold(), style(price).cyan());
+                    println!("{} {} MIST", style("Price:").bold(), theme().id(price));
                 }
             }
             _ => {}
@@ -272,8 +498,8 @@ fn display_detailed(capsules: &[CapsuleStatus]) -> Result<()> {
     Ok(())
 }
 
-fn display_csv(capsules: &[CapsuleStatus]) -> Result<()> {
-    println!("capsule_id,type,status,created_at,unlock_time,approvals_current,approvals_required,price,creator,content_size,cid");
+pub(crate) fn display_csv(capsules: &[CapsuleStatus]) -> Result<()> {
+    println!("capsule_id,type,status,created_at,unlock_time,approvals_current,approvals_required,price,creator,content_size,cid,error");
 
     for capsule in capsules {
         let unlock_time = capsule
@@ -298,9 +524,15 @@ fn display_csv(capsules: &[CapsuleStatus]) -> Result<()> {
             .map(|s| s.to_string())
             .unwrap_or_default();
         let cid = capsule.cid.as_ref().unwrap_or(&empty_string);
+        // Error messages are free text, so quote them
+        let error = capsule
+            .error
+            .as_ref()
+            .map(|e| format!("\"{}\"", e.replace('"', "\"\"")))
+            .unwrap_or_default();
 
         println!(
-            "{},{},{},{},{},{},{},{},{},{},{}",
+            "{},{},{},{},{},{},{},{},{},{},{},{}",
             capsule.capsule_id,
             capsule.capsule_type,
             capsule.status,
@@ -311,14 +543,15 @@ fn display_csv(capsules: &[CapsuleStatus]) -> Result<()> {
             price,
             creator,
             content_size,
-            cid
+            cid,
+            error
         );
     }
 
     Ok(())
 }
 
-fn format_duration_ms(duration_ms: u64) -> String {
+pub(crate) fn format_duration_ms(duration_ms: u64) -> String {
     let seconds = duration_ms / 1000;
     let minutes = seconds / 60;
     let hours = minutes / 60;
@@ -339,7 +572,7 @@ fn format_duration_ms(duration_ms: u64) -> String {
 pub async fn handle_list_interactive(config: &Config) -> Result<()> {
     use dialoguer::{Confirm, Input, Select};
 
-    println!("{}", style("Interactive Capsule Listing").bold().cyan());
+    println!("{}", theme().heading("Interactive Capsule Listing"));
     println!("{}", "=".repeat(50));
 
     // Filter by type
@@ -414,9 +647,144 @@ pub async fn handle_list_interactive(config: &Config) -> Result<()> {
         mine,
         limit,
         offset: 0,
-        format,
+        cursor: None,
+        format: Some(format),
         detailed,
+        stale: None,
+        local_time: false,
+        local: false,
     };
 
     handle_list(args, config).await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+    fn capsule(id: &str, status: &str, created_at: u64, unlock_time: Option<u64>) -> CapsuleStatus {
+        let mut capsule = CapsuleStatus::failed(id, String::new());
+        capsule.capsule_type = "time".to_string();
+        capsule.status = status.to_string();
+        capsule.created_at = created_at;
+        capsule.unlock_time = unlock_time;
+        capsule.error = None;
+        capsule
+    }
+
+    #[test]
+    fn test_filter_stale() {
+        let now = 100 * DAY_MS;
+        let capsules = vec![
+            // Created yesterday, still locked
+            capsule("0xfresh", "locked", now - DAY_MS, Some(now + DAY_MS)),
+            // Created 60 days ago
+            capsule("0xold", "locked", now - 60 * DAY_MS, Some(now + DAY_MS)),
+            // Ready for 40 days but never unlocked
+            capsule(
+                "0xoverdue",
+                "ready",
+                now - 45 * DAY_MS,
+                Some(now - 40 * DAY_MS),
+            ),
+            // Ready for 5 days only
+            capsule(
+                "0xrecent",
+                "ready",
+                now - 10 * DAY_MS,
+                Some(now - 5 * DAY_MS),
+            ),
+            CapsuleStatus::failed("0xmissing", "not found".to_string()),
+        ];
+
+        let stale: Vec<String> = filter_stale(capsules, now, 30 * DAY_MS)
+            .into_iter()
+            .map(|capsule| capsule.capsule_id)
+            .collect();
+        assert_eq!(stale, vec!["0xold", "0xoverdue"]);
+    }
+
+    #[test]
+    fn test_unlocked_capsule_is_not_overdue() {
+        let now = 100 * DAY_MS;
+        let unlocked = capsule(
+            "0xdone",
+            "unlocked",
+            now - 10 * DAY_MS,
+            Some(now - 5 * DAY_MS),
+        );
+        let ready = capsule(
+            "0xready",
+            "ready",
+            now - 10 * DAY_MS,
+            Some(now - 5 * DAY_MS),
+        );
+
+        assert_eq!(unlocked.overdue_ms(now), None);
+        assert_eq!(ready.overdue_ms(now), Some(5 * DAY_MS));
+        assert_eq!(ready.age_ms(now), 10 * DAY_MS);
+    }
+
+    #[test]
+    fn test_json_lines_match_json_array() {
+        let now = 100 * DAY_MS;
+        let capsules = vec![
+            capsule("0xlocked", "locked", now - DAY_MS, Some(now + DAY_MS)),
+            capsule("0xready", "ready", now - 3 * DAY_MS, Some(now - DAY_MS)),
+            CapsuleStatus::failed("0xmissing", "not found".to_string()),
+        ];
+
+        let array: Vec<serde_json::Value> =
+            serde_json::from_str(&json_array(&capsules, now).unwrap()).unwrap();
+
+        let mut out = Vec::new();
+        write_json_lines(&mut out, &capsules, now).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<serde_json::Value> = out
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(out.lines().count(), capsules.len());
+        assert_eq!(lines, array);
+        assert_eq!(lines[1]["overdue_ms"], DAY_MS);
+    }
+
+    #[test]
+    fn test_toml_and_yaml_round_trip() {
+        #[derive(serde::Deserialize)]
+        struct Document {
+            capsules: Vec<CapsuleStatus>,
+        }
+
+        let now = 100 * DAY_MS;
+        let mut multisig = capsule("0xmultisig", "locked", now - DAY_MS, None);
+        multisig.capsule_type = "multisig".to_string();
+        multisig.approvals = Some(crate::sdk::ApprovalInfo {
+            current: 1,
+            required: 2,
+            approvers: vec!["0xalice".to_string()],
+        });
+        let capsules = vec![
+            capsule("0xready", "ready", now - 3 * DAY_MS, Some(now - DAY_MS)),
+            multisig,
+            CapsuleStatus::failed("0xmissing", "not found".to_string()),
+        ];
+        let expected = serde_json::to_value(&capsules).unwrap();
+
+        let toml_out = toml_document(&capsules, now).unwrap();
+        let parsed: Document = toml::from_str(&toml_out).unwrap();
+        assert_eq!(serde_json::to_value(&parsed.capsules).unwrap(), expected);
+
+        let yaml_out = yaml_document(&capsules, now).unwrap();
+        let parsed: Vec<CapsuleStatus> = serde_yaml::from_str(&yaml_out).unwrap();
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), expected);
+
+        // The computed fields are written alongside the capsule's own
+        let values: serde_yaml::Value = serde_yaml::from_str(&yaml_out).unwrap();
+        assert_eq!(values[0]["overdue_ms"].as_u64(), Some(DAY_MS));
+        assert!(toml_out.contains("age_ms"));
+    }
+}
@@ -1,13 +1,46 @@
 pub mod approve;
 pub mod batch;
+pub mod bench;
+pub mod cancel;
 pub mod create;
+pub mod dashboard;
+pub mod diff;
+pub mod doctor;
+pub mod export;
+pub mod history;
+pub mod import;
+pub mod key;
 pub mod list;
+pub mod networks;
+pub mod reshare;
+pub mod revoke;
+pub mod selftest;
+pub mod status;
 pub mod unlock;
+pub mod verify;
+pub mod watch;
 
 pub use approve::{
-    handle_approve, handle_approve_interactive, handle_list_pending_approvals, ApproveArgs,
+    handle_approve, handle_approve_interactive, handle_list_pending_approvals,
+    handle_revoke_approval, ApproveArgs, RevokeApprovalArgs,
 };
 pub use batch::{handle_batch, handle_batch_interactive, BatchArgs};
+pub use bench::{handle_bench, BenchArgs};
+pub use cancel::{handle_cancel, CancelArgs};
 pub use create::{handle_create, CapsuleType, CreateArgs};
+pub use dashboard::{handle_dashboard, DashboardArgs};
+pub use diff::{handle_diff, DiffArgs};
+pub use doctor::{handle_doctor, DoctorArgs};
+pub use export::{handle_export, ExportArgs};
+pub use history::{handle_history, HistoryArgs};
+pub use import::{handle_import, ImportArgs};
+pub use key::{handle_key, KeyArgs};
 pub use list::{handle_list, handle_list_interactive, ListArgs};
+pub use networks::{handle_networks, NetworksArgs};
+pub use reshare::{handle_reshare, ReshareArgs};
+pub use revoke::{handle_revoke, RevokeArgs};
+pub use selftest::{handle_selftest, SelfTestArgs};
+pub use status::{handle_status, StatusArgs};
 pub use unlock::{handle_unlock, handle_unlock_interactive, UnlockArgs};
+pub use verify::{handle_verify, VerifyArgs};
+pub use watch::{handle_watch, WatchArgs};
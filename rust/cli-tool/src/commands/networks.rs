@@ -0,0 +1,124 @@
+use crate::config::{Config, Network};
+use crate::theme::{style, theme};
+use anyhow::Result;
+use clap::Args;
+use serde::Serialize;
+
+#[derive(Args)]
+pub struct NetworksArgs {
+    /// Output format (defaults to the configured format for this command)
+    #[arg(long)]
+    pub format: Option<String>,
+}
+
+/// A supported network and the endpoint the CLI would use for it
+#[derive(Debug, Serialize)]
+pub struct NetworkEndpoint {
+    pub network: Network,
+    pub rpc_url: String,
+    pub active: bool,
+}
+
+pub async fn handle_networks(args: NetworksArgs, config: &Config) -> Result<()> {
+    let format = config.output_format_for("networks", args.format.as_deref());
+    let endpoints = network_endpoints(config);
+
+    match format.as_str() {
+        "json" => {
+            println!("{}", serde_json::to_string_pretty(&endpoints)?);
+        }
+        _ => {
+            if !config.quiet {
+                println!("{}", theme().heading("Supported Networks"));
+                println!("{}", "=".repeat(50));
+                println!();
+            }
+            println!(
+                "{:<10} {}",
+                style("Network").bold(),
+                style("RPC URL").bold()
+            );
+            println!("{}", "-".repeat(50));
+
+            for endpoint in &endpoints {
+                let marker = if endpoint.active {
+                    theme().success(" (active)")
+                } else {
+                    theme().dim("")
+                };
+                println!(
+                    "{:<10} {}{}",
+                    endpoint.network,
+                    theme().id(&endpoint.rpc_url),
+                    marker
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolve every supported network's RPC URL with the current configuration
+pub fn network_endpoints(config: &Config) -> Vec<NetworkEndpoint> {
+    Network::ALL
+        .iter()
+        .map(|&network| NetworkEndpoint {
+            network,
+            rpc_url: config.rpc_url_for(network),
+            active: config.network == network.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lists_all_networks_with_default_urls() {
+        let endpoints = network_endpoints(&Config::default());
+        let listed: Vec<(String, &str, bool)> = endpoints
+            .iter()
+            .map(|e| (e.network.to_string(), e.rpc_url.as_str(), e.active))
+            .collect();
+
+        assert_eq!(
+            listed,
+            vec![
+                (
+                    "devnet".to_string(),
+                    "https://fullnode.devnet.sui.io:443",
+                    true
+                ),
+                (
+                    "testnet".to_string(),
+                    "https://fullnode.testnet.sui.io:443",
+                    false
+                ),
+                (
+                    "mainnet".to_string(),
+                    "https://fullnode.mainnet.sui.io:443",
+                    false
+                ),
+                ("localnet".to_string(), "http://127.0.0.1:9000", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_overrides_are_reflected() {
+        let config = Config {
+            network: "testnet".to_string(),
+            rpc_url: Some("https://rpc.example.com".to_string()),
+            localnet_port: Some(9123),
+            ..Config::default()
+        };
+        let endpoints = network_endpoints(&config);
+
+        assert_eq!(endpoints[1].rpc_url, "https://rpc.example.com");
+        assert!(endpoints[1].active);
+        assert_eq!(endpoints[0].rpc_url, "https://fullnode.devnet.sui.io:443");
+        assert_eq!(endpoints[3].rpc_url, "http://127.0.0.1:9123");
+    }
+}
@@ -0,0 +1,111 @@
+use crate::config::Config;
+use crate::sdk::{create_spinner, ReshareResult};
+use crate::shares::{split_key, KeyShare};
+use crate::theme::{style, theme};
+use crate::utils::init_sdk;
+use anyhow::{Context, Result};
+use clap::Args;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct ReshareArgs {
+    /// Capsule ID whose key shares to replace
+    #[arg(long)]
+    pub capsule_id: String,
+    /// An existing key share (repeatable); at least the old threshold is needed
+    #[arg(long = "share", value_name = "SHARE")]
+    pub shares: Vec<String>,
+    /// File with one existing key share per line
+    #[arg(long)]
+    pub shares_file: Option<PathBuf>,
+    /// Split the full encryption key instead of combining old shares
+    #[arg(short, long, conflicts_with_all = ["shares", "shares_file"])]
+    pub encryption_key: Option<String>,
+    /// Shares needed to reconstruct the key after resharing
+    #[arg(long)]
+    pub threshold: u8,
+    /// Number of new shares to produce
+    #[arg(long)]
+    pub count: u8,
+    /// Output format (defaults to the configured format for this command)
+    #[arg(long)]
+    pub format: Option<String>,
+}
+
+pub async fn handle_reshare(args: ReshareArgs, config: &Config) -> Result<()> {
+    let format = config.output_format_for("reshare", args.format.as_deref());
+    if !config.quiet {
+        println!("{}", theme().heading("Resharing Capsule Key"));
+        println!("{}", "=".repeat(50));
+    }
+
+    if !args.capsule_id.starts_with("0x") {
+        anyhow::bail!("Capsule ID must start with '0x'");
+    }
+
+    let old_shares = match args.encryption_key {
+        // A whole key is a single share that needs no others
+        Some(ref encryption_key) => split_key(encryption_key, 1, 1)?,
+        None => read_old_shares(&args)?,
+    };
+    if old_shares.is_empty() {
+        anyhow::bail!("Provide existing shares with --share or --shares-file, or --encryption-key");
+    }
+
+    let spinner = create_spinner("Reconstructing and splitting key...");
+    let sdk = init_sdk(config).await?;
+    let result = sdk
+        .reshare(&args.capsule_id, &old_shares, args.threshold, args.count)
+        .await;
+    spinner.finish_and_clear();
+    let result = result?;
+
+    display_reshare_result(&result, &format)
+}
+
+fn read_old_shares(args: &ReshareArgs) -> Result<Vec<KeyShare>> {
+    let mut encoded = args.shares.clone();
+    if let Some(ref shares_file) = args.shares_file {
+        let content = fs::read_to_string(shares_file)
+            .with_context(|| format!("Failed to read shares file: {}", shares_file.display()))?;
+        encoded.extend(
+            content
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(str::to_string),
+        );
+    }
+    encoded.iter().map(|share| share.parse()).collect()
+}
+
+fn display_reshare_result(result: &ReshareResult, format: &str) -> Result<()> {
+    match format {
+        "json" => {
+            println!("{}", serde_json::to_string_pretty(result)?);
+        }
+        _ => {
+            println!("\n{}", theme().success("Key Reshared Successfully!").bold());
+            println!(
+                "{} {}",
+                style("Capsule ID:").bold(),
+                theme().id(&result.capsule_id)
+            );
+            println!(
+                "{} {} of {}",
+                style("Threshold:").bold(),
+                result.threshold,
+                result.shares.len()
+            );
+            println!();
+            for (i, share) in result.shares.iter().enumerate() {
+                println!("{:>3}. {}", i + 1, theme().warning(share.to_string()));
+            }
+            println!("\n{}", theme().warning("⚠️  Important:").bold());
+            println!("Give each share to a different holder over a secure channel.");
+            println!("Old shares still reconstruct the key; make sure they are destroyed.");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,100 @@
+use crate::config::{Config, Network};
+use crate::sdk::{create_spinner, RevokeResult};
+use crate::theme::{style, theme};
+use crate::utils::init_sdk;
+use anyhow::Result;
+use clap::Args;
+use dialoguer::Confirm;
+
+#[derive(Args)]
+pub struct RevokeArgs {
+    /// Capsule ID to revoke
+    #[arg(long)]
+    pub capsule_id: String,
+    /// Revoke on mainnet without asking for confirmation
+    #[arg(short, long)]
+    pub yes: bool,
+    /// Output format (defaults to the configured format for this command)
+    #[arg(long)]
+    pub format: Option<String>,
+}
+
+pub async fn handle_revoke(args: RevokeArgs, config: &Config) -> Result<()> {
+    let format = config.output_format_for("revoke", args.format.as_deref());
+    if !config.quiet {
+        println!("{}", theme().heading("Revoking Capsule"));
+        println!("{}", "=".repeat(50));
+    }
+
+    if !args.capsule_id.starts_with("0x") {
+        anyhow::bail!("Capsule ID must start with '0x'");
+    }
+
+    // Revocation is permanent, so real funds and data get a second chance
+    if needs_confirmation(config, args.yes) {
+        let confirmed = Confirm::new()
+            .with_prompt(format!(
+                "Revoke {} on mainnet? It can never be unlocked afterwards",
+                args.capsule_id
+            ))
+            .default(false)
+            .interact()?;
+        if !confirmed {
+            println!("Revocation cancelled by user");
+            return Ok(());
+        }
+    }
+
+    let spinner = create_spinner("Submitting revocation...");
+    let sdk = init_sdk(config).await?;
+    let result = sdk.revoke(&args.capsule_id).await;
+    spinner.finish_and_clear();
+    let result = result?;
+
+    display_revoke_result(&result, &format)
+}
+
+fn needs_confirmation(config: &Config, yes: bool) -> bool {
+    !yes && config.network.parse::<Network>().ok() == Some(Network::Mainnet)
+}
+
+fn display_revoke_result(result: &RevokeResult, format: &str) -> Result<()> {
+    match format {
+        "json" => {
+            println!("{}", serde_json::to_string_pretty(result)?);
+        }
+        _ => {
+            println!(
+                "\n{}",
+                theme().success("Capsule Revoked Successfully!").bold()
+            );
+            println!(
+                "{} {}",
+                style("Capsule ID:").bold(),
+                theme().id(&result.capsule_id)
+            );
+            println!(
+                "{} {}",
+                style("Transaction:").bold(),
+                theme().id(&result.transaction_digest)
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_mainnet_asks_for_confirmation() {
+        let mainnet = Config {
+            network: "mainnet".to_string(),
+            ..Config::default()
+        };
+        assert!(needs_confirmation(&mainnet, false));
+        assert!(!needs_confirmation(&mainnet, true));
+        assert!(!needs_confirmation(&Config::default(), false));
+    }
+}
@@ -0,0 +1,55 @@
+use crate::config::Config;
+use crate::theme::theme;
+use anyhow::Result;
+use clap::Args;
+use encryptor_wasi::{run_selftest, SelfTestCheck};
+
+#[derive(Args)]
+pub struct SelfTestArgs {
+    /// Output format (defaults to the configured format for this command)
+    #[arg(long)]
+    pub format: Option<String>,
+}
+
+/// Run the encryptor's known-answer tests, failing if any check fails
+pub fn handle_selftest(args: SelfTestArgs, config: &Config) -> Result<()> {
+    let format = config.output_format_for("selftest", args.format.as_deref());
+    let checks = run_selftest();
+
+    match format.as_str() {
+        "json" => {
+            println!("{}", serde_json::to_string_pretty(&checks)?);
+        }
+        _ => display_checks(&checks, config.quiet),
+    }
+
+    let failed = checks.iter().filter(|check| !check.passed).count();
+    if failed > 0 {
+        anyhow::bail!("{} of {} self-test checks failed", failed, checks.len());
+    }
+
+    Ok(())
+}
+
+fn display_checks(checks: &[SelfTestCheck], quiet: bool) {
+    if !quiet {
+        println!("{}", theme().heading("Encryptor Self-Test"));
+        println!("{}", "=".repeat(50));
+    }
+
+    for check in checks {
+        if check.passed {
+            println!("{} {}", theme().success("✓"), check.name);
+        } else {
+            println!(
+                "{} {}: {}",
+                theme().error("✗"),
+                check.name,
+                theme().error(check.error.as_deref().unwrap_or("failed"))
+            );
+        }
+    }
+
+    let passed = checks.iter().filter(|check| check.passed).count();
+    println!("\n{}/{} checks passed", passed, checks.len());
+}
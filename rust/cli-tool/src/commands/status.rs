@@ -0,0 +1,221 @@
+use crate::commands::list::{display_csv, display_detailed, display_table};
+use crate::config::Config;
+use crate::sdk::{create_spinner, CapsuleSDK, CapsuleStatus};
+use crate::storage::UNAVAILABLE_CONTENT_HINTS;
+use crate::theme::theme;
+use crate::utils::init_sdk;
+use anyhow::{Context, Result};
+use clap::Args;
+use futures::stream::{self, StreamExt};
+use log::warn;
+use std::future::Future;
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct StatusArgs {
+    /// Capsule IDs to query (comma-separated)
+    #[arg(long, value_delimiter = ',', required_unless_present = "from_file")]
+    pub ids: Vec<String>,
+    /// File with one capsule ID per line
+    #[arg(long)]
+    pub from_file: Option<PathBuf>,
+    /// Maximum concurrent status queries
+    #[arg(long, default_value = "4")]
+    pub max_concurrent: usize,
+    /// Output format (defaults to the configured format for this command)
+    #[arg(long)]
+    pub format: Option<String>,
+    /// Show detailed information
+    #[arg(short, long)]
+    pub detailed: bool,
+    /// Check that each capsule's content can still be fetched from IPFS
+    #[arg(long)]
+    pub check_availability: bool,
+}
+
+pub async fn handle_status(args: StatusArgs, config: &Config) -> Result<()> {
+    let format = config.output_format_for("status", args.format.as_deref());
+    if !config.quiet {
+        println!("{}", theme().heading("Capsule Status"));
+        println!("{}", "=".repeat(50));
+    }
+
+    let ids = collect_ids(&args)?;
+    if args.max_concurrent == 0 {
+        anyhow::bail!("Max concurrent queries must be greater than 0");
+    }
+
+    let spinner = create_spinner(&format!("Fetching {} capsule statuses...", ids.len()));
+    let sdk = init_sdk(config).await?;
+    let sdk = &sdk;
+    let mut statuses = fetch_statuses(&ids, args.max_concurrent, |capsule_id| async move {
+        sdk.get_capsule_status(&capsule_id).await
+    })
+    .await;
+    if args.check_availability {
+        check_availability(sdk, &mut statuses, args.max_concurrent).await;
+    }
+
+    let failed = statuses.iter().filter(|s| s.error.is_some()).count();
+    spinner.finish_with_message(format!(
+        "Fetched {} statuses ({} failed) ✓",
+        statuses.len() - failed,
+        failed
+    ));
+
+    match format.as_str() {
+        "json" => {
+            println!("{}", serde_json::to_string_pretty(&statuses)?);
+        }
+        "csv" => display_csv(&statuses)?,
+        _ => {
+            if args.detailed {
+                display_detailed(&statuses, None, config.local_time)?;
+            } else {
+                display_table(&statuses, None, config.local_time)?;
+            }
+            display_unavailable(&statuses);
+        }
+    }
+
+    Ok(())
+}
+
+/// Record whether each capsule's CID can currently be fetched
+///
+/// Uses existence checks, not downloads. A check that fails leaves the
+/// answer unknown rather than claiming the content is gone.
+async fn check_availability(
+    sdk: &CapsuleSDK,
+    statuses: &mut [CapsuleStatus],
+    max_concurrent: usize,
+) {
+    stream::iter(statuses.iter_mut())
+        .for_each_concurrent(max_concurrent.max(1), |status| async move {
+            if let Some(cid) = status.cid.clone() {
+                match sdk.content_available(&cid).await {
+                    Ok(available) => status.content_available = Some(available),
+                    Err(e) => warn!("Could not check availability of {cid}: {e:#}"),
+                }
+            }
+        })
+        .await;
+}
+
+/// Flag capsules whose content was found to be unreachable, with what to try
+fn display_unavailable(statuses: &[CapsuleStatus]) {
+    let unavailable: Vec<&CapsuleStatus> = statuses
+        .iter()
+        .filter(|status| status.content_available == Some(false))
+        .collect();
+    if unavailable.is_empty() {
+        return;
+    }
+
+    println!(
+        "\n{}",
+        theme()
+            .warning(format!(
+                "{} capsule(s) with unreachable content:",
+                unavailable.len()
+            ))
+            .bold()
+    );
+    for status in unavailable {
+        println!(
+            "  • {} ({})",
+            theme().id(&status.capsule_id),
+            status.cid.as_deref().unwrap_or_default()
+        );
+    }
+    println!("\n{}", theme().warning("What to try:").bold());
+    for hint in UNAVAILABLE_CONTENT_HINTS {
+        println!("• {hint}");
+    }
+}
+
+/// Gather capsule IDs from --ids and --from-file, dropping blanks and repeats
+fn collect_ids(args: &StatusArgs) -> Result<Vec<String>> {
+    let mut raw: Vec<String> = args.ids.clone();
+    if let Some(ref path) = args.from_file {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read capsule ID file: {}", path.display()))?;
+        raw.extend(content.lines().map(str::to_string));
+    }
+
+    let mut ids: Vec<String> = Vec::with_capacity(raw.len());
+    for id in raw {
+        let id = id.trim();
+        if !id.is_empty() && !ids.iter().any(|existing| existing == id) {
+            ids.push(id.to_string());
+        }
+    }
+
+    if ids.is_empty() {
+        anyhow::bail!("No capsule IDs given");
+    }
+    Ok(ids)
+}
+
+/// Fetch statuses with bounded concurrency, keeping the input order
+///
+/// A failed lookup becomes an error row instead of failing the whole query.
+pub async fn fetch_statuses<F, Fut>(
+    ids: &[String],
+    max_concurrent: usize,
+    fetch: F,
+) -> Vec<CapsuleStatus>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<CapsuleStatus>>,
+{
+    stream::iter(ids.iter().cloned())
+        .map(|capsule_id| {
+            let lookup = fetch(capsule_id.clone());
+            async move {
+                lookup
+                    .await
+                    .unwrap_or_else(|e| CapsuleStatus::failed(&capsule_id, format!("{e:#}")))
+            }
+        })
+        .buffered(max_concurrent.max(1))
+        .collect()
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_missing_capsule_becomes_error_row() {
+        let ids = vec![
+            "0xaaa".to_string(),
+            "0xmissing".to_string(),
+            "0xbbb".to_string(),
+        ];
+
+        let statuses = fetch_statuses(&ids, 2, |capsule_id| async move {
+            if capsule_id == "0xmissing" {
+                anyhow::bail!("Capsule not found: {capsule_id}");
+            }
+            let mut status = CapsuleStatus::failed(&capsule_id, String::new());
+            status.status = "locked".to_string();
+            status.error = None;
+            Ok(status)
+        })
+        .await;
+
+        assert_eq!(statuses.len(), 3);
+        assert_eq!(statuses[0].capsule_id, "0xaaa");
+        assert_eq!(statuses[0].status, "locked");
+        assert_eq!(statuses[1].status, "error");
+        assert!(statuses[1]
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("Capsule not found"));
+        assert_eq!(statuses[2].capsule_id, "0xbbb");
+        assert!(statuses[2].error.is_none());
+    }
+}
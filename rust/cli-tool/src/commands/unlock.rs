@@ -1,11 +1,20 @@
+use crate::archive::extract_archive;
+use crate::commands::create::display_timings;
 use crate::config::Config;
-use crate::sdk::{create_progress_bar, create_spinner};
-use crate::utils::{init_sdk, write_file_content};
+use crate::inspect::{inspect_content, ContentInfo};
+use crate::sdk::{
+    create_progress_bar, create_spinner, CapsuleSDK, ClockSource, UnlockCondition,
+    UnlockFailureReason,
+};
+use crate::storage::UNAVAILABLE_CONTENT_HINTS;
+use crate::temp::write_via_temp;
+use crate::theme::{style, theme};
+use crate::utils::{format_timestamp, init_sdk};
 use anyhow::{Context, Result};
 use base64::Engine;
 use clap::Args;
-use console::style;
-use std::path::PathBuf;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
 
 #[derive(Args)]
 pub struct UnlockArgs {
@@ -13,25 +22,109 @@ pub struct UnlockArgs {
     #[arg(short, long)]
     pub capsule_id: String,
     /// Encryption key for the capsule
-    #[arg(short, long)]
-    pub encryption_key: String,
-    /// Output file path (optional, defaults to capsule_id.bin)
+    #[arg(short, long, required_unless_present = "check_only")]
+    pub encryption_key: Option<String>,
+    /// Output file path (optional, defaults to the preserved file name or capsule_id.bin)
     #[arg(short, long)]
     pub output: Option<PathBuf>,
+    /// Write into this directory under the preserved file name (or the capsule ID
+    /// with an extension matching the content)
+    #[arg(long, value_name = "DIR", conflicts_with_all = ["output", "to_clipboard"])]
+    pub output_dir: Option<PathBuf>,
     /// Payment amount for payment capsules (in MIST)
     #[arg(short, long)]
     pub payment: Option<u64>,
-    /// Output format
-    #[arg(long, default_value = "human")]
-    pub format: String,
+    /// Output format (defaults to the configured format for this command)
+    #[arg(long)]
+    pub format: Option<String>,
     /// Force overwrite existing output file
     #[arg(long)]
     pub force: bool,
+    /// Copy small text content to the clipboard instead of writing a file
+    #[arg(long)]
+    pub to_clipboard: bool,
+    /// Extract a multi-file capsule into this directory
+    #[arg(long, value_name = "DIR", conflicts_with_all = ["output", "output_dir", "to_clipboard"])]
+    pub extract: Option<PathBuf>,
+    /// Report the decrypted content's type and metadata without writing it
+    #[arg(long, conflicts_with_all = ["output", "output_dir", "to_clipboard", "extract"])]
+    pub inspect: bool,
+    /// Only check whether the capsule can be unlocked now; fails if it cannot
+    #[arg(
+        long,
+        conflicts_with_all = ["output", "output_dir", "to_clipboard", "extract", "inspect"]
+    )]
+    pub check_only: bool,
+    /// Print how long each stage of the unlock took
+    #[arg(long)]
+    pub timings: bool,
+}
+
+/// Result of `unlock --check-only`
+#[derive(Debug, Serialize)]
+struct UnlockCheck {
+    capsule_id: String,
+    capsule_type: String,
+    ready: bool,
+    reason: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    clock: Option<ClockSource>,
+}
+
+/// Largest decrypted payload that may be copied to the clipboard
+pub const CLIPBOARD_MAX_BYTES: usize = 64 * 1024;
+
+/// Destination for decrypted text, abstracted so the gating logic is testable
+pub trait ClipboardSink {
+    fn set_text(&mut self, text: &str) -> Result<()>;
+}
+
+/// System clipboard backed by `arboard`
+pub struct SystemClipboard;
+
+impl ClipboardSink for SystemClipboard {
+    fn set_text(&mut self, text: &str) -> Result<()> {
+        let mut clipboard = arboard::Clipboard::new().context("Failed to access clipboard")?;
+        clipboard
+            .set_text(text.to_string())
+            .context("Failed to copy content to clipboard")
+    }
+}
+
+/// Return the content as text if it is small enough and not binary
+pub fn clipboard_text(content: &[u8]) -> Result<&str> {
+    if content.len() > CLIPBOARD_MAX_BYTES {
+        anyhow::bail!(
+            "Content is too large for the clipboard ({}, max {}). Use --output instead.",
+            crate::utils::format_file_size(content.len() as u64),
+            crate::utils::format_file_size(CLIPBOARD_MAX_BYTES as u64)
+        );
+    }
+
+    let is_binary = |text: &str| {
+        text.chars()
+            .any(|c| c.is_control() && !matches!(c, '\n' | '\r' | '\t'))
+    };
+    match std::str::from_utf8(content) {
+        Ok(text) if !is_binary(text) => Ok(text),
+        _ => anyhow::bail!(
+            "Content is binary and cannot be copied to the clipboard. Use --output instead."
+        ),
+    }
+}
+
+/// Copy decrypted content to the clipboard, refusing binary or oversized payloads
+pub fn copy_to_clipboard(clipboard: &mut dyn ClipboardSink, content: &[u8]) -> Result<()> {
+    let text = clipboard_text(content)?;
+    clipboard.set_text(text)
 }
 
 pub async fn handle_unlock(args: UnlockArgs, config: &Config) -> Result<()> {
-    println!("{}", style("Unlocking Time Capsule").bold().cyan());
-    println!("{}", "=".repeat(50));
+    let format = config.output_format_for("unlock", args.format.as_deref());
+    if !config.quiet {
+        println!("{}", theme().heading("Unlocking Time Capsule"));
+        println!("{}", "=".repeat(50));
+    }
 
     // Initialize SDK
     let spinner = create_spinner("Initializing SDK...");
@@ -41,49 +134,158 @@ pub async fn handle_unlock(args: UnlockArgs, config: &Config) -> Result<()> {
     // Validate arguments
     validate_unlock_args(&args)?;
 
-    // Determine output path
-    let output_path = args
-        .output
-        .unwrap_or_else(|| PathBuf::from(format!("{}.bin", args.capsule_id)));
+    if args.check_only {
+        return check_unlockable(&sdk, &args.capsule_id, &format).await;
+    }
+    let encryption_key = args.encryption_key.as_deref().unwrap_or_default();
+
+    // Determine output path (clipboard mode only writes a file when one is requested)
+    let output_path = match args.output.clone() {
+        Some(path) => Some(path),
+        None if args.to_clipboard
+            || args.output_dir.is_some()
+            || args.extract.is_some()
+            || args.inspect =>
+        {
+            None
+        }
+        None => Some(PathBuf::from(format!("{}.bin", args.capsule_id))),
+    };
 
     // Check if output file exists
-    if output_path.exists() && !args.force {
-        anyhow::bail!(
-            "Output file already exists: {}. Use --force to overwrite.",
-            output_path.display()
-        );
+    if let Some(ref output_path) = output_path {
+        if output_path.exists() && !args.force {
+            anyhow::bail!(
+                "Output file already exists: {}. Use --force to overwrite.",
+                output_path.display()
+            );
+        }
     }
 
     println!(
         "\n{} Unlocking capsule: {}",
-        style("🔓").cyan(),
+        theme().id("🔓"),
         style(&args.capsule_id).bold()
     );
     if let Some(payment) = args.payment {
         println!("Payment amount: {payment} MIST");
     }
-    println!("Output file: {}", output_path.display());
+    if let Some(ref output_path) = output_path {
+        println!("Output file: {}", output_path.display());
+    }
+    if let Some(ref output_dir) = args.output_dir {
+        println!("Output directory: {}", output_dir.display());
+    }
+    if args.to_clipboard {
+        println!("Output: clipboard");
+    }
+    if let Some(ref extract_dir) = args.extract {
+        println!("Extract to: {}", extract_dir.display());
+    }
 
     // Create progress bar
     let pb = create_progress_bar(4, "Unlocking capsule...");
 
     // Unlock and decrypt the capsule
     let result = sdk
-        .unlock_and_decrypt(
-            &args.capsule_id,
-            &args.encryption_key,
-            args.payment,
-            Some(&pb),
-        )
+        .unlock_and_decrypt(&args.capsule_id, encryption_key, args.payment, Some(&pb))
         .await?;
 
     // Handle the result
     if result.success {
-        if let Some(ref content) = result.content {
-            // Write content to file
-            write_file_content(&output_path, content)
-                .context("Failed to write decrypted content to file")?;
-            display_unlock_success(&result, &output_path, content.len(), &args.format)?;
+        if result.content.is_some() {
+            // Metadata stored with `create --preserve-metadata` travels inside the plaintext
+            let (metadata, content) = result.content_and_metadata()?;
+            let stored_name = metadata.as_ref().map(|metadata| metadata.name.as_str());
+            let output_path = match (&args.output_dir, stored_name) {
+                (Some(output_dir), _) => {
+                    let content_type = result
+                        .content_type
+                        .clone()
+                        .unwrap_or_else(|| inspect_content(content, result.bundle).mime_type);
+                    let path = output_dir.join(output_file_name(
+                        &args.capsule_id,
+                        stored_name,
+                        &content_type,
+                    ));
+                    if path.exists() && !args.force {
+                        anyhow::bail!(
+                            "Output file already exists: {}. Use --force to overwrite.",
+                            path.display()
+                        );
+                    }
+                    Some(path)
+                }
+                (None, Some(_)) if args.output.is_none() && output_path.is_some() => {
+                    let path = PathBuf::from(output_file_name(
+                        &args.capsule_id,
+                        stored_name,
+                        "application/octet-stream",
+                    ));
+                    if path.exists() && !args.force {
+                        anyhow::bail!(
+                            "Output file already exists: {}. Use --force to overwrite.",
+                            path.display()
+                        );
+                    }
+                    Some(path)
+                }
+                _ => output_path,
+            };
+
+            if args.inspect {
+                display_content_info(
+                    &args.capsule_id,
+                    &inspect_content(content, result.bundle),
+                    &format,
+                )?;
+            }
+            if let Some(ref extract_dir) = args.extract {
+                if !result.bundle {
+                    anyhow::bail!(
+                        "Capsule does not contain a multi-file bundle. Use --output instead."
+                    );
+                }
+                let written = extract_archive(content, extract_dir, args.force, &config.temp_dir())
+                    .context("Failed to extract capsule bundle")?;
+                println!(
+                    "\n{} {}",
+                    theme().id("📦"),
+                    theme().success(format!(
+                        "Extracted {} files to {}",
+                        written.len(),
+                        extract_dir.display()
+                    ))
+                );
+                for path in &written {
+                    println!("  • {}", path.display());
+                }
+            }
+            if let Some(ref output_path) = output_path {
+                // Stage the plaintext so a failed write leaves nothing behind
+                write_via_temp(&config.temp_dir(), output_path, content)
+                    .context("Failed to write decrypted content to file")?;
+                if let Some(ref metadata) = metadata {
+                    metadata
+                        .apply(output_path)
+                        .context("Failed to restore file metadata")?;
+                }
+                display_unlock_success(&result, output_path, content.len(), &format, config.quiet)?;
+                if result.bundle {
+                    println!("Content is a multi-file bundle. Use --extract <DIR> to unpack it.");
+                }
+            }
+            if args.to_clipboard {
+                copy_to_clipboard(&mut SystemClipboard, content)?;
+                println!(
+                    "\n{} {}",
+                    theme().id("📋"),
+                    theme().success(format!(
+                        "Copied {} to the clipboard.",
+                        crate::utils::format_file_size(content.len() as u64)
+                    ))
+                );
+            }
         } else {
             anyhow::bail!("Unlock succeeded but no content was returned");
         }
@@ -92,9 +294,58 @@ pub async fn handle_unlock(args: UnlockArgs, config: &Config) -> Result<()> {
         anyhow::bail!("Failed to unlock capsule");
     }
 
+    if args.timings && format != "json" {
+        if let Some(ref metrics) = result.metrics {
+            display_timings(metrics);
+        }
+    }
+
     Ok(())
 }
 
+/// File name for unlocked content
+///
+/// The stored original name is used with any directory components stripped,
+/// so a name like `../escape` still lands in the output directory. Without a
+/// usable name, the capsule ID is used with an extension for `content_type`.
+pub(crate) fn output_file_name(
+    capsule_id: &str,
+    stored_name: Option<&str>,
+    content_type: &str,
+) -> String {
+    let stored = stored_name
+        .map(|name| name.replace('\\', "/"))
+        .and_then(|name| {
+            Path::new(&name)
+                .file_name()
+                .map(|file_name| file_name.to_string_lossy().into_owned())
+        });
+    match stored {
+        Some(name) => name,
+        None => format!("{capsule_id}.{}", extension_for(content_type)),
+    }
+}
+
+/// A file extension for a MIME type, preferring one named like its subtype
+///
+/// Generic types get their conventional extension rather than mime_guess's
+/// first (alphabetical) one.
+fn extension_for(content_type: &str) -> &'static str {
+    match content_type {
+        "text/plain" => return "txt",
+        "application/octet-stream" => return "bin",
+        _ => {}
+    }
+    let subtype = content_type.split_once('/').map(|(_, subtype)| subtype);
+    let extensions = mime_guess::get_mime_extensions_str(content_type).unwrap_or_default();
+    extensions
+        .iter()
+        .find(|extension| Some(**extension) == subtype)
+        .or_else(|| extensions.first())
+        .copied()
+        .unwrap_or("bin")
+}
+
 fn validate_unlock_args(args: &UnlockArgs) -> Result<()> {
     // Validate capsule ID format
     if args.capsule_id.is_empty() {
@@ -105,14 +356,17 @@ fn validate_unlock_args(args: &UnlockArgs) -> Result<()> {
     }
 
     // Validate encryption key format
-    if args.encryption_key.is_empty() {
-        anyhow::bail!("Encryption key cannot be empty");
-    }
+    if !args.check_only {
+        let encryption_key = args.encryption_key.as_deref().unwrap_or_default();
+        if encryption_key.is_empty() {
+            anyhow::bail!("Encryption key cannot be empty");
+        }
 
-    // Try to decode the encryption key to validate format
-    base64::engine::general_purpose::STANDARD
-        .decode(&args.encryption_key)
-        .context("Invalid encryption key format (must be base64)")?;
+        // Try to decode the encryption key to validate format
+        base64::engine::general_purpose::STANDARD
+            .decode(encryption_key)
+            .context("Invalid encryption key format (must be base64)")?;
+    }
 
     // Validate payment amount if provided
     if let Some(payment) = args.payment {
@@ -124,17 +378,138 @@ fn validate_unlock_args(args: &UnlockArgs) -> Result<()> {
     Ok(())
 }
 
+/// Report whether a capsule's unlock condition is met, without unlocking it
+///
+/// Time capsules are judged by `CapsuleSDK::time_capsule_readiness`, so with
+/// `use_chain_time` set the chain's clock decides rather than this machine's.
+async fn check_unlockable(sdk: &CapsuleSDK, capsule_id: &str, format: &str) -> Result<()> {
+    let status = sdk.get_capsule_status(capsule_id).await?;
+
+    let mut check = UnlockCheck {
+        capsule_id: capsule_id.to_string(),
+        capsule_type: status.capsule_type.clone(),
+        ready: false,
+        reason: String::new(),
+        clock: None,
+    };
+    match (
+        status.capsule_type.as_str(),
+        &status.approvals,
+        status.unlock_time,
+    ) {
+        _ if status.status == "unlocked" => {
+            check.reason = "Capsule has already been unlocked".to_string();
+        }
+        ("time", _, Some(unlock_time)) => {
+            let readiness = sdk.time_capsule_readiness(unlock_time).await;
+            check.ready = readiness.ready;
+            check.clock = Some(readiness.clock);
+            check.reason = if readiness.ready {
+                format!("Unlock time {} has passed", format_timestamp(unlock_time))
+            } else {
+                format!("Unlocks at {}", format_timestamp(unlock_time))
+            };
+        }
+        ("multisig", Some(approvals), _) => {
+            check.ready = approvals.current >= approvals.required;
+            check.reason = format!("{}/{} approvals", approvals.current, approvals.required);
+        }
+        ("composite", _, _) => {
+            let condition = status.composite_condition();
+            let readiness = sdk.condition_readiness(&condition).await;
+            check.ready = readiness.ready;
+            check.clock = Some(readiness.clock);
+            let parts: Vec<String> = condition
+                .conditions
+                .iter()
+                .zip(&readiness.parts_met)
+                .map(|(part, met)| describe_condition_part(part, *met))
+                .collect();
+            check.reason = if parts.is_empty() {
+                "Composite condition has no parts".to_string()
+            } else {
+                parts.join(&format!(" {} ", condition.combinator.unwrap_or_default()))
+            };
+        }
+        ("payment", _, _) => {
+            check.ready = true;
+            check.reason = match status.price {
+                Some(price) => format!("Unlockable with a payment of {price} MIST"),
+                None => "Unlockable with payment".to_string(),
+            };
+        }
+        _ => {
+            check.reason = format!("Unlock condition unknown (status: {})", status.status);
+        }
+    }
+
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(&check)?),
+        _ => {
+            let verdict = if check.ready {
+                theme().success("ready to unlock")
+            } else {
+                theme().warning("not ready")
+            };
+            println!(
+                "\n{} {}: {}",
+                theme().id(&check.capsule_id),
+                verdict,
+                check.reason
+            );
+            if check.clock == Some(ClockSource::Local) && sdk.uses_chain_time() {
+                println!(
+                    "{}",
+                    theme().dim("Chain time was unavailable; judged by the local clock.")
+                );
+            }
+        }
+    }
+
+    if !check.ready {
+        anyhow::bail!("Capsule {capsule_id} cannot be unlocked yet");
+    }
+    Ok(())
+}
+
+/// One part of a composite condition and whether it is met, for `--check-only`
+fn describe_condition_part(part: &UnlockCondition, met: bool) -> String {
+    let description = match (
+        part.condition_type.as_str(),
+        part.unlock_time,
+        part.threshold,
+    ) {
+        ("time", Some(unlock_time), _) => format!("unlock time {}", format_timestamp(unlock_time)),
+        ("multisig", _, Some(threshold)) => {
+            format!("{}/{threshold} approvals", part.approvals.len())
+        }
+        ("payment", _, _) => match part.price {
+            Some(price) => format!("payment of {price} MIST"),
+            None => "payment".to_string(),
+        },
+        (other, _, _) => other.to_string(),
+    };
+    if met {
+        format!("{description} (met)")
+    } else {
+        format!("{description} (not met)")
+    }
+}
+
 fn display_unlock_success(
     result: &crate::sdk::UnlockResult,
     output_path: &PathBuf,
     content_size: usize,
     format: &str,
+    quiet: bool,
 ) -> Result<()> {
-    println!(
-        "\n{}",
-        style("Capsule Unlocked Successfully!").bold().green()
-    );
-    println!("{}", "=".repeat(50));
+    if !quiet {
+        println!(
+            "\n{}",
+            theme().success("Capsule Unlocked Successfully!").bold()
+        );
+        println!("{}", "=".repeat(50));
+    }
 
     match format {
         "json" => {
@@ -150,23 +525,54 @@ fn display_unlock_success(
             println!(
                 "{} {}",
                 style("Output File:").bold(),
-                style(output_path.display()).cyan()
+                theme().id(output_path.display())
             );
             println!(
                 "{} {}",
                 style("Content Size:").bold(),
-                style(crate::utils::format_file_size(content_size as u64)).cyan()
+                theme().id(crate::utils::format_file_size(content_size as u64))
             );
             if let Some(ref tx_digest) = result.transaction_digest {
-                println!(
-                    "{} {}",
-                    style("Transaction:").bold(),
-                    style(tx_digest).cyan()
-                );
+                println!("{} {}", style("Transaction:").bold(), theme().id(tx_digest));
+            }
+            println!(
+                "\n{}",
+                theme().success("✅ Content has been saved to the output file.")
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn display_content_info(capsule_id: &str, info: &ContentInfo, format: &str) -> Result<()> {
+    match format {
+        "json" => {
+            let mut json = serde_json::to_value(info)?;
+            json["capsule_id"] = serde_json::Value::String(capsule_id.to_string());
+            println!("{}", serde_json::to_string_pretty(&json)?);
+        }
+        _ => {
+            println!("\n{}", theme().heading("Capsule Content"));
+            println!("{}", "=".repeat(50));
+            println!("Type: {}", theme().id(&info.mime_type));
+            println!("Size: {}", crate::utils::format_file_size(info.size));
+            println!("Text: {}", if info.is_text { "yes" } else { "no" });
+            if let Some((width, height)) = info.image_dimensions {
+                println!("Dimensions: {width}x{height}");
+            }
+            if let Some(pages) = info.pdf_pages {
+                println!("Pages: {pages}");
+            }
+            if let Some(ref entries) = info.bundle_entries {
+                println!("Bundle entries: {}", entries.len());
+                for entry in entries {
+                    println!("  • {entry}");
+                }
             }
             println!(
                 "\n{}",
-                style("✅ Content has been saved to the output file.").green()
+                theme().dim("Inspect only: decrypted content was not written.")
             );
         }
     }
@@ -175,14 +581,27 @@ fn display_unlock_success(
 }
 
 fn display_unlock_failure(result: &crate::sdk::UnlockResult) -> Result<()> {
-    println!("\n{}", style("Failed to Unlock Capsule").bold().red());
+    println!("\n{}", theme().error("Failed to Unlock Capsule").bold());
     println!("{}", "=".repeat(50));
 
     if let Some(ref error) = result.error {
-        println!("{} {}", style("Error:").bold().red(), error);
+        println!("{} {}", theme().error("Error:").bold(), error);
+    }
+
+    if let Some(UnlockFailureReason::ContentUnavailable { ref cid }) = result.failure_reason {
+        println!(
+            "\n{}",
+            theme()
+                .warning(format!("Content {cid} is not available from IPFS:"))
+                .bold()
+        );
+        for hint in UNAVAILABLE_CONTENT_HINTS {
+            println!("• {hint}");
+        }
+        return Ok(());
     }
 
-    println!("\n{}", style("Possible reasons:").bold().yellow());
+    println!("\n{}", theme().warning("Possible reasons:").bold());
     println!("• Unlock conditions not yet met (time not reached, insufficient approvals, payment not made)");
     println!("• Invalid encryption key");
     println!("• Capsule does not exist or has already been unlocked");
@@ -195,7 +614,7 @@ fn display_unlock_failure(result: &crate::sdk::UnlockResult) -> Result<()> {
 pub async fn handle_unlock_interactive(config: &Config) -> Result<()> {
     use dialoguer::{Confirm, Input};
 
-    println!("{}", style("Interactive Capsule Unlock").bold().cyan());
+    println!("{}", theme().heading("Interactive Capsule Unlock"));
     println!("{}", "=".repeat(50));
 
     // Get capsule ID
@@ -277,12 +696,94 @@ pub async fn handle_unlock_interactive(config: &Config) -> Result<()> {
     // Create unlock args and proceed
     let args = UnlockArgs {
         capsule_id,
-        encryption_key,
+        encryption_key: Some(encryption_key),
         output: Some(output_path),
+        output_dir: None,
         payment,
-        format: "human".to_string(),
+        format: Some("human".to_string()),
         force,
+        to_clipboard: false,
+        extract: None,
+        inspect: false,
+        check_only: false,
+        timings: false,
     };
 
     handle_unlock(args, config).await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct MockClipboard {
+        contents: Option<String>,
+    }
+
+    impl ClipboardSink for MockClipboard {
+        fn set_text(&mut self, text: &str) -> Result<()> {
+            self.contents = Some(text.to_string());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_copy_small_text() {
+        let mut clipboard = MockClipboard::default();
+        copy_to_clipboard(&mut clipboard, b"correct horse battery staple\n").unwrap();
+        assert_eq!(
+            clipboard.contents.as_deref(),
+            Some("correct horse battery staple\n")
+        );
+    }
+
+    #[test]
+    fn test_refuses_binary_content() {
+        let mut clipboard = MockClipboard::default();
+        assert!(copy_to_clipboard(&mut clipboard, &[0x89, b'P', b'N', b'G', 0x00]).is_err());
+        assert!(copy_to_clipboard(&mut clipboard, b"text\0with nul").is_err());
+        assert!(clipboard.contents.is_none());
+    }
+
+    #[test]
+    fn test_refuses_large_content() {
+        let mut clipboard = MockClipboard::default();
+        let large = vec![b'a'; CLIPBOARD_MAX_BYTES + 1];
+        assert!(copy_to_clipboard(&mut clipboard, &large).is_err());
+        assert!(clipboard.contents.is_none());
+
+        let at_limit = vec![b'a'; CLIPBOARD_MAX_BYTES];
+        assert!(clipboard_text(&at_limit).is_ok());
+    }
+
+    #[test]
+    fn test_output_file_name_uses_stored_name() {
+        let dir = Path::new("restored");
+        let name = output_file_name("0xabc", Some("letter.txt"), "text/plain");
+        assert_eq!(dir.join(name), dir.join("letter.txt"));
+    }
+
+    #[test]
+    fn test_output_file_name_falls_back_to_capsule_id() {
+        assert_eq!(output_file_name("0xabc", None, "text/plain"), "0xabc.txt");
+        assert_eq!(output_file_name("0xabc", None, "image/png"), "0xabc.png");
+        assert_eq!(
+            output_file_name("0xabc", None, "application/octet-stream"),
+            "0xabc.bin"
+        );
+        assert_eq!(
+            output_file_name("0xabc", Some(".."), "image/png"),
+            "0xabc.png"
+        );
+    }
+
+    #[test]
+    fn test_output_file_name_stays_inside_output_dir() {
+        let dir = Path::new("restored");
+        for name in ["../escape", "/etc/escape", "a/../../escape", "..\\escape"] {
+            let path = dir.join(output_file_name("0xabc", Some(name), "text/plain"));
+            assert_eq!(path, dir.join("escape"), "{name}");
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use crate::config::Config;
+use crate::sdk::{create_spinner, VerifyResult};
+use crate::storage::UNAVAILABLE_CONTENT_HINTS;
+use crate::theme::{style, theme};
+use crate::utils::init_sdk;
+use anyhow::Result;
+use clap::Args;
+
+#[derive(Args)]
+pub struct VerifyArgs {
+    /// Capsule ID to verify
+    #[arg(short, long)]
+    pub capsule_id: String,
+    /// Encryption key, to also compare the content against its recorded hash
+    #[arg(short, long)]
+    pub encryption_key: Option<String>,
+    /// Output format (defaults to the configured format for this command)
+    #[arg(long)]
+    pub format: Option<String>,
+}
+
+pub async fn handle_verify(args: VerifyArgs, config: &Config) -> Result<()> {
+    let format = config.output_format_for("verify", args.format.as_deref());
+
+    let spinner = create_spinner("Checking stored content...");
+    let sdk = init_sdk(config).await?;
+    let result = sdk
+        .verify_capsule(&args.capsule_id, args.encryption_key.as_deref())
+        .await;
+    spinner.finish_and_clear();
+    let result = result?;
+
+    match format.as_str() {
+        "json" => {
+            println!("{}", serde_json::to_string_pretty(&result)?);
+        }
+        _ => display_verify_result(&result),
+    }
+
+    if !is_intact(&result) {
+        anyhow::bail!("Capsule {} failed verification", result.capsule_id);
+    }
+    Ok(())
+}
+
+/// Whether every check that could run passed
+fn is_intact(result: &VerifyResult) -> bool {
+    result.cid_reachable && result.payload_valid && result.hash_matches != Some(false)
+}
+
+fn display_verify_result(result: &VerifyResult) {
+    let check = |passed: bool| {
+        if passed {
+            theme().success("✓ yes").to_string()
+        } else {
+            theme().error("✗ no").to_string()
+        }
+    };
+
+    println!(
+        "{} {}",
+        style("Capsule ID:").bold(),
+        theme().id(&result.capsule_id)
+    );
+    println!("{} {}", style("CID:").bold(), theme().id(&result.cid));
+    println!(
+        "{} {}",
+        style("Reachable:").bold(),
+        check(result.cid_reachable)
+    );
+    if result.cid_reachable {
+        println!(
+            "{} {}",
+            style("Valid payload:").bold(),
+            check(result.payload_valid)
+        );
+    }
+    match result.hash_matches {
+        Some(matches) => println!("{} {}", style("Hash matches:").bold(), check(matches)),
+        None if result.payload_valid => println!(
+            "{} {}",
+            style("Hash matches:").bold(),
+            theme().dim("not checked (pass --encryption-key to compare)")
+        ),
+        None => {}
+    }
+
+    if !result.cid_reachable {
+        println!(
+            "\n{}",
+            theme()
+                .warning(format!(
+                    "Content {} is not available from IPFS:",
+                    result.cid
+                ))
+                .bold()
+        );
+        for hint in UNAVAILABLE_CONTENT_HINTS {
+            println!("• {hint}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unchecked_hash_counts_as_intact() {
+        let mut result = VerifyResult {
+            capsule_id: "0xabc".to_string(),
+            cid: "QmTest".to_string(),
+            cid_reachable: true,
+            payload_valid: true,
+            hash_matches: None,
+        };
+        assert!(is_intact(&result));
+
+        result.hash_matches = Some(false);
+        assert!(!is_intact(&result));
+
+        result.hash_matches = None;
+        result.payload_valid = false;
+        assert!(!is_intact(&result));
+    }
+}
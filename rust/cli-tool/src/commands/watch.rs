@@ -0,0 +1,243 @@
+use crate::commands::list::format_duration_ms;
+use crate::config::Config;
+use crate::sdk::{create_spinner, CapsuleStatus};
+use crate::theme::theme;
+use crate::utils::{init_sdk, parse_duration};
+use anyhow::Result;
+use clap::Args;
+use log::warn;
+use std::future::Future;
+use std::time::Duration;
+
+#[derive(Args)]
+pub struct WatchArgs {
+    /// Capsule ID to watch
+    #[arg(short, long)]
+    pub capsule_id: String,
+    /// How often to check the capsule's status (e.g., "30s", "5m")
+    #[arg(long, default_value = "30s")]
+    pub interval: String,
+}
+
+pub async fn handle_watch(args: WatchArgs, config: &Config) -> Result<()> {
+    let interval_ms = parse_duration(&args.interval)?;
+    if interval_ms == 0 {
+        anyhow::bail!("Interval must be greater than 0");
+    }
+
+    let sdk = init_sdk(config).await?;
+    let sdk = &sdk;
+    let capsule_id = args.capsule_id.as_str();
+
+    let spinner = create_spinner(&format!("Watching {capsule_id}..."));
+    let mut last_approvals = None;
+    let result = watch_capsule(
+        Duration::from_millis(interval_ms),
+        || async move {
+            let status = sdk.get_capsule_status(capsule_id).await?;
+            let (now, _) = sdk.unlock_clock().await;
+            Ok((status, now))
+        },
+        |status, now| {
+            if let Some(ref approvals) = status.approvals {
+                if last_approvals.is_some_and(|last| last != approvals.current) {
+                    spinner.println(format!(
+                        "{} Approvals: {}/{}",
+                        theme().success("✓"),
+                        approvals.current,
+                        approvals.required
+                    ));
+                }
+                last_approvals = Some(approvals.current);
+            }
+            spinner.set_message(waiting_on(status, now));
+        },
+    )
+    .await;
+    spinner.finish_and_clear();
+    let status = result?;
+
+    println!(
+        "{} Capsule {} is {}",
+        theme().success("✓"),
+        theme().id(&status.capsule_id),
+        if status.status == "unlocked" {
+            "unlocked"
+        } else {
+            "ready to unlock"
+        }
+    );
+    if status.status != "unlocked" {
+        println!(
+            "{}",
+            theme().dim(format!(
+                "Unlock it with: capsule unlock -c {} -e <encryption_key>",
+                status.capsule_id
+            ))
+        );
+    }
+    Ok(())
+}
+
+/// Poll a capsule until it can be unlocked, returning its final status
+///
+/// `fetch` returns the status along with the time to judge unlock times by.
+/// `report` sees every status that is still waiting. Failed polls after the
+/// first are logged and retried; a revoked capsule ends the watch with an error.
+pub async fn watch_capsule<F, Fut>(
+    interval: Duration,
+    mut fetch: F,
+    mut report: impl FnMut(&CapsuleStatus, u64),
+) -> Result<CapsuleStatus>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<(CapsuleStatus, u64)>>,
+{
+    let mut polled = false;
+    loop {
+        match fetch().await {
+            Ok((status, now)) => {
+                if is_unlockable(&status, now) {
+                    return Ok(status);
+                }
+                if status.status == "revoked" {
+                    anyhow::bail!(
+                        "Capsule {} was revoked and can never be unlocked",
+                        status.capsule_id
+                    );
+                }
+                report(&status, now);
+                polled = true;
+            }
+            Err(e) if polled => warn!("Failed to check capsule status: {e:#}"),
+            Err(e) => return Err(e),
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Whether the chain or the clock says the capsule can be unlocked now
+fn is_unlockable(status: &CapsuleStatus, now: u64) -> bool {
+    match status.status.as_str() {
+        "ready" | "unlocked" => true,
+        "locked" => {
+            status.capsule_type == "time"
+                && status
+                    .unlock_time
+                    .is_some_and(|unlock_time| unlock_time <= now)
+        }
+        _ => false,
+    }
+}
+
+/// What a still-locked capsule is waiting for, as shown next to the spinner
+fn waiting_on(status: &CapsuleStatus, now: u64) -> String {
+    match (&status.unlock_time, &status.approvals) {
+        (Some(unlock_time), _) if status.capsule_type == "time" => format!(
+            "Unlocks in {}",
+            format_duration_ms(unlock_time.saturating_sub(now))
+        ),
+        (_, Some(approvals)) => format!(
+            "Waiting for approvals: {}/{}",
+            approvals.current, approvals.required
+        ),
+        _ => format!("Waiting ({})", status.status),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn locked(capsule_type: &str) -> CapsuleStatus {
+        let mut status = CapsuleStatus::failed("0xabc", String::new());
+        status.capsule_type = capsule_type.to_string();
+        status.status = "locked".to_string();
+        status.error = None;
+        status
+    }
+
+    #[tokio::test]
+    async fn test_watch_ends_when_status_flips() {
+        let polls = AtomicU32::new(0);
+        let mut reported = 0;
+        let status = watch_capsule(
+            Duration::from_millis(1),
+            || {
+                let poll = polls.fetch_add(1, Ordering::SeqCst) + 1;
+                async move {
+                    if poll == 2 {
+                        anyhow::bail!("temporarily unreachable");
+                    }
+                    let mut status = locked("multisig");
+                    if poll == 4 {
+                        status.status = "ready".to_string();
+                    }
+                    Ok((status, 0))
+                }
+            },
+            |_, _| reported += 1,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(status.status, "ready");
+        assert_eq!(polls.load(Ordering::SeqCst), 4);
+        assert_eq!(reported, 2);
+    }
+
+    #[tokio::test]
+    async fn test_watch_ends_when_unlock_time_passes() {
+        let now = AtomicU32::new(0);
+        let mut messages = Vec::new();
+        let status = watch_capsule(
+            Duration::from_millis(1),
+            || {
+                let now = u64::from(now.fetch_add(1_000, Ordering::SeqCst));
+                async move {
+                    let mut status = locked("time");
+                    status.unlock_time = Some(3_000);
+                    Ok((status, now))
+                }
+            },
+            |status, now| messages.push(waiting_on(status, now)),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(status.status, "locked");
+        assert_eq!(now.load(Ordering::SeqCst), 4_000);
+        assert_eq!(
+            messages,
+            [
+                "Unlocks in 3 seconds",
+                "Unlocks in 2 seconds",
+                "Unlocks in 1 second"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_watch_fails_for_revoked_or_unknown_capsule() {
+        let revoked = watch_capsule(
+            Duration::from_millis(1),
+            || async {
+                let mut status = locked("time");
+                status.status = "revoked".to_string();
+                Ok((status, 0))
+            },
+            |_, _| {},
+        )
+        .await;
+        assert!(revoked.unwrap_err().to_string().contains("revoked"));
+
+        let missing = watch_capsule(
+            Duration::from_millis(1),
+            || async { anyhow::bail!("Capsule not found") },
+            |_, _| {},
+        )
+        .await;
+        assert!(missing.is_err());
+    }
+}
@@ -0,0 +1,192 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::{Read, Write};
+use std::str::FromStr;
+
+/// Compression applied to content before it is encrypted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionAlgo {
+    /// Store content as is
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl CompressionAlgo {
+    /// How a capsule envelope records content compressed with this algorithm
+    pub fn to_envelope(self) -> Option<String> {
+        (self != CompressionAlgo::None).then(|| self.to_string())
+    }
+
+    /// The algorithm a capsule envelope records, `None` for content stored as is
+    pub fn from_envelope(recorded: Option<&str>) -> Result<Self> {
+        match recorded {
+            None => Ok(CompressionAlgo::None),
+            Some(name) => name.parse().with_context(|| {
+                format!("Capsule content uses compression '{name}', which this build cannot undo")
+            }),
+        }
+    }
+}
+
+/// What compressing a capsule's content did, reported by create
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct CompressionOutcome {
+    /// Algorithm applied; `none` when the content was stored uncompressed
+    pub applied: CompressionAlgo,
+    pub original_size: u64,
+    pub stored_size: u64,
+    /// Stored size as a fraction of the original
+    pub ratio: f64,
+}
+
+impl CompressionOutcome {
+    pub fn new(applied: CompressionAlgo, original_size: u64, stored_size: u64) -> Self {
+        let ratio = if original_size == 0 {
+            1.0
+        } else {
+            stored_size as f64 / original_size as f64
+        };
+        Self {
+            applied,
+            original_size,
+            stored_size,
+            ratio,
+        }
+    }
+}
+
+impl fmt::Display for CompressionAlgo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompressionAlgo::None => write!(f, "none"),
+            CompressionAlgo::Gzip => write!(f, "gzip"),
+            CompressionAlgo::Zstd => write!(f, "zstd"),
+        }
+    }
+}
+
+impl FromStr for CompressionAlgo {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "none" | "off" => Ok(CompressionAlgo::None),
+            "gzip" | "gz" => Ok(CompressionAlgo::Gzip),
+            "zstd" | "zst" => Ok(CompressionAlgo::Zstd),
+            other => anyhow::bail!("Invalid compression: {other}. Use 'none', 'gzip' or 'zstd'"),
+        }
+    }
+}
+
+/// Compress content ahead of encryption, returning the algorithm applied
+///
+/// Content `should_compress` rules out (already compressed formats, random
+/// or encrypted data, tiny inputs) is not attempted, and content that does
+/// not get smaller is not kept; both are returned unchanged with
+/// `CompressionAlgo::None`. The algorithm belongs in the capsule envelope,
+/// since nothing in the output says how it was compressed.
+pub fn compress(content: Vec<u8>, algo: CompressionAlgo) -> Result<(Vec<u8>, CompressionAlgo)> {
+    if !encryptor_wasi::should_compress(&content) {
+        return Ok((content, CompressionAlgo::None));
+    }
+
+    let mut out = Vec::new();
+    match algo {
+        CompressionAlgo::None => return Ok((content, CompressionAlgo::None)),
+        CompressionAlgo::Gzip => {
+            let mut encoder =
+                flate2::write::GzEncoder::new(&mut out, flate2::Compression::default());
+            encoder
+                .write_all(&content)
+                .context("Failed to gzip content")?;
+            encoder.finish().context("Failed to gzip content")?;
+        }
+        CompressionAlgo::Zstd => {
+            zstd::stream::copy_encode(content.as_slice(), &mut out, 0)
+                .context("Failed to zstd-compress content")?;
+        }
+    }
+
+    if out.len() >= content.len() {
+        return Ok((content, CompressionAlgo::None));
+    }
+    Ok((out, algo))
+}
+
+/// Undo `compress` on decrypted content, given the algorithm it applied
+pub fn decompress(data: Vec<u8>, algo: CompressionAlgo) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    match algo {
+        CompressionAlgo::None => return Ok(data),
+        CompressionAlgo::Gzip => {
+            flate2::read::GzDecoder::new(data.as_slice())
+                .read_to_end(&mut out)
+                .context("Failed to decompress gzip content")?;
+        }
+        CompressionAlgo::Zstd => {
+            zstd::stream::copy_decode(data.as_slice(), &mut out)
+                .context("Failed to decompress zstd content")?;
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compressed_content_round_trips() {
+        let text = "2024-01-01 INFO request handled in 3ms\n"
+            .repeat(200)
+            .into_bytes();
+        for algo in [CompressionAlgo::Gzip, CompressionAlgo::Zstd] {
+            let (compressed, applied) = compress(text.clone(), algo).unwrap();
+            assert_eq!(applied, algo);
+            assert!(compressed.len() < text.len() / 10);
+            assert_eq!(decompress(compressed, applied).unwrap(), text);
+        }
+    }
+
+    #[test]
+    fn test_incompressible_content_stored_as_is() {
+        let random: Vec<u8> = (0..4096).map(|_| rand::random::<u8>()).collect();
+        for algo in [
+            CompressionAlgo::None,
+            CompressionAlgo::Gzip,
+            CompressionAlgo::Zstd,
+        ] {
+            let (stored, applied) = compress(random.clone(), algo).unwrap();
+            assert_eq!(stored, random);
+            assert_eq!(applied, CompressionAlgo::None);
+            assert_eq!(applied.to_envelope(), None);
+        }
+    }
+
+    #[test]
+    fn test_compressed_formats_stored_as_is() {
+        let padding = vec![0u8; 4096];
+        let png = [b"\x89PNG\r\n\x1a\n".as_slice(), &padding].concat();
+        let gzip = [b"\x1f\x8b\x08\x00".as_slice(), &padding].concat();
+
+        for content in [png, gzip] {
+            let (stored, applied) = compress(content.clone(), CompressionAlgo::Zstd).unwrap();
+            assert_eq!(applied, CompressionAlgo::None);
+            assert_eq!(stored, content);
+        }
+    }
+
+    #[test]
+    fn test_unknown_algorithm_rejected() {
+        assert!(CompressionAlgo::from_envelope(Some("brotli")).is_err());
+        assert_eq!(
+            CompressionAlgo::from_envelope(CompressionAlgo::Zstd.to_envelope().as_deref()).unwrap(),
+            CompressionAlgo::Zstd
+        );
+        assert!(decompress(b"not gzip".to_vec(), CompressionAlgo::Gzip).is_err());
+    }
+}
@@ -1,40 +1,336 @@
 use anyhow::{Context, Result};
+use log::warn;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::env;
+use std::fmt;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::audit::AUDIT_LOG_FILE_NAME;
+use crate::keystore::KEYSTORE_FILE_NAME;
+use crate::records::CAPSULE_RECORDS_FILE_NAME;
+use crate::sdk::RetryPolicy;
+use crate::utils::normalize_sui_address;
+
+/// Environment variable relocating all CLI state, equivalent to `--config-dir`
+pub const CONFIG_DIR_ENV: &str = "CAPSULE_CONFIG_DIR";
+
+/// Environment variable selecting a config profile, equivalent to `--profile`
+pub const PROFILE_ENV: &str = "CAPSULE_PROFILE";
+
+/// Name of the config file inside the config directory
+pub const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// Port of a local Sui node when `localnet_port` is not set
+pub const DEFAULT_LOCALNET_PORT: u16 = 9000;
+
+/// Simultaneous IPFS requests allowed when `ipfs_max_connections` is not set
+pub const DEFAULT_IPFS_MAX_CONNECTIONS: usize = 8;
+
+/// Highest payment capsule price when `max_price` is not set: the total SUI supply in MIST
+pub const DEFAULT_MAX_PRICE_MIST: u64 = 10_000_000_000 * 1_000_000_000;
+
+/// Retries after a transient network failure when `retry_attempts` is not set
+pub const DEFAULT_RETRY_ATTEMPTS: u32 = 3;
+
+/// First retry delay in milliseconds when `retry_delay_ms` is not set
+pub const DEFAULT_RETRY_DELAY_MS: u64 = 500;
+
+/// Sui networks with a known RPC endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Network {
+    Devnet,
+    Testnet,
+    Mainnet,
+    Localnet,
+}
+
+impl Network {
+    pub const ALL: [Network; 4] = [
+        Network::Devnet,
+        Network::Testnet,
+        Network::Mainnet,
+        Network::Localnet,
+    ];
+
+    /// Public fullnode endpoint, or the local node on `localnet_port`
+    pub fn default_rpc_url(&self, localnet_port: u16) -> String {
+        match self {
+            Network::Devnet => "https://fullnode.devnet.sui.io:443".to_string(),
+            Network::Testnet => "https://fullnode.testnet.sui.io:443".to_string(),
+            Network::Mainnet => "https://fullnode.mainnet.sui.io:443".to_string(),
+            Network::Localnet => format!("http://127.0.0.1:{localnet_port}"),
+        }
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Network::Devnet => "devnet",
+            Network::Testnet => "testnet",
+            Network::Mainnet => "mainnet",
+            Network::Localnet => "localnet",
+        };
+        // Pad so column widths apply in tables
+        f.pad(name)
+    }
+}
+
+impl FromStr for Network {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "devnet" => Ok(Network::Devnet),
+            "testnet" => Ok(Network::Testnet),
+            "mainnet" => Ok(Network::Mainnet),
+            "localnet" => Ok(Network::Localnet),
+            other => {
+                anyhow::bail!("Unknown network: {other}. Use devnet, testnet, mainnet, or localnet")
+            }
+        }
+    }
+}
+
+/// Where capsule payloads are uploaded and pinned
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "service", rename_all = "lowercase")]
+pub enum IpfsBackend {
+    /// The IPFS node whose HTTP API is at `ipfs_url`
+    #[default]
+    #[serde(rename = "node")]
+    ApiNode,
+    /// Pinata, authenticated with a JWT
+    Pinata { jwt: String },
+    /// web3.storage, authenticated with an API token
+    Web3Storage { token: String },
+}
+
+impl IpfsBackend {
+    /// Backend for a service named on the command line or in the environment
+    ///
+    /// `token` is the JWT or API token the service authenticates with; the
+    /// IPFS node takes none.
+    pub fn from_service(service: &str, token: Option<String>) -> Result<Self> {
+        match service.trim().to_lowercase().as_str() {
+            "node" => match token {
+                Some(_) => anyhow::bail!("The IPFS node does not take a pinning token"),
+                None => Ok(Self::ApiNode),
+            },
+            "pinata" => Ok(Self::Pinata {
+                jwt: token.context("Pinata needs a JWT; pass it with --pinning-token")?,
+            }),
+            "web3storage" => Ok(Self::Web3Storage {
+                token: token
+                    .context("web3.storage needs an API token; pass it with --pinning-token")?,
+            }),
+            other => {
+                anyhow::bail!("Unknown pinning service: {other}. Use node, pinata, or web3storage")
+            }
+        }
+    }
+
+    /// Name accepted by `from_service`
+    pub fn service_name(&self) -> &'static str {
+        match self {
+            Self::ApiNode => "node",
+            Self::Pinata { .. } => "pinata",
+            Self::Web3Storage { .. } => "web3storage",
+        }
+    }
+
+    /// Credential sent to the service, if it needs one
+    pub fn token(&self) -> Option<&str> {
+        match self {
+            Self::ApiNode => None,
+            Self::Pinata { jwt } => Some(jwt),
+            Self::Web3Storage { token } => Some(token),
+        }
+    }
+
+    pub fn is_api_node(&self) -> bool {
+        *self == Self::ApiNode
+    }
+}
+
+/// File formats a configuration can be stored in, chosen by extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConfigFormat {
+    Toml,
+    Json,
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// Unknown or missing extensions are treated as TOML
+    fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => Self::Toml,
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Self::Json,
+            Some(ext) if ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml") => {
+                Self::Yaml
+            }
+            _ => {
+                warn!(
+                    "Unrecognized config file extension for {}, assuming TOML",
+                    path.display()
+                );
+                Self::Toml
+            }
+        }
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub network: String,
-    pub rpc_url: String,
+    /// Explicit RPC endpoint; when unset the network's default endpoint is used
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rpc_url: Option<String>,
     pub ipfs_url: String,
+    /// Pinning service uploads go to instead of the IPFS node at `ipfs_url`
+    #[serde(default, skip_serializing_if = "IpfsBackend::is_api_node")]
+    pub pinning_service: IpfsBackend,
     pub package_id: Option<String>,
     pub private_key_path: Option<PathBuf>,
+    /// Inline private key; read from existing files but never written back,
+    /// use `capsule key import` to store one encrypted instead
+    #[serde(default, skip_serializing)]
     pub private_key: Option<String>,
+    /// Address transactions are sent from, used to check capsule ownership
+    #[serde(default)]
+    pub address: Option<String>,
     pub default_output_format: String,
     pub verbose: bool,
+    /// Where mutating operations are recorded (no audit log when unset)
+    #[serde(default)]
+    pub audit_log_path: Option<PathBuf>,
+    /// Local index of created capsules for `list --local` and `export` (not kept when unset)
+    #[serde(default)]
+    pub capsule_records_path: Option<PathBuf>,
+    /// Keep encryption keys in the capsule records as well
+    #[serde(default)]
+    pub store_keys_locally: bool,
+    /// Maximum IPFS requests per second (unlimited when unset)
+    #[serde(default)]
+    pub ipfs_rate_limit: Option<f64>,
+    /// Maximum simultaneous IPFS requests across all files in flight
+    #[serde(default)]
+    pub ipfs_max_connections: Option<usize>,
+    /// HTTP gateways tried in order when content is missing from the IPFS node
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ipfs_gateways: Vec<String>,
+    /// Highest price in MIST a payment capsule may ask for
+    #[serde(default)]
+    pub max_price: Option<u64>,
+    /// Gas budget in MIST for each transaction (estimated per transaction when unset)
+    #[serde(default)]
+    pub gas_budget: Option<u64>,
+    /// Retries after a transient IPFS or RPC failure (3 when unset)
+    #[serde(default)]
+    pub retry_attempts: Option<u32>,
+    /// Wait before the first retry, doubling after each (500 ms when unset)
+    #[serde(default)]
+    pub retry_delay_ms: Option<u64>,
+    /// Output color theme: default, light, high-contrast or none
+    #[serde(default)]
+    pub theme: Option<String>,
+    /// Proxy for RPC requests (HTTP_PROXY/HTTPS_PROXY apply when unset)
+    #[serde(default)]
+    pub proxy_url: Option<String>,
+    /// Port of the local node used for localnet
+    #[serde(default)]
+    pub localnet_port: Option<u16>,
+    /// Judge time capsule readiness by the chain's clock instead of the local one
+    #[serde(default)]
+    pub use_chain_time: bool,
+    /// Show times in `list` tables in the local timezone instead of UTC
+    #[serde(default)]
+    pub local_time: bool,
+    /// How far the local clock may run ahead of the chain's (30 seconds when unset)
+    #[serde(default)]
+    pub clock_skew_tolerance_ms: Option<u64>,
+    /// Where temporary files such as staged plaintext go (the system temp dir when unset)
+    #[serde(default)]
+    pub temp_dir: Option<PathBuf>,
+    /// Per-command output formats overriding `default_output_format`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub output_formats: BTreeMap<String, String>,
+    /// Named sets of settings, such as `[profiles.work]`, that override the
+    /// rest of the file when selected
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, serde_json::Value>,
+    /// Profile applied to this configuration; not stored in the file
+    #[serde(skip)]
+    pub profile: Option<String>,
+    /// Directory this configuration was resolved against; not stored in the file
+    #[serde(skip)]
+    pub config_dir: Option<PathBuf>,
+    /// File this configuration was read from, if any; not stored in the file
+    #[serde(skip)]
+    pub config_file: Option<PathBuf>,
+    /// Print only results, without headings, separators or progress bars
+    /// (`--quiet`); not stored in the file
+    #[serde(skip)]
+    pub quiet: bool,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             network: "devnet".to_string(),
-            rpc_url: "https://fullnode.devnet.sui.io:443".to_string(),
+            rpc_url: None,
             ipfs_url: "https://ipfs.infura.io:5001".to_string(),
+            pinning_service: IpfsBackend::ApiNode,
             package_id: None,
             private_key_path: None,
             private_key: None,
+            address: None,
             default_output_format: "human".to_string(),
             verbose: false,
+            audit_log_path: None,
+            capsule_records_path: None,
+            store_keys_locally: false,
+            ipfs_rate_limit: None,
+            ipfs_max_connections: None,
+            max_price: None,
+            gas_budget: None,
+            retry_attempts: None,
+            retry_delay_ms: None,
+            ipfs_gateways: Vec::new(),
+            theme: None,
+            proxy_url: None,
+            localnet_port: None,
+            use_chain_time: false,
+            local_time: false,
+            clock_skew_tolerance_ms: None,
+            temp_dir: None,
+            output_formats: BTreeMap::new(),
+            profiles: BTreeMap::new(),
+            profile: None,
+            config_dir: None,
+            config_file: None,
+            quiet: false,
         }
     }
 }
 
 impl Config {
     /// Load configuration from file and CLI arguments
+    ///
+    /// `config_dir` relocates the default config file and all other state,
+    /// taking precedence over `CAPSULE_CONFIG_DIR`. `profile`, else
+    /// `CAPSULE_PROFILE`, names a profile whose settings override the file's;
+    /// flags and other environment variables still override both.
+    #[allow(clippy::too_many_arguments)]
     pub fn load(
         config_path: Option<&Path>,
+        config_dir: Option<&Path>,
+        profile: Option<&str>,
         network: Option<&str>,
         rpc_url: Option<&String>,
         ipfs_url: Option<&String>,
@@ -42,16 +338,28 @@ impl Config {
         verbose: bool,
     ) -> Result<Self> {
         let mut config = Self::default();
-
-        // Load from config file if it exists
-        if let Some(path) = config_path {
+        let relocated_dir = config_dir
+            .map(Path::to_path_buf)
+            .or_else(|| env::var_os(CONFIG_DIR_ENV).map(PathBuf::from));
+
+        // Load from config file if it exists, else try default config locations
+        let config_file = match config_path {
+            Some(path) => Some(path.to_path_buf()),
+            None => Self::find_default_config(relocated_dir.as_deref())?,
+        };
+        if let Some(path) = &config_file {
             config = Self::load_from_file(path)?;
-        } else {
-            // Try default config locations
-            if let Some(default_config) = Self::find_default_config()? {
-                config = Self::load_from_file(&default_config)?;
-            }
         }
+        let profile = profile
+            .map(str::to_string)
+            .or_else(|| env::var(PROFILE_ENV).ok().filter(|name| !name.is_empty()));
+        if let Some(profile) = profile {
+            config.apply_profile(&profile)?;
+        }
+        config.config_dir = Some(relocated_dir.unwrap_or_else(default_config_dir));
+        config.config_file = config_file;
+        let configured_network = config.network.clone();
+        let configured_rpc_url = config.rpc_url.clone();
 
         // Override with CLI arguments
         if let Some(network) = network {
@@ -59,7 +367,7 @@ impl Config {
         }
 
         if let Some(rpc_url) = rpc_url {
-            config.rpc_url = rpc_url.clone();
+            config.rpc_url = Some(rpc_url.clone());
         }
 
         if let Some(ipfs_url) = ipfs_url {
@@ -77,22 +385,154 @@ impl Config {
         // Load from environment variables
         config.load_from_env()?;
 
+        // Switching network without an explicit RPC URL targets that network's endpoint
+        if config.network != configured_network
+            && config.rpc_url == configured_rpc_url
+            && config.network.parse::<Network>().is_ok()
+        {
+            config.rpc_url = None;
+        }
+
+        // The CLI always keeps an audit log, under the config dir unless configured
+        if config.audit_log_path.is_none() {
+            config.audit_log_path = Some(config.config_dir().join(AUDIT_LOG_FILE_NAME));
+        }
+        if config.capsule_records_path.is_none() {
+            config.capsule_records_path = Some(config.config_dir().join(CAPSULE_RECORDS_FILE_NAME));
+        }
+
+        config.validate()?;
         Ok(config)
     }
 
-    /// Load configuration from file
+    /// Check settings that would otherwise only fail once a request is made
+    ///
+    /// Unknown network names are allowed with an explicit `rpc_url`, for
+    /// custom networks.
+    pub fn validate(&self) -> Result<()> {
+        if self.rpc_url.is_none() {
+            self.network.parse::<Network>().with_context(|| {
+                format!(
+                    "Invalid network {:?}; set rpc_url to use a custom network",
+                    self.network
+                )
+            })?;
+        }
+        if let Some(rpc_url) = &self.rpc_url {
+            check_http_url("rpc_url", rpc_url)?;
+        }
+        check_http_url("ipfs_url", &self.ipfs_url)?;
+        for gateway in &self.ipfs_gateways {
+            check_http_url("ipfs_gateways", gateway)?;
+        }
+        if let Some(proxy_url) = &self.proxy_url {
+            reqwest::Url::parse(proxy_url)
+                .with_context(|| format!("Invalid proxy_url {proxy_url:?}"))?;
+        }
+        if let Some(package_id) = &self.package_id {
+            normalize_sui_address(package_id).with_context(|| {
+                format!("Invalid package_id {package_id:?}; expected a 0x-prefixed hex object ID")
+            })?;
+        }
+        if let Some(address) = &self.address {
+            normalize_sui_address(address).with_context(|| {
+                format!("Invalid address {address:?}; expected a 0x-prefixed hex Sui address")
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Load configuration from file, as TOML, JSON or YAML depending on its extension
     pub fn load_from_file(path: &Path) -> Result<Self> {
         let content = fs::read_to_string(path)
             .with_context(|| format!("Failed to read config file: {}", path.display()))?;
 
-        let config: Self = toml::from_str(&content)
-            .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
+        let config: Self = match ConfigFormat::from_path(path) {
+            ConfigFormat::Toml => toml::from_str(&content).map_err(anyhow::Error::from),
+            ConfigFormat::Json => serde_json::from_str(&content).map_err(anyhow::Error::from),
+            ConfigFormat::Yaml => serde_yaml::from_str(&content).map_err(anyhow::Error::from),
+        }
+        .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
 
-        Ok(config)
+        if config.private_key.is_some() {
+            warn!(
+                "{} holds a plaintext private_key; move it to the encrypted keystore with `capsule key import`",
+                path.display()
+            );
+        }
+        Ok(config.without_stale_rpc_url())
+    }
+
+    /// Override settings with those of the profile called `name`
+    ///
+    /// A profile that switches network without giving an RPC URL uses that
+    /// network's default endpoint.
+    pub fn apply_profile(&mut self, name: &str) -> Result<()> {
+        let Some(profile) = self.profiles.get(name) else {
+            if self.profiles.is_empty() {
+                anyhow::bail!("Unknown profile '{name}': no profiles are configured");
+            }
+            anyhow::bail!(
+                "Unknown profile '{}'. Available profiles: {}",
+                name,
+                self.profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+            );
+        };
+        let Some(overrides) = profile.as_object() else {
+            anyhow::bail!("Profile '{name}' must be a table of settings");
+        };
+        if overrides.contains_key("profiles") {
+            anyhow::bail!("Profile '{name}' cannot define profiles of its own");
+        }
+
+        let mut merged = serde_json::to_value(&*self)?;
+        if let Some(settings) = merged.as_object_mut() {
+            settings.extend(overrides.clone());
+        }
+        let mut config: Self = serde_json::from_value(merged)
+            .with_context(|| format!("Invalid settings in profile '{name}'"))?;
+        if overrides.contains_key("network") && !overrides.contains_key("rpc_url") {
+            config.rpc_url = None;
+        }
+        // The inline key is never serialized, so it is carried over directly
+        if config.private_key.is_none() {
+            config.private_key = self.private_key.take();
+        }
+        config.profile = Some(name.to_string());
+        config.config_dir = self.config_dir.take();
+        *self = config.without_stale_rpc_url();
+        Ok(())
+    }
+
+    /// Drop an RPC URL that is another network's public endpoint
+    ///
+    /// `config init` used to write devnet's endpoint into every new file, so
+    /// changing only `network` afterwards kept talking to devnet.
+    fn without_stale_rpc_url(mut self) -> Self {
+        let network = self.network.parse::<Network>().ok();
+        let stale = self.rpc_url.as_deref().is_some_and(|url| {
+            Network::ALL.iter().any(|other| {
+                Some(*other) != network
+                    && *other != Network::Localnet
+                    && other.default_rpc_url(DEFAULT_LOCALNET_PORT) == url
+            })
+        });
+        if stale {
+            self.rpc_url = None;
+        }
+        self
     }
 
     /// Find default configuration file
-    fn find_default_config() -> Result<Option<PathBuf>> {
+    ///
+    /// A relocated config directory is the only place looked at, so state
+    /// from other environments never leaks in.
+    fn find_default_config(relocated_dir: Option<&Path>) -> Result<Option<PathBuf>> {
+        if let Some(dir) = relocated_dir {
+            let config = dir.join(CONFIG_FILE_NAME);
+            return Ok(config.exists().then_some(config));
+        }
+
         // Check current directory
         let current_dir_config = PathBuf::from("capsule.toml");
         if current_dir_config.exists() {
@@ -108,11 +548,9 @@ impl Config {
         }
 
         // Check XDG config directory
-        if let Some(config_dir) = dirs::config_dir() {
-            let xdg_config = config_dir.join("capsule").join("config.toml");
-            if xdg_config.exists() {
-                return Ok(Some(xdg_config));
-            }
+        let xdg_config = default_config_dir().join(CONFIG_FILE_NAME);
+        if xdg_config.exists() {
+            return Ok(Some(xdg_config));
         }
 
         Ok(None)
@@ -125,13 +563,17 @@ impl Config {
         }
 
         if let Ok(rpc_url) = env::var("CAPSULE_RPC_URL") {
-            self.rpc_url = rpc_url;
+            self.rpc_url = Some(rpc_url);
         }
 
         if let Ok(ipfs_url) = env::var("CAPSULE_IPFS_URL") {
             self.ipfs_url = ipfs_url;
         }
 
+        let pinning_service = env::var("CAPSULE_PINNING_SERVICE").ok();
+        let pinning_token = env::var("CAPSULE_PINNING_TOKEN").ok();
+        self.override_pinning(pinning_service.as_deref(), pinning_token)?;
+
         if let Ok(package_id) = env::var("CAPSULE_PACKAGE_ID") {
             self.package_id = Some(package_id);
         }
@@ -140,10 +582,103 @@ impl Config {
             self.private_key_path = Some(PathBuf::from(private_key_path));
         }
 
+        if let Ok(address) = env::var("CAPSULE_ADDRESS") {
+            self.address = Some(address);
+        }
+
+        if let Ok(audit_log_path) = env::var("CAPSULE_AUDIT_LOG") {
+            self.audit_log_path = Some(PathBuf::from(audit_log_path));
+        }
+
+        if let Ok(records_path) = env::var("CAPSULE_RECORDS") {
+            self.capsule_records_path = Some(PathBuf::from(records_path));
+        }
+
+        if let Ok(store_keys) = env::var("CAPSULE_STORE_KEYS") {
+            self.store_keys_locally = store_keys
+                .parse()
+                .with_context(|| format!("Invalid CAPSULE_STORE_KEYS: {store_keys}"))?;
+        }
+
+        if let Ok(rate_limit) = env::var("CAPSULE_IPFS_RATE_LIMIT") {
+            let rate_limit = rate_limit
+                .parse()
+                .with_context(|| format!("Invalid CAPSULE_IPFS_RATE_LIMIT: {rate_limit}"))?;
+            self.ipfs_rate_limit = Some(rate_limit);
+        }
+
+        if let Ok(max_connections) = env::var("CAPSULE_IPFS_MAX_CONNECTIONS") {
+            let max_connections = max_connections.parse().with_context(|| {
+                format!("Invalid CAPSULE_IPFS_MAX_CONNECTIONS: {max_connections}")
+            })?;
+            self.ipfs_max_connections = Some(max_connections);
+        }
+
+        if let Ok(max_price) = env::var("CAPSULE_MAX_PRICE") {
+            let max_price = max_price
+                .parse()
+                .with_context(|| format!("Invalid CAPSULE_MAX_PRICE: {max_price}"))?;
+            self.max_price = Some(max_price);
+        }
+
+        if let Ok(gas_budget) = env::var("CAPSULE_GAS_BUDGET") {
+            let gas_budget = gas_budget
+                .parse()
+                .with_context(|| format!("Invalid CAPSULE_GAS_BUDGET: {gas_budget}"))?;
+            self.gas_budget = Some(gas_budget);
+        }
+
+        if let Ok(retry_attempts) = env::var("CAPSULE_RETRY_ATTEMPTS") {
+            let retry_attempts = retry_attempts
+                .parse()
+                .with_context(|| format!("Invalid CAPSULE_RETRY_ATTEMPTS: {retry_attempts}"))?;
+            self.retry_attempts = Some(retry_attempts);
+        }
+
+        if let Ok(retry_delay_ms) = env::var("CAPSULE_RETRY_DELAY_MS") {
+            let retry_delay_ms = retry_delay_ms
+                .parse()
+                .with_context(|| format!("Invalid CAPSULE_RETRY_DELAY_MS: {retry_delay_ms}"))?;
+            self.retry_delay_ms = Some(retry_delay_ms);
+        }
+
+        if let Ok(gateways) = env::var("CAPSULE_IPFS_GATEWAYS") {
+            self.ipfs_gateways = gateways
+                .split(',')
+                .map(str::trim)
+                .filter(|gateway| !gateway.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+
+        if let Ok(proxy_url) = env::var("CAPSULE_PROXY_URL") {
+            self.proxy_url = Some(proxy_url);
+        }
+
+        if let Ok(theme) = env::var("CAPSULE_THEME") {
+            self.theme = Some(theme);
+        }
+
+        if let Ok(use_chain_time) = env::var("CAPSULE_USE_CHAIN_TIME") {
+            self.use_chain_time = use_chain_time
+                .parse()
+                .with_context(|| format!("Invalid CAPSULE_USE_CHAIN_TIME: {use_chain_time}"))?;
+        }
+
+        if let Ok(local_time) = env::var("CAPSULE_LOCAL_TIME") {
+            self.local_time = local_time
+                .parse()
+                .with_context(|| format!("Invalid CAPSULE_LOCAL_TIME: {local_time}"))?;
+        }
+
+        if let Ok(temp_dir) = env::var("CAPSULE_TEMP_DIR") {
+            self.temp_dir = Some(PathBuf::from(temp_dir));
+        }
+
         Ok(())
     }
 
-    /// Save configuration to file
+    /// Save configuration to file in the format its extension names
     pub fn save_to_file(&self, path: &Path) -> Result<()> {
         // Create parent directories if they don't exist
         if let Some(parent) = path.parent() {
@@ -152,7 +687,12 @@ impl Config {
             })?;
         }
 
-        let content = toml::to_string_pretty(self).context("Failed to serialize configuration")?;
+        let content = match ConfigFormat::from_path(path) {
+            ConfigFormat::Toml => toml::to_string_pretty(self).map_err(anyhow::Error::from),
+            ConfigFormat::Json => serde_json::to_string_pretty(self).map_err(anyhow::Error::from),
+            ConfigFormat::Yaml => serde_yaml::to_string(self).map_err(anyhow::Error::from),
+        }
+        .context("Failed to serialize configuration")?;
 
         fs::write(path, content)
             .with_context(|| format!("Failed to write config file: {}", path.display()))?;
@@ -160,32 +700,149 @@ impl Config {
         Ok(())
     }
 
-    /// Get the effective RPC URL
+    /// Get the effective RPC URL: the explicit one if set, else the network's default
+    ///
+    /// An unrecognised network name falls back to the devnet endpoint.
     pub fn get_rpc_url(&self) -> String {
-        self.rpc_url.clone()
+        if let Some(rpc_url) = &self.rpc_url {
+            return rpc_url.clone();
+        }
+        self.network
+            .parse::<Network>()
+            .unwrap_or(Network::Devnet)
+            .default_rpc_url(self.localnet_port.unwrap_or(DEFAULT_LOCALNET_PORT))
+    }
+
+    /// Use a pinning service or token given outside the config file
+    ///
+    /// A token alone replaces the configured service's token, and naming the
+    /// configured service again keeps its token.
+    pub fn override_pinning(&mut self, service: Option<&str>, token: Option<String>) -> Result<()> {
+        if service.is_none() && token.is_none() {
+            return Ok(());
+        }
+        let configured = self.pinning_service.service_name();
+        let service = service.unwrap_or(configured);
+        let token = token.or_else(|| {
+            service
+                .trim()
+                .eq_ignore_ascii_case(configured)
+                .then(|| self.pinning_service.token().map(str::to_string))
+                .flatten()
+        });
+        self.pinning_service = IpfsBackend::from_service(service, token)?;
+        Ok(())
+    }
+
+    /// Where uploads go, for messages: the node's URL or the service's name
+    pub fn ipfs_endpoint(&self) -> &str {
+        match self.pinning_service {
+            IpfsBackend::ApiNode => &self.ipfs_url,
+            IpfsBackend::Pinata { .. } => "Pinata",
+            IpfsBackend::Web3Storage { .. } => "web3.storage",
+        }
+    }
+
+    /// Simultaneous IPFS requests allowed, independent of how many files are processed at once
+    pub fn ipfs_max_connections(&self) -> usize {
+        self.ipfs_max_connections
+            .unwrap_or(DEFAULT_IPFS_MAX_CONNECTIONS)
+    }
+
+    /// Highest price in MIST a payment capsule may ask for
+    pub fn max_price(&self) -> u64 {
+        self.max_price.unwrap_or(DEFAULT_MAX_PRICE_MIST)
+    }
+
+    /// How SDK network calls are retried after transient failures
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            attempts: self.retry_attempts.unwrap_or(DEFAULT_RETRY_ATTEMPTS),
+            delay_ms: self.retry_delay_ms.unwrap_or(DEFAULT_RETRY_DELAY_MS),
+        }
+    }
+
+    /// Directory holding the config file, audit log and other state
+    pub fn config_dir(&self) -> PathBuf {
+        self.config_dir.clone().unwrap_or_else(default_config_dir)
+    }
+
+    /// Where `capsule key import` keeps the encrypted private key
+    pub fn keystore_path(&self) -> PathBuf {
+        self.config_dir().join(KEYSTORE_FILE_NAME)
+    }
+
+    /// Directory for temporary files: the configured one, else the system temp dir
+    pub fn temp_dir(&self) -> PathBuf {
+        self.temp_dir.clone().unwrap_or_else(env::temp_dir)
+    }
+
+    /// Output format for `command`: the `--format` flag if given, then the
+    /// per-command setting, then `default_output_format`
+    pub fn output_format_for(&self, command: &str, explicit: Option<&str>) -> String {
+        explicit
+            .or_else(|| self.output_formats.get(command).map(String::as_str))
+            .unwrap_or(&self.default_output_format)
+            .to_string()
+    }
+
+    /// RPC URL used for `network`: the configured one for the active network,
+    /// otherwise that network's default endpoint
+    pub fn rpc_url_for(&self, network: Network) -> String {
+        if self.network == network.to_string() {
+            self.get_rpc_url()
+        } else {
+            network.default_rpc_url(self.localnet_port.unwrap_or(DEFAULT_LOCALNET_PORT))
+        }
+    }
+
+    /// Target `network` for the rest of this invocation
+    ///
+    /// The RPC URL follows, except that a configured one is kept when the
+    /// network does not actually change.
+    pub fn switch_network(&mut self, network: Network) {
+        if self.network != network.to_string() {
+            self.rpc_url = None;
+        }
+        self.network = network.to_string();
     }
 
     /// Get the default config file path
     pub fn default_config_path() -> Result<PathBuf> {
-        let config_dir = dirs::config_dir()
-            .ok_or_else(|| anyhow::anyhow!("Could not determine config directory"))?;
-        Ok(config_dir.join("capsule").join("config.toml"))
+        default_config_path()
     }
 }
 
-/// Get default config file path
-pub fn default_config_path() -> Result<PathBuf> {
-    if let Some(config_dir) = dirs::config_dir() {
-        Ok(config_dir.join("capsule").join("config.toml"))
+/// Check that the `field` setting is an http or https URL
+fn check_http_url(field: &str, url: &str) -> Result<()> {
+    let parsed = reqwest::Url::parse(url).with_context(|| format!("Invalid {field} {url:?}"))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        anyhow::bail!("Invalid {field} {url:?}; expected an http or https URL");
+    }
+    Ok(())
+}
+
+/// Directory for CLI state: `CAPSULE_CONFIG_DIR` if set, else `capsule`
+/// under the platform config directory
+pub fn default_config_dir() -> PathBuf {
+    if let Some(config_dir) = env::var_os(CONFIG_DIR_ENV) {
+        PathBuf::from(config_dir)
+    } else if let Some(config_dir) = dirs::config_dir() {
+        config_dir.join("capsule")
     } else {
-        Ok(PathBuf::from(".capsule").join("config.toml"))
+        PathBuf::from(".capsule")
     }
 }
 
+/// Get default config file path
+pub fn default_config_path() -> Result<PathBuf> {
+    Ok(default_config_dir().join(CONFIG_FILE_NAME))
+}
+
 /// Handle config command
 pub async fn handle_config_command(config: &Config, show: bool, init: bool) -> Result<()> {
     if init {
-        let config_path = Config::default_config_path()?;
+        let config_path = config.config_dir().join(CONFIG_FILE_NAME);
         let default_config = Config::default();
 
         if config_path.exists() {
@@ -205,9 +862,43 @@ pub async fn handle_config_command(config: &Config, show: bool, init: bool) -> R
 
     if show {
         println!("Current Configuration:");
+        println!("Config Dir: {}", config.config_dir().display());
+        if let Some(profile) = &config.profile {
+            println!("Profile: {profile}");
+        }
         println!("Network: {}", config.network);
         println!("RPC URL: {}", config.get_rpc_url());
         println!("IPFS URL: {}", config.ipfs_url);
+        println!("Pinning Service: {}", config.pinning_service.service_name());
+
+        if let Some(rate_limit) = config.ipfs_rate_limit {
+            println!("IPFS Rate Limit: {rate_limit} req/s");
+        } else {
+            println!("IPFS Rate Limit: Unlimited");
+        }
+        println!("IPFS Max Connections: {}", config.ipfs_max_connections());
+        println!("Max Price: {} MIST", config.max_price());
+        if let Some(gas_budget) = config.gas_budget {
+            println!("Gas Budget: {gas_budget} MIST");
+        } else {
+            println!("Gas Budget: Estimated");
+        }
+        let retry = config.retry_policy();
+        println!(
+            "Network Retries: {} (starting {} ms apart)",
+            retry.attempts, retry.delay_ms
+        );
+        if config.ipfs_gateways.is_empty() {
+            println!("IPFS Gateways: None");
+        } else {
+            println!("IPFS Gateways: {}", config.ipfs_gateways.join(", "));
+        }
+
+        if let Some(proxy_url) = &config.proxy_url {
+            println!("Proxy: {proxy_url}");
+        } else {
+            println!("Proxy: From environment");
+        }
 
         if let Some(package_id) = &config.package_id {
             println!("Package ID: {package_id}");
@@ -220,9 +911,58 @@ pub async fn handle_config_command(config: &Config, show: bool, init: bool) -> R
         } else {
             println!("Private Key Path: Not set");
         }
+        if config.keystore_path().exists() {
+            println!("Keystore: {} (encrypted)", config.keystore_path().display());
+        } else {
+            println!("Keystore: Not set");
+        }
+
+        if let Some(address) = &config.address {
+            println!("Address: {address}");
+        } else {
+            println!("Address: Not set");
+        }
 
         println!("Default Output Format: {}", config.default_output_format);
+        for (command, format) in &config.output_formats {
+            println!("Output Format ({command}): {format}");
+        }
         println!("Verbose: {}", config.verbose);
+        println!("Theme: {}", config.theme.as_deref().unwrap_or("default"));
+        println!(
+            "Unlock Clock: {}",
+            if config.use_chain_time {
+                "Chain"
+            } else {
+                "Local"
+            }
+        );
+        println!(
+            "Time Zone: {}",
+            if config.local_time { "Local" } else { "UTC" }
+        );
+
+        println!("Temp Directory: {}", config.temp_dir().display());
+
+        if let Some(audit_log_path) = &config.audit_log_path {
+            println!("Audit Log: {}", audit_log_path.display());
+        } else {
+            println!("Audit Log: Disabled");
+        }
+
+        if let Some(records_path) = &config.capsule_records_path {
+            println!(
+                "Capsule Records: {}{}",
+                records_path.display(),
+                if config.store_keys_locally {
+                    " (with keys)"
+                } else {
+                    ""
+                }
+            );
+        } else {
+            println!("Capsule Records: Disabled");
+        }
 
         return Ok(());
     }
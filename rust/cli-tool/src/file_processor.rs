@@ -1,9 +1,14 @@
+use crate::logging::progress_bars;
+use crate::theme::theme;
 use anyhow::{Context, Result};
-use console::style;
+use encryptor_wasi::{hash_to_hex, ContentHasher};
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use indicatif::{MultiProgress, ProgressBar};
 use log::{error, info, warn};
 use mime_guess::MimeGuess;
+use rayon::prelude::*;
 use std::fs;
+use std::io::Read;
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
@@ -12,6 +17,13 @@ pub struct FileProcessor {
     pub max_file_size: u64,
     pub allowed_extensions: Option<Vec<String>>,
     pub recursive: bool,
+    pub include_hidden: bool,
+    /// Follow symlinks when walking a directory, as long as they stay inside it
+    pub follow_symlinks: bool,
+    /// When set, only files matching one of these are kept from a directory
+    pub include_globs: Option<GlobSet>,
+    /// Entries matching any of these are skipped when walking a directory
+    pub exclude_globs: Option<GlobSet>,
 }
 
 #[derive(Debug, Clone)]
@@ -23,8 +35,8 @@ pub struct FileInfo {
 }
 
 #[derive(Debug, Clone)]
-pub struct BatchResult {
-    pub successful: Vec<String>,
+pub struct BatchResult<T = String> {
+    pub successful: Vec<T>,
     pub failed: Vec<(String, String)>, // (file_path, error_message)
     pub total_processed: usize,
     pub total_size: u64,
@@ -36,6 +48,10 @@ impl Default for FileProcessor {
             max_file_size: 100 * 1024 * 1024, // 100MB
             allowed_extensions: None,
             recursive: false,
+            include_hidden: false,
+            follow_symlinks: false,
+            include_globs: None,
+            exclude_globs: None,
         }
     }
 }
@@ -60,6 +76,34 @@ impl FileProcessor {
         self
     }
 
+    pub fn include_hidden(mut self, include_hidden: bool) -> Self {
+        self.include_hidden = include_hidden;
+        self
+    }
+
+    pub fn follow_symlinks(mut self, follow_symlinks: bool) -> Self {
+        self.follow_symlinks = follow_symlinks;
+        self
+    }
+
+    /// Keep only the files in a directory that match one of `patterns`
+    ///
+    /// Patterns match paths relative to the directory being processed. An
+    /// empty list keeps everything.
+    pub fn with_include_globs(mut self, patterns: Vec<String>) -> Result<Self> {
+        self.include_globs = build_glob_set(&patterns)?;
+        Ok(self)
+    }
+
+    /// Skip directory entries matching any of `patterns`
+    ///
+    /// Patterns match paths relative to the directory being processed; a
+    /// matching directory is pruned along with everything inside it.
+    pub fn with_exclude_globs(mut self, patterns: Vec<String>) -> Result<Self> {
+        self.exclude_globs = build_glob_set(&patterns)?;
+        Ok(self)
+    }
+
     /// Process a single file or directory
     pub fn process_path(&self, path: &Path) -> Result<Vec<FileInfo>> {
         if path.is_file() {
@@ -76,29 +120,118 @@ impl FileProcessor {
     }
 
     /// Process a directory
+    ///
+    /// Hidden entries (names starting with `.`) below the given directory are
+    /// skipped unless `include_hidden` is set; a hidden directory is pruned
+    /// along with everything inside it. Entries matching an exclude glob are
+    /// skipped the same way, and with include globs only matching files are
+    /// kept.
+    ///
+    /// Symlinks are skipped unless `follow_symlinks` is set. Followed links
+    /// that resolve outside the directory are still skipped, and a link back
+    /// to one of its own ancestors is reported instead of walked forever.
     fn process_directory(&self, dir: &Path) -> Result<Vec<FileInfo>> {
         let mut files = Vec::new();
+        let mut skipped = 0;
+        let mut hidden = 0;
+        let mut symlinks = 0;
+        let mut excluded = 0;
+        let mut unmatched = 0;
 
         let walker = if self.recursive {
             WalkDir::new(dir)
         } else {
             WalkDir::new(dir).max_depth(1)
         };
+        let walker = walker.follow_links(self.follow_symlinks);
+
+        let root = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+        let include_hidden = self.include_hidden;
+        let follow_symlinks = self.follow_symlinks;
+        let exclude_globs = self.exclude_globs.as_ref();
+        let walker = walker.into_iter().filter_entry(|entry| {
+            if entry.depth() == 0 {
+                return true;
+            }
+            if !include_hidden && is_hidden(entry.file_name()) {
+                hidden += 1;
+                return false;
+            }
+            if entry.path_is_symlink() {
+                if !follow_symlinks {
+                    warn!(
+                        "Skipping symlink {} (use --follow-symlinks to follow it)",
+                        entry.path().display()
+                    );
+                    symlinks += 1;
+                    return false;
+                }
+                let inside =
+                    fs::canonicalize(entry.path()).is_ok_and(|target| target.starts_with(&root));
+                if !inside {
+                    warn!(
+                        "Skipping symlink {}: it points outside {}",
+                        entry.path().display(),
+                        dir.display()
+                    );
+                    symlinks += 1;
+                    return false;
+                }
+            }
+            if exclude_globs.is_some_and(|globs| globs.is_match(relative_to(entry.path(), dir))) {
+                excluded += 1;
+                return false;
+            }
+            true
+        });
 
         for entry in walker {
-            let entry = entry.context("Failed to read directory entry")?;
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) if e.loop_ancestor().is_some() => {
+                    warn!("Skipping symlink loop: {e}");
+                    skipped += 1;
+                    continue;
+                }
+                Err(e) => return Err(e).context("Failed to read directory entry"),
+            };
             let path = entry.path();
 
             if path.is_file() {
+                if let Some(ref globs) = self.include_globs {
+                    if !globs.is_match(relative_to(path, dir)) {
+                        unmatched += 1;
+                        continue;
+                    }
+                }
                 match self.analyze_file(path) {
                     Ok(file_info) => files.push(file_info),
                     Err(e) => {
                         warn!("Skipping file {}: {}", path.display(), e);
+                        skipped += 1;
                     }
                 }
             }
         }
 
+        let filtered = excluded + unmatched;
+        if skipped > 0 || hidden > 0 || symlinks > 0 || filtered > 0 {
+            info!(
+                "Skipped {} in {}: {} unusable, {} hidden, {} symlinked, {} filtered by pattern{}",
+                skipped + hidden + symlinks + filtered,
+                dir.display(),
+                skipped,
+                hidden,
+                symlinks,
+                filtered,
+                if hidden > 0 {
+                    " (use --include-hidden to include hidden entries)"
+                } else {
+                    ""
+                }
+            );
+        }
+
         Ok(files)
     }
 
@@ -136,10 +269,15 @@ impl FileProcessor {
             }
         }
 
-        // Determine MIME type
-        let mime_type = MimeGuess::from_path(path)
+        // Determine MIME type, sniffing the content when the extension says nothing
+        let mut mime_type = MimeGuess::from_path(path)
             .first_or_octet_stream()
             .to_string();
+        if mime_type == "application/octet-stream" {
+            if let Some(sniffed) = sniff_mime_type(path) {
+                mime_type = sniffed;
+            }
+        }
 
         // Check if binary
         let is_binary = !mime_type.starts_with("text/")
@@ -183,7 +321,7 @@ impl FileProcessor {
 
     /// Create a progress bar for batch operations
     pub fn create_batch_progress(&self, total_files: usize) -> (MultiProgress, ProgressBar) {
-        let multi_progress = MultiProgress::new();
+        let multi_progress = progress_bars().clone();
         let main_pb = multi_progress.add(ProgressBar::new(total_files as u64));
 
         main_pb.set_style(
@@ -201,30 +339,42 @@ impl FileProcessor {
 pub struct BatchProcessor;
 
 impl BatchProcessor {
-    /// Process multiple files with progress reporting and parallel execution
+    /// Process multiple files with progress reporting, running at most
+    /// `max_concurrent` of them at once (at least one)
+    ///
+    /// With `stop_on_error`, the first failure stops any more files from
+    /// starting and aborts those still running; the result only covers files
+    /// that finished.
     pub async fn process_files<F, Fut, T>(
         files: Vec<FileInfo>,
         processor: F,
+        max_concurrent: usize,
+        stop_on_error: bool,
         progress_bar: Option<&ProgressBar>,
-    ) -> BatchResult
+    ) -> BatchResult<T>
     where
         F: Fn(FileInfo) -> Fut + Send + Sync + 'static,
         Fut: std::future::Future<Output = Result<T>> + Send + 'static,
-        T: std::fmt::Display + Send + 'static,
+        T: Send + 'static,
     {
         let mut successful = Vec::new();
         let mut failed = Vec::new();
         let total_size: u64 = files.iter().map(|f| f.size).sum();
 
         // Process files with controlled concurrency
-        let semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new(4)); // Max 4 concurrent operations
+        let semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new(max_concurrent.max(1)));
         let processor = std::sync::Arc::new(processor);
+        let any_failed = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
         let mut tasks = Vec::new();
 
         for file_info in files.into_iter() {
             let file_path = file_info.path.display().to_string();
             let permit = semaphore.clone().acquire_owned().await.unwrap();
+            if stop_on_error && any_failed.load(std::sync::atomic::Ordering::SeqCst) {
+                break;
+            }
             let processor_clone = processor.clone();
+            let any_failed = any_failed.clone();
 
             if let Some(pb) = progress_bar {
                 pb.set_message(format!(
@@ -240,6 +390,9 @@ impl BatchProcessor {
             let task = tokio::spawn(async move {
                 let _permit = permit; // Keep permit alive
                 let result = processor_clone(file_info).await;
+                if result.is_err() {
+                    any_failed.store(true, std::sync::atomic::Ordering::SeqCst);
+                }
                 (file_path, result)
             });
 
@@ -247,16 +400,22 @@ impl BatchProcessor {
         }
 
         // Collect results
-        for task in tasks {
+        let mut tasks = tasks.into_iter();
+        while let Some(task) = tasks.next() {
             match task.await {
                 Ok((file_path, Ok(result))) => {
-                    successful.push(result.to_string());
+                    successful.push(result);
                     info!("Successfully processed: {file_path}");
                 }
                 Ok((file_path, Err(e))) => {
                     let error_msg = e.to_string();
                     failed.push((file_path.clone(), error_msg.clone()));
                     error!("Failed to process {file_path}: {error_msg}");
+                    if stop_on_error {
+                        for task in tasks.by_ref() {
+                            task.abort();
+                        }
+                    }
                 }
                 Err(e) => {
                     let error_msg = format!("Task failed: {e}");
@@ -351,7 +510,7 @@ impl BatchProcessor {
     /// Display batch results
     pub fn display_results(result: &BatchResult) {
         println!();
-        println!("{}", style("Batch Processing Results").bold().cyan());
+        println!("{}", theme().heading("Batch Processing Results"));
         println!("{}", "=".repeat(50));
 
         println!("Total files processed: {}", result.total_processed);
@@ -365,8 +524,8 @@ impl BatchProcessor {
             println!();
             println!(
                 "{} {} successful:",
-                style("✅").green(),
-                style(result.successful.len()).bold().green()
+                theme().success("✅"),
+                theme().success(result.successful.len()).bold()
             );
             for success in &result.successful {
                 println!("  • {success}");
@@ -377,11 +536,11 @@ impl BatchProcessor {
             println!();
             println!(
                 "{} {} failed:",
-                style("❌").red(),
-                style(result.failed.len()).bold().red()
+                theme().error("❌"),
+                theme().error(result.failed.len()).bold()
             );
             for (file, error) in &result.failed {
-                println!("  • {}: {}", style(file).dim(), style(error).red());
+                println!("  • {}: {}", theme().dim(file), theme().error(error));
             }
         }
 
@@ -389,13 +548,125 @@ impl BatchProcessor {
     }
 }
 
+/// Whether a file or directory name marks it as hidden
+fn is_hidden(name: &std::ffi::OsStr) -> bool {
+    name.to_str().is_some_and(|name| name.starts_with('.'))
+}
+
+/// Bytes read from the start of a file to sniff its MIME type
+const SNIFF_LEN: u64 = 8 * 1024;
+
+/// MIME type from a file's leading bytes, for when its extension is missing
+/// or unknown
+///
+/// Magic numbers are checked first; content that is otherwise valid UTF-8
+/// without NUL bytes is taken as plain text.
+fn sniff_mime_type(path: &Path) -> Option<String> {
+    let mut head = Vec::new();
+    fs::File::open(path)
+        .ok()?
+        .take(SNIFF_LEN)
+        .read_to_end(&mut head)
+        .ok()?;
+
+    if let Some(kind) = infer::get(&head) {
+        return Some(kind.mime_type().to_string());
+    }
+
+    // A multi-byte character may be cut off where the read stopped
+    let utf8 = match std::str::from_utf8(&head) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    };
+    (!head.is_empty() && utf8 && !head.contains(&0)).then(|| "text/plain".to_string())
+}
+
+/// Compile glob patterns into one set, or `None` when there are none
+fn build_glob_set(patterns: &[String]) -> Result<Option<GlobSet>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern).with_context(|| format!("Invalid glob pattern: {pattern}"))?;
+        builder.add(glob);
+    }
+    let set = builder.build().context("Failed to compile glob patterns")?;
+    Ok(Some(set))
+}
+
+/// `path` relative to the directory being walked, for glob matching
+fn relative_to<'a>(path: &'a Path, dir: &Path) -> &'a Path {
+    path.strip_prefix(dir).unwrap_or(path)
+}
+
+/// Read size when hashing, so memory per file stays bounded whatever its size
+const HASH_CHUNK_SIZE: usize = 64 * 1024;
+
+/// BLAKE3 hash of one file's content
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileHash {
+    pub path: PathBuf,
+    pub hash: String,
+}
+
+/// Content hashing for large file sets
+pub struct FileHasher;
+
+impl FileHasher {
+    /// Hash a file by streaming it in fixed-size chunks
+    pub fn hash_file(path: &Path) -> Result<FileHash> {
+        let mut file = fs::File::open(path)
+            .with_context(|| format!("Failed to open file: {}", path.display()))?;
+        let mut hasher = ContentHasher::new();
+        let mut buffer = vec![0u8; HASH_CHUNK_SIZE];
+        loop {
+            let read = file
+                .read(&mut buffer)
+                .with_context(|| format!("Failed to read file: {}", path.display()))?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+        }
+
+        Ok(FileHash {
+            path: path.to_path_buf(),
+            hash: hash_to_hex(&hasher.finalize().hash),
+        })
+    }
+
+    /// Hash files one after another
+    pub fn hash_serial(files: &[FileInfo]) -> Result<Vec<FileHash>> {
+        files.iter().map(|file| Self::hash_file(&file.path)).collect()
+    }
+
+    /// Hash files on a dedicated thread pool, returning results in input order
+    ///
+    /// Hashing is CPU-bound, so it runs on rayon rather than the async runtime
+    /// that bounds uploads. `threads` of 0 uses one thread per core.
+    pub fn hash_parallel(files: &[FileInfo], threads: usize) -> Result<Vec<FileHash>> {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .context("Failed to start hashing threads")?;
+        pool.install(|| {
+            files
+                .par_iter()
+                .map(|file| Self::hash_file(&file.path))
+                .collect()
+        })
+    }
+}
+
 /// Error reporting utilities
 pub struct ErrorReporter;
 
 impl ErrorReporter {
     /// Report a detailed error with context
     pub fn report_error(error: &anyhow::Error, context: &str) {
-        eprintln!("{} {}", style("Error:").bold().red(), context);
+        eprintln!("{} {}", theme().error("Error:").bold(), context);
 
         let mut current = error.source();
         let mut level = 0;
@@ -419,12 +690,12 @@ impl ErrorReporter {
 
         eprintln!(
             "{} {} errors occurred:",
-            style("⚠️").yellow(),
-            style(errors.len()).bold().yellow()
+            theme().warning("⚠️"),
+            theme().warning(errors.len()).bold()
         );
 
         for (context, error) in errors {
-            eprintln!("  {} {}: {}", style("•").red(), style(context).dim(), error);
+            eprintln!("  {} {}: {}", theme().error("•"), theme().dim(context), error);
         }
 
         eprintln!();
@@ -524,3 +795,235 @@ impl ErrorReporter {
         suggestions
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_hidden_entries_excluded_by_default() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("visible.txt"), b"visible").unwrap();
+        fs::write(temp_dir.path().join(".env"), b"SECRET=1").unwrap();
+        fs::create_dir(temp_dir.path().join(".git")).unwrap();
+        fs::write(temp_dir.path().join(".git").join("config"), b"[core]").unwrap();
+
+        let files = FileProcessor::new()
+            .recursive(true)
+            .process_path(temp_dir.path())
+            .unwrap();
+        assert_eq!(files.len(), 1);
+        assert!(files[0].path.ends_with("visible.txt"));
+
+        let files = FileProcessor::new()
+            .recursive(true)
+            .include_hidden(true)
+            .process_path(temp_dir.path())
+            .unwrap();
+        assert_eq!(files.len(), 3);
+    }
+
+    #[test]
+    fn test_include_and_exclude_globs() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("target").join("debug")).unwrap();
+        fs::create_dir_all(root.join("crates").join("core").join("target")).unwrap();
+        fs::write(root.join("notes.txt"), b"notes").unwrap();
+        fs::write(root.join("image.png"), b"png").unwrap();
+        fs::write(root.join("src").join("readme.txt"), b"readme").unwrap();
+        fs::write(root.join("target").join("debug").join("build.txt"), b"build").unwrap();
+        fs::write(root.join("crates").join("core").join("target").join("out.txt"), b"out").unwrap();
+
+        let names = |files: Vec<FileInfo>| {
+            let mut names: Vec<String> = files
+                .iter()
+                .map(|f| f.path.strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/"))
+                .collect();
+            names.sort();
+            names
+        };
+
+        let files = FileProcessor::new()
+            .recursive(true)
+            .with_include_globs(vec!["*.txt".to_string()])
+            .unwrap()
+            .process_path(root)
+            .unwrap();
+        assert_eq!(
+            names(files),
+            [
+                "crates/core/target/out.txt",
+                "notes.txt",
+                "src/readme.txt",
+                "target/debug/build.txt"
+            ]
+        );
+
+        let files = FileProcessor::new()
+            .recursive(true)
+            .with_exclude_globs(vec!["**/target/**".to_string()])
+            .unwrap()
+            .process_path(root)
+            .unwrap();
+        assert_eq!(names(files), ["image.png", "notes.txt", "src/readme.txt"]);
+
+        let files = FileProcessor::new()
+            .recursive(true)
+            .with_include_globs(vec!["*.txt".to_string()])
+            .unwrap()
+            .with_exclude_globs(vec!["**/target/**".to_string()])
+            .unwrap()
+            .process_path(root)
+            .unwrap();
+        assert_eq!(names(files), ["notes.txt", "src/readme.txt"]);
+
+        assert!(FileProcessor::new()
+            .with_exclude_globs(vec!["[".to_string()])
+            .is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_cycles_and_escapes_are_skipped() {
+        use std::os::unix::fs::symlink;
+
+        let outside = TempDir::new().unwrap();
+        fs::write(outside.path().join("secret.txt"), b"secret").unwrap();
+
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir(root.join("docs")).unwrap();
+        fs::write(root.join("docs").join("note.txt"), b"note").unwrap();
+        // A link back to an ancestor, which would recurse forever if followed blindly
+        symlink(root, root.join("docs").join("cycle")).unwrap();
+        symlink(outside.path().join("secret.txt"), root.join("escape.txt")).unwrap();
+        symlink(root.join("docs"), root.join("docs-link")).unwrap();
+
+        let files = FileProcessor::new().recursive(true).process_path(root).unwrap();
+        assert_eq!(files.len(), 1);
+        assert!(files[0].path.ends_with("docs/note.txt"));
+
+        let mut followed: Vec<PathBuf> = FileProcessor::new()
+            .recursive(true)
+            .follow_symlinks(true)
+            .process_path(root)
+            .unwrap()
+            .into_iter()
+            .map(|f| f.path.strip_prefix(root).unwrap().to_path_buf())
+            .collect();
+        followed.sort();
+        assert_eq!(
+            followed,
+            [PathBuf::from("docs/note.txt"), PathBuf::from("docs-link/note.txt")]
+        );
+    }
+
+    #[test]
+    fn test_content_sniffed_when_extension_is_unknown() {
+        use image::{ImageBuffer, ImageFormat, Rgb};
+        use std::io::Cursor;
+
+        let temp_dir = TempDir::new().unwrap();
+        let png: ImageBuffer<Rgb<u8>, Vec<u8>> = ImageBuffer::new(2, 2);
+        let mut bytes = Vec::new();
+        png.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .unwrap();
+        let renamed = temp_dir.path().join("photo.dat");
+        fs::write(&renamed, bytes).unwrap();
+        let text = temp_dir.path().join("README");
+        fs::write(&text, "Grüße aus der Vergangenheit\n").unwrap();
+        let blob = temp_dir.path().join("blob");
+        fs::write(&blob, [0u8, 159, 146, 150]).unwrap();
+
+        let processor = FileProcessor::new();
+        let info = processor.analyze_file(&renamed).unwrap();
+        assert_eq!(info.mime_type, "image/png");
+        assert!(info.is_binary);
+
+        let info = processor.analyze_file(&text).unwrap();
+        assert_eq!(info.mime_type, "text/plain");
+        assert!(!info.is_binary);
+
+        let info = processor.analyze_file(&blob).unwrap();
+        assert_eq!(info.mime_type, "application/octet-stream");
+        assert!(info.is_binary);
+    }
+
+    #[test]
+    fn test_parallel_hash_matches_serial() {
+        let temp_dir = TempDir::new().unwrap();
+        let sizes = [0, 1, HASH_CHUNK_SIZE, HASH_CHUNK_SIZE * 3 + 7];
+        for (i, size) in sizes.into_iter().enumerate() {
+            let data: Vec<u8> = (0..size).map(|b| (b * (i + 1)) as u8).collect();
+            fs::write(temp_dir.path().join(format!("file{i}.bin")), data).unwrap();
+        }
+        let files = FileProcessor::new().process_path(temp_dir.path()).unwrap();
+
+        let serial = FileHasher::hash_serial(&files).unwrap();
+        assert_eq!(serial.len(), 4);
+        assert_eq!(FileHasher::hash_parallel(&files, 2).unwrap(), serial);
+        assert_eq!(FileHasher::hash_parallel(&files, 0).unwrap(), serial);
+
+        for hash in &serial {
+            let content = fs::read(&hash.path).unwrap();
+            assert_eq!(
+                hash.hash,
+                encryptor_wasi::hash_to_hex(&encryptor_wasi::hash_content_bytes(&content))
+            );
+        }
+    }
+
+    /// Highest number of `files` in flight at once when processed with `max_concurrent`
+    async fn peak_concurrency(files: Vec<FileInfo>, max_concurrent: usize) -> usize {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let (counter, highest) = (in_flight.clone(), peak.clone());
+        let result = BatchProcessor::process_files(
+            files,
+            move |_file_info| {
+                let (in_flight, peak) = (counter.clone(), highest.clone());
+                async move {
+                    let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    Ok(())
+                }
+            },
+            max_concurrent,
+            false,
+            None,
+        )
+        .await;
+        assert!(result.failed.is_empty());
+        peak.load(Ordering::SeqCst)
+    }
+
+    fn placeholder_files(count: usize) -> Vec<FileInfo> {
+        (0..count)
+            .map(|i| FileInfo {
+                path: PathBuf::from(format!("file{i}.txt")),
+                size: 1,
+                mime_type: "text/plain".to_string(),
+                is_binary: false,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_max_concurrent_one_runs_sequentially() {
+        assert_eq!(peak_concurrency(placeholder_files(6), 1).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_higher_max_concurrent_allows_overlap() {
+        let peak = peak_concurrency(placeholder_files(6), 3).await;
+        assert!(peak > 1 && peak <= 3, "peak concurrency {peak}");
+    }
+}
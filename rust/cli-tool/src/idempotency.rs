@@ -0,0 +1,141 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long a completed operation is remembered for retries
+pub const IDEMPOTENCY_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+/// Generate a fresh client-side idempotency key
+pub fn new_idempotency_key() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+/// Remembers the results of recent operations by idempotency key
+///
+/// Calls with the same key are serialized; once one succeeds, later calls
+/// inside the window get its result back without running the operation again.
+/// Failed attempts are not remembered, so they can be retried. Each key is
+/// tied to a fingerprint of the request it was first used for, and reusing it
+/// for a different request is an error rather than a hit.
+pub struct IdempotencyCache<T> {
+    window: Duration,
+    slots: Mutex<HashMap<String, Arc<Slot<T>>>>,
+}
+
+struct Slot<T> {
+    created_at: Instant,
+    fingerprint: String,
+    result: tokio::sync::Mutex<Option<T>>,
+}
+
+impl<T: Clone> IdempotencyCache<T> {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            slots: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Run `operation` unless a result for `key` is already known
+    ///
+    /// `fingerprint` identifies the request's inputs; it must match the one
+    /// the key was first used with.
+    pub async fn run<F, Fut>(&self, key: &str, fingerprint: &str, operation: F) -> Result<T>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        if key.trim().is_empty() {
+            anyhow::bail!("Idempotency key cannot be empty");
+        }
+
+        let slot = {
+            let mut slots = self.slots.lock().unwrap();
+            slots.retain(|_, slot| slot.created_at.elapsed() < self.window);
+            slots
+                .entry(key.to_string())
+                .or_insert_with(|| {
+                    Arc::new(Slot {
+                        created_at: Instant::now(),
+                        fingerprint: fingerprint.to_string(),
+                        result: tokio::sync::Mutex::new(None),
+                    })
+                })
+                .clone()
+        };
+        if slot.fingerprint != fingerprint {
+            anyhow::bail!("Idempotency key {key} was already used for a different request");
+        }
+
+        let mut result = slot.result.lock().await;
+        if let Some(ref existing) = *result {
+            return Ok(existing.clone());
+        }
+
+        let value = operation().await?;
+        *result = Some(value.clone());
+        Ok(value)
+    }
+}
+
+impl<T: Clone> Default for IdempotencyCache<T> {
+    fn default() -> Self {
+        Self::new(IDEMPOTENCY_WINDOW)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_failed_attempt_can_be_retried() {
+        let cache = IdempotencyCache::default();
+        let calls = AtomicUsize::new(0);
+        let key = new_idempotency_key();
+
+        let first: Result<usize> = cache
+            .run(&key, "request", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                anyhow::bail!("response lost")
+            })
+            .await;
+        assert!(first.is_err());
+
+        let second = cache
+            .run(&key, "request", || async {
+                Ok(calls.fetch_add(1, Ordering::SeqCst))
+            })
+            .await
+            .unwrap();
+        let third = cache
+            .run(&key, "request", || async { Ok(99) })
+            .await
+            .unwrap();
+
+        assert_eq!(second, 1);
+        assert_eq!(third, 1);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_expired_key_runs_again() {
+        let cache = IdempotencyCache::new(Duration::ZERO);
+
+        assert_eq!(cache.run("key", "a", || async { Ok(1) }).await.unwrap(), 1);
+        assert_eq!(cache.run("key", "b", || async { Ok(2) }).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_key_reused_for_different_request_rejected() {
+        let cache = IdempotencyCache::default();
+
+        assert_eq!(cache.run("key", "a", || async { Ok(1) }).await.unwrap(), 1);
+        let error = cache.run("key", "b", || async { Ok(2) }).await.unwrap_err();
+        assert!(error.to_string().contains("different request"), "{error}");
+        assert_eq!(cache.run("key", "a", || async { Ok(3) }).await.unwrap(), 1);
+    }
+}
@@ -0,0 +1,136 @@
+use serde::Serialize;
+use std::io::Cursor;
+
+use crate::archive::read_archive;
+
+/// MIME type reported for multi-file capsule bundles
+pub const BUNDLE_MIME_TYPE: &str = "application/x-capsule-bundle";
+
+/// What decrypted content looks like, derived without writing it anywhere
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ContentInfo {
+    pub mime_type: String,
+    pub size: u64,
+    pub is_text: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_dimensions: Option<(u32, u32)>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pdf_pages: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bundle_entries: Option<Vec<String>>,
+}
+
+/// Detect the format of decrypted content from its magic bytes
+///
+/// Bundles are not detected that way: `bundle` says whether the capsule's
+/// envelope records the content as one.
+pub fn inspect_content(content: &[u8], bundle: bool) -> ContentInfo {
+    let is_text = !content.is_empty() && std::str::from_utf8(content).is_ok();
+    let mime_type = if bundle {
+        BUNDLE_MIME_TYPE.to_string()
+    } else if let Some(kind) = infer::get(content) {
+        kind.mime_type().to_string()
+    } else if is_text {
+        "text/plain".to_string()
+    } else {
+        "application/octet-stream".to_string()
+    };
+
+    let image_dimensions = match mime_type.as_str() {
+        "image/png" | "image/jpeg" => image::ImageReader::new(Cursor::new(content))
+            .with_guessed_format()
+            .ok()
+            .and_then(|reader| reader.into_dimensions().ok()),
+        _ => None,
+    };
+
+    let pdf_pages = (mime_type == "application/pdf").then(|| count_pdf_pages(content));
+
+    let bundle_entries = (mime_type == BUNDLE_MIME_TYPE)
+        .then(|| read_archive(content).ok())
+        .flatten()
+        .map(|entries| entries.into_iter().map(|entry| entry.name).collect());
+
+    ContentInfo {
+        mime_type,
+        size: content.len() as u64,
+        is_text,
+        image_dimensions,
+        pdf_pages,
+        bundle_entries,
+    }
+}
+
+/// Count page objects in a PDF
+///
+/// This scans for `/Type /Page` dictionaries rather than parsing the document,
+/// so compressed object streams can hide pages from it.
+fn count_pdf_pages(content: &[u8]) -> usize {
+    const MARKER: &[u8] = b"/Type";
+
+    let mut pages = 0;
+    let mut rest = content;
+    while let Some(pos) = rest.windows(MARKER.len()).position(|w| w == MARKER) {
+        rest = &rest[pos + MARKER.len()..];
+        let value = rest.trim_ascii_start();
+        // Only "/Page", not "/Pages" (the page tree node)
+        if value.starts_with(b"/Page") && !value[5..].first().is_some_and(u8::is_ascii_alphanumeric)
+        {
+            pages += 1;
+        }
+    }
+    pages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageBuffer, ImageFormat, Rgb};
+
+    #[test]
+    fn test_png_reports_dimensions() {
+        let png: ImageBuffer<Rgb<u8>, Vec<u8>> = ImageBuffer::new(7, 3);
+        let mut bytes = Vec::new();
+        png.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .unwrap();
+
+        let info = inspect_content(&bytes, false);
+        assert_eq!(info.mime_type, "image/png");
+        assert_eq!(info.image_dimensions, Some((7, 3)));
+        assert_eq!(info.size, bytes.len() as u64);
+        assert!(!info.is_text);
+    }
+
+    #[test]
+    fn test_text_and_pdf() {
+        let info = inspect_content(b"just some notes\n", false);
+        assert_eq!(info.mime_type, "text/plain");
+        assert!(info.is_text);
+
+        let pdf = b"%PDF-1.4\n1 0 obj << /Type /Pages /Count 2 >> endobj\n\
+                    2 0 obj << /Type /Page >> endobj\n3 0 obj << /Type/Page >> endobj\n";
+        let info = inspect_content(pdf, false);
+        assert_eq!(info.mime_type, "application/pdf");
+        assert_eq!(info.pdf_pages, Some(2));
+    }
+
+    #[test]
+    fn test_bundle_taken_from_envelope_not_content() {
+        let bundle = crate::archive::encode_archive(&[crate::archive::ArchiveEntry {
+            name: "a.txt".to_string(),
+            data: b"hello".to_vec(),
+            mtime: None,
+            mode: None,
+        }])
+        .unwrap();
+
+        let info = inspect_content(&bundle, true);
+        assert_eq!(info.mime_type, BUNDLE_MIME_TYPE);
+        assert_eq!(info.bundle_entries, Some(vec!["a.txt".to_string()]));
+
+        // The same bytes from a capsule that is not a bundle are just data
+        let info = inspect_content(&bundle, false);
+        assert_ne!(info.mime_type, BUNDLE_MIME_TYPE);
+        assert_eq!(info.bundle_entries, None);
+    }
+}
@@ -0,0 +1,116 @@
+use crate::temp::write_via_temp;
+use anyhow::{Context, Result};
+use dialoguer::Password;
+use encryptor_wasi::{
+    decrypt_content_with_password, encrypt_content_with_password, Argon2Params,
+    PasswordEncryptionResult, Zeroizing,
+};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// Name of the encrypted private key file inside the config directory
+pub const KEYSTORE_FILE_NAME: &str = "keystore";
+
+/// Environment variable holding the keystore passphrase, so scripts need no prompt
+pub const KEY_PASSPHRASE_ENV: &str = "CAPSULE_KEY_PASSPHRASE";
+
+/// Layout version of the keystore file
+const KEYSTORE_VERSION: u32 = 1;
+
+/// On-disk form of the keystore
+#[derive(Serialize, Deserialize)]
+struct Keystore {
+    version: u32,
+    /// The private key, encrypted under an Argon2id-derived key
+    encrypted: PasswordEncryptionResult,
+}
+
+/// Encrypt `private_key` with `passphrase` and write it to `path`, readable
+/// only by the owner
+pub fn write_keystore(
+    temp_dir: &Path,
+    path: &Path,
+    private_key: &str,
+    passphrase: &str,
+) -> Result<()> {
+    let encrypted =
+        encrypt_content_with_password(private_key.as_bytes(), passphrase, Argon2Params::default())
+            .context("Failed to encrypt private key")?;
+    let data = serde_json::to_vec_pretty(&Keystore {
+        version: KEYSTORE_VERSION,
+        encrypted,
+    })
+    .context("Failed to serialize keystore")?;
+    write_via_temp(temp_dir, path, &data)
+        .with_context(|| format!("Failed to write keystore: {}", path.display()))
+}
+
+/// Decrypt the private key stored at `path`
+pub fn read_keystore(path: &Path, passphrase: &str) -> Result<Zeroizing<String>> {
+    let data =
+        fs::read(path).with_context(|| format!("Failed to read keystore: {}", path.display()))?;
+    let keystore: Keystore = serde_json::from_slice(&data)
+        .with_context(|| format!("Failed to parse keystore: {}", path.display()))?;
+    if keystore.version != KEYSTORE_VERSION {
+        anyhow::bail!(
+            "Keystore uses layout v{} but this build reads v{KEYSTORE_VERSION}",
+            keystore.version
+        );
+    }
+
+    let encrypted = keystore.encrypted;
+    let decrypted = decrypt_content_with_password(
+        &encrypted.ciphertext,
+        &encrypted.nonce,
+        passphrase,
+        &encrypted.kdf,
+    )
+    .context("Failed to decrypt keystore (wrong passphrase?)")?;
+    let private_key =
+        String::from_utf8(decrypted.content.to_vec()).context("Keystore does not hold a key")?;
+    Ok(Zeroizing::new(private_key))
+}
+
+/// The keystore passphrase from `CAPSULE_KEY_PASSPHRASE`, else prompted for,
+/// asking twice when `confirm` is set
+pub fn keystore_passphrase(confirm: bool) -> Result<String> {
+    if let Ok(passphrase) = env::var(KEY_PASSPHRASE_ENV) {
+        return Ok(passphrase);
+    }
+    let mut prompt = Password::new().with_prompt("Keystore passphrase");
+    if confirm {
+        prompt = prompt.with_confirmation("Confirm passphrase", "Passphrases do not match");
+    }
+    Ok(prompt.interact()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const KEY: &str = "suiprivkey1qzdlfxn2qa2lj5uprl8pyhexs02sg2wrhdy7qaq50cqgnffw4c2477kg9h3";
+
+    #[test]
+    fn test_keystore_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(KEYSTORE_FILE_NAME);
+
+        write_keystore(temp_dir.path(), &path, KEY, "correct horse").unwrap();
+
+        let stored = fs::read_to_string(&path).unwrap();
+        assert!(!stored.contains(KEY));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        assert_eq!(*read_keystore(&path, "correct horse").unwrap(), KEY);
+        let err = read_keystore(&path, "battery staple").unwrap_err();
+        assert!(err.to_string().contains("wrong passphrase"), "{err}");
+    }
+}
@@ -1,9 +1,30 @@
 
+pub mod archive;
+pub mod audit;
 pub mod batch;
+pub mod capsule_ref;
+pub mod chain;
+pub mod chunking;
 pub mod commands;
+pub mod compression;
 pub mod config;
 pub mod file_processor;
+pub mod idempotency;
+pub mod inspect;
+pub mod keystore;
+pub mod logging;
+pub mod manifest;
+pub mod metadata;
+pub mod progress;
+pub mod qr;
+pub mod rate_limit;
+pub mod records;
 pub mod sdk;
+pub mod shares;
+pub mod storage;
+pub mod temp;
+pub mod theme;
+pub mod unixfs;
 pub mod utils;
 
 pub use batch::*;
@@ -27,15 +48,128 @@ mod tests {
     fn test_config_rpc_url() {
         let config = Config::default();
 
-        // Test that get_rpc_url returns the configured rpc_url
+        // Test that get_rpc_url follows the network when no URL is set
         assert_eq!(config.get_rpc_url(), "https://fullnode.devnet.sui.io:443");
 
         // Test custom RPC URL override
         let mut config_custom = config.clone();
-        config_custom.rpc_url = "https://custom.rpc.url".to_string();
+        config_custom.rpc_url = Some("https://custom.rpc.url".to_string());
         assert_eq!(config_custom.get_rpc_url(), "https://custom.rpc.url");
     }
 
+    #[test]
+    fn test_rpc_url_follows_network_without_override() {
+        for (network, expected) in [
+            ("mainnet", "https://fullnode.mainnet.sui.io:443"),
+            ("testnet", "https://fullnode.testnet.sui.io:443"),
+            ("devnet", "https://fullnode.devnet.sui.io:443"),
+            ("localnet", "http://127.0.0.1:9000"),
+        ] {
+            let config = Config {
+                network: network.to_string(),
+                ..Config::default()
+            };
+            assert_eq!(config.get_rpc_url(), expected, "{network}");
+        }
+
+        let config = Config {
+            network: "localnet".to_string(),
+            localnet_port: Some(9123),
+            ..Config::default()
+        };
+        assert_eq!(config.get_rpc_url(), "http://127.0.0.1:9123");
+    }
+
+    #[test]
+    fn test_leftover_devnet_rpc_url_ignored_for_mainnet() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("capsule.toml");
+        std::fs::write(
+            &config_path,
+            r#"
+            network = "mainnet"
+            rpc_url = "https://fullnode.devnet.sui.io:443"
+            ipfs_url = "https://ipfs.infura.io:5001"
+            default_output_format = "human"
+            verbose = false
+            "#,
+        )
+        .unwrap();
+
+        let config = Config::load_from_file(&config_path).unwrap();
+        assert_eq!(config.rpc_url, None);
+        assert_eq!(config.get_rpc_url(), "https://fullnode.mainnet.sui.io:443");
+    }
+
+    #[test]
+    fn test_pinning_service_config() {
+        use crate::config::IpfsBackend;
+
+        let mut config: Config = toml::from_str(
+            r#"
+            network = "devnet"
+            ipfs_url = "https://ipfs.infura.io:5001"
+            default_output_format = "human"
+            verbose = false
+
+            [pinning_service]
+            service = "pinata"
+            jwt = "configured-jwt"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.pinning_service,
+            IpfsBackend::Pinata {
+                jwt: "configured-jwt".to_string()
+            }
+        );
+
+        // Naming the configured service keeps its token; another one needs its own
+        config.override_pinning(Some("pinata"), None).unwrap();
+        assert_eq!(config.pinning_service.token(), Some("configured-jwt"));
+        assert!(config.override_pinning(Some("web3storage"), None).is_err());
+        config
+            .override_pinning(Some("web3storage"), Some("w3-token".to_string()))
+            .unwrap();
+        assert_eq!(
+            config.pinning_service,
+            IpfsBackend::Web3Storage {
+                token: "w3-token".to_string()
+            }
+        );
+        assert!(config
+            .override_pinning(Some("node"), Some("token".to_string()))
+            .is_err());
+
+        // The IPFS node is the default and is left out of saved files
+        assert!(!toml::to_string(&Config::default())
+            .unwrap()
+            .contains("pinning_service"));
+    }
+
+    #[test]
+    fn test_per_command_output_format() {
+        let config: Config = toml::from_str(
+            r#"
+            network = "devnet"
+            rpc_url = "https://fullnode.devnet.sui.io:443"
+            ipfs_url = "https://ipfs.infura.io:5001"
+            default_output_format = "human"
+            verbose = false
+
+            [output_formats]
+            list = "json"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.output_format_for("list", None), "json");
+        assert_eq!(config.output_format_for("create", None), "human");
+        // An explicit --format still wins
+        assert_eq!(config.output_format_for("list", Some("csv")), "csv");
+    }
+
     #[tokio::test]
     async fn test_config_save_and_load() {
         let temp_dir = TempDir::new().unwrap();
@@ -56,4 +190,235 @@ mod tests {
         assert!(loaded_config.verbose);
         assert_eq!(loaded_config.package_id, Some("0x123".to_string()));
     }
+
+    #[test]
+    fn test_config_round_trips_through_each_format() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let original_config = Config {
+            network: "testnet".to_string(),
+            ipfs_rate_limit: Some(2.5),
+            temp_dir: Some(temp_dir.path().join("staging")),
+            output_formats: [("list".to_string(), "json".to_string())].into(),
+            ..Default::default()
+        };
+        let expected = serde_json::to_value(&original_config).unwrap();
+
+        for name in ["config.toml", "config.json", "config.yaml", "config.yml"] {
+            let config_path = temp_dir.path().join(name);
+            original_config.save_to_file(&config_path).unwrap();
+
+            let loaded_config = Config::load_from_file(&config_path).unwrap();
+            assert_eq!(
+                serde_json::to_value(&loaded_config).unwrap(),
+                expected,
+                "{name}"
+            );
+        }
+
+        // The format follows the extension, not the content
+        let json_path = temp_dir.path().join("config.json");
+        let content = std::fs::read_to_string(&json_path).unwrap();
+        assert!(content.trim_start().starts_with('{'));
+    }
+
+    #[test]
+    fn test_unknown_config_extension_reads_toml() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("capsule.conf");
+        std::fs::write(
+            &config_path,
+            r#"
+            network = "mainnet"
+            rpc_url = "https://fullnode.mainnet.sui.io:443"
+            ipfs_url = "https://ipfs.infura.io:5001"
+            default_output_format = "json"
+            verbose = false
+            "#,
+        )
+        .unwrap();
+
+        let config = Config::load_from_file(&config_path).unwrap();
+        assert_eq!(config.network, "mainnet");
+        assert_eq!(config.default_output_format, "json");
+    }
+
+    #[test]
+    fn test_config_dir_redirects_save_and_load() {
+        let temp_dir = TempDir::new().unwrap();
+        let saved = Config {
+            network: "testnet".to_string(),
+            ..Config::default()
+        };
+        saved
+            .save_to_file(&temp_dir.path().join(config::CONFIG_FILE_NAME))
+            .unwrap();
+
+        let config = Config::load(
+            None,
+            Some(temp_dir.path()),
+            None,
+            None,
+            None,
+            None,
+            None,
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(config.network, "testnet");
+        assert_eq!(config.config_dir(), temp_dir.path());
+        assert_eq!(
+            config.audit_log_path,
+            Some(temp_dir.path().join(audit::AUDIT_LOG_FILE_NAME))
+        );
+        assert_eq!(
+            config.capsule_records_path,
+            Some(temp_dir.path().join(records::CAPSULE_RECORDS_FILE_NAME))
+        );
+    }
+
+    #[test]
+    fn test_config_profiles() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join(config::CONFIG_FILE_NAME),
+            r#"
+            network = "devnet"
+            ipfs_url = "https://ipfs.infura.io:5001"
+            package_id = "0xde5"
+            default_output_format = "human"
+            verbose = false
+
+            [profiles.work]
+            network = "mainnet"
+            package_id = "0xa11ce"
+            private_key_path = "/keys/work.key"
+
+            [profiles.personal]
+            network = "testnet"
+            rpc_url = "https://rpc.example.com"
+            package_id = "0xb0b"
+            "#,
+        )
+        .unwrap();
+        let load = |profile| {
+            Config::load(
+                None,
+                Some(temp_dir.path()),
+                profile,
+                None,
+                None,
+                None,
+                None,
+                false,
+            )
+        };
+
+        let base = load(None).unwrap();
+        assert_eq!(base.network, "devnet");
+        assert_eq!(base.package_id.as_deref(), Some("0xde5"));
+        assert_eq!(base.profile, None);
+
+        let work = load(Some("work")).unwrap();
+        assert_eq!(work.network, "mainnet");
+        assert_eq!(work.package_id.as_deref(), Some("0xa11ce"));
+        assert_eq!(work.get_rpc_url(), "https://fullnode.mainnet.sui.io:443");
+        assert_eq!(work.private_key_path, Some("/keys/work.key".into()));
+        assert_eq!(work.profile.as_deref(), Some("work"));
+        // Settings the profile leaves alone come from the rest of the file
+        assert_eq!(work.ipfs_url, "https://ipfs.infura.io:5001");
+        assert_eq!(work.config_dir(), temp_dir.path());
+
+        let personal = load(Some("personal")).unwrap();
+        assert_eq!(personal.network, "testnet");
+        assert_eq!(personal.package_id.as_deref(), Some("0xb0b"));
+        assert_eq!(personal.get_rpc_url(), "https://rpc.example.com");
+        assert_eq!(personal.private_key_path, None);
+
+        let err = load(Some("home")).unwrap_err().to_string();
+        assert!(err.contains("personal, work"), "{err}");
+    }
+    #[test]
+    fn test_private_key_never_saved() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join(config::CONFIG_FILE_NAME);
+        let config = Config {
+            private_key: Some("suiprivkey1secret".to_string()),
+            ..Config::default()
+        };
+
+        config.save_to_file(&config_path).unwrap();
+        let saved = std::fs::read_to_string(&config_path).unwrap();
+        assert!(!saved.contains("suiprivkey1secret"));
+
+        // Files written by older versions still load
+        std::fs::write(
+            &config_path,
+            format!("{saved}\nprivate_key = \"suiprivkey1old\"\n"),
+        )
+        .unwrap();
+        let loaded = Config::load_from_file(&config_path).unwrap();
+        assert_eq!(loaded.private_key.as_deref(), Some("suiprivkey1old"));
+    }
+
+    #[test]
+    fn test_config_validation() {
+        let error = |config: Config| format!("{:#}", config.validate().unwrap_err());
+
+        assert!(Config::default().validate().is_ok());
+
+        // An unknown network is a typo unless a custom RPC endpoint is given
+        let typo = Config {
+            network: "mainet".to_string(),
+            ..Config::default()
+        };
+        assert!(error(typo.clone()).contains("Invalid network \"mainet\""));
+        Config {
+            rpc_url: Some("http://10.0.0.5:9000".to_string()),
+            ..typo
+        }
+        .validate()
+        .unwrap();
+
+        let bad_url = Config {
+            rpc_url: Some("fullnode.mainnet.sui.io".to_string()),
+            ..Config::default()
+        };
+        assert!(error(bad_url).contains("Invalid rpc_url"));
+        let bad_scheme = Config {
+            ipfs_url: "ftp://ipfs.example.com".to_string(),
+            ..Config::default()
+        };
+        assert!(error(bad_scheme).contains("Invalid ipfs_url"));
+
+        for package_id in ["1234", "0x12g4", "0x"] {
+            let bad_package = Config {
+                package_id: Some(package_id.to_string()),
+                ..Config::default()
+            };
+            assert!(error(bad_package).contains("Invalid package_id"));
+        }
+
+        // Loading rejects the same mistakes
+        let temp_dir = TempDir::new().unwrap();
+        Config {
+            package_id: Some("package".to_string()),
+            ..Config::default()
+        }
+        .save_to_file(&temp_dir.path().join(config::CONFIG_FILE_NAME))
+        .unwrap();
+        let err = Config::load(
+            None,
+            Some(temp_dir.path()),
+            None,
+            None,
+            None,
+            None,
+            None,
+            false,
+        )
+        .unwrap_err();
+        assert!(format!("{err:#}").contains("Invalid package_id"));
+    }
 }
@@ -0,0 +1,128 @@
+use env_logger::fmt::Formatter;
+use env_logger::{Builder, Target, WriteStyle};
+use indicatif::MultiProgress;
+use log::Record;
+use std::io::{self, Write};
+use std::sync::OnceLock;
+
+static PROGRESS_BARS: OnceLock<MultiProgress> = OnceLock::new();
+
+/// How log records are written to stderr, as chosen with `--log-format`
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// `env_logger`'s usual human-readable lines
+    #[default]
+    Text,
+    /// One JSON object per line, with timestamp, level, target and message
+    Json,
+}
+
+/// Install the logger, filtered by `RUST_LOG`
+pub fn init_logging(format: LogFormat) {
+    match format {
+        LogFormat::Text => env_logger::init(),
+        LogFormat::Json => json_logger(Target::Pipe(Box::new(ProgressAwareStderr))).init(),
+    }
+}
+
+/// Progress bars drawn on stderr
+///
+/// JSON log lines are written while the bars are cleared, so neither ends up
+/// in the middle of the other.
+pub fn progress_bars() -> &'static MultiProgress {
+    PROGRESS_BARS.get_or_init(MultiProgress::new)
+}
+
+fn json_logger(target: Target) -> Builder {
+    let mut builder = Builder::from_default_env();
+    builder
+        .format(write_json_line)
+        .target(target)
+        .write_style(WriteStyle::Never);
+    builder
+}
+
+fn write_json_line(buf: &mut Formatter, record: &Record) -> io::Result<()> {
+    let line = serde_json::json!({
+        "timestamp": buf.timestamp_millis().to_string(),
+        "level": record.level().as_str(),
+        "target": record.target(),
+        "message": record.args().to_string(),
+    });
+    writeln!(buf, "{line}")
+}
+
+/// Stderr, with the progress bars suspended around each write
+struct ProgressAwareStderr;
+
+impl Write for ProgressAwareStderr {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        progress_bars().suspend(|| io::stderr().lock().write_all(buf))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stderr().flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::{Level, LevelFilter, Log};
+    use std::sync::{Arc, Mutex};
+
+    /// Log output kept in memory
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_json_log_lines() {
+        let captured = Captured::default();
+        let logger = json_logger(Target::Pipe(Box::new(captured.clone())))
+            .filter_level(LevelFilter::Debug)
+            .build();
+
+        for (level, message) in [
+            (Level::Info, "Uploading 3 files"),
+            (Level::Warn, "Gateway said \"no\"\nretrying"),
+            (Level::Debug, "done"),
+        ] {
+            logger.log(
+                &Record::builder()
+                    .level(level)
+                    .target("capsule_cli::sdk")
+                    .args(format_args!("{message}"))
+                    .build(),
+            );
+        }
+
+        let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        for line in &lines {
+            let object = line.as_object().unwrap();
+            let mut keys: Vec<&str> = object.keys().map(String::as_str).collect();
+            keys.sort();
+            assert_eq!(keys, ["level", "message", "target", "timestamp"]);
+            assert_eq!(line["target"], "capsule_cli::sdk");
+            assert!(line["timestamp"].as_str().unwrap().ends_with('Z'));
+        }
+        assert_eq!(lines[1]["level"], "WARN");
+        assert_eq!(lines[1]["message"], "Gateway said \"no\"\nretrying");
+    }
+}
@@ -1,15 +1,24 @@
 use clap::{Parser, Subcommand};
+use indicatif::ProgressDrawTarget;
 use std::env;
 use std::path::PathBuf;
 
 use capsule_cli::{
+    capsule_ref::{resolve_capsule_ref, resolve_capsule_refs},
     commands::{
         handle_approve, handle_approve_interactive, handle_batch, handle_batch_interactive,
-        handle_create, handle_list, handle_list_interactive, handle_list_pending_approvals,
-        handle_unlock, handle_unlock_interactive, ApproveArgs, BatchArgs, CapsuleType, CreateArgs,
-        ListArgs, UnlockArgs,
+        handle_bench, handle_cancel, handle_create, handle_dashboard, handle_diff, handle_doctor,
+        handle_export, handle_history, handle_import, handle_key, handle_list,
+        handle_list_interactive, handle_list_pending_approvals, handle_networks, handle_reshare,
+        handle_revoke, handle_revoke_approval, handle_selftest, handle_status, handle_unlock,
+        handle_unlock_interactive, handle_verify, handle_watch, ApproveArgs, BatchArgs, BenchArgs,
+        CancelArgs, CapsuleType, CreateArgs, DashboardArgs, DiffArgs, DoctorArgs, ExportArgs,
+        HistoryArgs, ImportArgs, KeyArgs, ListArgs, NetworksArgs, ReshareArgs, RevokeApprovalArgs,
+        RevokeArgs, SelfTestArgs, StatusArgs, UnlockArgs, VerifyArgs, WatchArgs,
     },
     config::{handle_config_command, Config},
+    logging::{init_logging, progress_bars, LogFormat},
+    theme::{init_style, init_theme, theme, ColorChoice, StyleContext, Theme},
 };
 
 #[derive(Parser)]
@@ -22,20 +31,57 @@ Time capsules allow you to encrypt and store content with various unlock conditi
 - Time-based: Unlock after a specific timestamp
 - Multisig: Require multiple approvals to unlock
 - Payment: Require payment to unlock
+- Composite: Combine the above, requiring all or any of them
 
 Examples:
     capsule create -f document.pdf -c time -t 1h
+    capsule create -f letter.txt -c time -t 2030-01-01T00:00:00Z
     capsule create -f secret.txt -c multisig --threshold 3 --approvers addr1,addr2,addr3
     capsule create -f image.jpg -c payment -p 1000000000
+    capsule create -f will.pdf -c composite -t 365d --threshold 2 --approvers addr1,addr2
+    capsule create -f video.mp4 -c time -t 30d --estimate-only
+    capsule create -f server.log -c time -t 90d --compress zstd
+    capsule create -f archive.tar -c time -t 10y --max-size 10000000000 --chunk-size 67108864
+    capsule create -f project/ -r -c time -t 1y --include '*.rs' --exclude '**/target/**'
+    capsule create -f notes.txt -c time -t 7d --pinning-service pinata --pinning-token <jwt>
+    capsule create -f letter.txt -c time -t 1y --qr --qr-output key.png
     capsule list
+    capsule list --local
+    capsule list --profile work
+    capsule dashboard
+    capsule status --ids 0xabc,0xdef
+    capsule diff 0xabc 0xdef
     capsule unlock -c abc123def456 -e <encryption_key>
-    capsule approve -c abc123def456
+    capsule verify -c abc123def456 -e <encryption_key>
+    capsule watch -c abc123def456 --interval 1m
+    capsule approve -c abc123def456 --gas-budget 20000000
+    capsule revoke-approval -c abc123def456
+    capsule revoke --capsule-id 0xabc123def456
+    capsule cancel -c 0xabc123def456 --unpin
+    capsule reshare --capsule-id 0xabc --share <share> --share <share> --threshold 3 --count 5
+    capsule history --since 7d --type unlock
+    capsule export --output capsules.json --encrypt
+    capsule import capsules.json
+    capsule key import --file sui.key
+    capsule selftest
+    capsule bench --size 64
+    capsule networks --format json
+    capsule doctor
 ")]
 struct Cli {
     /// Configuration file path
     #[arg(short, long, global = true)]
     config: Option<PathBuf>,
 
+    /// Directory for the config file, audit log and other state
+    #[arg(long, global = true)]
+    config_dir: Option<PathBuf>,
+
+    /// Config profile whose settings override the rest of the config file
+    /// (also CAPSULE_PROFILE)
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
     /// Sui network to use
     #[arg(short, long, global = true)]
     network: Option<String>,
@@ -48,6 +94,47 @@ struct Cli {
     #[arg(long, global = true)]
     ipfs_url: Option<String>,
 
+    /// Pinning service to upload through instead of the IPFS node (node, pinata, web3storage)
+    #[arg(long, global = true)]
+    pinning_service: Option<String>,
+
+    /// JWT or API token for the pinning service
+    #[arg(long, global = true)]
+    pinning_token: Option<String>,
+
+    /// Maximum IPFS requests per second
+    #[arg(long, global = true)]
+    ipfs_rate_limit: Option<f64>,
+
+    /// Maximum simultaneous IPFS requests, shared by all files in flight
+    #[arg(long, global = true)]
+    ipfs_max_connections: Option<usize>,
+
+    /// Gas budget in MIST for each transaction (estimated when not given)
+    #[arg(long, global = true)]
+    gas_budget: Option<u64>,
+
+    /// IPFS HTTP gateway to fetch content from when the node lacks it (repeatable)
+    #[arg(long = "ipfs-gateway", global = true)]
+    ipfs_gateways: Vec<String>,
+
+    /// Output color theme (default, light, high-contrast, none)
+    #[arg(long, global = true)]
+    theme: Option<String>,
+
+    /// When to color output; `auto` skips color when output is not a
+    /// terminal or NO_COLOR is set
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    /// Log line format; `json` writes one JSON object per line to stderr
+    #[arg(long, global = true, value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+
+    /// Directory for temporary files (defaults to the system temp dir)
+    #[arg(long, global = true)]
+    temp_dir: Option<PathBuf>,
+
     /// Private key file path
     #[arg(long, global = true)]
     private_key_path: Option<PathBuf>,
@@ -60,6 +147,10 @@ struct Cli {
     #[arg(short, long, global = true)]
     verbose: bool,
 
+    /// Print only results, without headings, separators or progress bars
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -72,15 +163,66 @@ enum Commands {
     /// List time capsules
     List(ListArgs),
 
+    /// Show the status of specific capsules
+    Status(StatusArgs),
+
+    /// Browse, filter and approve capsules in a terminal dashboard
+    Dashboard(DashboardArgs),
+
+    /// Compare two capsules' content hash, CID and unlock condition
+    Diff(DiffArgs),
+
     /// Unlock a time capsule
     Unlock(UnlockArgs),
 
+    /// Check that a capsule's stored content is intact, without unlocking it
+    Verify(VerifyArgs),
+
+    /// Wait until a capsule can be unlocked, showing what it is waiting on
+    Watch(WatchArgs),
+
     /// Approve a multisig capsule
     Approve(ApproveArgs),
 
+    /// Withdraw your approval of a multisig capsule before it unlocks
+    RevokeApproval(RevokeApprovalArgs),
+
+    /// Permanently revoke a capsule you own so it can never be unlocked
+    Revoke(RevokeArgs),
+
+    /// Destroy a capsule you own, optionally unpinning its content
+    Cancel(CancelArgs),
+
+    /// Redistribute a capsule's key shares under a new threshold, without re-encrypting
+    Reshare(ReshareArgs),
+
     /// Batch operations on multiple files
     Batch(BatchArgs),
 
+    /// Show the local audit log of past operations
+    History(HistoryArgs),
+
+    /// Write the capsules this installation knows about to a bundle file
+    Export(ExportArgs),
+
+    /// Merge capsules from a bundle written by `export`
+    Import(ImportArgs),
+
+    /// Manage the encrypted private key keystore
+    Key(KeyArgs),
+
+    /// Run offline known-answer tests of the encryption primitives
+    Selftest(SelfTestArgs),
+
+    /// Measure local encryption and hashing throughput
+    Bench(BenchArgs),
+
+    /// List supported networks and the RPC URL each resolves to
+    Networks(NetworksArgs),
+
+    /// Check the configuration, endpoints, private key and clock
+    Doctor(DoctorArgs),
+
     /// Interactive commands
     #[command(subcommand)]
     Interactive(InteractiveCommands),
@@ -116,18 +258,23 @@ enum InteractiveCommands {
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
+    init_style(StyleContext::new(cli.color));
 
     // Initialize logging
     if cli.verbose {
         env::set_var("RUST_LOG", "debug");
+    } else if cli.quiet {
+        env::set_var("RUST_LOG", "warn");
     } else {
         env::set_var("RUST_LOG", "info");
     }
-    env_logger::init();
+    init_logging(cli.log_format);
 
     // Load configuration
     let mut config = Config::load(
         cli.config.as_deref(),
+        cli.config_dir.as_deref(),
+        cli.profile.as_deref(),
         cli.network.as_deref(),
         cli.rpc_url.as_ref(),
         cli.ipfs_url.as_ref(),
@@ -139,14 +286,61 @@ async fn main() -> anyhow::Result<()> {
     if let Some(private_key) = cli.private_key {
         config.private_key = Some(private_key);
     }
+    config.override_pinning(cli.pinning_service.as_deref(), cli.pinning_token)?;
+    if let Some(rate_limit) = cli.ipfs_rate_limit {
+        config.ipfs_rate_limit = Some(rate_limit);
+    }
+    if let Some(max_connections) = cli.ipfs_max_connections {
+        config.ipfs_max_connections = Some(max_connections);
+    }
+    if let Some(gas_budget) = cli.gas_budget {
+        config.gas_budget = Some(gas_budget);
+    }
+    if !cli.ipfs_gateways.is_empty() {
+        config.ipfs_gateways = cli.ipfs_gateways;
+    }
+    if let Some(theme) = cli.theme {
+        config.theme = Some(theme);
+    }
+    if let Some(temp_dir) = cli.temp_dir {
+        config.temp_dir = Some(temp_dir);
+    }
+    if cli.quiet {
+        config.quiet = true;
+        progress_bars().set_draw_target(ProgressDrawTarget::hidden());
+    }
+    init_theme(Theme::from_name(
+        config.theme.as_deref().unwrap_or("default"),
+    )?);
+
+    let mut command = cli.command;
+    let endpoint_explicit = cli.network.is_some() || cli.rpc_url.is_some();
+    resolve_command_refs(&mut command, &mut config, endpoint_explicit)?;
 
     // Execute command
-    match cli.command {
+    match command {
         Commands::Create(args) => handle_create(args, &config).await,
         Commands::List(args) => handle_list(args, &config).await,
+        Commands::Status(args) => handle_status(args, &config).await,
+        Commands::Dashboard(args) => handle_dashboard(args, &config).await,
+        Commands::Diff(args) => handle_diff(args, &config).await,
         Commands::Unlock(args) => handle_unlock(args, &config).await,
+        Commands::Verify(args) => handle_verify(args, &config).await,
+        Commands::Watch(args) => handle_watch(args, &config).await,
         Commands::Approve(args) => handle_approve(args, &config).await,
+        Commands::RevokeApproval(args) => handle_revoke_approval(args, &config).await,
+        Commands::Revoke(args) => handle_revoke(args, &config).await,
+        Commands::Cancel(args) => handle_cancel(args, &config).await,
+        Commands::Reshare(args) => handle_reshare(args, &config).await,
         Commands::Batch(args) => handle_batch(args, &config).await,
+        Commands::History(args) => handle_history(args, &config).await,
+        Commands::Export(args) => handle_export(args, &config).await,
+        Commands::Import(args) => handle_import(args, &config).await,
+        Commands::Key(args) => handle_key(args, &config),
+        Commands::Selftest(args) => handle_selftest(args, &config),
+        Commands::Bench(args) => handle_bench(args, &config),
+        Commands::Networks(args) => handle_networks(args, &config).await,
+        Commands::Doctor(args) => handle_doctor(args, &config).await,
 
         Commands::Interactive(interactive_cmd) => {
             match interactive_cmd {
@@ -168,14 +362,57 @@ async fn main() -> anyhow::Result<()> {
     }
 }
 
+/// Replace capsule references in the arguments with bare IDs, following the
+/// network a `capsule://` URI names unless the endpoint was given explicitly
+fn resolve_command_refs(
+    command: &mut Commands,
+    config: &mut Config,
+    endpoint_explicit: bool,
+) -> anyhow::Result<()> {
+    match command {
+        Commands::Unlock(args) => {
+            args.capsule_id = resolve_capsule_ref(config, &args.capsule_id, endpoint_explicit)?;
+        }
+        Commands::Verify(args) => {
+            args.capsule_id = resolve_capsule_ref(config, &args.capsule_id, endpoint_explicit)?;
+        }
+        Commands::Watch(args) => {
+            args.capsule_id = resolve_capsule_ref(config, &args.capsule_id, endpoint_explicit)?;
+        }
+        Commands::Approve(args) => {
+            args.capsule_id = resolve_capsule_ref(config, &args.capsule_id, endpoint_explicit)?;
+        }
+        Commands::RevokeApproval(args) => {
+            args.capsule_id = resolve_capsule_ref(config, &args.capsule_id, endpoint_explicit)?;
+        }
+        Commands::Revoke(args) => {
+            args.capsule_id = resolve_capsule_ref(config, &args.capsule_id, endpoint_explicit)?;
+        }
+        Commands::Cancel(args) => {
+            args.capsule_id = resolve_capsule_ref(config, &args.capsule_id, endpoint_explicit)?;
+        }
+        Commands::Reshare(args) => {
+            args.capsule_id = resolve_capsule_ref(config, &args.capsule_id, endpoint_explicit)?;
+        }
+        Commands::Diff(args) => {
+            let refs = [args.first.clone(), args.second.clone()];
+            let ids = resolve_capsule_refs(config, &refs, endpoint_explicit)?;
+            args.first = ids[0].clone();
+            args.second = ids[1].clone();
+        }
+        Commands::Status(args) => {
+            args.ids = resolve_capsule_refs(config, &args.ids, endpoint_explicit)?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
 async fn handle_interactive_create(config: &Config) -> anyhow::Result<()> {
     use dialoguer::{Confirm, Input, Select};
     use std::path::PathBuf;
 
-    println!(
-        "{}",
-        console::style("Interactive Capsule Creation").bold().cyan()
-    );
+    println!("{}", theme().heading("Interactive Capsule Creation"));
     println!("{}", "=".repeat(50));
 
     // Get file path
@@ -209,7 +446,7 @@ async fn handle_interactive_create(config: &Config) -> anyhow::Result<()> {
     let (unlock_time, threshold, approvers, price) = match capsule_type {
         CapsuleType::Time => {
             let time_input: String = Input::new()
-                .with_prompt("Enter unlock time (e.g., '1h', '30m', '2d')")
+                .with_prompt("Enter unlock time (e.g., '1h', '2d', '2030-12-31T23:59:59Z')")
                 .validate_with(|input: &String| -> Result<(), &str> {
                     if input.is_empty() {
                         Err("Unlock time cannot be empty")
@@ -268,6 +505,8 @@ async fn handle_interactive_create(config: &Config) -> anyhow::Result<()> {
                 .interact_text()?;
             (None, None, Vec::new(), Some(price))
         }
+        // Not offered above; composite capsules need several conditions as flags
+        CapsuleType::Composite => unreachable!(),
     };
 
     // Ask about recursive processing
@@ -282,16 +521,38 @@ async fn handle_interactive_create(config: &Config) -> anyhow::Result<()> {
 
     // Create args and execute
     let args = CreateArgs {
-        file: PathBuf::from(file),
+        file: Some(PathBuf::from(file)),
+        files: Vec::new(),
         capsule_type,
         unlock_time,
         threshold,
         approvers,
+        approvers_file: None,
         price,
+        combinator: Default::default(),
         recursive,
+        include_hidden: false,
+        follow_symlinks: false,
         max_size: 104857600, // 100MB default
         extensions: Vec::new(),
-        format: "human".to_string(),
+        include: Vec::new(),
+        exclude: Vec::new(),
+        format: Some("human".to_string()),
+        verify_after: false,
+        estimate_only: false,
+        preserve_metadata: false,
+        compress: Default::default(),
+        chunk_size: None,
+        escrow_pubkey: None,
+        timings: false,
+        key: None,
+        strict: false,
+        password: None,
+        qr: false,
+        qr_output: None,
+        qr_include_id: false,
+        key_out: None,
+        force: false,
     };
 
     handle_create(args, config).await
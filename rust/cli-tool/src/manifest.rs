@@ -0,0 +1,126 @@
+use anyhow::{Context, Result};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// One file a batch finished, with what is needed to unlock its capsule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Canonical path of the input file
+    pub path: PathBuf,
+    pub capsule_id: String,
+    pub cid: String,
+    pub encryption_key: String,
+}
+
+impl ManifestEntry {
+    pub fn new(path: &Path, capsule_id: &str, cid: &str, encryption_key: &str) -> Self {
+        Self {
+            path: canonical_path(path),
+            capsule_id: capsule_id.to_string(),
+            cid: cid.to_string(),
+            encryption_key: encryption_key.to_string(),
+        }
+    }
+}
+
+/// Append-only JSON lines record of a batch's completed files
+///
+/// Re-running the batch with the same manifest skips the files it lists, so
+/// an interrupted batch resumes where it stopped. Entries hold encryption
+/// keys, so the file is created readable only by the owner.
+#[derive(Debug, Clone)]
+pub struct BatchManifest {
+    path: PathBuf,
+}
+
+impl BatchManifest {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Canonical paths of the files already completed
+    ///
+    /// A missing manifest is treated as empty; malformed lines are skipped.
+    pub fn completed(&self) -> Result<HashSet<PathBuf>> {
+        if !self.path.exists() {
+            return Ok(HashSet::new());
+        }
+        Ok(self
+            .entries()?
+            .into_iter()
+            .map(|entry| entry.path)
+            .collect())
+    }
+
+    /// Encryption keys of the capsules the manifest lists, by capsule ID
+    pub fn keys(&self) -> Result<BTreeMap<String, String>> {
+        if !self.path.exists() {
+            anyhow::bail!("Manifest not found: {}", self.path.display());
+        }
+        Ok(self
+            .entries()?
+            .into_iter()
+            .map(|entry| (entry.capsule_id, entry.encryption_key))
+            .collect())
+    }
+
+    /// Every well-formed entry, skipping malformed lines
+    fn entries(&self) -> Result<Vec<ManifestEntry>> {
+        let content = fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read manifest: {}", self.path.display()))?;
+
+        let mut entries = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<ManifestEntry>(line) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => warn!("Skipping malformed manifest line {}: {}", index + 1, e),
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Append one completed file as a single JSON line
+    pub fn append(&self, entry: &ManifestEntry) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create manifest directory: {}", parent.display())
+            })?;
+        }
+
+        let mut line =
+            serde_json::to_string(entry).context("Failed to serialize manifest entry")?;
+        line.push('\n');
+
+        let mut options = OpenOptions::new();
+        options.create(true).append(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options
+            .open(&self.path)
+            .with_context(|| format!("Failed to open manifest: {}", self.path.display()))?;
+        file.write_all(line.as_bytes())
+            .with_context(|| format!("Failed to write manifest: {}", self.path.display()))?;
+
+        Ok(())
+    }
+}
+
+/// The path manifests key files by, so the same file matches however it was named
+pub fn canonical_path(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
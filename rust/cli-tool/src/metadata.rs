@@ -0,0 +1,191 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::path::Path;
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Magic prefix of plaintext that carries file metadata
+///
+/// Only checked once the capsule's envelope says metadata was attached, to
+/// catch a malformed payload; it is never used to detect metadata.
+pub const METADATA_MAGIC: &[u8; 8] = b"CAPSMETA";
+
+/// Original file attributes, encrypted together with the content
+///
+/// Prepended to the plaintext before encryption, so the AEAD protects it and
+/// nothing about the file leaks in the clear. The capsule's envelope records
+/// that it is there.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileMetadata {
+    /// File name without any directory components
+    pub name: String,
+    /// Modification time in milliseconds since the Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mtime: Option<u64>,
+    /// Unix permission bits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<u32>,
+}
+
+impl FileMetadata {
+    /// Read the name, modification time and permissions of a file
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| anyhow::anyhow!("Invalid file name: {}", path.display()))?
+            .to_string();
+        let metadata = fs::metadata(path)
+            .with_context(|| format!("Failed to read metadata: {}", path.display()))?;
+
+        let mtime = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|since| since.as_millis() as u64);
+        #[cfg(unix)]
+        let mode = {
+            use std::os::unix::fs::PermissionsExt;
+            Some(metadata.permissions().mode() & 0o777)
+        };
+        #[cfg(not(unix))]
+        let mode = None;
+
+        Ok(Self { name, mtime, mode })
+    }
+
+    /// Restore the modification time and permissions onto a written file
+    ///
+    /// Only the permission bits are restored; setuid, setgid and sticky bits
+    /// from a capsule are never applied.
+    pub fn apply(&self, path: &Path) -> Result<()> {
+        if let Some(mtime) = self.mtime {
+            let file = File::options()
+                .write(true)
+                .open(path)
+                .with_context(|| format!("Failed to open file: {}", path.display()))?;
+            file.set_modified(UNIX_EPOCH + Duration::from_millis(mtime))
+                .with_context(|| format!("Failed to set modification time: {}", path.display()))?;
+        }
+
+        #[cfg(unix)]
+        if let Some(mode) = self.mode {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o777))
+                .with_context(|| format!("Failed to set permissions: {}", path.display()))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Prepend file metadata to content
+///
+/// Layout: magic, u32 LE metadata length, JSON metadata, then the content.
+pub fn attach_metadata(metadata: &FileMetadata, content: &[u8]) -> Result<Vec<u8>> {
+    let metadata_bytes =
+        serde_json::to_vec(metadata).context("Failed to serialize file metadata")?;
+
+    let mut out =
+        Vec::with_capacity(METADATA_MAGIC.len() + 4 + metadata_bytes.len() + content.len());
+    out.extend_from_slice(METADATA_MAGIC);
+    out.extend_from_slice(&(metadata_bytes.len() as u32).to_le_bytes());
+    out.extend_from_slice(&metadata_bytes);
+    out.extend_from_slice(content);
+    Ok(out)
+}
+
+/// Separate file metadata from the content `attach_metadata` attached it to
+pub fn split_metadata(data: &[u8]) -> Result<(FileMetadata, &[u8])> {
+    if !data.starts_with(METADATA_MAGIC) {
+        anyhow::bail!("Content does not start with file metadata");
+    }
+
+    let header_len = METADATA_MAGIC.len() + 4;
+    if data.len() < header_len {
+        anyhow::bail!("File metadata is truncated");
+    }
+    let metadata_len =
+        u32::from_le_bytes(data[METADATA_MAGIC.len()..header_len].try_into().unwrap()) as usize;
+    let metadata_end = header_len
+        .checked_add(metadata_len)
+        .filter(|end| *end <= data.len())
+        .ok_or_else(|| anyhow::anyhow!("File metadata is truncated"))?;
+
+    let metadata: FileMetadata = serde_json::from_slice(&data[header_len..metadata_end])
+        .context("Failed to parse file metadata")?;
+    Ok((metadata, &data[metadata_end..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::write_via_temp;
+    use crate::utils::sanitize_relative_path;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_name_and_mtime_restored_on_unlock() {
+        let temp_dir = TempDir::new().unwrap();
+        let original = temp_dir.path().join("diary.txt");
+        fs::write(&original, b"dear diary").unwrap();
+        let mtime = UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        File::options()
+            .write(true)
+            .open(&original)
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+
+        let plaintext =
+            attach_metadata(&FileMetadata::from_path(&original).unwrap(), b"dear diary").unwrap();
+
+        // What unlock does with the decrypted plaintext
+        let (metadata, content) = split_metadata(&plaintext).unwrap();
+        let out_dir = temp_dir.path().join("out");
+        let restored = sanitize_relative_path(&out_dir, &metadata.name).unwrap();
+        write_via_temp(temp_dir.path(), &restored, content).unwrap();
+        metadata.apply(&restored).unwrap();
+
+        assert_eq!(restored, out_dir.join("diary.txt"));
+        assert_eq!(fs::read(&restored).unwrap(), b"dear diary");
+        assert_eq!(fs::metadata(&restored).unwrap().modified().unwrap(), mtime);
+    }
+
+    #[test]
+    fn test_malformed_metadata_rejected() {
+        assert!(split_metadata(b"no metadata here").is_err());
+
+        let plaintext = attach_metadata(
+            &FileMetadata {
+                name: "a.txt".to_string(),
+                mtime: None,
+                mode: None,
+            },
+            b"hello",
+        )
+        .unwrap();
+        assert!(split_metadata(&plaintext[..12]).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_special_mode_bits_dropped() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("tool");
+        fs::write(&path, b"#!/bin/sh").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o4755)).unwrap();
+        assert_eq!(FileMetadata::from_path(&path).unwrap().mode, Some(0o755));
+
+        let metadata = FileMetadata {
+            name: "tool".to_string(),
+            mtime: None,
+            mode: Some(0o6777),
+        };
+        metadata.apply(&path).unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o7777, 0o777);
+    }
+}
@@ -0,0 +1,45 @@
+use indicatif::ProgressBar;
+
+/// Receives progress of an SDK operation, so callers can show it however
+/// they like instead of through a terminal progress bar
+pub trait ProgressSink: Send + Sync {
+    /// Stage `step` of `total` (counting from 1) has started
+    fn stage(&self, name: &str, step: u64, total: u64);
+    /// The operation has finished
+    fn finish(&self);
+}
+
+/// Shows SDK progress on a terminal bar, counting the stages already done
+impl ProgressSink for ProgressBar {
+    fn stage(&self, name: &str, step: u64, total: u64) {
+        self.set_length(total);
+        self.set_position(step.saturating_sub(1));
+        self.set_message(format!("{name}..."));
+    }
+
+    fn finish(&self) {
+        self.set_message("Complete!");
+        ProgressBar::finish(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_bar_sink() {
+        let pb = ProgressBar::hidden();
+        let sink: &dyn ProgressSink = &pb;
+
+        sink.stage("Uploading to IPFS", 2, 3);
+        assert_eq!(pb.length(), Some(3));
+        assert_eq!(pb.position(), 1);
+        assert_eq!(pb.message(), "Uploading to IPFS...");
+
+        sink.finish();
+        assert!(pb.is_finished());
+        assert_eq!(pb.position(), 3);
+        assert_eq!(pb.message(), "Complete!");
+    }
+}
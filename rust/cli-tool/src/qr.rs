@@ -0,0 +1,105 @@
+use anyhow::{Context, Result};
+use image::{ImageFormat, Luma};
+use qrcode::render::unicode::Dense1x2;
+use qrcode::QrCode;
+use serde::Serialize;
+use std::io::Cursor;
+
+/// Width and height of one QR module in written images, in pixels
+pub const PNG_MODULE_SIZE: u32 = 8;
+
+/// What a key QR code holds when the capsule ID is included
+#[derive(Serialize)]
+struct KeyPayload<'a> {
+    capsule_id: &'a str,
+    encryption_key: &'a str,
+}
+
+/// The text to encode for a capsule's key, optionally alongside its ID
+///
+/// The key alone is encoded as-is; with the ID both go in a small JSON object.
+pub fn key_payload(encryption_key: &str, capsule_id: Option<&str>) -> Result<String> {
+    match capsule_id {
+        Some(capsule_id) => serde_json::to_string(&KeyPayload {
+            capsule_id,
+            encryption_key,
+        })
+        .context("Failed to serialize QR payload"),
+        None => Ok(encryption_key.to_string()),
+    }
+}
+
+/// Render `data` as a QR code made of half-block characters for the terminal
+///
+/// Colors are inverted so the code scans on dark terminal backgrounds.
+pub fn render_terminal(data: &str) -> Result<String> {
+    let code = QrCode::new(data.as_bytes()).context("Failed to build QR code")?;
+    Ok(code
+        .render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .build())
+}
+
+/// Encode `data` as a QR code in a PNG image
+pub fn render_png(data: &str) -> Result<Vec<u8>> {
+    let code = QrCode::new(data.as_bytes()).context("Failed to build QR code")?;
+    let image = code
+        .render::<Luma<u8>>()
+        .module_dimensions(PNG_MODULE_SIZE, PNG_MODULE_SIZE)
+        .build();
+
+    let mut bytes = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+        .context("Failed to encode QR code image")?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qrcode::Color;
+
+    const KEY: &str = "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=";
+
+    #[test]
+    fn test_key_payload() {
+        assert_eq!(key_payload(KEY, None).unwrap(), KEY);
+        let payload: serde_json::Value =
+            serde_json::from_str(&key_payload(KEY, Some("0xabc")).unwrap()).unwrap();
+        assert_eq!(payload["capsule_id"], "0xabc");
+        assert_eq!(payload["encryption_key"], KEY);
+    }
+
+    #[test]
+    fn test_png_holds_the_modules_for_the_key() {
+        let terminal = render_terminal(KEY).unwrap();
+        assert!(terminal.lines().count() > 10);
+
+        let png = render_png(KEY).unwrap();
+        let image = image::load_from_memory(&png).unwrap().to_luma8();
+
+        // Read the modules back from the image, skipping the quiet zone
+        let code = QrCode::new(KEY.as_bytes()).unwrap();
+        let width = code.width() as u32;
+        let quiet_zone = (image.width() / PNG_MODULE_SIZE - width) / 2;
+        assert_eq!(image.width(), (width + 2 * quiet_zone) * PNG_MODULE_SIZE);
+        let read: Vec<Color> = (0..width)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let center = |module: u32| (module + quiet_zone) * PNG_MODULE_SIZE + 1;
+                if image.get_pixel(center(x), center(y)).0[0] < 128 {
+                    Color::Dark
+                } else {
+                    Color::Light
+                }
+            })
+            .collect();
+
+        // The same modules come out of encoding the key, and only the key
+        assert_eq!(read, code.to_colors());
+        let other = QrCode::new(format!("{KEY}x").as_bytes()).unwrap();
+        assert_ne!(read, other.to_colors());
+    }
+}
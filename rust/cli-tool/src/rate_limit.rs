@@ -0,0 +1,95 @@
+use anyhow::Result;
+use tokio::sync::Mutex;
+use tokio::time::{Duration, Instant};
+
+/// Token-bucket rate limiter shared by all tasks using one SDK instance
+///
+/// The bucket holds up to one second's worth of requests, so short bursts
+/// pass immediately and sustained load is spread out at the configured rate.
+/// Callers that find the bucket empty wait for the next token rather than fail.
+pub struct RateLimiter {
+    rate: f64,
+    capacity: f64,
+    state: Mutex<BucketState>,
+}
+
+struct BucketState {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_second: f64) -> Result<Self> {
+        if !requests_per_second.is_finite() || requests_per_second <= 0.0 {
+            anyhow::bail!("Rate limit must be a positive number of requests per second");
+        }
+
+        let capacity = requests_per_second.max(1.0);
+        Ok(Self {
+            rate: requests_per_second,
+            capacity,
+            state: Mutex::new(BucketState {
+                tokens: capacity,
+                last_refill: Instant::now(),
+            }),
+        })
+    }
+
+    pub fn requests_per_second(&self) -> f64 {
+        self.rate
+    }
+
+    /// Wait until a request may proceed
+    pub async fn acquire(&self) {
+        // Holding the lock while sleeping queues waiters in arrival order
+        let mut state = self.state.lock().await;
+        self.refill(&mut state);
+
+        if state.tokens < 1.0 {
+            let wait = Duration::from_secs_f64((1.0 - state.tokens) / self.rate);
+            tokio::time::sleep(wait).await;
+            self.refill(&mut state);
+        }
+
+        state.tokens -= 1.0;
+    }
+
+    fn refill(&self, state: &mut BucketState) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(state.last_refill).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * self.rate).min(self.capacity);
+        state.last_refill = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_operations_wait_for_tokens() {
+        let limiter = Arc::new(RateLimiter::new(10.0).unwrap());
+        let start = Instant::now();
+
+        // 10 pass on the initial burst, the other 5 need 100ms each
+        let handles: Vec<_> = (0..15)
+            .map(|_| {
+                let limiter = limiter.clone();
+                tokio::spawn(async move { limiter.acquire().await })
+            })
+            .collect();
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        assert!(start.elapsed() >= Duration::from_millis(450));
+    }
+
+    #[test]
+    fn test_invalid_rate_rejected() {
+        assert!(RateLimiter::new(0.0).is_err());
+        assert!(RateLimiter::new(-1.0).is_err());
+        assert!(RateLimiter::new(f64::NAN).is_err());
+    }
+}
@@ -0,0 +1,383 @@
+use anyhow::{Context, Result};
+use encryptor_wasi::{
+    decrypt_content_with_password, encrypt_content_with_password, Argon2Params,
+    PasswordEncryptionResult,
+};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::sdk::{ApprovalInfo, BoundCondition, CapsuleStatus, CreateCapsuleResult};
+use crate::utils::current_timestamp_ms;
+
+/// Name of the capsule record store inside the config directory
+pub const CAPSULE_RECORDS_FILE_NAME: &str = "capsules.jsonl";
+
+/// Version of the export bundle layout written by this build
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// What this installation knows about a capsule it created or imported
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CapsuleRecord {
+    pub capsule_id: String,
+    pub cid: String,
+    /// Only kept when `store_keys_locally` is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encryption_key: Option<String>,
+    pub content_hash: String,
+    pub condition: BoundCondition,
+    pub network: String,
+    pub created_at: u64,
+}
+
+impl CapsuleRecord {
+    pub fn new(result: &CreateCapsuleResult, network: &str, keep_key: bool) -> Self {
+        Self {
+            capsule_id: result.capsule_id.clone(),
+            cid: result.cid.clone(),
+            encryption_key: keep_key.then(|| result.encryption_key.clone()),
+            content_hash: result.content_hash.clone(),
+            condition: result.condition.clone(),
+            network: network.to_string(),
+            created_at: current_timestamp_ms(),
+        }
+    }
+
+    /// The record as a listing entry, with what it says about the condition
+    ///
+    /// The chain is not consulted, so the status is always "recorded".
+    pub fn to_status(&self) -> CapsuleStatus {
+        let mut status = CapsuleStatus {
+            capsule_id: self.capsule_id.clone(),
+            capsule_type: self.condition.type_name().to_string(),
+            status: "recorded".to_string(),
+            created_at: self.created_at,
+            creator: None,
+            content_size: None,
+            cid: Some(self.cid.clone()),
+            content_hash: Some(self.content_hash.clone()),
+            unlock_time: None,
+            approvals: None,
+            price: None,
+            transaction_digest: None,
+            combinator: None,
+            conditions: vec![],
+            content_available: None,
+            error: None,
+        };
+        match &self.condition {
+            BoundCondition::Time { unlock_time } => status.unlock_time = Some(*unlock_time),
+            BoundCondition::Multisig {
+                threshold,
+                approvers,
+            } => {
+                status.approvals = Some(ApprovalInfo {
+                    current: 0,
+                    required: *threshold,
+                    approvers: approvers.clone(),
+                })
+            }
+            BoundCondition::Payment { price } => status.price = Some(*price),
+            BoundCondition::Composite { combinator, .. } => status.combinator = Some(*combinator),
+        }
+        status
+    }
+}
+
+/// Append-only JSON lines store of known capsules
+///
+/// Records may hold encryption keys, so the file is created readable only by
+/// the owner.
+#[derive(Debug, Clone)]
+pub struct CapsuleRecords {
+    path: PathBuf,
+}
+
+impl CapsuleRecords {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Every known capsule, oldest first, once per capsule ID
+    ///
+    /// A missing store is treated as empty; malformed lines are skipped.
+    pub fn read(&self) -> Result<Vec<CapsuleRecord>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read capsule records: {}", self.path.display()))?;
+
+        let mut seen = HashSet::new();
+        let mut records = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<CapsuleRecord>(line) {
+                Ok(record) if seen.insert(record.capsule_id.clone()) => records.push(record),
+                Ok(_) => {}
+                Err(e) => warn!(
+                    "Skipping malformed capsule record line {}: {}",
+                    index + 1,
+                    e
+                ),
+            }
+        }
+
+        Ok(records)
+    }
+
+    /// Append one record as a single JSON line
+    pub fn append(&self, record: &CapsuleRecord) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!(
+                    "Failed to create capsule records directory: {}",
+                    parent.display()
+                )
+            })?;
+        }
+
+        let mut line =
+            serde_json::to_string(record).context("Failed to serialize capsule record")?;
+        line.push('\n');
+
+        let mut options = OpenOptions::new();
+        options.create(true).append(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options
+            .open(&self.path)
+            .with_context(|| format!("Failed to open capsule records: {}", self.path.display()))?;
+        file.write_all(line.as_bytes())
+            .with_context(|| format!("Failed to write capsule records: {}", self.path.display()))?;
+
+        Ok(())
+    }
+
+    /// Drop every record of a capsule, returning whether there were any
+    ///
+    /// Lines that cannot be parsed are kept as they are.
+    pub fn remove(&self, capsule_id: &str) -> Result<bool> {
+        if !self.path.exists() {
+            return Ok(false);
+        }
+
+        let content = fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read capsule records: {}", self.path.display()))?;
+        let kept: Vec<&str> = content
+            .lines()
+            .filter(|line| {
+                serde_json::from_str::<CapsuleRecord>(line)
+                    .map_or(true, |record| record.capsule_id != capsule_id)
+            })
+            .collect();
+        if kept.len() == content.lines().count() {
+            return Ok(false);
+        }
+
+        let mut rewritten = kept.join("\n");
+        if !rewritten.is_empty() {
+            rewritten.push('\n');
+        }
+        fs::write(&self.path, rewritten)
+            .with_context(|| format!("Failed to write capsule records: {}", self.path.display()))?;
+        Ok(true)
+    }
+
+    /// Add the records for capsules not already known, returning how many
+    ///
+    /// A capsule that is already known keeps its local record.
+    pub fn merge(&self, records: &[CapsuleRecord]) -> Result<usize> {
+        let mut known: HashSet<String> = self
+            .read()?
+            .into_iter()
+            .map(|record| record.capsule_id)
+            .collect();
+
+        let mut added = 0;
+        for record in records {
+            if known.insert(record.capsule_id.clone()) {
+                self.append(record)?;
+                added += 1;
+            }
+        }
+        Ok(added)
+    }
+}
+
+/// Capsule records exported for moving to another installation
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ExportBundle {
+    pub version: u32,
+    pub exported_at: u64,
+    pub capsules: Vec<CapsuleRecord>,
+}
+
+/// On-disk form of a password-protected bundle
+#[derive(Serialize, Deserialize)]
+struct EncryptedBundle {
+    version: u32,
+    /// The plain bundle's JSON, encrypted under an Argon2id-derived key
+    encrypted: PasswordEncryptionResult,
+}
+
+impl ExportBundle {
+    pub fn new(capsules: Vec<CapsuleRecord>) -> Self {
+        Self {
+            version: BUNDLE_VERSION,
+            exported_at: current_timestamp_ms(),
+            capsules,
+        }
+    }
+
+    /// Serialize as JSON, encrypted with `password` when one is given
+    pub fn encode(&self, password: Option<&str>) -> Result<Vec<u8>> {
+        let json = serde_json::to_vec_pretty(self).context("Failed to serialize bundle")?;
+        let Some(password) = password else {
+            return Ok(json);
+        };
+
+        let encrypted = encrypt_content_with_password(&json, password, Argon2Params::default())
+            .context("Failed to encrypt bundle")?;
+        serde_json::to_vec_pretty(&EncryptedBundle {
+            version: BUNDLE_VERSION,
+            encrypted,
+        })
+        .context("Failed to serialize encrypted bundle")
+    }
+
+    /// Whether encoded bundle bytes need a password to read
+    pub fn is_encrypted(data: &[u8]) -> bool {
+        serde_json::from_slice::<serde_json::Value>(data)
+            .is_ok_and(|value| value.get("encrypted").is_some())
+    }
+
+    /// Parse a bundle written by `encode`, rejecting other layout versions
+    pub fn decode(data: &[u8], password: Option<&str>) -> Result<Self> {
+        let json = if Self::is_encrypted(data) {
+            let bundle: EncryptedBundle =
+                serde_json::from_slice(data).context("Failed to parse encrypted bundle")?;
+            check_bundle_version(bundle.version)?;
+            let password = password
+                .ok_or_else(|| anyhow::anyhow!("Bundle is encrypted; a password is required"))?;
+            let encrypted = bundle.encrypted;
+            decrypt_content_with_password(
+                &encrypted.ciphertext,
+                &encrypted.nonce,
+                password,
+                &encrypted.kdf,
+            )
+            .context("Failed to decrypt bundle (wrong password?)")?
+            .content
+            .to_vec()
+        } else {
+            data.to_vec()
+        };
+
+        let bundle: ExportBundle =
+            serde_json::from_slice(&json).context("Failed to parse bundle")?;
+        check_bundle_version(bundle.version)?;
+        Ok(bundle)
+    }
+}
+
+fn check_bundle_version(version: u32) -> Result<()> {
+    if version != BUNDLE_VERSION {
+        anyhow::bail!("Bundle uses layout v{version} but this build reads v{BUNDLE_VERSION}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn record(capsule_id: &str) -> CapsuleRecord {
+        CapsuleRecord {
+            capsule_id: capsule_id.to_string(),
+            cid: format!("Qm{capsule_id}"),
+            encryption_key: Some("a2V5".to_string()),
+            content_hash: "00".repeat(32),
+            condition: BoundCondition::Time {
+                unlock_time: 1_700_000_000_000,
+            },
+            network: "devnet".to_string(),
+            created_at: 1_000,
+        }
+    }
+
+    #[test]
+    fn test_bundle_round_trips_with_and_without_password() {
+        let bundle = ExportBundle::new(vec![record("0x1"), record("0x2")]);
+
+        let plain = bundle.encode(None).unwrap();
+        assert!(!ExportBundle::is_encrypted(&plain));
+        assert_eq!(ExportBundle::decode(&plain, None).unwrap(), bundle);
+
+        let encrypted = bundle.encode(Some("correct horse")).unwrap();
+        assert!(ExportBundle::is_encrypted(&encrypted));
+        assert!(!String::from_utf8_lossy(&encrypted).contains("0x1"));
+        assert_eq!(
+            ExportBundle::decode(&encrypted, Some("correct horse")).unwrap(),
+            bundle
+        );
+        assert!(ExportBundle::decode(&encrypted, Some("wrong")).is_err());
+        assert!(ExportBundle::decode(&encrypted, None).is_err());
+    }
+
+    #[test]
+    fn test_other_bundle_version_rejected() {
+        let mut bundle = ExportBundle::new(vec![record("0x1")]);
+        bundle.version = BUNDLE_VERSION + 1;
+        let error = ExportBundle::decode(&bundle.encode(None).unwrap(), None).unwrap_err();
+        assert!(error.to_string().contains("v2"));
+    }
+
+    #[test]
+    fn test_merge_adds_only_unknown_capsules() {
+        let temp_dir = TempDir::new().unwrap();
+        let records = CapsuleRecords::new(temp_dir.path().join(CAPSULE_RECORDS_FILE_NAME));
+        records.append(&record("0x1")).unwrap();
+
+        let mut changed = record("0x1");
+        changed.cid = "QmOther".to_string();
+        let added = records.merge(&[changed, record("0x2")]).unwrap();
+        assert_eq!(added, 1);
+
+        let all = records.read().unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0], record("0x1"));
+        assert_eq!(all[1].capsule_id, "0x2");
+    }
+
+    #[test]
+    fn test_record_without_key_omits_it() {
+        let mut record = record("0x1");
+        record.encryption_key = None;
+        let line = serde_json::to_string(&record).unwrap();
+        assert!(!line.contains("encryption_key"));
+        assert_eq!(
+            serde_json::from_str::<CapsuleRecord>(&line).unwrap(),
+            record
+        );
+
+        let status = record.to_status();
+        assert_eq!(status.capsule_type, "time");
+        assert_eq!(status.unlock_time, Some(1_700_000_000_000));
+        assert_eq!(status.cid.as_deref(), Some("Qm0x1"));
+    }
+}
@@ -1,17 +1,48 @@
 ﻿use anyhow::{Context, Result};
 use base64::Engine;
-use encryptor_wasi::encrypt_content;
+use encryptor_wasi::{
+    decrypt_result_with_aad, encrypt_content_with_aad, hash_content_bytes, hash_from_hex,
+    hash_to_hex, hybrid_decrypt, hybrid_encrypt, verify_content_hash, Argon2Params,
+    ContentEncoding, EncryptionResult, HybridCiphertext, PasswordKdf, Zeroizing,
+};
 use indicatif::{ProgressBar, ProgressStyle};
-use ipfs_api_backend_hyper::{IpfsClient, TryFromUri};
 use log::{debug, info, warn};
-use reqwest::Client;
+use reqwest::{Client, NoProxy, Proxy};
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::fs;
+use tokio::sync::{Semaphore, SemaphorePermit};
 
-use crate::config::Config;
+use crate::audit::{AuditLog, AuditOperation, AuditRecord};
+use crate::chain::{CapsuleChainState, ChainClient, SuiRpcChain};
+use crate::chunking::{chunk_associated_data, ChunkManifest, ChunkRef};
+use crate::compression::{compress, decompress, CompressionAlgo, CompressionOutcome};
+use crate::config::{Config, IpfsBackend};
+use crate::idempotency::{new_idempotency_key, IdempotencyCache};
+use crate::inspect::BUNDLE_MIME_TYPE;
+use crate::logging::progress_bars;
+use crate::metadata::{split_metadata, FileMetadata};
+use crate::progress::ProgressSink;
+use crate::rate_limit::RateLimiter;
+use crate::records::{CapsuleRecord, CapsuleRecords};
+use crate::shares::{combine_shares, split_key, KeyShare};
+use crate::storage::{
+    is_not_found, ContentStore, GatewayStore, IpfsStore, PinningStore, StoreRequestFailed,
+};
+use crate::utils::{current_timestamp_ms, normalize_sui_address};
+
+/// Version of the capsule metadata layout written by this build
+///
+/// v1 had no version field, label or tags.
+pub const CAPSULE_SCHEMA_VERSION: u32 = 2;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Capsule {
+    #[serde(default = "legacy_schema_version")]
+    pub schema_version: u32,
     pub id: String,
     pub owner: String,
     pub cid: String,
@@ -19,6 +50,55 @@ pub struct Capsule {
     pub unlock_condition: UnlockCondition,
     pub created_at: u64,
     pub unlocked: bool,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+fn legacy_schema_version() -> u32 {
+    1
+}
+
+impl Capsule {
+    /// Read capsule metadata written by this or any older build
+    ///
+    /// Each step upgrades the raw JSON by one version, so renames can be
+    /// handled before the typed struct sees the data; fields that were simply
+    /// added are filled in by their serde defaults. Metadata from a newer
+    /// build is rejected rather than silently losing fields.
+    pub fn migrate(mut raw: serde_json::Value) -> Result<Capsule> {
+        let object = raw
+            .as_object_mut()
+            .ok_or_else(|| anyhow::anyhow!("Capsule metadata must be a JSON object"))?;
+
+        let mut version = match object.get("schema_version") {
+            None => legacy_schema_version(),
+            Some(value) => value
+                .as_u64()
+                .and_then(|v| u32::try_from(v).ok())
+                .ok_or_else(|| anyhow::anyhow!("Invalid capsule schema version: {value}"))?,
+        };
+        if version > CAPSULE_SCHEMA_VERSION {
+            anyhow::bail!(
+                "Capsule metadata uses schema v{} but this build supports up to v{}; please upgrade",
+                version,
+                CAPSULE_SCHEMA_VERSION
+            );
+        }
+
+        while version < CAPSULE_SCHEMA_VERSION {
+            match version {
+                // v2 only added optional fields
+                1 => {}
+                _ => anyhow::bail!("Unknown capsule schema version: {version}"),
+            }
+            version += 1;
+        }
+        object.insert("schema_version".to_string(), version.into());
+
+        serde_json::from_value(raw).context("Failed to parse capsule metadata")
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,6 +109,68 @@ pub struct UnlockCondition {
     pub approvals: Vec<String>,
     pub price: Option<u64>,
     pub paid: bool,
+    /// How the parts of a `composite` condition combine
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub combinator: Option<Combinator>,
+    /// Parts of a `composite` condition
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conditions: Vec<UnlockCondition>,
+}
+
+impl UnlockCondition {
+    /// Whether the condition is met at `now_ms`, given the approvals recorded so far
+    ///
+    /// Payment conditions count as met, since the payment is made as part of
+    /// the unlock itself.
+    pub fn is_met(&self, now_ms: u64) -> bool {
+        match self.condition_type.as_str() {
+            "time" => self.unlock_time.is_some_and(|t| now_ms >= t),
+            "multisig" => self
+                .threshold
+                .is_some_and(|t| self.approvals.len() as u64 >= t),
+            "payment" => true,
+            "composite" => {
+                let mut parts = self.conditions.iter().map(|c| c.is_met(now_ms));
+                match self.combinator.unwrap_or_default() {
+                    Combinator::And => !self.conditions.is_empty() && parts.all(|met| met),
+                    Combinator::Or => parts.any(|met| met),
+                }
+            }
+            _ => false,
+        }
+    }
+}
+
+/// How the parts of a composite unlock condition combine
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Combinator {
+    /// Every part must be met
+    #[default]
+    And,
+    /// Any one part is enough
+    Or,
+}
+
+impl fmt::Display for Combinator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Combinator::And => write!(f, "and"),
+            Combinator::Or => write!(f, "or"),
+        }
+    }
+}
+
+impl FromStr for Combinator {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "and" | "all" => Ok(Combinator::And),
+            "or" | "any" => Ok(Combinator::Or),
+            other => anyhow::bail!("Invalid combinator: {other}. Use 'and' or 'or'"),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -46,6 +188,114 @@ pub struct UnlockResult {
     pub content_type: Option<String>,
     pub error: Option<String>,
     pub transaction_digest: Option<String>,
+    pub failure_reason: Option<UnlockFailureReason>,
+    /// Measurements of the unlock; `None` when it was refused up front
+    pub metrics: Option<OperationMetrics>,
+    /// Whether `content` starts with file metadata, as the capsule's envelope records
+    pub file_metadata: bool,
+    /// Whether `content` is a multi-file bundle, as the capsule's envelope records
+    pub bundle: bool,
+}
+
+impl UnlockResult {
+    /// The unlocked content, with the file metadata stored alongside it split off
+    pub fn content_and_metadata(&self) -> Result<(Option<FileMetadata>, &[u8])> {
+        let content = self.content.as_deref().unwrap_or_default();
+        if !self.file_metadata {
+            return Ok((None, content));
+        }
+        let (metadata, content) = split_metadata(content)?;
+        Ok((Some(metadata), content))
+    }
+}
+
+/// Measurements taken while an SDK operation ran, for callers that want
+/// more than the CLI's progress output
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OperationMetrics {
+    pub bytes_uploaded: u64,
+    pub bytes_downloaded: u64,
+    pub retries: u32,
+    /// Time spent in each stage, in the order the stages ran
+    pub stage_durations: Vec<StageDuration>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StageDuration {
+    pub stage: &'static str,
+    pub duration_ms: f64,
+}
+
+impl OperationMetrics {
+    /// Record a stage that started at `started` and has just finished
+    pub fn record_stage(&mut self, stage: &'static str, started: Instant) {
+        self.stage_durations.push(StageDuration {
+            stage,
+            duration_ms: started.elapsed().as_secs_f64() * 1000.0,
+        });
+    }
+
+    /// Total time across all recorded stages
+    pub fn total_ms(&self) -> f64 {
+        self.stage_durations.iter().map(|s| s.duration_ms).sum()
+    }
+}
+
+/// Why an unlock was refused, when the refusal has a known cause
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnlockFailureReason {
+    /// The owner revoked the capsule; it can never be unlocked
+    Revoked,
+    /// Neither the IPFS node nor any gateway has the content, typically
+    /// because it was unpinned and garbage-collected
+    ContentUnavailable { cid: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RevokeResult {
+    pub capsule_id: String,
+    pub transaction_digest: String,
+}
+
+/// A capsule destroyed by its owner
+#[derive(Debug, Clone, Serialize)]
+pub struct CancelResult {
+    pub capsule_id: String,
+    pub transaction_digest: String,
+    /// CID of the content the store was asked to stop pinning, if any
+    pub unpinned_cid: Option<String>,
+}
+
+/// A withdrawn approval and the approvals a multisig capsule has left
+#[derive(Debug, Clone, Serialize)]
+pub struct RevokeApprovalResult {
+    pub capsule_id: String,
+    pub transaction_digest: String,
+    pub current_approvals: u64,
+    pub required_approvals: u64,
+}
+
+/// What `verify_capsule` found about a capsule's stored content
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifyResult {
+    pub capsule_id: String,
+    pub cid: String,
+    /// Whether the content could be downloaded from IPFS or a gateway
+    pub cid_reachable: bool,
+    /// Whether the downloaded bytes are a well-formed encrypted payload
+    pub payload_valid: bool,
+    /// Whether the decrypted content matches the recorded hash; `None`
+    /// without a key, or when there was nothing valid to decrypt
+    pub hash_matches: Option<bool>,
+}
+
+/// New key shares for a capsule, replacing an earlier split
+#[derive(Debug, Clone, Serialize)]
+pub struct ReshareResult {
+    pub capsule_id: String,
+    pub threshold: u8,
+    pub shares: Vec<KeyShare>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -54,6 +304,8 @@ pub struct ApprovalResult {
     pub transaction_digest: String,
     pub current_approvals: u64,
     pub required_approvals: u64,
+    /// Gas the approval transaction used, in MIST
+    pub gas_used: u64,
     pub error: Option<String>,
 }
 
@@ -63,9 +315,421 @@ pub struct CreateCapsuleResult {
     pub transaction_digest: String,
     pub cid: String,
     pub encryption_key: String,
+    pub content_hash: String,
+    pub condition: BoundCondition,
+    /// How the content was prepared before encryption, as its envelope records it
+    #[serde(skip_serializing_if = "ContentEncoding::is_plain")]
+    pub encoding: ContentEncoding,
+    /// What compression did to the content, when it was requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compression: Option<CompressionOutcome>,
+    /// Content key wrapped to an escrow holder, when escrow was requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub escrow: Option<EscrowKey>,
+    /// Gas the create transaction used, in MIST
+    pub gas_used: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metrics: Option<OperationMetrics>,
+}
+
+impl CreateCapsuleResult {
+    /// Associated data the capsule's content was encrypted with
+    pub fn associated_data(&self) -> Result<Vec<u8>> {
+        let content_hash =
+            hash_from_hex(&self.content_hash).context("Invalid content hash format")?;
+        Ok(capsule_associated_data(
+            &content_hash,
+            self.condition.recorded_unlock_time(),
+            &self.encoding,
+        ))
+    }
+}
+
+/// A capsule's content key, recoverable by whoever holds the escrow secret key
+///
+/// Organizations opt into this for compliance or legal hold, so content can
+/// be recovered even if the creator loses their encryption key.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EscrowKey {
+    /// Base64 X25519 public key the content key was wrapped to
+    pub public_key: String,
+    /// Base64 hybrid ciphertext of the content key
+    pub wrapped_key: String,
+}
+
+impl EscrowKey {
+    /// Wrap a content key to an escrow public key
+    pub fn wrap(escrow_public_key: &[u8; 32], encryption_key: &[u8; 32]) -> Result<Self> {
+        let sealed = hybrid_encrypt(escrow_public_key, encryption_key)
+            .context("Failed to wrap content key for escrow")?;
+        Ok(Self {
+            public_key: base64::engine::general_purpose::STANDARD.encode(escrow_public_key),
+            wrapped_key: base64::engine::general_purpose::STANDARD.encode(sealed.to_bytes()),
+        })
+    }
+
+    /// Recover the content key with the escrow secret key
+    ///
+    /// Returns the key base64-encoded, as `unlock_and_decrypt` expects it.
+    pub fn recover(&self, escrow_secret_key: &[u8; 32]) -> Result<String> {
+        let wrapped = base64::engine::general_purpose::STANDARD
+            .decode(&self.wrapped_key)
+            .context("Invalid wrapped escrow key")?;
+        let sealed = HybridCiphertext::from_bytes(&wrapped)?;
+        let key = hybrid_decrypt(escrow_secret_key, &sealed)
+            .context("Failed to unwrap escrow key; is this the matching secret key?")?;
+        Ok(base64::engine::general_purpose::STANDARD.encode(key))
+    }
+}
+
+/// Parse a base64 X25519 key
+pub fn parse_x25519_key(encoded: &str) -> Result<[u8; 32]> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim())
+        .context("X25519 key must be base64")?;
+    bytes.try_into().map_err(|bytes: Vec<u8>| {
+        anyhow::anyhow!("X25519 key must be 32 bytes, got {}", bytes.len())
+    })
+}
+
+/// Decode a base64 content key as returned in `CreateCapsuleResult`
+fn decode_encryption_key(encoded: &str) -> Result<Zeroizing<[u8; 32]>> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim())
+        .context("Invalid encryption key format")?;
+    let key: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Encryption key must be 32 bytes"))?;
+    Ok(Zeroizing::new(key))
+}
+
+/// Poly1305 tag added to every ciphertext
+const AEAD_TAG_BYTES: u64 = 16;
+
+/// How often, and how patiently, a network operation is retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt
+    pub attempts: u32,
+    /// Wait before the first retry; it doubles for each one after
+    pub delay_ms: u64,
+}
+
+impl RetryPolicy {
+    /// Run `operation`, retrying it while it fails with a transient error
+    pub async fn run<F, Fut, T>(&self, operation: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<T>>,
+    {
+        retry_with_backoff(*self, operation).await
+    }
+}
+
+/// Run `operation`, retrying it with exponential backoff while it fails with
+/// a transient error
+///
+/// Anything else, such as invalid input, a wrong key or a 4xx response,
+/// fails at once.
+pub async fn retry_with_backoff<F, Fut, T>(policy: RetryPolicy, operation: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T>>,
+{
+    retry_counted(policy, &mut 0, operation).await
 }
 
+/// `retry_with_backoff`, adding the number of retries it made to `retries`
+async fn retry_counted<F, Fut, T>(
+    policy: RetryPolicy,
+    retries: &mut u32,
+    mut operation: F,
+) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T>>,
+{
+    let mut delay_ms = policy.delay_ms;
+    let mut attempt = 0;
+    loop {
+        match operation().await {
+            Err(e) if attempt < policy.attempts && is_transient(&e) => {
+                attempt += 1;
+                *retries += 1;
+                warn!(
+                    "Attempt {attempt} of {} failed, retrying in {delay_ms} ms: {e:#}",
+                    policy.attempts + 1
+                );
+                tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+                delay_ms = delay_ms.saturating_mul(2);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Whether an error came from a network, IPFS or RPC request that may succeed if repeated
+fn is_transient(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        if cause.is::<StoreRequestFailed>() {
+            return true;
+        }
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            return e.is_timeout()
+                || e.is_connect()
+                || e.status().is_some_and(|status| {
+                    status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                });
+        }
+        cause.downcast_ref::<std::io::Error>().is_some_and(|e| {
+            matches!(
+                e.kind(),
+                std::io::ErrorKind::ConnectionRefused
+                    | std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::TimedOut
+            )
+        })
+    })
+}
+
+/// Gas budget in MIST when none is configured and the chain cannot estimate one
+pub const DEFAULT_GAS_BUDGET: u64 = 50_000_000;
+
+/// Extra gas allowed on top of a dry-run estimate, in percent
+const GAS_ESTIMATE_HEADROOM_PERCENT: u64 = 20;
+
+/// Gas every mocked transaction is charged, in MIST
+const MOCK_GAS_USED: u64 = 2_000_000;
+
+/// A Move call built by the SDK, before it is signed and submitted
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TransactionRequest {
+    /// Move function the transaction calls
+    pub function: String,
+    pub arguments: Vec<String>,
+    /// Most gas the transaction may use, in MIST
+    pub gas_budget: u64,
+    /// Client-generated key identifying one logical create, so a resubmitted
+    /// transaction is recognized as the same create
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
+}
+
+/// What creating a capsule is expected to cost, before anything is submitted
+#[derive(Debug, Clone, Serialize)]
+pub struct CostEstimate {
+    /// Encrypted bytes that will be uploaded to IPFS
+    pub storage_bytes: u64,
+    /// Gas from a dry run of the create transaction, when the network can provide one
+    pub estimated_gas: Option<u64>,
+    pub notes: Vec<String>,
+}
+
+/// Default allowance for the local clock running ahead of the chain's
+pub const DEFAULT_CLOCK_SKEW_TOLERANCE_MS: u64 = 30_000;
+
+/// Which clock a readiness decision was made against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClockSource {
+    Chain,
+    Local,
+}
+
+/// Whether a time capsule can be unlocked yet
+#[derive(Debug, Clone, Serialize)]
+pub struct TimeReadiness {
+    pub ready: bool,
+    pub now_ms: u64,
+    pub unlock_time: u64,
+    pub clock: ClockSource,
+}
+
+/// Whether a capsule's unlock condition is met
 #[derive(Debug, Clone, Serialize)]
+pub struct ConditionReadiness {
+    pub ready: bool,
+    /// Whether each part of a composite condition is met, in order
+    pub parts_met: Vec<bool>,
+    pub clock: ClockSource,
+}
+
+/// Most approvers a multisig capsule may have; the chain stores the count in a `u8`
+pub const MAX_MULTISIG_APPROVERS: u64 = 255;
+
+/// Prefix that keeps capsule associated data distinct from other AEAD uses
+const CAPSULE_AAD_DOMAIN: &str = "time-capsule/record/v2";
+
+/// Associated data binding a capsule's ciphertext to its chain record
+///
+/// Only what unlock can read back from the chain is bound: the content hash
+/// and the unlock time the contract checks, which is 0 for capsules that are
+/// not time-locked. The capsule ID cannot be, because the chain assigns it
+/// when the create transaction runs, after the content is encrypted and
+/// uploaded; the content hash ties the ciphertext to its record instead.
+/// The envelope's content encoding is bound too, so it cannot be altered to
+/// change how the plaintext is decoded.
+pub fn capsule_associated_data(
+    content_hash: &[u8; 32],
+    unlock_time_ms: u64,
+    encoding: &ContentEncoding,
+) -> Vec<u8> {
+    let encoding = serde_json::to_string(encoding).unwrap_or_default();
+    format!(
+        "{CAPSULE_AAD_DOMAIN}\ncontent_hash:{}\nunlock_time_ms:{unlock_time_ms}\nencoding:{encoding}",
+        hash_to_hex(content_hash)
+    )
+    .into_bytes()
+}
+
+/// Digest of what a create was asked to do, checked when its idempotency key is reused
+fn create_fingerprint(content_hash: &[u8; 32], condition: &BoundCondition) -> Result<String> {
+    let condition = serde_json::to_vec(condition).context("Failed to serialize condition")?;
+    Ok(hash_to_hex(&hash_content_bytes(
+        &[content_hash.as_slice(), &condition].concat(),
+    )))
+}
+
+/// The unlock condition a capsule is created with
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum BoundCondition {
+    Time {
+        unlock_time: u64,
+    },
+    Multisig {
+        threshold: u64,
+        approvers: Vec<String>,
+    },
+    Payment {
+        price: u64,
+    },
+    /// Several conditions, all or any of which must be met
+    Composite {
+        combinator: Combinator,
+        conditions: Vec<BoundCondition>,
+    },
+}
+
+impl BoundCondition {
+    /// Unlock time the contract records for the capsule
+    ///
+    /// Only time capsules have one; the contract stores 0 for every other
+    /// type, whose conditions it does not keep yet.
+    pub fn recorded_unlock_time(&self) -> u64 {
+        match self {
+            BoundCondition::Time { unlock_time } => *unlock_time,
+            _ => 0,
+        }
+    }
+
+    /// The same condition with every multisig approver list normalized (see
+    /// `normalize_approvers`)
+    fn with_normalized_approvers(self) -> Result<Self> {
+        Ok(match self {
+            BoundCondition::Multisig {
+                threshold,
+                approvers,
+            } => BoundCondition::Multisig {
+                threshold,
+                approvers: normalize_approvers(&approvers)?,
+            },
+            BoundCondition::Composite {
+                combinator,
+                conditions,
+            } => BoundCondition::Composite {
+                combinator,
+                conditions: conditions
+                    .into_iter()
+                    .map(Self::with_normalized_approvers)
+                    .collect::<Result<_>>()?,
+            },
+            other => other,
+        })
+    }
+
+    /// Value passed to the create transaction: the unlock time, approval
+    /// threshold, price, or number of combined conditions
+    fn transaction_value(&self) -> u64 {
+        match self {
+            BoundCondition::Time { unlock_time } => *unlock_time,
+            BoundCondition::Multisig { threshold, .. } => *threshold,
+            BoundCondition::Payment { price } => *price,
+            BoundCondition::Composite { conditions, .. } => conditions.len() as u64,
+        }
+    }
+
+    /// Name used for the capsule type on chain
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            BoundCondition::Time { .. } => "time",
+            BoundCondition::Multisig { .. } => "multisig",
+            BoundCondition::Payment { .. } => "payment",
+            BoundCondition::Composite { .. } => "composite",
+        }
+    }
+
+    /// Check the condition fits what the chain accepts before anything is built
+    ///
+    /// Shared by the CLI and the SDK so both reject the same values with the
+    /// same messages. `approvers` are counted as given.
+    pub fn validate(&self, max_price: u64) -> Result<()> {
+        match self {
+            BoundCondition::Time { .. } => {}
+            BoundCondition::Multisig {
+                threshold,
+                approvers,
+            } => {
+                if *threshold == 0 {
+                    anyhow::bail!("Threshold must be greater than 0");
+                }
+                if approvers.len() as u64 > MAX_MULTISIG_APPROVERS {
+                    anyhow::bail!(
+                        "Multisig capsules allow at most {MAX_MULTISIG_APPROVERS} approvers, got {}",
+                        approvers.len()
+                    );
+                }
+                if *threshold > MAX_MULTISIG_APPROVERS {
+                    anyhow::bail!(
+                        "Threshold ({threshold}) cannot exceed the platform maximum of {MAX_MULTISIG_APPROVERS}"
+                    );
+                }
+                if *threshold > approvers.len() as u64 {
+                    anyhow::bail!(
+                        "Threshold ({}) cannot be greater than number of unique approvers ({})",
+                        threshold,
+                        approvers.len()
+                    );
+                }
+            }
+            BoundCondition::Payment { price } => {
+                if *price == 0 {
+                    anyhow::bail!("Price must be greater than 0");
+                }
+                if *price > max_price {
+                    anyhow::bail!(
+                        "Price ({price} MIST) exceeds the maximum of {max_price} MIST; raise `max_price` to allow it"
+                    );
+                }
+            }
+            BoundCondition::Composite { conditions, .. } => {
+                if conditions.len() < 2 {
+                    anyhow::bail!("Composite capsules need at least two conditions");
+                }
+                for condition in conditions {
+                    if let BoundCondition::Composite { .. } = condition {
+                        anyhow::bail!("Composite conditions cannot be nested");
+                    }
+                    condition.validate(max_price)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CapsuleStatus {
     pub capsule_id: String,
     pub capsule_type: String,
@@ -74,13 +738,90 @@ pub struct CapsuleStatus {
     pub creator: Option<String>,
     pub content_size: Option<u64>,
     pub cid: Option<String>,
+    /// BLAKE3 hash of the plaintext, identical for copies of the same content
+    pub content_hash: Option<String>,
     pub unlock_time: Option<u64>,
     pub approvals: Option<ApprovalInfo>,
     pub price: Option<u64>,
     pub transaction_digest: Option<String>,
+    /// How the parts of a composite capsule combine
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub combinator: Option<Combinator>,
+    /// Parts of a composite capsule's unlock condition
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conditions: Vec<UnlockCondition>,
+    /// Whether the content could be fetched, when that was checked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_available: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+impl CapsuleStatus {
+    /// Placeholder row for a capsule whose status could not be fetched
+    pub fn failed(capsule_id: &str, error: String) -> Self {
+        Self {
+            capsule_id: capsule_id.to_string(),
+            capsule_type: "unknown".to_string(),
+            status: "error".to_string(),
+            created_at: 0,
+            creator: None,
+            content_size: None,
+            cid: None,
+            content_hash: None,
+            unlock_time: None,
+            approvals: None,
+            price: None,
+            transaction_digest: None,
+            combinator: None,
+            conditions: vec![],
+            content_available: None,
+            error: Some(error),
+        }
+    }
+
+    /// The combined unlock condition of a composite capsule
+    pub fn composite_condition(&self) -> UnlockCondition {
+        UnlockCondition {
+            condition_type: "composite".to_string(),
+            unlock_time: None,
+            threshold: None,
+            approvals: vec![],
+            price: None,
+            paid: false,
+            combinator: self.combinator,
+            conditions: self.conditions.clone(),
+        }
+    }
+
+    /// Time since the capsule was created
+    pub fn age_ms(&self, now: u64) -> u64 {
+        now.saturating_sub(self.created_at)
+    }
+
+    /// How long the capsule has been past its unlock time without being unlocked
+    pub fn overdue_ms(&self, now: u64) -> Option<u64> {
+        match self.unlock_time {
+            Some(unlock_time)
+                if !matches!(self.status.as_str(), "unlocked" | "revoked") && now > unlock_time =>
+            {
+                Some(now - unlock_time)
+            }
+            _ => None,
+        }
+    }
+
+    /// Older than `max_age_ms`, or past its unlock time by more than that
+    pub fn is_stale(&self, now: u64, max_age_ms: u64) -> bool {
+        self.error.is_none()
+            && (self.age_ms(now) > max_age_ms
+                || self
+                    .overdue_ms(now)
+                    .is_some_and(|overdue| overdue > max_age_ms))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApprovalInfo {
     pub current: u64,
     pub required: u64,
@@ -93,7 +834,10 @@ pub struct CapsuleQuery {
     pub status: Option<String>,
     pub mine_only: bool,
     pub limit: u32,
+    #[deprecated(note = "offsets skip or repeat capsules as new ones are created; use `cursor`")]
     pub offset: u32,
+    /// Where to continue from, as returned in `CapsuleListPage::next_cursor`
+    pub cursor: Option<String>,
 }
 
 impl Default for CapsuleQuery {
@@ -103,6 +847,7 @@ impl Default for CapsuleQuery {
 }
 
 impl CapsuleQuery {
+    #[allow(deprecated)]
     pub fn new() -> Self {
         Self {
             capsule_type: None,
@@ -110,6 +855,7 @@ impl CapsuleQuery {
             mine_only: false,
             limit: 50,
             offset: 0,
+            cursor: None,
         }
     }
 
@@ -133,10 +879,76 @@ impl CapsuleQuery {
         self
     }
 
+    #[deprecated(
+        note = "offsets skip or repeat capsules as new ones are created; use `with_cursor`"
+    )]
+    #[allow(deprecated)]
     pub fn with_offset(mut self, offset: u32) -> Self {
         self.offset = offset;
         self
     }
+
+    pub fn with_cursor(mut self, cursor: &str) -> Self {
+        self.cursor = Some(cursor.to_string());
+        self
+    }
+}
+
+/// One page of `list_capsules` results
+#[derive(Debug, Clone, Serialize)]
+pub struct CapsuleListPage {
+    pub capsules: Vec<CapsuleStatus>,
+    /// Cursor for the page after this one, when there are more results
+    pub next_cursor: Option<String>,
+}
+
+/// Opaque cursor pointing just past a capsule in listing order
+fn encode_cursor(capsule: &CapsuleStatus) -> String {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .encode(format!("{}:{}", capsule.created_at, capsule.capsule_id))
+}
+
+fn decode_cursor(cursor: &str) -> Result<(u64, String)> {
+    let decoded = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(cursor.trim())
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .ok_or_else(|| anyhow::anyhow!("Invalid cursor: {cursor}"))?;
+    let (created_at, capsule_id) = decoded
+        .split_once(':')
+        .ok_or_else(|| anyhow::anyhow!("Invalid cursor: {cursor}"))?;
+    let created_at = created_at
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid cursor: {cursor}"))?;
+    Ok((created_at, capsule_id.to_string()))
+}
+
+/// Take one page of capsules, ordered oldest first with ties broken by ID
+///
+/// A cursor names the last capsule already seen, so capsules created while
+/// paging land after it and are neither skipped nor repeated.
+pub(crate) fn paginate(
+    mut capsules: Vec<CapsuleStatus>,
+    cursor: Option<&str>,
+    offset: u32,
+    limit: u32,
+) -> Result<CapsuleListPage> {
+    capsules.sort_by(|a, b| (a.created_at, &a.capsule_id).cmp(&(b.created_at, &b.capsule_id)));
+    if let Some(cursor) = cursor {
+        let (created_at, capsule_id) = decode_cursor(cursor)?;
+        capsules.retain(|c| (c.created_at, &c.capsule_id) > (created_at, &capsule_id));
+    }
+
+    let mut remaining = capsules.into_iter().skip(offset as usize);
+    let capsules: Vec<CapsuleStatus> = remaining.by_ref().take(limit as usize).collect();
+    let next_cursor = match (remaining.next(), capsules.last()) {
+        (Some(_), Some(last)) => Some(encode_cursor(last)),
+        _ => None,
+    };
+    Ok(CapsuleListPage {
+        capsules,
+        next_cursor,
+    })
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -148,9 +960,28 @@ pub struct PendingApproval {
     pub creator: Option<String>,
 }
 
+/// Canonicalize, de-duplicate and sort a multisig approver list
+///
+/// Addresses are compared in canonical form, so `0xABC` and `0x0abc` count as
+/// the same approver. Duplicates are rejected rather than silently dropped,
+/// since they usually indicate a mistake in the approver list.
+pub fn normalize_approvers(approvers: &[String]) -> Result<Vec<String>> {
+    let mut normalized = Vec::with_capacity(approvers.len());
+    for approver in approvers {
+        let address = normalize_sui_address(approver)
+            .with_context(|| format!("Invalid approver address: {approver}"))?;
+        if normalized.contains(&address) {
+            anyhow::bail!("Duplicate approver address: {approver}");
+        }
+        normalized.push(address);
+    }
+    normalized.sort();
+    Ok(normalized)
+}
+
 // Progress bar utilities
 pub fn create_progress_bar(len: u64, message: &str) -> ProgressBar {
-    let pb = ProgressBar::new(len);
+    let pb = progress_bars().add(ProgressBar::new(len));
     pb.set_style(
         ProgressStyle::default_bar()
             .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}")
@@ -162,7 +993,7 @@ pub fn create_progress_bar(len: u64, message: &str) -> ProgressBar {
 }
 
 pub fn create_spinner(message: &str) -> ProgressBar {
-    let pb = ProgressBar::new_spinner();
+    let pb = progress_bars().add(ProgressBar::new_spinner());
     pb.set_style(
         ProgressStyle::default_spinner()
             .template("{spinner:.green} {msg}")
@@ -176,313 +1007,729 @@ pub fn create_spinner(message: &str) -> ProgressBar {
 pub struct CapsuleSDK {
     config: Config,
     http_client: Client,
-    ipfs_client: IpfsClient,
+    store: Arc<dyn ContentStore>,
+    /// Fallback for content the IPFS node does not have
+    gateways: Option<Arc<dyn ContentStore>>,
+    audit_log: Option<AuditLog>,
+    /// Local index of created capsules
+    records: Option<CapsuleRecords>,
+    ipfs_limiter: Option<RateLimiter>,
+    /// Bounds IPFS requests across every caller, however many files are in flight
+    ipfs_connections: Semaphore,
+    recent_creates: IdempotencyCache<CreateCapsuleResult>,
+    chain: Arc<dyn ChainClient>,
+    /// Public key every new capsule's content key is also wrapped to
+    escrow_public_key: Option<[u8; 32]>,
+    /// Caller-supplied key used instead of a fresh one per capsule
+    encryption_key: Option<[u8; 32]>,
+    /// Passphrase new capsules' content keys are derived from
+    password: Option<Zeroizing<String>>,
+    /// Compression applied to new capsules' content before encryption
+    compression: CompressionAlgo,
+    /// Content larger than this is split into separately uploaded chunks
+    chunk_size: Option<usize>,
+    /// Whether new capsules' content starts with attached file metadata
+    file_metadata: bool,
+    /// Whether new capsules' content is a multi-file bundle
+    bundle: bool,
+    /// How network calls are retried after transient failures
+    retry: RetryPolicy,
+}
+
+/// Build the HTTP client used for RPC calls
+///
+/// With `proxy_url` set, all requests go through that proxy except hosts
+/// listed in `NO_PROXY`. Otherwise the standard `HTTP_PROXY`, `HTTPS_PROXY`
+/// and `NO_PROXY` environment variables apply.
+pub(crate) fn build_http_client(config: &Config) -> Result<Client> {
+    let mut builder = Client::builder();
+    if let Some(ref proxy_url) = config.proxy_url {
+        let proxy = Proxy::all(proxy_url)
+            .with_context(|| format!("Invalid proxy URL: {proxy_url}"))?
+            .no_proxy(NoProxy::from_env());
+        builder = builder.proxy(proxy);
+    }
+    builder.build().context("Failed to build HTTP client")
 }
 
 impl CapsuleSDK {
     pub async fn new(config: Config) -> Result<Self> {
-        let http_client = Client::new();
-        let ipfs_client =
-            IpfsClient::from_str(&config.ipfs_url).context("Failed to create IPFS client")?;
+        let http_client = build_http_client(&config)?;
+        let chain = Arc::new(SuiRpcChain::new(http_client.clone(), config.get_rpc_url()));
+        let store: Arc<dyn ContentStore> = match config.pinning_service {
+            IpfsBackend::ApiNode => {
+                if config.proxy_url.is_some() {
+                    // The hyper IPFS backend has no proxy support
+                    warn!(
+                        "IPFS requests to {} do not go through the configured proxy",
+                        config.ipfs_url
+                    );
+                }
+                Arc::new(IpfsStore::new(&config.ipfs_url).context("Failed to create IPFS client")?)
+            }
+            IpfsBackend::Pinata { ref jwt } => {
+                Arc::new(PinningStore::pinata(http_client.clone(), jwt))
+            }
+            IpfsBackend::Web3Storage { ref token } => {
+                Arc::new(PinningStore::web3_storage(http_client.clone(), token))
+            }
+        };
+
+        let gateways = (!config.ipfs_gateways.is_empty()).then(|| {
+            Arc::new(GatewayStore::new(
+                http_client.clone(),
+                config.ipfs_gateways.clone(),
+            )) as Arc<dyn ContentStore>
+        });
+
+        let audit_log = config.audit_log_path.clone().map(AuditLog::new);
+        let records = config.capsule_records_path.clone().map(CapsuleRecords::new);
+        let ipfs_limiter = config
+            .ipfs_rate_limit
+            .map(RateLimiter::new)
+            .transpose()
+            .context("Invalid IPFS rate limit")?;
+        let max_connections = config.ipfs_max_connections();
+        if max_connections == 0 {
+            anyhow::bail!("IPFS max connections must be greater than 0");
+        }
+        if config.gas_budget == Some(0) {
+            anyhow::bail!("Gas budget must be greater than 0");
+        }
+        let retry = config.retry_policy();
 
         Ok(Self {
             config,
             http_client,
-            ipfs_client,
+            store,
+            gateways,
+            audit_log,
+            records,
+            ipfs_limiter,
+            ipfs_connections: Semaphore::new(max_connections),
+            recent_creates: IdempotencyCache::default(),
+            chain,
+            escrow_public_key: None,
+            encryption_key: None,
+            password: None,
+            compression: CompressionAlgo::None,
+            chunk_size: None,
+            file_metadata: false,
+            bundle: false,
+            retry,
         })
     }
 
-    pub async fn get_capsules_by_owner(&self, owner: &str) -> Result<Vec<Capsule>> {
-        info!("Fetching capsules for owner: {owner}");
+    /// Retry network calls after transient failures according to `retry`
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
 
-        // Mock implementation - in real version would query Sui blockchain
-        let mock_capsules = vec![
-            Capsule {
-                id: "0x1234567890abcdef".to_string(),
-                owner: owner.to_string(),
-                cid: "QmTest1234567890".to_string(),
-                content_hash: "hash1".to_string(),
-                unlock_condition: UnlockCondition {
-                    condition_type: "time".to_string(),
-                    unlock_time: Some(1735689600000),
-                    threshold: None,
-                    approvals: vec![],
-                    price: None,
-                    paid: false,
-                },
-                created_at: 1705689600000,
-                unlocked: false,
-            },
-            Capsule {
-                id: "0xabcdef1234567890".to_string(),
-                owner: owner.to_string(),
-                cid: "QmTest0987654321".to_string(),
-                content_hash: "hash2".to_string(),
-                unlock_condition: UnlockCondition {
-                    condition_type: "multisig".to_string(),
-                    unlock_time: None,
-                    threshold: Some(3),
-                    approvals: vec![
-                        "0xapprover1".to_string(),
-                        "0xapprover2".to_string(),
-                        "0xapprover3".to_string(),
-                    ],
-                    price: None,
-                    paid: false,
-                },
-                created_at: 1705689600000,
-                unlocked: false,
-            },
-        ];
+    /// Use a different content store for encrypted payloads
+    pub fn with_store(mut self, store: Arc<dyn ContentStore>) -> Self {
+        self.store = store;
+        self
+    }
 
-        Ok(mock_capsules)
+    /// Use a different chain client for dry runs
+    pub fn with_chain(mut self, chain: Arc<dyn ChainClient>) -> Self {
+        self.chain = chain;
+        self
     }
 
-    pub async fn unlock_capsule(
-        &self,
-        capsule_id: &str,
-        encryption_key: &str,
-        payment: Option<u64>,
-    ) -> Result<UnlockResult> {
-        info!("Unlocking capsule: {} (payment: {:?})", capsule_id, payment);
+    /// Also wrap every new capsule's content key to an escrow public key
+    pub fn with_escrow_key(mut self, public_key: [u8; 32]) -> Self {
+        self.escrow_public_key = Some(public_key);
+        self
+    }
 
-        let pb = ProgressBar::new(4);
-        pb.set_style(
-            ProgressStyle::default_bar()
-                .template(
-                    "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}",
-                )
-                .unwrap(),
-        );
+    /// Encrypt new capsules with this key instead of generating one for each
+    ///
+    /// Sharing a key across capsules means one leak exposes all of them; the
+    /// CLI warns when a supplied key was used before.
+    pub fn with_encryption_key(mut self, encryption_key: [u8; 32]) -> Self {
+        self.encryption_key = Some(encryption_key);
+        self
+    }
 
-        pb.set_message("Validating unlock conditions...");
-        pb.inc(1);
+    /// Derive each new capsule's content key from a passphrase with Argon2id
+    ///
+    /// A fresh salt is drawn per capsule and stored, with the Argon2
+    /// parameters, in the uploaded envelope so the key can be derived again.
+    pub fn with_password(mut self, password: String) -> Self {
+        self.password = Some(Zeroizing::new(password));
+        self
+    }
 
-        // Mock validation - in real version would check blockchain state
-        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+    /// Compress each new capsule's content before it is encrypted
+    ///
+    /// The algorithm applied is recorded in the capsule's envelope, so
+    /// unlocking needs no matching setting.
+    pub fn with_compression(mut self, compression: CompressionAlgo) -> Self {
+        self.compression = compression;
+        self
+    }
 
-        pb.set_message("Executing unlock transaction...");
-        pb.inc(1);
+    /// Split content larger than `chunk_size` bytes across several uploads
+    ///
+    /// Each chunk is encrypted under its own key and uploaded on its own; the
+    /// capsule then holds only the ordered list of chunks (see `ChunkManifest`).
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = Some(chunk_size.max(1));
+        self
+    }
 
-        // Mock blockchain transaction
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+    /// Record in each new capsule's envelope that its content starts with
+    /// metadata from `attach_metadata`, so unlock knows to split it off
+    pub fn with_file_metadata(mut self, attached: bool) -> Self {
+        self.file_metadata = attached;
+        self
+    }
 
-        pb.set_message("Downloading from IPFS...");
-        pb.inc(1);
+    /// Record in each new capsule's envelope that its content is a bundle
+    /// from `build_archive`, so unlock can offer to extract it
+    pub fn with_bundle(mut self, bundle: bool) -> Self {
+        self.bundle = bundle;
+        self
+    }
 
-        // Mock IPFS download and decryption
-        let key_bytes = base64::engine::general_purpose::STANDARD
-            .decode(encryption_key)
-            .context("Invalid encryption key format")?;
+    /// Record mutating operations to the given audit log
+    pub fn with_audit_log(mut self, audit_log: AuditLog) -> Self {
+        self.audit_log = Some(audit_log);
+        self
+    }
 
-        debug!(
-            "Using encryption key of {} bytes for decryption",
-            key_bytes.len()
+    /// Mark a capsule revoked on chain so it can never be unlocked
+    ///
+    /// Only the capsule's owner may revoke it; the sender is the configured
+    /// `address`. Revoking is permanent and, unlike deleting, keeps the
+    /// capsule visible with a `revoked` status.
+    pub async fn revoke(&self, capsule_id: &str) -> Result<RevokeResult> {
+        info!("Revoking capsule: {capsule_id}");
+
+        let sender = self.require_owner(capsule_id, "revoke").await?;
+        let transaction_digest = self.chain.revoke_capsule(capsule_id, &sender).await?;
+        self.record_audit(
+            AuditOperation::Revoke,
+            capsule_id,
+            Some(&transaction_digest),
         );
 
-        // For mock implementation, just return mock content
-        let mock_content = b"This is the decrypted content of the time capsule!".to_vec();
+        Ok(RevokeResult {
+            capsule_id: capsule_id.to_string(),
+            transaction_digest,
+        })
+    }
 
-        pb.set_message("Complete!");
-        pb.finish();
+    /// Destroy a capsule the configured address owns, optionally unpinning its content
+    ///
+    /// The capsule object is deleted on chain and dropped from the local index.
+    /// Unpinning only asks the configured IPFS node or pinning service to let
+    /// the content go; gateways and other nodes may keep cached copies. The
+    /// chunks of a chunked capsule are listed inside its encrypted content, so
+    /// only the top-level CID is unpinned.
+    pub async fn cancel_capsule(&self, capsule_id: &str, unpin: bool) -> Result<CancelResult> {
+        info!("Cancelling capsule: {capsule_id}");
 
-        Ok(UnlockResult {
-            success: true,
-            content: Some(mock_content),
-            content_type: Some("text/plain".to_string()),
-            error: None,
-            transaction_digest: Some(format!("0x{:x}", rand::random::<u64>())),
+        let (sender, state) = self.owned_state(capsule_id, "cancel").await?;
+        let cid = match state.cid {
+            Some(cid) => Some(cid),
+            None => self
+                .load_local_capsules()?
+                .into_iter()
+                .find(|record| record.capsule_id == capsule_id)
+                .map(|record| record.cid),
+        };
+        if unpin && cid.is_none() {
+            anyhow::bail!(
+                "The content CID of capsule {capsule_id} is unknown, so it cannot be unpinned"
+            );
+        }
+
+        let transaction_digest = self.chain.destroy_capsule(capsule_id, &sender).await?;
+        self.record_audit(
+            AuditOperation::Cancel,
+            capsule_id,
+            Some(&transaction_digest),
+        );
+        if let Some(ref records) = self.records {
+            if let Err(e) = records.remove(capsule_id) {
+                warn!("Failed to remove capsule {capsule_id} from the local index: {e:#}");
+            }
+        }
+
+        let unpinned_cid = match cid.filter(|_| unpin) {
+            Some(cid) => {
+                retry_with_backoff(self.retry, || async {
+                    let _connection = self.ipfs_connection().await?;
+                    self.store.unpin(&cid).await
+                })
+                .await
+                .with_context(|| {
+                    format!("Capsule {capsule_id} was destroyed ({transaction_digest}), but unpinning {cid} failed")
+                })?;
+                Some(cid)
+            }
+            None => None,
+        };
+
+        Ok(CancelResult {
+            capsule_id: capsule_id.to_string(),
+            transaction_digest,
+            unpinned_cid,
         })
     }
 
-    pub async fn approve_capsule(&self, capsule_id: &str) -> Result<ApprovalResult> {
-        info!("Approving capsule: {capsule_id}");
+    /// Withdraw the configured address's approval of a multisig capsule
+    ///
+    /// Only an approval the chain has recorded can be withdrawn, and only
+    /// while the capsule is still locked.
+    pub async fn revoke_approval(&self, capsule_id: &str) -> Result<RevokeApprovalResult> {
+        info!("Withdrawing approval of capsule: {capsule_id}");
 
-        // Mock blockchain transaction
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+        let sender = self.configured_sender("withdraw approvals of")?;
+        let state = self.chain.capsule_state(capsule_id).await?;
+        if state.revoked {
+            anyhow::bail!("Capsule {capsule_id} is revoked");
+        }
+        if state.unlocked {
+            anyhow::bail!(
+                "Capsule {capsule_id} is already unlocked, so approvals can no longer be withdrawn"
+            );
+        }
+        let required_approvals = state
+            .required_approvals
+            .ok_or_else(|| anyhow::anyhow!("Capsule {capsule_id} is not a multisig capsule"))?;
+        let approved = state.approvals.iter().any(|approver| {
+            normalize_sui_address(approver).is_ok_and(|approver| approver == sender)
+        });
+        if !approved {
+            anyhow::bail!("{sender} has not approved capsule {capsule_id}");
+        }
 
-        Ok(ApprovalResult {
-            success: true,
-            transaction_digest: format!("0x{:x}", rand::random::<u64>()),
-            current_approvals: 3,
-            required_approvals: 3,
-            error: None,
+        let transaction_digest = self.chain.revoke_approval(capsule_id, &sender).await?;
+        self.record_audit(
+            AuditOperation::RevokeApproval,
+            capsule_id,
+            Some(&transaction_digest),
+        );
+
+        Ok(RevokeApprovalResult {
+            capsule_id: capsule_id.to_string(),
+            transaction_digest,
+            current_approvals: state.approvals.len() as u64 - 1,
+            required_approvals,
         })
     }
 
-    // New methods needed by the commands
-    pub async fn create_time_capsule(
+    /// Redistribute a capsule's key under a new threshold and share count
+    ///
+    /// The key is reconstructed from at least a threshold of `old_shares` and
+    /// split anew, so the content is not re-encrypted. The old shares still
+    /// reconstruct the same key, so holders who should lose access must
+    /// discard theirs. Only the capsule's owner may reshare.
+    pub async fn reshare(
         &self,
-        content: Vec<u8>,
-        unlock_time: u64,
-        progress: Option<&ProgressBar>,
-    ) -> Result<CreateCapsuleResult> {
-        if let Some(pb) = progress {
-            pb.set_message("Encrypting content...");
-            pb.inc(1);
-        }
+        capsule_id: &str,
+        old_shares: &[KeyShare],
+        new_threshold: u8,
+        new_shares: u8,
+    ) -> Result<ReshareResult> {
+        info!("Resharing key of capsule {capsule_id} as {new_threshold} of {new_shares}");
 
-        let encryption_key = self.generate_encryption_key();
-        let encrypted_result =
-            encrypt_content(&content, &encryption_key).context("Failed to encrypt content")?;
+        self.require_owner(capsule_id, "reshare").await?;
+        let encryption_key =
+            combine_shares(old_shares).context("Failed to reconstruct the key from old shares")?;
+        let shares = split_key(&encryption_key, new_threshold, new_shares)?;
+        self.record_audit(AuditOperation::Reshare, capsule_id, None);
 
-        if let Some(pb) = progress {
-            pb.set_message("Uploading to IPFS...");
-            pb.inc(1);
-        }
+        Ok(ReshareResult {
+            capsule_id: capsule_id.to_string(),
+            threshold: new_threshold,
+            shares,
+        })
+    }
 
-        let cid = self.upload_to_ipfs(&encrypted_result.ciphertext).await?;
+    /// Check the configured address owns an unrevoked capsule, returning that address
+    async fn require_owner(&self, capsule_id: &str, action: &str) -> Result<String> {
+        let (sender, state) = self.owned_state(capsule_id, action).await?;
+        if state.revoked {
+            anyhow::bail!("Capsule {capsule_id} is already revoked");
+        }
+        Ok(sender)
+    }
 
-        if let Some(pb) = progress {
-            pb.set_message("Creating blockchain transaction...");
-            pb.inc(1);
+    /// Check the configured address owns a capsule, returning that address and
+    /// what the chain records about the capsule
+    async fn owned_state(
+        &self,
+        capsule_id: &str,
+        action: &str,
+    ) -> Result<(String, CapsuleChainState)> {
+        let sender = self.configured_sender(action)?;
+        let state = self.chain.capsule_state(capsule_id).await?;
+        let owner = normalize_sui_address(&state.owner)
+            .with_context(|| format!("Invalid owner address: {}", state.owner))?;
+        if owner != sender {
+            anyhow::bail!("Only the owner ({owner}) can {action} capsule {capsule_id}");
         }
+        Ok((sender, state))
+    }
 
-        let capsule_id = self
-            .create_blockchain_capsule(&cid, unlock_time, "time")
-            .await?;
+    /// The configured address that transactions are sent from, normalized
+    fn configured_sender(&self, action: &str) -> Result<String> {
+        let sender = self.config.address.as_deref().ok_or_else(|| {
+            anyhow::anyhow!("Set `address` in the configuration to {action} capsules")
+        })?;
+        normalize_sui_address(sender).context("Invalid configured address")
+    }
 
-        if let Some(pb) = progress {
-            pb.set_message("Complete!");
-            pb.finish();
+    /// Whether the chain reports the capsule as revoked
+    ///
+    /// The contract refuses to unlock revoked capsules regardless, so when the
+    /// chain can't be asked this answers `false` and leaves it to the contract.
+    async fn is_revoked(&self, capsule_id: &str) -> bool {
+        match self.chain.capsule_state(capsule_id).await {
+            Ok(state) => state.revoked,
+            Err(e) => {
+                debug!("Could not check revocation of {capsule_id}: {e:#}");
+                false
+            }
         }
+    }
 
-        Ok(CreateCapsuleResult {
-            capsule_id,
-            transaction_digest: format!("0x{:x}", rand::random::<u64>()),
-            cid,
-            encryption_key: base64::engine::general_purpose::STANDARD.encode(encryption_key),
-        })
+    /// Why an unlock should be refused before any transaction is sent, if it should
+    ///
+    /// Revoked capsules never unlock, and content nobody can serve would leave
+    /// nothing to decrypt. Checks that cannot be made are left to later steps.
+    async fn unlock_refusal(&self, capsule_id: &str) -> Option<UnlockResult> {
+        match self.chain.capsule_state(capsule_id).await {
+            Ok(state) => self.state_refusal(capsule_id, &state).await,
+            Err(e) => {
+                debug!("Could not check capsule {capsule_id} before unlocking: {e:#}");
+                None
+            }
+        }
     }
 
-    pub async fn create_multisig_capsule(
+    /// `unlock_refusal` for a capsule whose chain state is already known
+    async fn state_refusal(
         &self,
-        content: Vec<u8>,
-        threshold: u64,
-        approvers: Vec<String>,
-        progress: Option<&ProgressBar>,
-    ) -> Result<CreateCapsuleResult> {
-        info!(
-            "Creating multisig capsule with threshold {} and {} approvers",
-            threshold,
-            approvers.len()
-        );
-
-        if let Some(pb) = progress {
-            pb.set_message("Encrypting content...");
-            pb.inc(1);
+        capsule_id: &str,
+        state: &CapsuleChainState,
+    ) -> Option<UnlockResult> {
+        if state.revoked {
+            return Some(Self::revoked_unlock(capsule_id));
         }
 
-        let encryption_key = self.generate_encryption_key();
-        let encrypted_result =
-            encrypt_content(&content, &encryption_key).context("Failed to encrypt content")?;
-
-        if let Some(pb) = progress {
-            pb.set_message("Uploading to IPFS...");
-            pb.inc(1);
+        let cid = state.cid.as_ref()?;
+        match self.content_available(cid).await {
+            Ok(true) => None,
+            Ok(false) => Some(Self::content_unavailable_unlock(capsule_id, cid.clone())),
+            Err(e) => {
+                debug!("Could not check availability of {cid}: {e:#}");
+                None
+            }
         }
+    }
 
-        let cid = self.upload_to_ipfs(&encrypted_result.ciphertext).await?;
+    /// The refusal returned when no store or gateway has a capsule's content
+    fn content_unavailable_unlock(capsule_id: &str, cid: String) -> UnlockResult {
+        UnlockResult {
+            success: false,
+            content: None,
+            content_type: None,
+            error: Some(format!(
+                "Content {cid} of capsule {capsule_id} was not found on the IPFS node or any gateway"
+            )),
+            transaction_digest: None,
+            failure_reason: Some(UnlockFailureReason::ContentUnavailable { cid }),
+            metrics: None,
+            file_metadata: false,
+            bundle: false,
+        }
+    }
 
-        if let Some(pb) = progress {
-            pb.set_message("Creating blockchain transaction...");
-            pb.inc(1);
+    /// The refusal returned instead of unlocking a revoked capsule
+    fn revoked_unlock(capsule_id: &str) -> UnlockResult {
+        UnlockResult {
+            success: false,
+            content: None,
+            content_type: None,
+            error: Some(format!(
+                "Capsule {capsule_id} has been revoked by its owner"
+            )),
+            transaction_digest: None,
+            failure_reason: Some(UnlockFailureReason::Revoked),
+            metrics: None,
+            file_metadata: false,
+            bundle: false,
         }
+    }
 
-        let capsule_id = self
-            .create_blockchain_capsule(&cid, threshold, "multisig")
-            .await?;
+    /// Append an audit record; a failed write only warns so the operation still succeeds
+    fn record_audit(
+        &self,
+        operation: AuditOperation,
+        capsule_id: &str,
+        transaction_digest: Option<&str>,
+    ) {
+        if let Some(ref audit_log) = self.audit_log {
+            let record = AuditRecord::new(
+                operation,
+                capsule_id,
+                transaction_digest,
+                &self.config.network,
+            );
+            if let Err(e) = audit_log.append(&record) {
+                warn!(
+                    "Failed to write audit log {}: {:#}",
+                    audit_log.path().display(),
+                    e
+                );
+            }
+        }
+    }
 
-        if let Some(pb) = progress {
-            pb.set_message("Complete!");
-            pb.finish();
+    /// Add a created capsule to the local index, if one is configured
+    ///
+    /// The encryption key is only kept when `store_keys_locally` is set.
+    pub fn record_capsule(&self, result: &CreateCapsuleResult) -> Result<()> {
+        if let Some(ref records) = self.records {
+            let record =
+                CapsuleRecord::new(result, &self.config.network, self.config.store_keys_locally);
+            records.append(&record)?;
         }
+        Ok(())
+    }
 
-        Ok(CreateCapsuleResult {
-            capsule_id,
-            transaction_digest: format!("0x{:x}", rand::random::<u64>()),
-            cid,
-            encryption_key: base64::engine::general_purpose::STANDARD.encode(encryption_key),
-        })
+    /// Capsules in the local index, oldest first, without touching the network
+    pub fn load_local_capsules(&self) -> Result<Vec<CapsuleRecord>> {
+        match self.records {
+            Some(ref records) => records.read(),
+            None => Ok(Vec::new()),
+        }
     }
 
-    pub async fn create_payment_capsule(
-        &self,
-        content: Vec<u8>,
-        price: u64,
-        progress: Option<&ProgressBar>,
-    ) -> Result<CreateCapsuleResult> {
-        if let Some(pb) = progress {
-            pb.set_message("Encrypting content...");
-            pb.inc(1);
+    /// Record a created capsule, logging rather than failing on errors
+    fn remember_capsule(&self, result: &CreateCapsuleResult) {
+        if let Err(e) = self.record_capsule(result) {
+            warn!("Failed to record capsule locally: {:#}", e);
         }
+    }
 
-        let encryption_key = self.generate_encryption_key();
-        let encrypted_result =
-            encrypt_content(&content, &encryption_key).context("Failed to encrypt content")?;
+    /// Current time for deciding whether time capsules are unlockable
+    ///
+    /// With `use_chain_time` set this is the latest checkpoint time, since the
+    /// contract checks unlock times against the chain's clock. If that cannot
+    /// be fetched, the local clock is used and a warning is logged.
+    pub async fn unlock_clock(&self) -> (u64, ClockSource) {
+        let local_now = current_timestamp_ms();
+        if !self.config.use_chain_time {
+            return (local_now, ClockSource::Local);
+        }
 
-        if let Some(pb) = progress {
-            pb.set_message("Uploading to IPFS...");
-            pb.inc(1);
+        match self.chain.latest_timestamp_ms().await {
+            Ok(chain_now) => {
+                let skew = local_now.abs_diff(chain_now);
+                if skew > self.clock_skew_tolerance_ms() {
+                    warn!("Local clock differs from the chain's by {skew} ms");
+                }
+                (chain_now, ClockSource::Chain)
+            }
+            Err(e) => {
+                warn!("Could not fetch chain time, using the local clock: {e:#}");
+                (local_now, ClockSource::Local)
+            }
         }
+    }
 
-        let cid = self.upload_to_ipfs(&encrypted_result.ciphertext).await?;
+    /// Whether a time capsule unlocking at `unlock_time` can be unlocked now
+    ///
+    /// When chain time was wanted but the local clock had to stand in, a
+    /// capsule only counts as ready once it is past its unlock time by the
+    /// skew tolerance, so a fast local clock does not report it ready before
+    /// the chain would accept the unlock.
+    pub async fn time_capsule_readiness(&self, unlock_time: u64) -> TimeReadiness {
+        let (now_ms, clock) = self.unlock_clock().await;
+        let margin = self.clock_margin(clock);
 
-        if let Some(pb) = progress {
-            pb.set_message("Creating blockchain transaction...");
-            pb.inc(1);
+        TimeReadiness {
+            ready: now_ms >= unlock_time.saturating_add(margin),
+            now_ms,
+            unlock_time,
+            clock,
         }
+    }
 
-        let capsule_id = self
-            .create_blockchain_capsule(&cid, price, "payment")
-            .await?;
+    /// Whether an unlock condition, composite or not, is met now
+    ///
+    /// Time parts get the same margin as `time_capsule_readiness` when the
+    /// local clock stands in for the chain's.
+    pub async fn condition_readiness(&self, condition: &UnlockCondition) -> ConditionReadiness {
+        let (now_ms, clock) = self.unlock_clock().await;
+        let now_ms = now_ms.saturating_sub(self.clock_margin(clock));
 
-        if let Some(pb) = progress {
-            pb.set_message("Complete!");
-            pb.finish();
+        ConditionReadiness {
+            ready: condition.is_met(now_ms),
+            parts_met: condition
+                .conditions
+                .iter()
+                .map(|part| part.is_met(now_ms))
+                .collect(),
+            clock,
         }
+    }
 
-        Ok(CreateCapsuleResult {
-            capsule_id,
-            transaction_digest: format!("0x{:x}", rand::random::<u64>()),
-            cid,
-            encryption_key: base64::engine::general_purpose::STANDARD.encode(encryption_key),
+    /// Extra time a time condition must be past when judged by a fallback clock
+    fn clock_margin(&self, clock: ClockSource) -> u64 {
+        if self.config.use_chain_time && clock == ClockSource::Local {
+            self.clock_skew_tolerance_ms()
+        } else {
+            0
+        }
+    }
+
+    /// Whether unlock decisions are meant to use the chain's clock
+    pub fn uses_chain_time(&self) -> bool {
+        self.config.use_chain_time
+    }
+
+    fn clock_skew_tolerance_ms(&self) -> u64 {
+        self.config
+            .clock_skew_tolerance_ms
+            .unwrap_or(DEFAULT_CLOCK_SKEW_TOLERANCE_MS)
+    }
+
+    /// Estimate storage and gas for creating a capsule over `content_len` bytes
+    ///
+    /// Gas comes from a dry run of the create transaction; when the chain
+    /// cannot provide one, `estimated_gas` is `None` and a note says why.
+    pub async fn estimate_cost(
+        &self,
+        content_len: u64,
+        capsule_type: &str,
+    ) -> Result<CostEstimate> {
+        let storage_bytes = content_len + AEAD_TAG_BYTES;
+        let mut notes = vec![
+            "IPFS pinning cost depends on the pinning provider and is not included".to_string(),
+        ];
+
+        let estimated_gas = match self
+            .chain
+            .estimate_create_gas(capsule_type, storage_bytes)
+            .await
+        {
+            Ok(gas) => Some(gas),
+            Err(e) => {
+                debug!("Gas dry run failed: {e:#}");
+                notes.push(format!("Gas estimate unavailable: {e:#}"));
+                None
+            }
+        };
+
+        Ok(CostEstimate {
+            storage_bytes,
+            estimated_gas,
+            notes,
         })
     }
 
-    pub async fn unlock_and_decrypt(
+    pub async fn get_capsules_by_owner(&self, owner: &str) -> Result<Vec<Capsule>> {
+        info!("Fetching capsules for owner: {owner}");
+
+        // Mock implementation - in real version would query Sui blockchain
+        let mock_capsules = vec![
+            Capsule {
+                schema_version: CAPSULE_SCHEMA_VERSION,
+                id: "0x1234567890abcdef".to_string(),
+                owner: owner.to_string(),
+                cid: "QmTest1234567890".to_string(),
+                content_hash: "hash1".to_string(),
+                unlock_condition: UnlockCondition {
+                    condition_type: "time".to_string(),
+                    unlock_time: Some(1735689600000),
+                    threshold: None,
+                    approvals: vec![],
+                    price: None,
+                    paid: false,
+                    combinator: None,
+                    conditions: vec![],
+                },
+                created_at: 1705689600000,
+                unlocked: false,
+                label: None,
+                tags: vec![],
+            },
+            Capsule {
+                schema_version: CAPSULE_SCHEMA_VERSION,
+                id: "0xabcdef1234567890".to_string(),
+                owner: owner.to_string(),
+                cid: "QmTest0987654321".to_string(),
+                content_hash: "hash2".to_string(),
+                unlock_condition: UnlockCondition {
+                    condition_type: "multisig".to_string(),
+                    unlock_time: None,
+                    threshold: Some(3),
+                    approvals: vec![
+                        "0xapprover1".to_string(),
+                        "0xapprover2".to_string(),
+                        "0xapprover3".to_string(),
+                    ],
+                    price: None,
+                    paid: false,
+                    combinator: None,
+                    conditions: vec![],
+                },
+                created_at: 1705689600000,
+                unlocked: false,
+                label: None,
+                tags: vec![],
+            },
+        ];
+
+        Ok(mock_capsules)
+    }
+
+    pub async fn unlock_capsule(
         &self,
         capsule_id: &str,
         encryption_key: &str,
         payment: Option<u64>,
-        progress: Option<&ProgressBar>,
+        progress: Option<&dyn ProgressSink>,
     ) -> Result<UnlockResult> {
-        info!(
-            "Unlocking and decrypting capsule: {} (payment: {:?})",
-            capsule_id, payment
-        );
+        info!("Unlocking capsule: {} (payment: {:?})", capsule_id, payment);
 
-        if let Some(pb) = progress {
-            pb.set_message("Validating unlock conditions...");
-            pb.inc(1);
+        if let Some(refusal) = self.unlock_refusal(capsule_id).await {
+            return Ok(refusal);
         }
 
-        // Mock validation
+        if let Some(progress) = progress {
+            progress.stage("Validating unlock conditions", 1, 3);
+        }
+
+        let mut metrics = OperationMetrics::default();
+        let stage = Instant::now();
+        // Mock validation - in real version would check blockchain state
         tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+        metrics.record_stage("validate", stage);
 
-        if let Some(pb) = progress {
-            pb.set_message("Executing unlock transaction...");
-            pb.inc(1);
+        if let Some(progress) = progress {
+            progress.stage("Executing unlock transaction", 2, 3);
         }
 
+        let stage = Instant::now();
         // Mock blockchain transaction
         tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+        metrics.record_stage("transaction", stage);
 
-        if let Some(pb) = progress {
-            pb.set_message("Downloading and decrypting...");
-            pb.inc(1);
+        if let Some(progress) = progress {
+            progress.stage("Downloading from IPFS", 3, 3);
         }
 
+        let stage = Instant::now();
+        // Mock IPFS download and decryption
         let key_bytes = base64::engine::general_purpose::STANDARD
             .decode(encryption_key)
             .context("Invalid encryption key format")?;
@@ -492,55 +1739,434 @@ impl CapsuleSDK {
             key_bytes.len()
         );
 
-        // Mock content
+        // For mock implementation, just return mock content
         let mock_content = b"This is the decrypted content of the time capsule!".to_vec();
+        metrics.record_stage("decrypt", stage);
 
-        if let Some(pb) = progress {
-            pb.set_message("Complete!");
-            pb.finish();
+        if let Some(progress) = progress {
+            progress.finish();
         }
 
-        Ok(UnlockResult {
+        let result = UnlockResult {
             success: true,
             content: Some(mock_content),
             content_type: Some("text/plain".to_string()),
             error: None,
             transaction_digest: Some(format!("0x{:x}", rand::random::<u64>())),
-        })
+            failure_reason: None,
+            metrics: Some(metrics),
+            file_metadata: false,
+            bundle: false,
+        };
+        let operation = if payment.is_some() {
+            AuditOperation::Payment
+        } else {
+            AuditOperation::Unlock
+        };
+        self.record_audit(operation, capsule_id, result.transaction_digest.as_deref());
+
+        Ok(result)
     }
 
-    pub async fn approve_multisig_capsule(
-        &self,
-        capsule_id: &str,
-        progress: Option<&ProgressBar>,
-    ) -> Result<ApprovalResult> {
-        info!("Approving multisig capsule: {}", capsule_id);
+    pub async fn approve_capsule(&self, capsule_id: &str) -> Result<ApprovalResult> {
+        info!("Approving capsule: {capsule_id}");
 
-        if let Some(pb) = progress {
-            pb.set_message("Submitting approval...");
-            pb.inc(1);
-        }
+        // Mock blockchain transaction
+        let request = self.build_approve_transaction(capsule_id);
+        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+        let gas_used = self.submit_transaction(&request)?;
+
+        let result = ApprovalResult {
+            success: true,
+            transaction_digest: format!("0x{:x}", rand::random::<u64>()),
+            current_approvals: 3,
+            required_approvals: 3,
+            gas_used,
+            error: None,
+        };
+        self.record_audit(
+            AuditOperation::Approve,
+            capsule_id,
+            Some(&result.transaction_digest),
+        );
+
+        Ok(result)
+    }
+
+    // New methods needed by the commands
+    pub async fn create_time_capsule(
+        &self,
+        content: Vec<u8>,
+        unlock_time: u64,
+        progress: Option<&dyn ProgressSink>,
+    ) -> Result<CreateCapsuleResult> {
+        self.create_with_condition(
+            content,
+            BoundCondition::Time { unlock_time },
+            progress,
+            None,
+        )
+        .await
+    }
+
+    pub async fn create_multisig_capsule(
+        &self,
+        content: Vec<u8>,
+        threshold: u64,
+        approvers: Vec<String>,
+        progress: Option<&dyn ProgressSink>,
+    ) -> Result<CreateCapsuleResult> {
+        let approvers = normalize_approvers(&approvers)?;
+        info!(
+            "Creating multisig capsule with threshold {} and {} approvers",
+            threshold,
+            approvers.len()
+        );
+        let condition = BoundCondition::Multisig {
+            threshold,
+            approvers,
+        };
+        self.create_with_condition(content, condition, progress, None)
+            .await
+    }
+
+    pub async fn create_payment_capsule(
+        &self,
+        content: Vec<u8>,
+        price: u64,
+        progress: Option<&dyn ProgressSink>,
+    ) -> Result<CreateCapsuleResult> {
+        self.create_with_condition(content, BoundCondition::Payment { price }, progress, None)
+            .await
+    }
+
+    pub async fn create_composite_capsule(
+        &self,
+        content: Vec<u8>,
+        combinator: Combinator,
+        conditions: Vec<BoundCondition>,
+        progress: Option<&dyn ProgressSink>,
+    ) -> Result<CreateCapsuleResult> {
+        let conditions = conditions
+            .into_iter()
+            .map(BoundCondition::with_normalized_approvers)
+            .collect::<Result<Vec<_>>>()?;
+        info!(
+            "Creating composite capsule requiring {} of {} conditions",
+            if combinator == Combinator::And {
+                "all"
+            } else {
+                "any"
+            },
+            conditions.len()
+        );
+        let condition = BoundCondition::Composite {
+            combinator,
+            conditions,
+        };
+        self.create_with_condition(content, condition, progress, None)
+            .await
+    }
+
+    /// Create a capsule at most once per idempotency key
+    ///
+    /// Retrying with the same key (see `new_idempotency_key`) within
+    /// `IDEMPOTENCY_WINDOW` returns the original result instead of creating a
+    /// second capsule, and the key travels on the create transaction so the
+    /// chain can recognize a resubmission too. Reusing a key for different
+    /// content or a different condition is an error.
+    pub async fn create_idempotent(
+        &self,
+        idempotency_key: &str,
+        content: Vec<u8>,
+        condition: BoundCondition,
+        progress: Option<&dyn ProgressSink>,
+    ) -> Result<CreateCapsuleResult> {
+        let condition = condition.with_normalized_approvers()?;
+        self.create_with_condition(content, condition, progress, Some(idempotency_key))
+            .await
+    }
+
+    /// Compress, chunk, encrypt and upload content, then create its capsule
+    /// on chain under `condition`
+    ///
+    /// Without an idempotency key a fresh one is generated, so the create
+    /// transaction still carries one key however often it is resubmitted.
+    async fn create_with_condition(
+        &self,
+        content: Vec<u8>,
+        condition: BoundCondition,
+        progress: Option<&dyn ProgressSink>,
+        idempotency_key: Option<&str>,
+    ) -> Result<CreateCapsuleResult> {
+        condition.validate(self.config.max_price())?;
+        let content_hash = hash_content_bytes(&content);
+
+        let Some(idempotency_key) = idempotency_key else {
+            let idempotency_key = new_idempotency_key();
+            return self
+                .create_once(content, content_hash, condition, progress, &idempotency_key)
+                .await;
+        };
+        debug!("Create with idempotency key {idempotency_key}");
+        let fingerprint = create_fingerprint(&content_hash, &condition)?;
+        self.recent_creates
+            .run(idempotency_key, &fingerprint, || {
+                self.create_once(content, content_hash, condition, progress, idempotency_key)
+            })
+            .await
+    }
+
+    /// Run one create, whose transaction carries `idempotency_key`
+    async fn create_once(
+        &self,
+        content: Vec<u8>,
+        content_hash: [u8; 32],
+        condition: BoundCondition,
+        progress: Option<&dyn ProgressSink>,
+        idempotency_key: &str,
+    ) -> Result<CreateCapsuleResult> {
+        if let Some(progress) = progress {
+            progress.stage("Encrypting content", 1, 3);
+        }
+
+        let mut metrics = OperationMetrics::default();
+        let (content, compression) = self.compress_content(content, &mut metrics)?;
+        let (content, chunked) = self.split_large_file(content, &mut metrics).await?;
+        let encoding = ContentEncoding {
+            compression: compression.and_then(|outcome| outcome.applied.to_envelope()),
+            chunked,
+            file_metadata: self.file_metadata,
+            bundle: self.bundle,
+        };
+        let stage = Instant::now();
+        let (encryption_key, kdf) = self.new_content_key()?;
+        let associated_data =
+            capsule_associated_data(&content_hash, condition.recorded_unlock_time(), &encoding);
+        let mut encrypted_result =
+            encrypt_content_with_aad(&content, &encryption_key, &associated_data)
+                .context("Failed to encrypt content")?;
+        encrypted_result.envelope.kdf = kdf;
+        encrypted_result.envelope.encoding = encoding.clone();
+        let escrow = self.escrow_key(&encryption_key)?;
+        metrics.record_stage("encrypt", stage);
+
+        if let Some(progress) = progress {
+            progress.stage("Uploading to IPFS", 2, 3);
+        }
+
+        let stage = Instant::now();
+        let cid = self
+            .upload_encrypted(&encrypted_result, &mut metrics)
+            .await?;
+        metrics.record_stage("upload", stage);
+
+        if let Some(progress) = progress {
+            progress.stage("Creating blockchain transaction", 3, 3);
+        }
+
+        let stage = Instant::now();
+        let (capsule_id, gas_used) = self
+            .create_blockchain_capsule(
+                &cid,
+                condition.transaction_value(),
+                condition.type_name(),
+                idempotency_key,
+                &mut metrics,
+            )
+            .await?;
+        metrics.record_stage("transaction", stage);
+
+        if let Some(progress) = progress {
+            progress.finish();
+        }
+
+        let result = CreateCapsuleResult {
+            capsule_id,
+            transaction_digest: format!("0x{:x}", rand::random::<u64>()),
+            cid,
+            encryption_key: base64::engine::general_purpose::STANDARD.encode(encryption_key),
+            content_hash: hash_to_hex(&content_hash),
+            condition,
+            encoding,
+            compression,
+            escrow,
+            gas_used,
+            metrics: Some(metrics),
+        };
+        self.record_audit(
+            AuditOperation::Create,
+            &result.capsule_id,
+            Some(&result.transaction_digest),
+        );
+        self.remember_capsule(&result);
+
+        Ok(result)
+    }
+
+    pub async fn unlock_and_decrypt(
+        &self,
+        capsule_id: &str,
+        encryption_key: &str,
+        payment: Option<u64>,
+        progress: Option<&dyn ProgressSink>,
+    ) -> Result<UnlockResult> {
+        info!(
+            "Unlocking and decrypting capsule: {} (payment: {:?})",
+            capsule_id, payment
+        );
+
+        let state = self
+            .chain
+            .capsule_state(capsule_id)
+            .await
+            .with_context(|| format!("Could not read capsule {capsule_id} from the chain"))?;
+        if let Some(refusal) = self.state_refusal(capsule_id, &state).await {
+            return Ok(refusal);
+        }
+        let cid = state
+            .cid
+            .ok_or_else(|| anyhow::anyhow!("Capsule {capsule_id} has no content CID on chain"))?;
+        let unlock_time_ms = state
+            .unlock_time_ms
+            .ok_or_else(|| anyhow::anyhow!("Capsule {capsule_id} has no unlock time on chain"))?;
+        let expected_hash = state
+            .content_hash
+            .ok_or_else(|| anyhow::anyhow!("Capsule {capsule_id} has no content hash on chain"))?;
+        let expected_hash = hash_from_hex(&expected_hash).context("Invalid content hash format")?;
+        let key = decode_encryption_key(encryption_key)?;
+
+        if let Some(progress) = progress {
+            progress.stage("Validating unlock conditions", 1, 3);
+        }
+
+        let mut metrics = OperationMetrics::default();
+        let stage = Instant::now();
+        // Mock validation
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+        metrics.record_stage("validate", stage);
+
+        if let Some(progress) = progress {
+            progress.stage("Executing unlock transaction", 2, 3);
+        }
+
+        let stage = Instant::now();
+        // Mock blockchain transaction
+        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+        let transaction_digest = format!("0x{:x}", rand::random::<u64>());
+        metrics.record_stage("transaction", stage);
+
+        if let Some(progress) = progress {
+            progress.stage("Downloading and decrypting", 3, 3);
+        }
+
+        let stage = Instant::now();
+        let payload = self
+            .download_from_ipfs(&cid, &mut metrics)
+            .await
+            .with_context(|| format!("Failed to download capsule content {cid}"))?;
+        metrics.bytes_downloaded += payload.len() as u64;
+        metrics.record_stage("download", stage);
+
+        let stage = Instant::now();
+        let encrypted: EncryptionResult = serde_json::from_slice(&payload)
+            .context("Capsule content is not a valid encrypted payload")?;
+        let associated_data =
+            capsule_associated_data(&expected_hash, unlock_time_ms, &encrypted.envelope.encoding);
+        let decrypted = decrypt_result_with_aad(&encrypted, &key, &associated_data)
+            .context("Failed to decrypt capsule content")?;
+        metrics.record_stage("decrypt", stage);
+
+        let content = self
+            .decode_content(
+                decrypted.content.to_vec(),
+                &encrypted.envelope.encoding,
+                &mut metrics,
+            )
+            .await?;
+
+        if let Some(progress) = progress {
+            progress.finish();
+        }
+
+        let result = if verify_content_hash(&content, &expected_hash) {
+            let encoding = &encrypted.envelope.encoding;
+            UnlockResult {
+                success: true,
+                content: Some(content),
+                content_type: encoding.bundle.then(|| BUNDLE_MIME_TYPE.to_string()),
+                error: None,
+                transaction_digest: Some(transaction_digest),
+                failure_reason: None,
+                metrics: Some(metrics),
+                file_metadata: encoding.file_metadata,
+                bundle: encoding.bundle,
+            }
+        } else {
+            UnlockResult {
+                success: false,
+                content: None,
+                content_type: None,
+                error: Some(format!(
+                    "Decrypted content of capsule {capsule_id} does not match its content hash"
+                )),
+                transaction_digest: Some(transaction_digest),
+                failure_reason: None,
+                metrics: Some(metrics),
+                file_metadata: false,
+                bundle: false,
+            }
+        };
+        let operation = if payment.is_some() {
+            AuditOperation::Payment
+        } else {
+            AuditOperation::Unlock
+        };
+        self.record_audit(operation, capsule_id, result.transaction_digest.as_deref());
+
+        Ok(result)
+    }
+
+    pub async fn approve_multisig_capsule(
+        &self,
+        capsule_id: &str,
+        progress: Option<&dyn ProgressSink>,
+    ) -> Result<ApprovalResult> {
+        info!("Approving multisig capsule: {}", capsule_id);
+
+        if let Some(progress) = progress {
+            progress.stage("Submitting approval", 1, 1);
+        }
 
         // Mock blockchain transaction
+        let request = self.build_approve_transaction(capsule_id);
         tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+        let gas_used = self.submit_transaction(&request)?;
 
-        if let Some(pb) = progress {
-            pb.set_message("Complete!");
-            pb.finish();
+        if let Some(progress) = progress {
+            progress.finish();
         }
 
-        Ok(ApprovalResult {
+        let result = ApprovalResult {
             success: true,
             transaction_digest: format!("0x{:x}", rand::random::<u64>()),
             current_approvals: 2,
             required_approvals: 3,
+            gas_used,
             error: None,
-        })
+        };
+        self.record_audit(
+            AuditOperation::Approve,
+            capsule_id,
+            Some(&result.transaction_digest),
+        );
+
+        Ok(result)
     }
 
     pub async fn get_capsule_status(&self, capsule_id: &str) -> Result<CapsuleStatus> {
         // Mock implementation
-        Ok(CapsuleStatus {
+        let mut status = CapsuleStatus {
             capsule_id: capsule_id.to_string(),
             capsule_type: "multisig".to_string(),
             status: "locked".to_string(),
@@ -548,6 +2174,9 @@ impl CapsuleSDK {
             creator: Some("0x1234567890abcdef".to_string()),
             content_size: Some(1024),
             cid: Some("QmTest1234567890".to_string()),
+            content_hash: Some(
+                "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262".to_string(),
+            ),
             unlock_time: None,
             approvals: Some(ApprovalInfo {
                 current: 1,
@@ -560,10 +2189,18 @@ impl CapsuleSDK {
             }),
             price: None,
             transaction_digest: Some(format!("0x{:x}", rand::random::<u64>())),
-        })
+            combinator: None,
+            conditions: vec![],
+            content_available: None,
+            error: None,
+        };
+        if self.is_revoked(capsule_id).await {
+            status.status = "revoked".to_string();
+        }
+        Ok(status)
     }
 
-    pub async fn list_capsules(&self, query: CapsuleQuery) -> Result<Vec<CapsuleStatus>> {
+    pub async fn list_capsules(&self, query: CapsuleQuery) -> Result<CapsuleListPage> {
         // Mock implementation
         let mut capsules = vec![
             CapsuleStatus {
@@ -574,10 +2211,17 @@ impl CapsuleSDK {
                 creator: Some("0x1234567890abcdef".to_string()),
                 content_size: Some(2048),
                 cid: Some("QmTest1234567890".to_string()),
+                content_hash: Some(
+                    "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262".to_string(),
+                ),
                 unlock_time: Some(1735689600000),
                 approvals: None,
                 price: None,
                 transaction_digest: Some(format!("0x{:x}", rand::random::<u64>())),
+                combinator: None,
+                conditions: vec![],
+                content_available: None,
+                error: None,
             },
             CapsuleStatus {
                 capsule_id: "0xabcdef1234567890".to_string(),
@@ -587,6 +2231,9 @@ impl CapsuleSDK {
                 creator: Some("0x1234567890abcdef".to_string()),
                 content_size: Some(1024),
                 cid: Some("QmTest0987654321".to_string()),
+                content_hash: Some(
+                    "4d7a1f2c3b6e5d8f9a0b1c2d3e4f5a6b7c8d9e0f1a2b3c4d5e6f7a8b9c0d1e2f".to_string(),
+                ),
                 unlock_time: None,
                 approvals: Some(ApprovalInfo {
                     current: 3,
@@ -599,6 +2246,10 @@ impl CapsuleSDK {
                 }),
                 price: None,
                 transaction_digest: Some(format!("0x{:x}", rand::random::<u64>())),
+                combinator: None,
+                conditions: vec![],
+                content_available: None,
+                error: None,
             },
         ];
 
@@ -611,17 +2262,9 @@ impl CapsuleSDK {
             capsules.retain(|c| c.status == *status);
         }
 
-        // Apply limit and offset
-        let start = query.offset as usize;
-        let end = std::cmp::min(start + query.limit as usize, capsules.len());
-
-        if start < capsules.len() {
-            capsules = capsules[start..end].to_vec();
-        } else {
-            capsules.clear();
-        }
-
-        Ok(capsules)
+        #[allow(deprecated)]
+        let offset = query.offset;
+        paginate(capsules, query.cursor.as_deref(), offset, query.limit)
     }
 
     pub async fn get_pending_approvals(&self) -> Result<Vec<PendingApproval>> {
@@ -647,6 +2290,9 @@ impl CapsuleSDK {
     // Helper methods
 
     fn generate_encryption_key(&self) -> [u8; 32] {
+        if let Some(key) = self.encryption_key {
+            return key;
+        }
         let mut key = [0u8; 32];
         for i in 0..32 {
             key[i] = rand::random::<u8>();
@@ -654,40 +2300,420 @@ impl CapsuleSDK {
         key
     }
 
-    async fn upload_to_ipfs(&self, content: &[u8]) -> Result<String> {
+    /// Content key for a new capsule, with how it was derived if it came from a passphrase
+    fn new_content_key(&self) -> Result<([u8; 32], Option<PasswordKdf>)> {
+        let Some(ref password) = self.password else {
+            return Ok((self.generate_encryption_key(), None));
+        };
+        let kdf = PasswordKdf::generate(Argon2Params::default())
+            .context("Failed to generate key derivation salt")?;
+        let key = kdf
+            .derive_key(password)
+            .context("Failed to derive key from password")?;
+        Ok((*key, Some(kdf)))
+    }
+
+    /// Compress content with the configured algorithm, timed as its own stage,
+    /// returning what compression did; `None` when none was configured
+    fn compress_content(
+        &self,
+        content: Vec<u8>,
+        metrics: &mut OperationMetrics,
+    ) -> Result<(Vec<u8>, Option<CompressionOutcome>)> {
+        if self.compression == CompressionAlgo::None {
+            return Ok((content, None));
+        }
+        let stage = Instant::now();
+        let original_size = content.len() as u64;
+        let (stored, applied) = compress(content, self.compression)?;
+        metrics.record_stage("compress", stage);
+        let outcome = CompressionOutcome::new(applied, original_size, stored.len() as u64);
+        Ok((stored, Some(outcome)))
+    }
+
+    /// Upload content over the chunk size as separately encrypted chunks,
+    /// returning the manifest to store in the capsule instead and whether it
+    /// was split
+    ///
+    /// Smaller content, or any content when no chunk size is set, is returned
+    /// unchanged.
+    async fn split_large_file(
+        &self,
+        content: Vec<u8>,
+        metrics: &mut OperationMetrics,
+    ) -> Result<(Vec<u8>, bool)> {
+        let Some(chunk_size) = self.chunk_size.filter(|size| content.len() > *size) else {
+            return Ok((content, false));
+        };
+
+        let stage = Instant::now();
+        let pieces: Vec<&[u8]> = content.chunks(chunk_size).collect();
+        let mut chunks = Vec::with_capacity(pieces.len());
+        for (index, piece) in pieces.iter().enumerate() {
+            let key: [u8; 32] = rand::random();
+            let encrypted = encrypt_content_with_aad(piece, &key, &chunk_associated_data(index))
+                .with_context(|| format!("Failed to encrypt chunk {index}"))?;
+            let cid = self
+                .upload_encrypted(&encrypted, metrics)
+                .await
+                .with_context(|| format!("Failed to upload chunk {index}"))?;
+            chunks.push(ChunkRef {
+                cid,
+                key: base64::engine::general_purpose::STANDARD.encode(key),
+                content_hash: hash_to_hex(&encrypted.content_hash),
+                size: piece.len() as u64,
+                proof: Vec::new(),
+            });
+        }
+        debug!("Split {} bytes into {} chunks", content.len(), chunks.len());
+        let manifest = ChunkManifest::new(&pieces, chunks)?;
+        metrics.record_stage("chunks", stage);
+
+        Ok((manifest.to_plaintext()?, true))
+    }
+
+    /// Undo the chunking and compression an envelope records, giving back
+    /// the content the capsule was created with
+    async fn decode_content(
+        &self,
+        plaintext: Vec<u8>,
+        encoding: &ContentEncoding,
+        metrics: &mut OperationMetrics,
+    ) -> Result<Vec<u8>> {
+        let compression = CompressionAlgo::from_envelope(encoding.compression.as_deref())?;
+        let content = if encoding.chunked {
+            let manifest = ChunkManifest::from_plaintext(&plaintext)?;
+            self.reassemble_chunks(&manifest, metrics).await?
+        } else {
+            plaintext
+        };
+        decompress(content, compression).context("Failed to decompress capsule content")
+    }
+
+    /// Download, decrypt and join the chunks a manifest lists
+    async fn reassemble_chunks(
+        &self,
+        manifest: &ChunkManifest,
+        metrics: &mut OperationMetrics,
+    ) -> Result<Vec<u8>> {
+        let stage = Instant::now();
+        let mut chunks = Vec::with_capacity(manifest.chunks.len());
+        for (index, chunk) in manifest.chunks.iter().enumerate() {
+            let payload = self
+                .download_from_ipfs(&chunk.cid, metrics)
+                .await
+                .with_context(|| format!("Failed to download chunk {index} ({})", chunk.cid))?;
+            metrics.bytes_downloaded += payload.len() as u64;
+            let encrypted: EncryptionResult = serde_json::from_slice(&payload)
+                .with_context(|| format!("Chunk {index} is not a valid encrypted payload"))?;
+            let key = decode_encryption_key(&chunk.key)?;
+            let decrypted =
+                decrypt_result_with_aad(&encrypted, &key, &chunk_associated_data(index))
+                    .with_context(|| format!("Failed to decrypt chunk {index}"))?;
+            chunks.push(decrypted.content.to_vec());
+        }
+        let content = manifest.reassemble(chunks)?;
+        metrics.record_stage("chunks", stage);
+
+        Ok(content)
+    }
+
+    fn escrow_key(&self, encryption_key: &[u8; 32]) -> Result<Option<EscrowKey>> {
+        self.escrow_public_key
+            .as_ref()
+            .map(|public_key| EscrowKey::wrap(public_key, encryption_key))
+            .transpose()
+    }
+
+    /// Download a just-created capsule and check that it decrypts to the original content
+    pub async fn verify_upload(&self, result: &CreateCapsuleResult) -> Result<()> {
+        let payload = self
+            .download_from_ipfs(&result.cid, &mut OperationMetrics::default())
+            .await
+            .context("Uploaded content could not be retrieved")?;
+        let encrypted: EncryptionResult = serde_json::from_slice(&payload)
+            .context("Uploaded content is not a valid encrypted payload")?;
+
+        let key = decode_encryption_key(&result.encryption_key)?;
+
+        let decrypted = decrypt_result_with_aad(&encrypted, &key, &result.associated_data()?)
+            .context("Uploaded content failed to decrypt")?;
+        let content = self
+            .decode_content(
+                decrypted.content.to_vec(),
+                &encrypted.envelope.encoding,
+                &mut OperationMetrics::default(),
+            )
+            .await
+            .context("Uploaded content could not be decoded")?;
+
+        let expected_hash =
+            hash_from_hex(&result.content_hash).context("Invalid content hash format")?;
+        if !verify_content_hash(&content, &expected_hash) {
+            anyhow::bail!("Uploaded content does not match the original (hash mismatch)");
+        }
+
+        debug!("Verified upload for CID: {}", result.cid);
+        Ok(())
+    }
+
+    /// Check that a capsule's content is still stored intact, without unlocking it
+    ///
+    /// Downloads the payload its chain record points to and checks that it
+    /// parses. The recorded hash covers the original content, so comparing it
+    /// needs `encryption_key`; the payload is decrypted locally, and the
+    /// chunks of a chunked capsule are fetched and joined.
+    pub async fn verify_capsule(
+        &self,
+        capsule_id: &str,
+        encryption_key: Option<&str>,
+    ) -> Result<VerifyResult> {
+        let state = self
+            .chain
+            .capsule_state(capsule_id)
+            .await
+            .with_context(|| format!("Could not read capsule {capsule_id} from the chain"))?;
+        let cid = state
+            .cid
+            .ok_or_else(|| anyhow::anyhow!("Capsule {capsule_id} has no content CID on chain"))?;
+        let mut result = VerifyResult {
+            capsule_id: capsule_id.to_string(),
+            cid: cid.clone(),
+            cid_reachable: false,
+            payload_valid: false,
+            hash_matches: None,
+        };
+
+        let payload = match self
+            .download_from_ipfs(&cid, &mut OperationMetrics::default())
+            .await
+        {
+            Ok(payload) => payload,
+            Err(e) if is_not_found(&e) => return Ok(result),
+            Err(e) => return Err(e).with_context(|| format!("Failed to download {cid}")),
+        };
+        result.cid_reachable = true;
+
+        let Ok(encrypted) = serde_json::from_slice::<EncryptionResult>(&payload) else {
+            return Ok(result);
+        };
+        result.payload_valid = true;
+
+        if let Some(encryption_key) = encryption_key {
+            let unlock_time_ms = state.unlock_time_ms.ok_or_else(|| {
+                anyhow::anyhow!("Capsule {capsule_id} has no unlock time on chain")
+            })?;
+            let expected_hash = state.content_hash.ok_or_else(|| {
+                anyhow::anyhow!("Capsule {capsule_id} has no content hash on chain")
+            })?;
+            let expected_hash =
+                hash_from_hex(&expected_hash).context("Invalid content hash format")?;
+            let key = decode_encryption_key(encryption_key)?;
+            let decrypted = decrypt_result_with_aad(
+                &encrypted,
+                &key,
+                &capsule_associated_data(
+                    &expected_hash,
+                    unlock_time_ms,
+                    &encrypted.envelope.encoding,
+                ),
+            )
+            .context("Failed to decrypt capsule content (wrong key or corrupted content)")?;
+            let content = self
+                .decode_content(
+                    decrypted.content.to_vec(),
+                    &encrypted.envelope.encoding,
+                    &mut OperationMetrics::default(),
+                )
+                .await?;
+            result.hash_matches = Some(verify_content_hash(&content, &expected_hash));
+        }
+
+        Ok(result)
+    }
+
+    async fn upload_encrypted(
+        &self,
+        encrypted: &EncryptionResult,
+        metrics: &mut OperationMetrics,
+    ) -> Result<String> {
+        let payload =
+            serde_json::to_vec(encrypted).context("Failed to serialize encrypted content")?;
+        let cid = self.upload_to_ipfs(&payload, metrics).await?;
+        metrics.bytes_uploaded += payload.len() as u64;
+        Ok(cid)
+    }
+
+    async fn upload_to_ipfs(
+        &self,
+        content: &[u8],
+        metrics: &mut OperationMetrics,
+    ) -> Result<String> {
         debug!(
             "Uploading {} bytes to IPFS using {}",
             content.len(),
-            self.config.ipfs_url
+            self.config.ipfs_endpoint()
         );
 
-        // Mock IPFS upload - in real version would use ipfs_client
-        let _client = &self.ipfs_client; // Would be used in real implementation
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-
-        // Generate mock CID
-        let cid = format!("Qm{:x}", rand::random::<u64>());
-        debug!("Generated CID: {cid}");
+        let cid = retry_counted(self.retry, &mut metrics.retries, || async {
+            let _connection = self.ipfs_connection().await?;
+            self.store.put(content).await
+        })
+        .await
+        .context("Failed to upload encrypted content to IPFS")?;
+        debug!("Uploaded as CID: {cid}");
 
         Ok(cid)
     }
 
+    async fn download_from_ipfs(
+        &self,
+        cid: &str,
+        metrics: &mut OperationMetrics,
+    ) -> Result<Vec<u8>> {
+        debug!(
+            "Downloading {cid} from IPFS using {}",
+            self.config.ipfs_endpoint()
+        );
+        retry_counted(self.retry, &mut metrics.retries, || async {
+            let _connection = self.ipfs_connection().await?;
+            match (self.store.get(cid).await, &self.gateways) {
+                (Err(e), Some(gateways)) if is_not_found(&e) => {
+                    debug!("{cid} is not on the IPFS node, trying gateways");
+                    gateways.get(cid).await
+                }
+                (result, _) => result,
+            }
+        })
+        .await
+    }
+
+    /// Whether a CID can be fetched from the IPFS node or any configured gateway
+    ///
+    /// Uses existence checks rather than downloads. Only a definite "not found"
+    /// everywhere answers `false`; requests that fail are errors.
+    pub async fn content_available(&self, cid: &str) -> Result<bool> {
+        let _connection = self.ipfs_connection().await?;
+        if self.store.contains(cid).await? {
+            return Ok(true);
+        }
+        match self.gateways {
+            Some(ref gateways) => gateways.contains(cid).await,
+            None => Ok(false),
+        }
+    }
+
+    /// Wait for a free IPFS connection, then for the rate limiter if one is
+    /// configured; the request holds the returned permit until it finishes
+    async fn ipfs_connection(&self) -> Result<SemaphorePermit<'_>> {
+        let permit = self
+            .ipfs_connections
+            .acquire()
+            .await
+            .context("IPFS connection pool closed")?;
+        if let Some(ref limiter) = self.ipfs_limiter {
+            limiter.acquire().await;
+        }
+        Ok(permit)
+    }
+
+    /// Create the capsule object on chain, returning its ID and the gas used
+    ///
+    /// The transaction is built once, so every resubmission after a transient
+    /// failure carries the same idempotency key.
     async fn create_blockchain_capsule(
         &self,
-        _cid: &str,
+        cid: &str,
         value: u64,
         capsule_type: &str,
-    ) -> Result<String> {
+        idempotency_key: &str,
+        metrics: &mut OperationMetrics,
+    ) -> Result<(String, u64)> {
         debug!("Creating {capsule_type} capsule on blockchain with value: {value}");
+        let request = self
+            .build_create_transaction(
+                cid,
+                value,
+                capsule_type,
+                metrics.bytes_uploaded,
+                idempotency_key,
+            )
+            .await;
 
         // Mock blockchain transaction - in real version would use http_client
         let _client = &self.http_client; // Would be used for Sui RPC calls
-        tokio::time::sleep(tokio::time::Duration::from_millis(1500)).await;
+        let gas_used = retry_counted(self.retry, &mut metrics.retries, || async {
+            tokio::time::sleep(tokio::time::Duration::from_millis(1500)).await;
+            self.submit_transaction(&request)
+        })
+        .await?;
 
         let capsule_id = format!("0x{:x}", rand::random::<u64>());
         debug!("Generated capsule ID: {capsule_id}");
 
-        Ok(capsule_id)
+        Ok((capsule_id, gas_used))
+    }
+
+    /// Build the transaction that creates a capsule
+    ///
+    /// The configured gas budget is used as given. Otherwise the budget is a
+    /// dry run's estimate plus headroom, or `DEFAULT_GAS_BUDGET` when the chain
+    /// cannot estimate.
+    pub async fn build_create_transaction(
+        &self,
+        cid: &str,
+        value: u64,
+        capsule_type: &str,
+        storage_bytes: u64,
+        idempotency_key: &str,
+    ) -> TransactionRequest {
+        let gas_budget = match self.config.gas_budget {
+            Some(gas_budget) => gas_budget,
+            None => match self
+                .chain
+                .estimate_create_gas(capsule_type, storage_bytes)
+                .await
+            {
+                Ok(gas) => gas.saturating_add(gas * GAS_ESTIMATE_HEADROOM_PERCENT / 100),
+                Err(e) => {
+                    debug!("Gas dry run failed, using the default budget: {e:#}");
+                    DEFAULT_GAS_BUDGET
+                }
+            },
+        };
+        TransactionRequest {
+            function: format!("create_{capsule_type}_capsule"),
+            arguments: vec![cid.to_string(), value.to_string()],
+            gas_budget,
+            idempotency_key: Some(idempotency_key.to_string()),
+        }
+    }
+
+    /// Build the transaction that approves a multisig capsule
+    pub fn build_approve_transaction(&self, capsule_id: &str) -> TransactionRequest {
+        TransactionRequest {
+            function: "approve".to_string(),
+            arguments: vec![capsule_id.to_string()],
+            gas_budget: self.config.gas_budget.unwrap_or(DEFAULT_GAS_BUDGET),
+            idempotency_key: None,
+        }
+    }
+
+    /// Submit a built transaction, returning the gas it used
+    ///
+    /// Mocked: every transaction costs `MOCK_GAS_USED`, and a smaller budget
+    /// fails the way an out-of-gas transaction would.
+    fn submit_transaction(&self, request: &TransactionRequest) -> Result<u64> {
+        if request.gas_budget < MOCK_GAS_USED {
+            anyhow::bail!(
+                "{} ran out of gas: the budget of {} MIST is below the {} MIST it needs \
+                 (raise --gas-budget)",
+                request.function,
+                request.gas_budget,
+                MOCK_GAS_USED
+            );
+        }
+        Ok(MOCK_GAS_USED)
     }
 }
 
@@ -708,7 +2734,7 @@ impl BatchOperation {
         let total_files = self.files.len();
         info!("Processing batch of {total_files} files");
 
-        let pb = ProgressBar::new(total_files as u64);
+        let pb = progress_bars().add(ProgressBar::new(total_files as u64));
         pb.set_style(ProgressStyle::default_bar()
             .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} Processing {msg}")
             .unwrap());
@@ -746,3 +2772,1666 @@ impl BatchOperation {
         Ok(results)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStore;
+
+    const ADDR_A: &str = "0x1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef";
+    const ADDR_B: &str = "0xabcdef1234567890abcdef1234567890abcdef1234567890abcdef1234567890";
+
+    /// SDK whose payloads stay in memory instead of going to an IPFS node
+    async fn memory_sdk(config: Config) -> CapsuleSDK {
+        CapsuleSDK::new(config)
+            .await
+            .unwrap()
+            .with_store(Arc::new(MemoryStore::new()))
+    }
+
+    #[tokio::test]
+    async fn test_http_client_uses_configured_proxy() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let proxy = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/rpc"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("via proxy"))
+            .expect(1)
+            .mount(&proxy)
+            .await;
+
+        let config = Config {
+            proxy_url: Some(proxy.uri()),
+            ..Config::default()
+        };
+        let client = build_http_client(&config).unwrap();
+
+        // The host does not resolve, so only the proxy can answer
+        let body = client
+            .get("http://capsule-rpc.invalid/rpc")
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert_eq!(body, "via proxy");
+    }
+
+    /// Chain for SDK tests holding at most one capsule, with a dry-run gas
+    /// figure and a clock that are unavailable unless set
+    ///
+    /// Every capsule ID reads back `state`, and transactions change it the
+    /// way the contract would.
+    #[derive(Default)]
+    struct MockChain {
+        /// Dry-run gas estimate; `None` fails like a node that cannot dry-run
+        gas: Option<u64>,
+        /// Latest checkpoint time; `None` fails like an unreachable node
+        clock_ms: Option<u64>,
+        /// The capsule on chain; `None` if there is none or it was destroyed
+        state: std::sync::Mutex<Option<CapsuleChainState>>,
+    }
+
+    impl MockChain {
+        /// Chain holding one capsule owned by `ADDR_A`, with content at `cid`
+        fn with_capsule(cid: Option<&str>) -> Self {
+            Self {
+                state: std::sync::Mutex::new(Some(CapsuleChainState {
+                    owner: ADDR_A.to_string(),
+                    revoked: false,
+                    cid: cid.map(str::to_string),
+                    content_hash: None,
+                    unlock_time_ms: None,
+                    unlocked: false,
+                    approvals: Vec::new(),
+                    required_approvals: None,
+                })),
+                ..Self::default()
+            }
+        }
+
+        /// Chain holding the capsule a create call returned
+        fn created(created: &CreateCapsuleResult) -> Self {
+            let chain = Self::with_capsule(Some(&created.cid));
+            chain.update(|state| {
+                state.content_hash = Some(created.content_hash.clone());
+                state.unlock_time_ms = Some(created.condition.recorded_unlock_time());
+            });
+            chain
+        }
+
+        /// Chain holding one 2-of-3 multisig capsule with these approvals recorded
+        fn multisig(approvals: &[&str], unlocked: bool) -> Self {
+            let chain = Self::with_capsule(None);
+            chain.update(|state| {
+                state.approvals = approvals.iter().map(|a| a.to_string()).collect();
+                state.unlocked = unlocked;
+                state.required_approvals = Some(2);
+            });
+            chain
+        }
+
+        fn capsule(&self) -> Option<CapsuleChainState> {
+            self.state.lock().unwrap().clone()
+        }
+
+        fn update(&self, change: impl FnOnce(&mut CapsuleChainState)) {
+            change(self.state.lock().unwrap().as_mut().expect("no capsule"));
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl ChainClient for MockChain {
+        async fn estimate_create_gas(
+            &self,
+            _capsule_type: &str,
+            _storage_bytes: u64,
+        ) -> Result<u64> {
+            self.gas.ok_or_else(|| anyhow::anyhow!("no dry run"))
+        }
+
+        async fn latest_timestamp_ms(&self) -> Result<u64> {
+            self.clock_ms
+                .ok_or_else(|| anyhow::anyhow!("node unreachable"))
+        }
+
+        async fn capsule_state(&self, capsule_id: &str) -> Result<CapsuleChainState> {
+            self.capsule()
+                .ok_or_else(|| anyhow::anyhow!("Capsule {capsule_id} not found on chain"))
+        }
+
+        async fn revoke_capsule(&self, capsule_id: &str, sender: &str) -> Result<String> {
+            assert_eq!(self.capsule_state(capsule_id).await?.owner, sender);
+            self.update(|state| state.revoked = true);
+            Ok("0xrevoke".to_string())
+        }
+
+        async fn destroy_capsule(&self, capsule_id: &str, sender: &str) -> Result<String> {
+            assert_eq!(self.capsule_state(capsule_id).await?.owner, sender);
+            *self.state.lock().unwrap() = None;
+            Ok("0xdestroy".to_string())
+        }
+
+        async fn revoke_approval(&self, capsule_id: &str, sender: &str) -> Result<String> {
+            if self
+                .capsule_state(capsule_id)
+                .await?
+                .required_approvals
+                .is_none()
+            {
+                anyhow::bail!("not a multisig capsule");
+            }
+            self.update(|state| state.approvals.retain(|approver| approver != sender));
+            Ok("0xwithdraw".to_string())
+        }
+    }
+
+    async fn sdk_for(address: &str, chain: Arc<dyn ChainClient>) -> CapsuleSDK {
+        let config = Config {
+            address: Some(address.to_string()),
+            ..Config::default()
+        };
+        CapsuleSDK::new(config).await.unwrap().with_chain(chain)
+    }
+
+    #[tokio::test]
+    async fn test_revoked_capsule_cannot_be_unlocked() {
+        let chain = Arc::new(MockChain::with_capsule(None));
+        let sdk = sdk_for(ADDR_A, chain.clone()).await;
+        let key = base64::engine::general_purpose::STANDARD.encode([7u8; 32]);
+
+        let result = sdk.revoke("0xcap").await.unwrap();
+        assert_eq!(result.transaction_digest, "0xrevoke");
+
+        let unlock = sdk
+            .unlock_and_decrypt("0xcap", &key, None, None)
+            .await
+            .unwrap();
+        assert!(!unlock.success);
+        assert!(unlock.content.is_none());
+        assert_eq!(unlock.failure_reason, Some(UnlockFailureReason::Revoked));
+
+        let status = sdk.get_capsule_status("0xcap").await.unwrap();
+        assert_eq!(status.status, "revoked");
+        assert!(sdk.revoke("0xcap").await.is_err());
+    }
+
+    /// SDK sending from `address` whose local index and content live in memory or `dir`
+    async fn cancel_sdk(
+        address: &str,
+        dir: &std::path::Path,
+        store: Arc<MemoryStore>,
+    ) -> CapsuleSDK {
+        let config = Config {
+            address: Some(address.to_string()),
+            capsule_records_path: Some(dir.join("capsules.jsonl")),
+            ..Config::default()
+        };
+        CapsuleSDK::new(config).await.unwrap().with_store(store)
+    }
+
+    #[tokio::test]
+    async fn test_owner_cancels_capsule_and_unpins_content() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = Arc::new(MemoryStore::new());
+        let sdk = cancel_sdk(ADDR_A, dir.path(), store.clone()).await;
+        let created = sdk
+            .create_time_capsule(b"no longer wanted".to_vec(), 0, None)
+            .await
+            .unwrap();
+        let chain = Arc::new(MockChain::with_capsule(Some(&created.cid)));
+        let sdk = sdk.with_chain(chain.clone());
+
+        let result = sdk.cancel_capsule(&created.capsule_id, true).await.unwrap();
+        assert_eq!(result.transaction_digest, "0xdestroy");
+        assert_eq!(result.unpinned_cid.as_deref(), Some(created.cid.as_str()));
+        assert!(chain.capsule().is_none());
+        assert!(!store.contains(&created.cid).await.unwrap());
+        assert!(sdk.load_local_capsules().unwrap().is_empty());
+        assert!(sdk
+            .cancel_capsule(&created.capsule_id, false)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_only_owner_can_cancel_and_content_stays_pinned_by_default() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = Arc::new(MemoryStore::new());
+        let owner = cancel_sdk(ADDR_A, dir.path(), store.clone()).await;
+        let created = owner
+            .create_time_capsule(b"still wanted".to_vec(), 0, None)
+            .await
+            .unwrap();
+        let chain = Arc::new(MockChain::with_capsule(Some(&created.cid)));
+
+        let stranger = cancel_sdk(ADDR_B, dir.path(), store.clone())
+            .await
+            .with_chain(chain.clone());
+        let error = stranger
+            .cancel_capsule(&created.capsule_id, true)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("Only the owner"), "{error:#}");
+        assert!(chain.capsule().is_some());
+        assert!(store.contains(&created.cid).await.unwrap());
+        assert_eq!(stranger.load_local_capsules().unwrap().len(), 1);
+
+        let result = owner
+            .with_chain(chain.clone())
+            .cancel_capsule(&created.capsule_id, false)
+            .await
+            .unwrap();
+        assert!(result.unpinned_cid.is_none());
+        assert!(chain.capsule().is_none());
+        assert!(store.contains(&created.cid).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_revoking_approval_restores_prior_count() {
+        let chain = Arc::new(MockChain::multisig(&[ADDR_A], false));
+        let sdk = sdk_for(ADDR_B, chain.clone()).await;
+
+        chain.update(|state| state.approvals.push(ADDR_B.to_string()));
+        let result = sdk.revoke_approval("0xcap").await.unwrap();
+        assert_eq!(result.transaction_digest, "0xwithdraw");
+        assert_eq!(
+            (result.current_approvals, result.required_approvals),
+            (1, 2)
+        );
+        assert_eq!(chain.capsule().unwrap().approvals, [ADDR_A]);
+    }
+
+    #[tokio::test]
+    async fn test_revoking_approval_needs_a_prior_approval_on_a_locked_capsule() {
+        let chain = Arc::new(MockChain::multisig(&[ADDR_A], false));
+        let error = sdk_for(ADDR_B, chain.clone())
+            .await
+            .revoke_approval("0xcap")
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("has not approved"));
+        assert_eq!(chain.capsule().unwrap().approvals.len(), 1);
+
+        let unlocked = Arc::new(MockChain::multisig(&[ADDR_A, ADDR_B], true));
+        let error = sdk_for(ADDR_B, unlocked.clone())
+            .await
+            .revoke_approval("0xcap")
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("already unlocked"));
+        assert_eq!(unlocked.capsule().unwrap().approvals.len(), 2);
+
+        let not_multisig = sdk_for(ADDR_A, Arc::new(MockChain::with_capsule(None))).await;
+        assert!(not_multisig.revoke_approval("0xcap").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_cid_reports_content_unavailable() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let gateways = [MockServer::start().await, MockServer::start().await];
+        for gateway in &gateways {
+            Mock::given(method("HEAD"))
+                .respond_with(ResponseTemplate::new(404))
+                .expect(1..)
+                .mount(gateway)
+                .await;
+        }
+        let config = Config {
+            ipfs_gateways: gateways.iter().map(|gateway| gateway.uri()).collect(),
+            ..Config::default()
+        };
+        let sdk = memory_sdk(config)
+            .await
+            .with_chain(Arc::new(MockChain::with_capsule(Some("QmGone"))));
+        let key = base64::engine::general_purpose::STANDARD.encode([7u8; 32]);
+
+        let unlock = sdk
+            .unlock_and_decrypt("0xcap", &key, None, None)
+            .await
+            .unwrap();
+
+        assert!(!unlock.success);
+        assert_eq!(
+            unlock.failure_reason,
+            Some(UnlockFailureReason::ContentUnavailable {
+                cid: "QmGone".to_string()
+            })
+        );
+        assert!(!sdk.content_available("QmGone").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_unlock_decrypts_created_capsule() {
+        let sdk = memory_sdk(Config::default()).await;
+        let created = sdk
+            .create_time_capsule(b"opened at last".to_vec(), 1_700_000_000_000, None)
+            .await
+            .unwrap();
+
+        let sdk = sdk.with_chain(Arc::new(MockChain::created(&created)));
+        let unlock = sdk
+            .unlock_and_decrypt(&created.capsule_id, &created.encryption_key, None, None)
+            .await
+            .unwrap();
+        assert!(unlock.success);
+        assert_eq!(unlock.content.as_deref(), Some(&b"opened at last"[..]));
+
+        // The content is bound to the hash the chain records
+        let mut mismatched = created.clone();
+        mismatched.content_hash = hash_to_hex(&[0u8; 32]);
+        let sdk = sdk.with_chain(Arc::new(MockChain::created(&mismatched)));
+        assert!(sdk
+            .unlock_and_decrypt(&created.capsule_id, &created.encryption_key, None, None)
+            .await
+            .is_err());
+
+        // A key from another capsule does not decrypt it
+        let other_key = base64::engine::general_purpose::STANDARD.encode([7u8; 32]);
+        assert!(sdk
+            .unlock_and_decrypt(&created.capsule_id, &other_key, None, None)
+            .await
+            .is_err());
+    }
+
+    /// `sui_getObject` response for a capsule as `simple_capsule.move` records it
+    fn contract_object(created: &CreateCapsuleResult) -> serde_json::Value {
+        serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {"data": {
+                "objectId": created.capsule_id,
+                "owner": {"AddressOwner": ADDR_A},
+                "content": {"dataType": "moveObject", "fields": {
+                    "owner": ADDR_A,
+                    "cid": created.cid,
+                    "content_hash": hash_from_hex(&created.content_hash).unwrap(),
+                    "unlock_time_ms": created.condition.recorded_unlock_time().to_string(),
+                    "unlocked": false
+                }}
+            }}
+        })
+    }
+
+    /// Sui node answering every `sui_getObject` request with `object`
+    async fn node_serving(object: serde_json::Value) -> wiremock::MockServer {
+        use wiremock::matchers::{body_partial_json, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let node = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(
+                serde_json::json!({"method": "sui_getObject"}),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(object))
+            .mount(&node)
+            .await;
+        node
+    }
+
+    fn every_capsule_type() -> Vec<BoundCondition> {
+        vec![
+            BoundCondition::Time {
+                unlock_time: 1_700_000_000_000,
+            },
+            BoundCondition::Multisig {
+                threshold: 1,
+                approvers: vec![ADDR_A.to_string()],
+            },
+            BoundCondition::Payment { price: 1_000 },
+            BoundCondition::Composite {
+                combinator: Combinator::Or,
+                conditions: vec![
+                    BoundCondition::Time {
+                        unlock_time: 1_700_000_000_000,
+                    },
+                    BoundCondition::Payment { price: 1_000 },
+                ],
+            },
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_every_capsule_type_unlocks_from_its_chain_record() {
+        for condition in every_capsule_type() {
+            let sdk = memory_sdk(Config::default()).await;
+            let created = sdk
+                .create_with_condition(b"sealed".to_vec(), condition, None, None)
+                .await
+                .unwrap();
+
+            let node = node_serving(contract_object(&created)).await;
+            let sdk = sdk.with_chain(Arc::new(SuiRpcChain::new(Client::new(), node.uri())));
+            let unlock = sdk
+                .unlock_and_decrypt(&created.capsule_id, &created.encryption_key, None, None)
+                .await
+                .unwrap();
+            assert!(unlock.success, "{}", created.condition.type_name());
+            assert_eq!(unlock.content.as_deref(), Some(&b"sealed"[..]));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_altered_chain_record_fails_decryption() {
+        const FIELDS: &str = "/result/data/content/fields";
+
+        for condition in every_capsule_type() {
+            let store = Arc::new(MemoryStore::new());
+            let created = memory_sdk(Config::default())
+                .await
+                .with_store(store.clone())
+                .create_with_condition(b"sealed".to_vec(), condition, None, None)
+                .await
+                .unwrap();
+
+            let mut other_time = contract_object(&created);
+            let recorded = created.condition.recorded_unlock_time();
+            *other_time
+                .pointer_mut(&format!("{FIELDS}/unlock_time_ms"))
+                .unwrap() = serde_json::json!((recorded + 1).to_string());
+            let mut other_content = contract_object(&created);
+            *other_content
+                .pointer_mut(&format!("{FIELDS}/content_hash"))
+                .unwrap() = serde_json::json!(vec![0u8; 32]);
+
+            for object in [other_time, other_content] {
+                let node = node_serving(object).await;
+                let sdk = memory_sdk(Config::default())
+                    .await
+                    .with_store(store.clone())
+                    .with_chain(Arc::new(SuiRpcChain::new(Client::new(), node.uri())));
+                let error = sdk
+                    .unlock_and_decrypt(&created.capsule_id, &created.encryption_key, None, None)
+                    .await
+                    .unwrap_err();
+                assert!(
+                    format!("{error:#}").contains("Failed to decrypt"),
+                    "{}: {error:#}",
+                    created.condition.type_name()
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_compressed_capsule_unlocks_to_original() {
+        let text = "line of a long diary entry\n".repeat(500).into_bytes();
+        let random: Vec<u8> = (0..2048).map(|_| rand::random::<u8>()).collect();
+
+        for (algo, content) in [
+            (CompressionAlgo::Gzip, text.clone()),
+            (CompressionAlgo::Zstd, text),
+            (CompressionAlgo::Zstd, random),
+        ] {
+            let sdk = memory_sdk(Config::default()).await.with_compression(algo);
+            let created = sdk
+                .create_time_capsule(content.clone(), 1_700_000_000_000, None)
+                .await
+                .unwrap();
+            sdk.verify_upload(&created).await.unwrap();
+
+            let sdk = sdk.with_chain(Arc::new(MockChain::created(&created)));
+            let unlock = sdk
+                .unlock_and_decrypt(&created.capsule_id, &created.encryption_key, None, None)
+                .await
+                .unwrap();
+            assert!(unlock.success);
+            assert_eq!(unlock.content.unwrap(), content);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_already_compressed_content_stored_uncompressed() {
+        let png = [b"\x89PNG\r\n\x1a\n".as_slice(), &[0u8; 4096]].concat();
+        let text = "line of a long diary entry\n".repeat(500).into_bytes();
+
+        let sdk = memory_sdk(Config::default())
+            .await
+            .with_compression(CompressionAlgo::Gzip);
+        let created = sdk
+            .create_time_capsule(png.clone(), 1_700_000_000_000, None)
+            .await
+            .unwrap();
+        let outcome = created.compression.unwrap();
+        assert_eq!(outcome.applied, CompressionAlgo::None);
+        assert_eq!(outcome.ratio, 1.0);
+        assert_eq!(created.encoding.compression, None);
+        sdk.verify_upload(&created).await.unwrap();
+
+        let created = sdk
+            .create_time_capsule(text, 1_700_000_000_000, None)
+            .await
+            .unwrap();
+        let outcome = created.compression.unwrap();
+        assert_eq!(outcome.applied, CompressionAlgo::Gzip);
+        assert!(outcome.ratio < 0.1);
+        assert_eq!(created.encoding.compression.as_deref(), Some("gzip"));
+    }
+
+    #[tokio::test]
+    async fn test_plaintext_starting_with_old_encoding_magic_round_trips() {
+        let short = b"CAPSZIP\x01 is how this note starts".to_vec();
+        let long = [b"CAPSZIP\x01".as_slice(), &b"abc".repeat(500)].concat();
+        let manifest_like =
+            b"CAPSCHNK{\"total_size\":0,\"merkle_root\":\"\",\"chunks\":[]}".to_vec();
+
+        for (algo, content, applied) in [
+            (CompressionAlgo::None, short.clone(), None),
+            (CompressionAlgo::Gzip, short, None),
+            (CompressionAlgo::Gzip, long, Some("gzip")),
+            (CompressionAlgo::None, manifest_like, None),
+        ] {
+            let sdk = memory_sdk(Config::default()).await.with_compression(algo);
+            let created = sdk
+                .create_time_capsule(content.clone(), 1_700_000_000_000, None)
+                .await
+                .unwrap();
+            assert_eq!(created.encoding.compression.as_deref(), applied);
+            assert!(!created.encoding.chunked);
+            assert_eq!(
+                created.content_hash,
+                hash_to_hex(&hash_content_bytes(&content))
+            );
+
+            let sdk = sdk.with_chain(Arc::new(MockChain::created(&created)));
+            let unlock = sdk
+                .unlock_and_decrypt(&created.capsule_id, &created.encryption_key, None, None)
+                .await
+                .unwrap();
+            assert_eq!(unlock.content.unwrap(), content);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_file_metadata_split_off_only_when_recorded() {
+        let metadata = FileMetadata {
+            name: "diary.txt".to_string(),
+            mtime: Some(1_600_000_000_000),
+            mode: Some(0o644),
+        };
+        let with_metadata = crate::metadata::attach_metadata(&metadata, b"dear diary").unwrap();
+
+        for (attached, content) in [(true, with_metadata.clone()), (false, with_metadata)] {
+            let sdk = memory_sdk(Config::default())
+                .await
+                .with_file_metadata(attached);
+            let created = sdk
+                .create_time_capsule(content.clone(), 1_700_000_000_000, None)
+                .await
+                .unwrap();
+
+            let sdk = sdk.with_chain(Arc::new(MockChain::created(&created)));
+            let unlock = sdk
+                .unlock_and_decrypt(&created.capsule_id, &created.encryption_key, None, None)
+                .await
+                .unwrap();
+            assert_eq!(unlock.file_metadata, attached);
+            let (found, unlocked) = unlock.content_and_metadata().unwrap();
+            if attached {
+                assert_eq!(found, Some(metadata.clone()));
+                assert_eq!(unlocked, b"dear diary");
+            } else {
+                // Plaintext that merely starts with the metadata magic is left alone
+                assert_eq!(found, None);
+                assert_eq!(unlocked, content);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bundle_recognized_only_when_recorded() {
+        let bundle = crate::archive::encode_archive(&[crate::archive::ArchiveEntry {
+            name: "notes.txt".to_string(),
+            data: b"remember the milk".to_vec(),
+            mtime: None,
+            mode: None,
+        }])
+        .unwrap();
+
+        for recorded in [true, false] {
+            let sdk = memory_sdk(Config::default()).await.with_bundle(recorded);
+            let created = sdk
+                .create_time_capsule(bundle.clone(), 1_700_000_000_000, None)
+                .await
+                .unwrap();
+
+            let sdk = sdk.with_chain(Arc::new(MockChain::created(&created)));
+            let unlock = sdk
+                .unlock_and_decrypt(&created.capsule_id, &created.encryption_key, None, None)
+                .await
+                .unwrap();
+            assert_eq!(unlock.bundle, recorded);
+            assert_eq!(
+                unlock.content_type.as_deref(),
+                recorded.then_some(BUNDLE_MIME_TYPE)
+            );
+            assert_eq!(unlock.content.unwrap(), bundle);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_large_file_split_into_chunks_and_reassembled() {
+        let store = Arc::new(MemoryStore::new());
+        let content: Vec<u8> = (0..2500u32).map(|i| (i % 251) as u8).collect();
+        let sdk = CapsuleSDK::new(Config::default())
+            .await
+            .unwrap()
+            .with_store(store.clone())
+            .with_chunk_size(1000);
+        let created = sdk
+            .create_time_capsule(content.clone(), 1_700_000_000_000, None)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            created.content_hash,
+            hash_to_hex(&hash_content_bytes(&content))
+        );
+
+        // The capsule itself only lists the three chunks
+        let key = decode_encryption_key(&created.encryption_key).unwrap();
+        let payload: EncryptionResult =
+            serde_json::from_slice(&store.get(&created.cid).await.unwrap()).unwrap();
+        let manifest =
+            decrypt_result_with_aad(&payload, &key, &created.associated_data().unwrap()).unwrap();
+        assert!(payload.envelope.encoding.chunked);
+        let manifest = ChunkManifest::from_plaintext(&manifest.content).unwrap();
+        let sizes: Vec<u64> = manifest.chunks.iter().map(|c| c.size).collect();
+        assert_eq!(sizes, [1000, 1000, 500]);
+
+        let sdk = sdk.with_chain(Arc::new(MockChain::created(&created)));
+        let unlock = sdk
+            .unlock_and_decrypt(&created.capsule_id, &created.encryption_key, None, None)
+            .await
+            .unwrap();
+        assert!(unlock.success);
+        assert_eq!(unlock.content.unwrap(), content);
+
+        // A chunk altered in storage is caught on unlock
+        let altered =
+            encrypt_content_with_aad(&[0u8; 1000], &[9u8; 32], &chunk_associated_data(1)).unwrap();
+        store.replace(
+            &manifest.chunks[1].cid,
+            serde_json::to_vec(&altered).unwrap(),
+        );
+        assert!(sdk
+            .unlock_and_decrypt(&created.capsule_id, &created.encryption_key, None, None)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_verify_capsule_checks_stored_content() {
+        let store = Arc::new(MemoryStore::new());
+        let sdk = memory_sdk(Config::default())
+            .await
+            .with_store(store.clone());
+        let created = sdk
+            .create_time_capsule(b"still here".to_vec(), 1_700_000_000_000, None)
+            .await
+            .unwrap();
+        let sdk = sdk.with_chain(Arc::new(MockChain::created(&created)));
+
+        let verified = sdk.verify_capsule(&created.capsule_id, None).await.unwrap();
+        assert!(verified.cid_reachable);
+        assert!(verified.payload_valid);
+        assert_eq!(verified.hash_matches, None);
+
+        let verified = sdk
+            .verify_capsule(&created.capsule_id, Some(&created.encryption_key))
+            .await
+            .unwrap();
+        assert_eq!(verified.hash_matches, Some(true));
+
+        // The content is bound to the hash the chain records
+        let mut mismatched = created.clone();
+        mismatched.content_hash = hash_to_hex(&[0u8; 32]);
+        let sdk = sdk.with_chain(Arc::new(MockChain::created(&mismatched)));
+        assert!(sdk
+            .verify_capsule(&created.capsule_id, Some(&created.encryption_key))
+            .await
+            .is_err());
+
+        // Stored bytes that are not an encrypted payload
+        store.replace(&created.cid, b"garbage".to_vec());
+        let verified = sdk.verify_capsule(&created.capsule_id, None).await.unwrap();
+        assert!(verified.cid_reachable);
+        assert!(!verified.payload_valid);
+
+        // A CID nothing serves
+        let mut gone = created.clone();
+        gone.cid = "QmGone".to_string();
+        let sdk = sdk.with_chain(Arc::new(MockChain::created(&gone)));
+        let verified = sdk
+            .verify_capsule(&created.capsule_id, Some(&created.encryption_key))
+            .await
+            .unwrap();
+        assert!(!verified.cid_reachable);
+        assert!(!verified.payload_valid);
+        assert_eq!(verified.hash_matches, None);
+    }
+
+    #[tokio::test]
+    async fn test_non_owner_cannot_revoke() {
+        let chain = Arc::new(MockChain::with_capsule(None));
+        let sdk = sdk_for(ADDR_B, chain.clone()).await;
+
+        let err = sdk.revoke("0xcap").await.unwrap_err();
+        assert!(err.to_string().contains("Only the owner"));
+        assert!(!chain.capsule().unwrap().revoked);
+    }
+
+    #[tokio::test]
+    async fn test_reshare_reconstructs_and_resplits_key() {
+        let key = base64::engine::general_purpose::STANDARD.encode([9u8; 32]);
+        let old_shares = split_key(&key, 2, 3).unwrap();
+        let chain = Arc::new(MockChain::with_capsule(None));
+        let sdk = sdk_for(ADDR_A, chain.clone()).await;
+
+        let result = sdk.reshare("0xcap", &old_shares[1..], 3, 5).await.unwrap();
+        assert_eq!(result.threshold, 3);
+        assert_eq!(result.shares.len(), 5);
+        assert_eq!(combine_shares(&result.shares[2..]).unwrap(), key);
+        assert!(combine_shares(&result.shares[..2]).is_err());
+
+        // Not enough old shares
+        assert!(sdk.reshare("0xcap", &old_shares[..1], 3, 5).await.is_err());
+
+        // Only the owner may reshare
+        let err = sdk_for(ADDR_B, chain)
+            .await
+            .reshare("0xcap", &old_shares, 3, 5)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Only the owner"));
+    }
+
+    #[tokio::test]
+    async fn test_chain_time_decides_readiness() {
+        const HOUR_MS: u64 = 3_600_000;
+        let local_now = current_timestamp_ms();
+        let unlock_time = local_now + HOUR_MS;
+
+        let local_sdk = CapsuleSDK::new(Config::default()).await.unwrap();
+        let readiness = local_sdk.time_capsule_readiness(unlock_time).await;
+        assert!(!readiness.ready);
+        assert_eq!(readiness.clock, ClockSource::Local);
+
+        // The chain is two hours ahead of this machine, so the capsule is ready
+        let config = Config {
+            use_chain_time: true,
+            ..Config::default()
+        };
+        let chain_sdk = CapsuleSDK::new(config.clone())
+            .await
+            .unwrap()
+            .with_chain(Arc::new(MockChain {
+                clock_ms: Some(local_now + 2 * HOUR_MS),
+                ..MockChain::default()
+            }));
+        let readiness = chain_sdk.time_capsule_readiness(unlock_time).await;
+        assert!(readiness.ready);
+        assert_eq!(readiness.clock, ClockSource::Chain);
+
+        // Falling back to the local clock requires the tolerance to have passed too
+        let fallback_sdk = CapsuleSDK::new(config)
+            .await
+            .unwrap()
+            .with_chain(Arc::new(MockChain::default()));
+        let readiness = fallback_sdk.time_capsule_readiness(local_now - 1_000).await;
+        assert_eq!(readiness.clock, ClockSource::Local);
+        assert!(!readiness.ready);
+        let long_past = local_now - 2 * DEFAULT_CLOCK_SKEW_TOLERANCE_MS;
+        assert!(fallback_sdk.time_capsule_readiness(long_past).await.ready);
+    }
+
+    #[tokio::test]
+    async fn test_and_composite_needs_time_and_approvals() {
+        const UNLOCK_TIME: u64 = 1_800_000_000_000;
+        let part = |condition_type: &str| UnlockCondition {
+            condition_type: condition_type.to_string(),
+            unlock_time: None,
+            threshold: None,
+            approvals: vec![],
+            price: None,
+            paid: false,
+            combinator: None,
+            conditions: vec![],
+        };
+        let composite = |approvals: usize| UnlockCondition {
+            combinator: Some(Combinator::And),
+            conditions: vec![
+                UnlockCondition {
+                    unlock_time: Some(UNLOCK_TIME),
+                    ..part("time")
+                },
+                UnlockCondition {
+                    threshold: Some(2),
+                    approvals: vec!["0xapprover".to_string(); approvals],
+                    ..part("multisig")
+                },
+            ],
+            ..part("composite")
+        };
+        let config = Config {
+            use_chain_time: true,
+            ..Config::default()
+        };
+        let sdk_at = |now_ms: u64| {
+            let config = config.clone();
+            async move {
+                CapsuleSDK::new(config)
+                    .await
+                    .unwrap()
+                    .with_chain(Arc::new(MockChain {
+                        clock_ms: Some(now_ms),
+                        ..MockChain::default()
+                    }))
+            }
+        };
+        let before = sdk_at(UNLOCK_TIME - 1).await;
+        let after = sdk_at(UNLOCK_TIME).await;
+
+        // Neither, only approvals, only time: not ready
+        assert!(!before.condition_readiness(&composite(0)).await.ready);
+        assert!(!before.condition_readiness(&composite(2)).await.ready);
+        let readiness = after.condition_readiness(&composite(1)).await;
+        assert!(!readiness.ready);
+        assert_eq!(readiness.parts_met, vec![true, false]);
+
+        let readiness = after.condition_readiness(&composite(2)).await;
+        assert!(readiness.ready);
+        assert_eq!(readiness.clock, ClockSource::Chain);
+
+        // With OR, either part on its own is enough
+        let mut either = composite(2);
+        either.combinator = Some(Combinator::Or);
+        assert!(before.condition_readiness(&either).await.ready);
+        either.conditions[1].approvals.clear();
+        assert!(!before.condition_readiness(&either).await.ready);
+        assert!(after.condition_readiness(&either).await.ready);
+    }
+
+    fn listed(capsule_id: &str, created_at: u64) -> CapsuleStatus {
+        let mut status = CapsuleStatus::failed(capsule_id, String::new());
+        status.error = None;
+        status.created_at = created_at;
+        status
+    }
+
+    #[test]
+    fn test_cursor_pages_visit_every_capsule_once() {
+        let mut all: Vec<CapsuleStatus> = (0..7u64)
+            .map(|i| listed(&format!("0x{i}"), 1_000 + i / 2))
+            .collect();
+
+        let mut seen = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = paginate(all.clone(), cursor.as_deref(), 0, 3).unwrap();
+            assert!(page.capsules.len() <= 3);
+            seen.extend(page.capsules.iter().map(|c| c.capsule_id.clone()));
+            if seen.len() == 3 {
+                // A capsule created mid-way is picked up without shifting the rest
+                all.insert(0, listed("0xnew", 5_000));
+            }
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+
+        let expected: Vec<String> = (0..7)
+            .map(|i| format!("0x{i}"))
+            .chain(["0xnew".to_string()])
+            .collect();
+        assert_eq!(seen, expected);
+        assert!(paginate(all, Some("not a cursor"), 0, 3).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_list_capsules_pages_with_cursor() {
+        let sdk = memory_sdk(Config::default()).await;
+        let everything = sdk.list_capsules(CapsuleQuery::new()).await.unwrap();
+        assert!(everything.next_cursor.is_none());
+
+        let mut seen = Vec::new();
+        let mut query = CapsuleQuery::new().with_limit(1);
+        loop {
+            let page = sdk.list_capsules(query.clone()).await.unwrap();
+            seen.extend(page.capsules.into_iter().map(|c| c.capsule_id));
+            match page.next_cursor {
+                Some(ref next) => query = query.with_cursor(next),
+                None => break,
+            }
+        }
+        let expected: Vec<String> = everything
+            .capsules
+            .into_iter()
+            .map(|c| c.capsule_id)
+            .collect();
+        assert_eq!(seen, expected);
+        assert!(seen.len() > 1);
+    }
+
+    #[tokio::test]
+    async fn test_estimate_cost_uses_chain_dry_run() {
+        let sdk = CapsuleSDK::new(Config::default()).await.unwrap();
+        let offline = sdk.estimate_cost(1000, "time").await.unwrap();
+        assert_eq!(offline.storage_bytes, 1016);
+        assert_eq!(offline.estimated_gas, None);
+        assert!(offline
+            .notes
+            .iter()
+            .any(|note| note.starts_with("Gas estimate unavailable")));
+
+        let sdk = sdk.with_chain(Arc::new(MockChain {
+            gas: Some(2_500_000),
+            ..MockChain::default()
+        }));
+        let estimate = sdk.estimate_cost(1000, "time").await.unwrap();
+        assert_eq!(estimate.storage_bytes, 1016);
+        assert_eq!(estimate.estimated_gas, Some(2_500_000));
+    }
+
+    #[tokio::test]
+    async fn test_gas_budget_propagates_into_transactions() {
+        // Without a configured budget, the dry run plus headroom, or the default
+        let sdk = memory_sdk(Config::default()).await;
+        let request = sdk
+            .build_create_transaction("QmCid", 0, "time", 100, "key")
+            .await;
+        assert_eq!(request.gas_budget, DEFAULT_GAS_BUDGET);
+        assert_eq!(request.function, "create_time_capsule");
+        let sdk = sdk.with_chain(Arc::new(MockChain {
+            gas: Some(2_500_000),
+            ..MockChain::default()
+        }));
+        let request = sdk
+            .build_create_transaction("QmCid", 0, "time", 100, "key")
+            .await;
+        assert_eq!(request.gas_budget, 3_000_000);
+
+        // A configured budget is used as given, even with a dry run available
+        let sdk = memory_sdk(Config {
+            gas_budget: Some(7_000_000),
+            ..Config::default()
+        })
+        .await
+        .with_chain(Arc::new(MockChain {
+            gas: Some(2_500_000),
+            ..MockChain::default()
+        }));
+        let request = sdk
+            .build_create_transaction("QmCid", 0, "time", 100, "key")
+            .await;
+        assert_eq!(request.gas_budget, 7_000_000);
+        assert_eq!(sdk.build_approve_transaction("0xabc").gas_budget, 7_000_000);
+        let result = sdk
+            .create_time_capsule(b"gas".to_vec(), 0, None)
+            .await
+            .unwrap();
+        assert_eq!(result.gas_used, MOCK_GAS_USED);
+
+        // Too small a budget fails the transaction
+        let sdk = memory_sdk(Config {
+            gas_budget: Some(1_000),
+            ..Config::default()
+        })
+        .await;
+        let error = sdk.approve_capsule("0xabc").await.unwrap_err();
+        assert!(error.to_string().contains("ran out of gas"));
+        assert!(CapsuleSDK::new(Config {
+            gas_budget: Some(0),
+            ..Config::default()
+        })
+        .await
+        .is_err());
+    }
+
+    #[test]
+    fn test_v1_capsule_metadata_migrates() {
+        let v1 = serde_json::json!({
+            "id": "0x1234567890abcdef",
+            "owner": ADDR_A,
+            "cid": "QmTest1234567890",
+            "content_hash": "hash1",
+            "unlock_condition": {
+                "condition_type": "time",
+                "unlock_time": 1735689600000u64,
+                "threshold": null,
+                "approvals": [],
+                "price": null,
+                "paid": false
+            },
+            "created_at": 1705689600000u64,
+            "unlocked": false
+        });
+
+        let capsule = Capsule::migrate(v1.clone()).unwrap();
+        assert_eq!(capsule.schema_version, CAPSULE_SCHEMA_VERSION);
+        assert_eq!(capsule.id, "0x1234567890abcdef");
+        assert_eq!(capsule.label, None);
+        assert!(capsule.tags.is_empty());
+
+        // A current blob round trips, and one from a newer build is refused
+        let current = serde_json::to_value(&capsule).unwrap();
+        assert_eq!(Capsule::migrate(current).unwrap().schema_version, 2);
+
+        let mut future = v1;
+        future["schema_version"] = (CAPSULE_SCHEMA_VERSION + 1).into();
+        assert!(Capsule::migrate(future).is_err());
+    }
+
+    #[test]
+    fn test_invalid_proxy_url_rejected() {
+        let config = Config {
+            proxy_url: Some("not a url".to_string()),
+            ..Config::default()
+        };
+        assert!(build_http_client(&config).is_err());
+    }
+
+    #[test]
+    fn test_normalize_approvers_sorts() {
+        let approvers = vec![ADDR_B.to_string(), ADDR_A.to_string(), "0x2".to_string()];
+        let normalized = normalize_approvers(&approvers).unwrap();
+
+        assert_eq!(normalized.len(), 3);
+        assert_eq!(normalized[0], format!("0x{}2", "0".repeat(63)));
+        assert_eq!(normalized[1], ADDR_A);
+        assert_eq!(normalized[2], ADDR_B);
+    }
+
+    #[test]
+    fn test_normalize_approvers_rejects_duplicates() {
+        let approvers = vec![ADDR_A.to_string(), ADDR_B.to_string(), ADDR_A.to_string()];
+        assert!(normalize_approvers(&approvers).is_err());
+
+        let mixed_case = vec![
+            ADDR_B.to_string(),
+            ADDR_B.to_uppercase().replace("0X", "0x"),
+        ];
+        assert!(normalize_approvers(&mixed_case).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_verify_upload() {
+        let store = Arc::new(MemoryStore::new());
+        let sdk = CapsuleSDK::new(Config::default())
+            .await
+            .unwrap()
+            .with_store(store.clone());
+
+        let result = sdk
+            .create_payment_capsule(b"verify me".to_vec(), 1000, None)
+            .await
+            .unwrap();
+        assert!(sdk.verify_upload(&result).await.is_ok());
+
+        // Corrupt the stored ciphertext
+        let mut payload: EncryptionResult =
+            serde_json::from_slice(&store.get(&result.cid).await.unwrap()).unwrap();
+        payload.ciphertext[0] ^= 0xff;
+        store.replace(&result.cid, serde_json::to_vec(&payload).unwrap());
+
+        assert!(sdk.verify_upload(&result).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_escrow_holder_recovers_content() {
+        let (escrow_secret, escrow_public) = encryptor_wasi::generate_hybrid_keypair();
+        let store = Arc::new(MemoryStore::new());
+        let sdk = CapsuleSDK::new(Config::default())
+            .await
+            .unwrap()
+            .with_store(store.clone())
+            .with_escrow_key(escrow_public);
+
+        let result = sdk
+            .create_time_capsule(b"legal hold".to_vec(), 0, None)
+            .await
+            .unwrap();
+        let escrow = result.escrow.clone().unwrap();
+
+        // The creator's key is lost; the escrow holder recovers it
+        let recovered = escrow.recover(&escrow_secret).unwrap();
+        assert_eq!(recovered, result.encryption_key);
+
+        let key: [u8; 32] = base64::engine::general_purpose::STANDARD
+            .decode(&recovered)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let payload: EncryptionResult =
+            serde_json::from_slice(&store.get(&result.cid).await.unwrap()).unwrap();
+        let decrypted =
+            decrypt_result_with_aad(&payload, &key, &result.associated_data().unwrap()).unwrap();
+        assert_eq!(*decrypted.content, b"legal hold");
+
+        let (other_secret, _) = encryptor_wasi::generate_hybrid_keypair();
+        assert!(escrow.recover(&other_secret).is_err());
+
+        // Escrow is opt-in
+        let plain = memory_sdk(Config::default())
+            .await
+            .create_time_capsule(b"no escrow".to_vec(), 0, None)
+            .await
+            .unwrap();
+        assert!(plain.escrow.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_password_capsule_key_derives_from_envelope() {
+        let store = Arc::new(MemoryStore::new());
+        let sdk = CapsuleSDK::new(Config::default())
+            .await
+            .unwrap()
+            .with_store(store.clone())
+            .with_password("correct horse".to_string());
+
+        let result = sdk
+            .create_time_capsule(b"passphrase only".to_vec(), 0, None)
+            .await
+            .unwrap();
+        let payload: EncryptionResult =
+            serde_json::from_slice(&store.get(&result.cid).await.unwrap()).unwrap();
+        let kdf = payload.envelope.kdf.clone().unwrap();
+        assert_eq!(kdf.params, Argon2Params::default());
+
+        // The passphrase and the stored salt are enough to unlock
+        let key = kdf.derive_key("correct horse").unwrap();
+        assert_eq!(
+            base64::engine::general_purpose::STANDARD.encode(*key),
+            result.encryption_key
+        );
+        let decrypted =
+            decrypt_result_with_aad(&payload, &key, &result.associated_data().unwrap()).unwrap();
+        assert_eq!(*decrypted.content, b"passphrase only");
+
+        let wrong = kdf.derive_key("battery staple").unwrap();
+        assert!(
+            decrypt_result_with_aad(&payload, &wrong, &result.associated_data().unwrap()).is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_reports_metrics() {
+        let store = Arc::new(MemoryStore::new());
+        let sdk = CapsuleSDK::new(Config::default())
+            .await
+            .unwrap()
+            .with_store(store.clone());
+
+        let result = sdk
+            .create_time_capsule(b"measured".to_vec(), 0, None)
+            .await
+            .unwrap();
+        let metrics = result.metrics.unwrap();
+
+        let stored = store.get(&result.cid).await.unwrap();
+        assert_eq!(metrics.bytes_uploaded, stored.len() as u64);
+        assert_eq!(metrics.bytes_downloaded, 0);
+        let stages: Vec<_> = metrics.stage_durations.iter().map(|s| s.stage).collect();
+        assert_eq!(stages, ["encrypt", "upload", "transaction"]);
+    }
+
+    /// Store that records how many requests it is serving at once
+    #[derive(Default)]
+    struct ConcurrencyProbe {
+        inner: MemoryStore,
+        in_flight: std::sync::atomic::AtomicUsize,
+        peak: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl ContentStore for ConcurrencyProbe {
+        async fn put(&self, data: &[u8]) -> Result<String> {
+            use std::sync::atomic::Ordering;
+
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            self.inner.put(data).await
+        }
+
+        async fn get(&self, cid: &str) -> Result<Vec<u8>> {
+            self.inner.get(cid).await
+        }
+
+        async fn contains(&self, cid: &str) -> Result<bool> {
+            self.inner.contains(cid).await
+        }
+
+        async fn unpin(&self, cid: &str) -> Result<()> {
+            self.inner.unpin(cid).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ipfs_connections_bounded_across_files() {
+        let store = Arc::new(ConcurrencyProbe::default());
+        let config = Config {
+            ipfs_max_connections: Some(2),
+            ..Config::default()
+        };
+        let sdk = Arc::new(
+            CapsuleSDK::new(config)
+                .await
+                .unwrap()
+                .with_store(store.clone()),
+        );
+
+        // Eight files in flight at once, sharing two IPFS connections
+        let handles: Vec<_> = (0..8u8)
+            .map(|i| {
+                let sdk = sdk.clone();
+                tokio::spawn(
+                    async move { sdk.create_payment_capsule(vec![i; 64], 1000, None).await },
+                )
+            })
+            .collect();
+        for handle in handles {
+            handle.await.unwrap().unwrap();
+        }
+
+        assert_eq!(store.peak.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    /// Store whose uploads fail with a given error until it has been asked enough times
+    struct FlakyStore {
+        inner: MemoryStore,
+        failures: u32,
+        transient: bool,
+        puts: std::sync::atomic::AtomicU32,
+    }
+
+    impl FlakyStore {
+        fn new(failures: u32, transient: bool) -> Self {
+            Self {
+                inner: MemoryStore::new(),
+                failures,
+                transient,
+                puts: Default::default(),
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl ContentStore for FlakyStore {
+        async fn put(&self, data: &[u8]) -> Result<String> {
+            let attempt = self.puts.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            if attempt < self.failures {
+                if self.transient {
+                    return Err(StoreRequestFailed {
+                        message: "IPFS node unreachable".to_string(),
+                    }
+                    .into());
+                }
+                anyhow::bail!("IPFS node rejected the upload: 400 Bad Request");
+            }
+            self.inner.put(data).await
+        }
+
+        async fn get(&self, cid: &str) -> Result<Vec<u8>> {
+            self.inner.get(cid).await
+        }
+
+        async fn contains(&self, cid: &str) -> Result<bool> {
+            self.inner.contains(cid).await
+        }
+
+        async fn unpin(&self, cid: &str) -> Result<()> {
+            self.inner.unpin(cid).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transient_upload_failures_are_retried() {
+        let config = Config {
+            retry_delay_ms: Some(1),
+            ..Config::default()
+        };
+
+        let store = Arc::new(FlakyStore::new(2, true));
+        let sdk = CapsuleSDK::new(config.clone())
+            .await
+            .unwrap()
+            .with_store(store.clone());
+        assert!(sdk
+            .create_time_capsule(b"flaky".to_vec(), 0, None)
+            .await
+            .is_ok());
+        assert_eq!(store.puts.load(std::sync::atomic::Ordering::SeqCst), 3);
+
+        // Permanent failures are not retried
+        let store = Arc::new(FlakyStore::new(2, false));
+        let sdk = CapsuleSDK::new(config.clone())
+            .await
+            .unwrap()
+            .with_store(store.clone());
+        assert!(sdk
+            .create_time_capsule(b"rejected".to_vec(), 0, None)
+            .await
+            .is_err());
+        assert_eq!(store.puts.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        // Nor are transient ones once the attempts run out
+        let store = Arc::new(FlakyStore::new(10, true));
+        let sdk = CapsuleSDK::new(Config {
+            retry_attempts: Some(1),
+            ..config
+        })
+        .await
+        .unwrap()
+        .with_store(store.clone());
+        assert!(sdk
+            .create_time_capsule(b"down".to_vec(), 0, None)
+            .await
+            .is_err());
+        assert_eq!(store.puts.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_retries_counted_in_metrics() {
+        let config = Config {
+            retry_delay_ms: Some(1),
+            ..Config::default()
+        };
+        let sdk = CapsuleSDK::new(config)
+            .await
+            .unwrap()
+            .with_store(Arc::new(FlakyStore::new(1, true)));
+
+        let created = sdk
+            .create_time_capsule(b"flaky once".to_vec(), 0, None)
+            .await
+            .unwrap();
+        assert_eq!(created.metrics.unwrap().retries, 1);
+
+        let created = sdk
+            .create_time_capsule(b"steady".to_vec(), 0, None)
+            .await
+            .unwrap();
+        assert_eq!(created.metrics.unwrap().retries, 0);
+    }
+
+    #[tokio::test]
+    async fn test_zero_ipfs_connections_rejected() {
+        let config = Config {
+            ipfs_max_connections: Some(0),
+            ..Config::default()
+        };
+        assert!(CapsuleSDK::new(config).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_altered_unlock_time_fails_decryption() {
+        let sdk = memory_sdk(Config::default()).await;
+
+        let mut result = sdk
+            .create_time_capsule(b"not before noon".to_vec(), 1_700_000_000_000, None)
+            .await
+            .unwrap();
+        assert!(sdk.verify_upload(&result).await.is_ok());
+
+        // Same ciphertext, earlier unlock time
+        result.condition = BoundCondition::Time { unlock_time: 0 };
+        assert!(sdk.verify_upload(&result).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_multisig_threshold_uses_unique_approvers() {
+        let sdk = CapsuleSDK::new(Config::default()).await.unwrap();
+        let approvers = vec![
+            ADDR_A.to_string(),
+            ADDR_A.to_uppercase().replace("0X", "0x"),
+        ];
+
+        let result = sdk
+            .create_multisig_capsule(b"content".to_vec(), 2, approvers, None)
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_threshold_above_platform_cap_rejected() {
+        let approvers: Vec<String> = (0..=MAX_MULTISIG_APPROVERS)
+            .map(|i| format!("0x{i:064x}"))
+            .collect();
+
+        let too_many = BoundCondition::Multisig {
+            threshold: 2,
+            approvers: approvers.clone(),
+        };
+        let message = too_many.validate(u64::MAX).unwrap_err().to_string();
+        assert!(message.contains("at most 255 approvers"), "{message}");
+
+        let over_cap = BoundCondition::Multisig {
+            threshold: MAX_MULTISIG_APPROVERS + 1,
+            approvers: approvers[..2].to_vec(),
+        };
+        let message = over_cap.validate(u64::MAX).unwrap_err().to_string();
+        assert!(message.contains("platform maximum of 255"), "{message}");
+
+        let at_cap = BoundCondition::Multisig {
+            threshold: MAX_MULTISIG_APPROVERS,
+            approvers: approvers[..MAX_MULTISIG_APPROVERS as usize].to_vec(),
+        };
+        assert!(at_cap.validate(u64::MAX).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_price_above_max_rejected() {
+        let config = Config {
+            max_price: Some(1_000),
+            ..Config::default()
+        };
+        let sdk = memory_sdk(config).await;
+
+        let message = sdk
+            .create_payment_capsule(b"content".to_vec(), 1_001, None)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(
+            message.contains("exceeds the maximum of 1000 MIST"),
+            "{message}"
+        );
+        assert!(sdk
+            .create_payment_capsule(b"content".to_vec(), 1_000, None)
+            .await
+            .is_ok());
+        assert!(BoundCondition::Payment { price: 0 }
+            .validate(1_000)
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_create_appends_audit_record() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let audit_log = AuditLog::new(temp_dir.path().join("audit.jsonl"));
+        let sdk = memory_sdk(Config::default())
+            .await
+            .with_audit_log(audit_log.clone());
+
+        let result = sdk
+            .create_time_capsule(b"audited".to_vec(), 0, None)
+            .await
+            .unwrap();
+
+        let records = audit_log.read(&Default::default()).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].operation, AuditOperation::Create);
+        assert_eq!(records[0].capsule_id, result.capsule_id);
+        assert_eq!(
+            records[0].transaction_digest.as_deref(),
+            Some(result.transaction_digest.as_str())
+        );
+        assert_eq!(records[0].network, "devnet");
+
+        // Secrets never reach the log
+        let raw = std::fs::read_to_string(audit_log.path()).unwrap();
+        assert!(!raw.contains(&result.encryption_key));
+    }
+
+    #[tokio::test]
+    async fn test_created_capsule_listed_from_local_index() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config = Config {
+            capsule_records_path: Some(temp_dir.path().join("capsules.jsonl")),
+            ..Config::default()
+        };
+        let sdk = memory_sdk(config.clone()).await;
+
+        let result = sdk
+            .create_time_capsule(b"indexed".to_vec(), 1_900_000_000_000, None)
+            .await
+            .unwrap();
+
+        let local = sdk.load_local_capsules().unwrap();
+        assert_eq!(local.len(), 1);
+        assert_eq!(local[0].capsule_id, result.capsule_id);
+        assert_eq!(local[0].cid, result.cid);
+        assert_eq!(local[0].condition.type_name(), "time");
+        // Keys stay out of the index unless opted in
+        assert_eq!(local[0].encryption_key, None);
+        let raw = std::fs::read_to_string(temp_dir.path().join("capsules.jsonl")).unwrap();
+        assert!(!raw.contains(&result.encryption_key));
+
+        // A fresh SDK over the same config still sees it
+        drop(sdk);
+        let reloaded = memory_sdk(config).await;
+        let local = reloaded.load_local_capsules().unwrap();
+        assert_eq!(local.len(), 1);
+        assert_eq!(local[0].capsule_id, result.capsule_id);
+    }
+
+    #[tokio::test]
+    async fn test_local_index_keeps_key_when_opted_in() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let sdk = memory_sdk(Config {
+            capsule_records_path: Some(temp_dir.path().join("capsules.jsonl")),
+            store_keys_locally: true,
+            ..Config::default()
+        })
+        .await;
+
+        let result = sdk
+            .create_time_capsule(b"indexed".to_vec(), 0, None)
+            .await
+            .unwrap();
+
+        let local = sdk.load_local_capsules().unwrap();
+        assert_eq!(
+            local[0].encryption_key.as_deref(),
+            Some(result.encryption_key.as_str())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unwritable_audit_log_does_not_fail() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        // A directory in place of the log file makes every append fail
+        let sdk = memory_sdk(Config::default())
+            .await
+            .with_audit_log(AuditLog::new(temp_dir.path()));
+
+        assert!(sdk
+            .create_time_capsule(b"audited".to_vec(), 0, None)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_same_idempotency_key_creates_once() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let audit_log = AuditLog::new(temp_dir.path().join("audit.jsonl"));
+        let sdk = memory_sdk(Config::default())
+            .await
+            .with_audit_log(audit_log.clone());
+        let key = new_idempotency_key();
+        let condition = BoundCondition::Time { unlock_time: 0 };
+
+        let first = sdk
+            .create_idempotent(&key, b"once".to_vec(), condition.clone(), None)
+            .await
+            .unwrap();
+        let retry = sdk
+            .create_idempotent(&key, b"once".to_vec(), condition.clone(), None)
+            .await
+            .unwrap();
+
+        assert_eq!(first.capsule_id, retry.capsule_id);
+        assert_eq!(first.encryption_key, retry.encryption_key);
+        assert_eq!(audit_log.read(&Default::default()).unwrap().len(), 1);
+
+        // The key cannot stand in for a different create
+        for (content, condition) in [
+            (b"twice".to_vec(), condition),
+            (b"once".to_vec(), BoundCondition::Time { unlock_time: 1 }),
+        ] {
+            let error = sdk
+                .create_idempotent(&key, content, condition, None)
+                .await
+                .unwrap_err();
+            assert!(error.to_string().contains("different request"), "{error}");
+        }
+        assert_eq!(audit_log.read(&Default::default()).unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_create_transaction_carries_idempotency_key() {
+        let sdk = memory_sdk(Config::default()).await;
+        let request = sdk
+            .build_create_transaction("QmCid", 0, "time", 100, "create-1")
+            .await;
+        assert_eq!(request.idempotency_key.as_deref(), Some("create-1"));
+        assert_eq!(sdk.build_approve_transaction("0x1").idempotency_key, None);
+    }
+
+    /// Sink recording every stage it is told about
+    #[derive(Default)]
+    struct RecordingSink {
+        events: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl ProgressSink for RecordingSink {
+        fn stage(&self, name: &str, step: u64, total: u64) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("{step}/{total} {name}"));
+        }
+
+        fn finish(&self) {
+            self.events.lock().unwrap().push("finish".to_string());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_reports_stages_to_progress_sink() {
+        let sdk = memory_sdk(Config::default()).await;
+        let sink = RecordingSink::default();
+
+        sdk.create_time_capsule(b"progress".to_vec(), 0, Some(&sink))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *sink.events.lock().unwrap(),
+            [
+                "1/3 Encrypting content",
+                "2/3 Uploading to IPFS",
+                "3/3 Creating blockchain transaction",
+                "finish",
+            ]
+        );
+    }
+}
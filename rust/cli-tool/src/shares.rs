@@ -0,0 +1,186 @@
+use anyhow::{Context, Result};
+use base64::Engine;
+use encryptor_wasi::KEY_SHARE_LEN;
+use serde::{Serialize, Serializer};
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::utils::key_fingerprint;
+
+/// Prefix identifying an encoded key share
+pub const KEY_SHARE_PREFIX: &str = "capsule-share";
+
+/// One Shamir share of a capsule's encryption key
+///
+/// Any `threshold` shares from the same split reconstruct the key. Each share
+/// carries the key's fingerprint so a reconstruction can be checked, without
+/// revealing anything about the key itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyShare {
+    pub threshold: u8,
+    pub fingerprint: String,
+    share: Vec<u8>,
+}
+
+impl KeyShare {
+    /// Index of this share within its split; shares with equal indexes are duplicates
+    pub fn index(&self) -> u8 {
+        self.share[0]
+    }
+}
+
+impl fmt::Display for KeyShare {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{KEY_SHARE_PREFIX}:{}:{}:{}",
+            self.threshold,
+            self.fingerprint,
+            base64::engine::general_purpose::STANDARD.encode(&self.share)
+        )
+    }
+}
+
+/// Serialized in its encoded form, ready to hand to a share holder
+impl Serialize for KeyShare {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl FromStr for KeyShare {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let parts: Vec<&str> = s.trim().split(':').collect();
+        let [KEY_SHARE_PREFIX, threshold, fingerprint, share] = parts[..] else {
+            anyhow::bail!(
+                "Invalid key share. Expected {KEY_SHARE_PREFIX}:<threshold>:<fingerprint>:<share>"
+            );
+        };
+
+        let threshold = threshold
+            .parse()
+            .with_context(|| format!("Invalid key share threshold: {threshold}"))?;
+        let share = base64::engine::general_purpose::STANDARD
+            .decode(share)
+            .context("Key share is not valid base64")?;
+        if share.len() != KEY_SHARE_LEN {
+            anyhow::bail!("Key share has the wrong length");
+        }
+
+        Ok(Self {
+            threshold,
+            fingerprint: fingerprint.to_string(),
+            share,
+        })
+    }
+}
+
+/// Split a base64 encryption key into `count` shares, any `threshold` of which recover it
+pub fn split_key(encryption_key: &str, threshold: u8, count: u8) -> Result<Vec<KeyShare>> {
+    if threshold == 0 {
+        anyhow::bail!("Share threshold must be greater than 0");
+    }
+    if count < threshold {
+        anyhow::bail!("Cannot split into {count} shares with a threshold of {threshold}");
+    }
+
+    let key: [u8; 32] = base64::engine::general_purpose::STANDARD
+        .decode(encryption_key.trim())
+        .context("Encryption key is not valid base64")?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Encryption key must be 32 bytes"))?;
+    let fingerprint = key_fingerprint(encryption_key)?;
+
+    let shares = encryptor_wasi::split_key(&key, threshold, count)
+        .map_err(|e| anyhow::anyhow!("Failed to split key: {e}"))?;
+    Ok(shares
+        .iter()
+        .map(|share| KeyShare {
+            threshold,
+            fingerprint: fingerprint.clone(),
+            share: share.to_bytes(),
+        })
+        .collect())
+}
+
+/// Reconstruct a base64 encryption key from at least a threshold of its shares
+pub fn combine_shares(shares: &[KeyShare]) -> Result<String> {
+    let Some(first) = shares.first() else {
+        anyhow::bail!("No key shares given");
+    };
+    if shares
+        .iter()
+        .any(|s| s.fingerprint != first.fingerprint || s.threshold != first.threshold)
+    {
+        anyhow::bail!("Key shares come from different splits");
+    }
+
+    let distinct: HashSet<u8> = shares.iter().map(KeyShare::index).collect();
+    if distinct.len() < first.threshold as usize {
+        anyhow::bail!(
+            "Need at least {} distinct key shares, got {}",
+            first.threshold,
+            distinct.len()
+        );
+    }
+
+    // Duplicates add nothing, and combining rejects them
+    let mut seen = HashSet::new();
+    let parsed = shares
+        .iter()
+        .filter(|s| seen.insert(s.index()))
+        .map(|s| encryptor_wasi::KeyShare::from_bytes(&s.share).map_err(|e| anyhow::anyhow!(e)))
+        .collect::<Result<Vec<_>>>()?;
+    let key = encryptor_wasi::combine_shares(&parsed)
+        .map_err(|e| anyhow::anyhow!("Failed to combine key shares: {e}"))?;
+
+    let encryption_key = base64::engine::general_purpose::STANDARD.encode(*key);
+    if key_fingerprint(&encryption_key)? != first.fingerprint {
+        anyhow::bail!("Key shares do not reconstruct the expected key; one may be corrupted");
+    }
+    Ok(encryption_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=";
+
+    #[test]
+    fn test_any_threshold_of_shares_recovers_key() {
+        let shares = split_key(KEY, 2, 3).unwrap();
+        assert_eq!(shares.len(), 3);
+
+        let parsed: Vec<KeyShare> = shares
+            .iter()
+            .map(|s| s.to_string().parse().unwrap())
+            .collect();
+        assert_eq!(parsed, shares);
+        assert_eq!(combine_shares(&parsed[1..]).unwrap(), KEY);
+        assert_eq!(
+            combine_shares(&[parsed[0].clone(), parsed[2].clone()]).unwrap(),
+            KEY
+        );
+    }
+
+    #[test]
+    fn test_too_few_or_mixed_shares_rejected() {
+        let shares = split_key(KEY, 2, 3).unwrap();
+        let message = combine_shares(&shares[..1]).unwrap_err().to_string();
+        assert!(message.contains("at least 2"), "{message}");
+
+        // The same share twice is still one share
+        let repeated = [shares[0].clone(), shares[0].clone()];
+        assert!(combine_shares(&repeated).is_err());
+
+        let other = split_key(KEY, 2, 3).unwrap();
+        assert!(combine_shares(&[shares[0].clone(), other[1].clone()]).is_err());
+
+        assert!(split_key(KEY, 3, 2).is_err());
+        assert!("capsule-share:2:abc".parse::<KeyShare>().is_err());
+    }
+}
@@ -0,0 +1,648 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use encryptor_wasi::{hash_content_bytes, hash_to_hex};
+use futures::TryStreamExt;
+use ipfs_api_backend_hyper::{IpfsApi, IpfsClient, TryFromUri};
+use log::{info, warn};
+use reqwest::multipart::{Form, Part};
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Cursor;
+use std::sync::Mutex;
+
+use crate::unixfs::verify_content;
+
+/// Suggestions for when no store or gateway has a capsule's content
+pub const UNAVAILABLE_CONTENT_HINTS: &[&str] = &[
+    "Try another gateway with --ipfs-gateway <URL>",
+    "Check that the content is still pinned; unpinned content is eventually garbage-collected",
+    "Ask the capsule creator to pin or re-upload the content",
+];
+
+/// Error returned when a store definitively does not have a CID
+///
+/// Kept distinct from network failures so callers can tell "gone" from
+/// "could not ask".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentNotFound {
+    pub cid: String,
+}
+
+impl fmt::Display for ContentNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Content not found: {}", self.cid)
+    }
+}
+
+impl std::error::Error for ContentNotFound {}
+
+/// Whether an error means the content does not exist, rather than a failed request
+pub fn is_not_found(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<ContentNotFound>().is_some()
+}
+
+/// Error returned when a request to an IPFS node fails
+///
+/// The node may only have been unreachable or overloaded, so unlike
+/// `ContentNotFound` the request is worth retrying.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoreRequestFailed {
+    pub message: String,
+}
+
+impl fmt::Display for StoreRequestFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for StoreRequestFailed {}
+
+/// Content-addressed storage used by the SDK for encrypted capsule payloads
+#[async_trait]
+pub trait ContentStore: Send + Sync {
+    /// Store bytes and return their content identifier
+    async fn put(&self, data: &[u8]) -> Result<String>;
+
+    /// Fetch the bytes previously stored under a content identifier
+    async fn get(&self, cid: &str) -> Result<Vec<u8>>;
+
+    /// Whether the content can be fetched, without downloading it
+    async fn contains(&self, cid: &str) -> Result<bool>;
+
+    /// Stop keeping the content, so the store may garbage-collect it
+    ///
+    /// Content that is not pinned counts as already unpinned.
+    async fn unpin(&self, cid: &str) -> Result<()>;
+}
+
+/// In-memory content store, used as the mock IPFS backend
+#[derive(Default)]
+pub struct MemoryStore {
+    objects: Mutex<HashMap<String, Vec<u8>>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Derive a mock CIDv0-style identifier from the content
+    pub fn cid_for(data: &[u8]) -> String {
+        format!("Qm{}", &hash_to_hex(&hash_content_bytes(data))[..44])
+    }
+
+    /// Overwrite stored bytes in place (for simulating corruption in tests)
+    pub fn replace(&self, cid: &str, data: Vec<u8>) {
+        self.objects.lock().unwrap().insert(cid.to_string(), data);
+    }
+}
+
+#[async_trait]
+impl ContentStore for MemoryStore {
+    async fn put(&self, data: &[u8]) -> Result<String> {
+        let cid = Self::cid_for(data);
+        self.objects
+            .lock()
+            .unwrap()
+            .insert(cid.clone(), data.to_vec());
+        Ok(cid)
+    }
+
+    async fn get(&self, cid: &str) -> Result<Vec<u8>> {
+        self.objects
+            .lock()
+            .unwrap()
+            .get(cid)
+            .cloned()
+            .ok_or_else(|| {
+                ContentNotFound {
+                    cid: cid.to_string(),
+                }
+                .into()
+            })
+    }
+
+    async fn contains(&self, cid: &str) -> Result<bool> {
+        Ok(self.objects.lock().unwrap().contains_key(cid))
+    }
+
+    async fn unpin(&self, cid: &str) -> Result<()> {
+        self.objects.lock().unwrap().remove(cid);
+        Ok(())
+    }
+}
+
+/// Content store backed by an IPFS node's HTTP API
+pub struct IpfsStore {
+    client: IpfsClient,
+    url: String,
+}
+
+impl IpfsStore {
+    /// Connect to the API at `url`, e.g. `http://127.0.0.1:5001`
+    pub fn new(url: &str) -> Result<Self> {
+        let client = IpfsClient::from_str(url)
+            .map_err(|e| anyhow::anyhow!("Invalid IPFS API URL {url}: {e}"))?;
+        Ok(Self {
+            client,
+            url: url.to_string(),
+        })
+    }
+
+    /// Turn the node's "not found" answers into `ContentNotFound`
+    fn request_error(&self, cid: &str, action: &str, message: String) -> anyhow::Error {
+        if message.contains("not found")
+            || message.contains("could not find")
+            || message.contains("not pinned")
+        {
+            ContentNotFound {
+                cid: cid.to_string(),
+            }
+            .into()
+        } else {
+            StoreRequestFailed {
+                message: format!("Failed to {action} {cid} via {}: {message}", self.url),
+            }
+            .into()
+        }
+    }
+}
+
+#[async_trait]
+impl ContentStore for IpfsStore {
+    async fn put(&self, data: &[u8]) -> Result<String> {
+        let response = self
+            .client
+            .add(Cursor::new(data.to_vec()))
+            .await
+            .map_err(|e| StoreRequestFailed {
+                message: format!("Failed to upload to IPFS at {}: {e}", self.url),
+            })?;
+        if response.hash.is_empty() {
+            anyhow::bail!("IPFS node at {} returned no CID", self.url);
+        }
+        Ok(response.hash)
+    }
+
+    async fn get(&self, cid: &str) -> Result<Vec<u8>> {
+        self.client
+            .cat(cid)
+            .map_ok(|chunk| chunk.to_vec())
+            .try_concat()
+            .await
+            .map_err(|e| self.request_error(cid, "download", e.to_string()))
+    }
+
+    /// Asks the node, which may search the network for blocks it does not hold
+    async fn contains(&self, cid: &str) -> Result<bool> {
+        match self.client.block_stat(cid).await {
+            Ok(_) => Ok(true),
+            Err(e) => {
+                let error = self.request_error(cid, "look up", e.to_string());
+                if is_not_found(&error) {
+                    Ok(false)
+                } else {
+                    Err(error)
+                }
+            }
+        }
+    }
+
+    async fn unpin(&self, cid: &str) -> Result<()> {
+        match self.client.pin_rm(cid, true).await {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                let error = self.request_error(cid, "unpin", e.to_string());
+                if is_not_found(&error) {
+                    Ok(())
+                } else {
+                    Err(error)
+                }
+            }
+        }
+    }
+}
+
+/// Read-only store fetching content through IPFS HTTP gateways, in order
+///
+/// Content counts as not found only when every gateway answers 404; any other
+/// failure is reported as an error, since the content may still exist.
+/// Downloads are checked against their CID, so a gateway serving anything
+/// else is skipped like one that failed.
+pub struct GatewayStore {
+    client: Client,
+    gateways: Vec<String>,
+}
+
+impl GatewayStore {
+    pub fn new(client: Client, gateways: Vec<String>) -> Self {
+        Self { client, gateways }
+    }
+
+    fn url(gateway: &str, cid: &str) -> String {
+        format!("{}/ipfs/{cid}", gateway.trim_end_matches('/'))
+    }
+
+    fn not_found(cid: &str) -> anyhow::Error {
+        ContentNotFound {
+            cid: cid.to_string(),
+        }
+        .into()
+    }
+}
+
+#[async_trait]
+impl ContentStore for GatewayStore {
+    async fn put(&self, _data: &[u8]) -> Result<String> {
+        anyhow::bail!("IPFS gateways are read-only; content cannot be uploaded through them")
+    }
+
+    async fn get(&self, cid: &str) -> Result<Vec<u8>> {
+        let mut last_error = None;
+        for gateway in &self.gateways {
+            let url = Self::url(gateway, cid);
+            match self.client.get(&url).send().await {
+                Ok(response) if response.status() == StatusCode::NOT_FOUND => {}
+                Ok(response) if response.status().is_success() => {
+                    let body = match response.bytes().await {
+                        Ok(body) => body,
+                        Err(e) => {
+                            warn!("Download of {cid} from gateway {gateway} was cut off");
+                            last_error = Some(anyhow::Error::new(e).context(url));
+                            continue;
+                        }
+                    };
+                    match verify_content(cid, &body) {
+                        Ok(()) => {
+                            info!("Fetched {cid} from gateway {gateway}");
+                            return Ok(body.to_vec());
+                        }
+                        Err(e) => {
+                            warn!("Gateway {gateway} returned content that does not match {cid}");
+                            last_error = Some(e.context(url));
+                        }
+                    }
+                }
+                Ok(response) => {
+                    last_error = Some(anyhow::anyhow!("{url} returned {}", response.status()))
+                }
+                Err(e) => last_error = Some(anyhow::Error::new(e).context(url)),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| Self::not_found(cid)))
+    }
+
+    async fn contains(&self, cid: &str) -> Result<bool> {
+        let mut last_error = None;
+        for gateway in &self.gateways {
+            let url = Self::url(gateway, cid);
+            match self.client.head(&url).send().await {
+                Ok(response) if response.status().is_success() => return Ok(true),
+                Ok(response) if response.status() == StatusCode::NOT_FOUND => {}
+                Ok(response) => {
+                    last_error = Some(anyhow::anyhow!("{url} returned {}", response.status()))
+                }
+                Err(e) => last_error = Some(anyhow::Error::new(e).context(url)),
+            }
+        }
+        match last_error {
+            Some(e) => Err(e),
+            None => Ok(false),
+        }
+    }
+
+    async fn unpin(&self, _cid: &str) -> Result<()> {
+        anyhow::bail!("IPFS gateways are read-only; content cannot be unpinned through them")
+    }
+}
+
+/// Hosted pinning services that take uploads over their own HTTP APIs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PinningService {
+    Pinata,
+    Web3Storage,
+}
+
+impl PinningService {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Pinata => "Pinata",
+            Self::Web3Storage => "web3.storage",
+        }
+    }
+}
+
+/// Pinata's answer to a file upload
+#[derive(Deserialize)]
+struct PinataUpload {
+    #[serde(rename = "IpfsHash")]
+    ipfs_hash: String,
+}
+
+/// web3.storage's answer to an upload
+#[derive(Deserialize)]
+struct Web3StorageUpload {
+    cid: String,
+}
+
+/// Content store that uploads through a pinning service's API
+///
+/// The services only serve content over IPFS, so reads go through their
+/// public gateway.
+pub struct PinningStore {
+    client: Client,
+    service: PinningService,
+    token: String,
+    api_url: String,
+    gateway: GatewayStore,
+}
+
+impl PinningStore {
+    /// Upload to Pinata, authenticating with `jwt`
+    pub fn pinata(client: Client, jwt: &str) -> Self {
+        Self::new(
+            client,
+            PinningService::Pinata,
+            jwt,
+            "https://api.pinata.cloud",
+            "https://gateway.pinata.cloud",
+        )
+    }
+
+    /// Upload to web3.storage, authenticating with an API `token`
+    pub fn web3_storage(client: Client, token: &str) -> Self {
+        Self::new(
+            client,
+            PinningService::Web3Storage,
+            token,
+            "https://api.web3.storage",
+            "https://w3s.link",
+        )
+    }
+
+    fn new(
+        client: Client,
+        service: PinningService,
+        token: &str,
+        api_url: &str,
+        gateway: &str,
+    ) -> Self {
+        Self {
+            gateway: GatewayStore::new(client.clone(), vec![gateway.to_string()]),
+            client,
+            service,
+            token: token.to_string(),
+            api_url: api_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Send API requests to `api_url` instead of the service's public endpoint
+    pub fn with_api_url(mut self, api_url: &str) -> Self {
+        self.api_url = api_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Read content through `gateway` instead of the service's own
+    pub fn with_gateway(mut self, gateway: &str) -> Self {
+        self.gateway = GatewayStore::new(self.client.clone(), vec![gateway.to_string()]);
+        self
+    }
+
+    /// Check that the service answers, and for Pinata that it accepts the JWT
+    pub async fn ping(&self) -> Result<()> {
+        let url = match self.service {
+            PinningService::Pinata => format!("{}/data/testAuthentication", self.api_url),
+            PinningService::Web3Storage => self.api_url.clone(),
+        };
+        let response = self
+            .client
+            .get(url)
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach {}", self.service.name()))?;
+        if self.service == PinningService::Pinata {
+            response
+                .error_for_status()
+                .context("Pinata did not accept the JWT")?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ContentStore for PinningStore {
+    async fn put(&self, data: &[u8]) -> Result<String> {
+        let request = match self.service {
+            PinningService::Pinata => {
+                let file = Part::bytes(data.to_vec()).file_name("capsule.json");
+                self.client
+                    .post(format!("{}/pinning/pinFileToIPFS", self.api_url))
+                    .multipart(Form::new().part("file", file))
+            }
+            PinningService::Web3Storage => self
+                .client
+                .post(format!("{}/upload", self.api_url))
+                .body(data.to_vec()),
+        };
+        let response = request
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("Failed to upload to {}", self.service.name()))?;
+
+        let cid = match self.service {
+            PinningService::Pinata => response.json::<PinataUpload>().await.map(|u| u.ipfs_hash),
+            PinningService::Web3Storage => {
+                response.json::<Web3StorageUpload>().await.map(|u| u.cid)
+            }
+        }
+        .with_context(|| format!("Invalid upload response from {}", self.service.name()))?;
+        if cid.is_empty() {
+            anyhow::bail!("{} returned no CID", self.service.name());
+        }
+        Ok(cid)
+    }
+
+    async fn get(&self, cid: &str) -> Result<Vec<u8>> {
+        self.gateway.get(cid).await
+    }
+
+    async fn contains(&self, cid: &str) -> Result<bool> {
+        self.gateway.contains(cid).await
+    }
+
+    async fn unpin(&self, cid: &str) -> Result<()> {
+        match self.service {
+            PinningService::Pinata => {
+                let response = self
+                    .client
+                    .delete(format!("{}/pinning/unpin/{cid}", self.api_url))
+                    .bearer_auth(&self.token)
+                    .send()
+                    .await
+                    .with_context(|| format!("Failed to unpin {cid} from Pinata"))?;
+                if response.status() != StatusCode::NOT_FOUND {
+                    response
+                        .error_for_status()
+                        .with_context(|| format!("Failed to unpin {cid} from Pinata"))?;
+                }
+                Ok(())
+            }
+            PinningService::Web3Storage => {
+                anyhow::bail!("web3.storage does not support unpinning content through its API")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unixfs::file_cid;
+    use wiremock::matchers::{body_string, body_string_contains, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_gateway_store_falls_through_to_next_gateway() {
+        let missing = MockServer::start().await;
+        let pinned = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&missing)
+            .await;
+        let cid = file_cid(b"payload", 0, false).to_string();
+        Mock::given(method("GET"))
+            .and(path(format!("/ipfs/{cid}")))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"payload".to_vec()))
+            .mount(&pinned)
+            .await;
+
+        let store = GatewayStore::new(Client::new(), vec![missing.uri(), pinned.uri()]);
+        assert_eq!(store.get(&cid).await.unwrap(), b"payload");
+
+        let error = store.get("QmGone").await.unwrap_err();
+        assert!(is_not_found(&error), "{error:#}");
+    }
+
+    /// Gateway that answers every request with a body cut off part way through
+    async fn truncating_gateway() -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut request = [0u8; 1024];
+                let _ = socket.read(&mut request).await;
+                let _ = socket
+                    .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 1000\r\n\r\nsealed")
+                    .await;
+            }
+        });
+        format!("http://{address}")
+    }
+
+    #[tokio::test]
+    async fn test_gateway_store_fails_over_and_checks_content() {
+        let failing = MockServer::start().await;
+        let serving = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(2)
+            .mount(&failing)
+            .await;
+
+        let valid = file_cid(b"sealed payload", 1, true).to_string();
+        let tampered = file_cid(b"another payload", 0, false).to_string();
+        for cid in [&valid, &tampered] {
+            Mock::given(method("GET"))
+                .and(path(format!("/ipfs/{cid}")))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(b"sealed payload".to_vec()))
+                .mount(&serving)
+                .await;
+        }
+
+        let truncating = truncating_gateway().await;
+        let store = GatewayStore::new(
+            Client::new(),
+            vec![failing.uri(), truncating, serving.uri()],
+        );
+        assert_eq!(store.get(&valid).await.unwrap(), b"sealed payload");
+
+        let error = store.get(&tampered).await.unwrap_err();
+        assert!(!is_not_found(&error));
+        assert!(format!("{error:#}").contains("does not match"), "{error:#}");
+    }
+
+    #[tokio::test]
+    async fn test_pinata_upload_request() {
+        let api = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/pinning/pinFileToIPFS"))
+            .and(header("authorization", "Bearer pinata-jwt"))
+            .and(body_string_contains("name=\"file\""))
+            .and(body_string_contains("sealed payload"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "IpfsHash": "QmPinata",
+                "PinSize": 14,
+                "Timestamp": "2024-01-01T00:00:00Z"
+            })))
+            .expect(1)
+            .mount(&api)
+            .await;
+
+        let store = PinningStore::pinata(Client::new(), "pinata-jwt").with_api_url(&api.uri());
+        assert_eq!(store.put(b"sealed payload").await.unwrap(), "QmPinata");
+    }
+
+    #[tokio::test]
+    async fn test_pinata_unpin_request() {
+        let api = MockServer::start().await;
+        Mock::given(method("DELETE"))
+            .and(path("/pinning/unpin/QmPinata"))
+            .and(header("authorization", "Bearer pinata-jwt"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("OK"))
+            .expect(1)
+            .mount(&api)
+            .await;
+
+        let store = PinningStore::pinata(Client::new(), "pinata-jwt").with_api_url(&api.uri());
+        store.unpin("QmPinata").await.unwrap();
+        // Pinata answers 404 for content it is not pinning
+        store.unpin("QmGone").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_web3_storage_upload_request() {
+        let web3_cid = file_cid(b"sealed payload", 1, true).to_string();
+        let api = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/upload"))
+            .and(header("authorization", "Bearer w3-token"))
+            .and(body_string("sealed payload"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({"cid": web3_cid})),
+            )
+            .expect(1)
+            .mount(&api)
+            .await;
+        let gateway = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(format!("/ipfs/{web3_cid}")))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"sealed payload".to_vec()))
+            .mount(&gateway)
+            .await;
+
+        let store = PinningStore::web3_storage(Client::new(), "w3-token")
+            .with_api_url(&api.uri())
+            .with_gateway(&gateway.uri());
+        let cid = store.put(b"sealed payload").await.unwrap();
+        assert_eq!(cid, web3_cid);
+        assert_eq!(store.get(&cid).await.unwrap(), b"sealed payload");
+    }
+}
@@ -0,0 +1,145 @@
+use anyhow::{Context, Result};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// A temporary file that is deleted when dropped unless it was persisted
+///
+/// Dropping runs on early returns and during panic unwinding, so staged
+/// plaintext is not left behind when an operation fails halfway.
+#[derive(Debug)]
+pub struct TempFile {
+    path: PathBuf,
+    file: Option<File>,
+}
+
+impl TempFile {
+    /// Create an empty, uniquely named file in `dir`, readable only by the owner
+    pub fn new_in(dir: &Path, prefix: &str) -> Result<Self> {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create temp directory: {}", dir.display()))?;
+
+        let path = dir.join(format!(".{prefix}-{}.tmp", uuid::Uuid::new_v4()));
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let file = options
+            .open(&path)
+            .with_context(|| format!("Failed to create temp file: {}", path.display()))?;
+
+        Ok(Self {
+            path,
+            file: Some(file),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn write_all(&mut self, data: &[u8]) -> Result<()> {
+        let file = self
+            .file
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("Temp file is already closed"))?;
+        file.write_all(data)
+            .with_context(|| format!("Failed to write temp file: {}", self.path.display()))
+    }
+
+    /// Move the finished file to `dest`, creating parent directories as needed
+    ///
+    /// Renames when possible and falls back to copying when `dest` is on
+    /// another filesystem; either way the temp file is gone afterwards.
+    pub fn persist(mut self, dest: &Path) -> Result<()> {
+        if let Some(file) = self.file.take() {
+            file.sync_all()
+                .with_context(|| format!("Failed to flush temp file: {}", self.path.display()))?;
+        }
+
+        if let Some(parent) = dest.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+
+        if fs::rename(&self.path, dest).is_err() {
+            if let Err(e) = fs::copy(&self.path, dest) {
+                let _ = fs::remove_file(dest);
+                return Err(e).with_context(|| format!("Failed to write file: {}", dest.display()));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        self.file.take();
+        // Already gone after a successful rename
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Write `data` to `dest` by staging it in `temp_dir` first
+///
+/// A failed write never leaves a partial `dest` or a stray temp file.
+pub fn write_via_temp(temp_dir: &Path, dest: &Path, data: &[u8]) -> Result<()> {
+    let mut temp = TempFile::new_in(temp_dir, "capsule")?;
+    temp.write_all(data)?;
+    temp.persist(dest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn entries(dir: &Path) -> usize {
+        fs::read_dir(dir).unwrap().count()
+    }
+
+    #[test]
+    fn test_persist_moves_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let staging = temp_dir.path().join("staging");
+        let dest = temp_dir.path().join("out").join("secret.txt");
+
+        write_via_temp(&staging, &dest, b"plaintext").unwrap();
+
+        assert_eq!(fs::read(&dest).unwrap(), b"plaintext");
+        assert_eq!(entries(&staging), 0);
+    }
+
+    #[test]
+    fn test_temp_file_removed_after_failed_operation() {
+        let temp_dir = TempDir::new().unwrap();
+        let staging = temp_dir.path().join("staging");
+        // A regular file where a directory is needed makes persisting fail
+        let blocker = temp_dir.path().join("blocker");
+        fs::write(&blocker, b"").unwrap();
+
+        let result = write_via_temp(&staging, &blocker.join("secret.txt"), b"plaintext");
+
+        assert!(result.is_err());
+        assert_eq!(entries(&staging), 0);
+    }
+
+    #[test]
+    fn test_temp_file_removed_on_panic() {
+        let temp_dir = TempDir::new().unwrap();
+        let staging = temp_dir.path().to_path_buf();
+
+        let outcome = std::panic::catch_unwind(|| {
+            let mut temp = TempFile::new_in(&staging, "capsule").unwrap();
+            temp.write_all(b"plaintext").unwrap();
+            assert!(temp.path().exists());
+            panic!("interrupted");
+        });
+
+        assert!(outcome.is_err());
+        assert_eq!(entries(temp_dir.path()), 0);
+    }
+}
@@ -0,0 +1,285 @@
+use anyhow::Result;
+use console::{Style, StyledObject};
+use std::sync::OnceLock;
+
+/// Names accepted by `--theme` and the `theme` config setting
+pub const THEME_NAMES: &[&str] = &["default", "light", "high-contrast", "none"];
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+static STYLE_CONTEXT: OnceLock<StyleContext> = OnceLock::new();
+
+/// When to color output, as chosen with `--color`
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Color terminals, unless `NO_COLOR` is set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// Whether output is styled, settled once from `--color`, `NO_COLOR` and
+/// whether stdout and stderr are color terminals
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StyleContext {
+    stdout: bool,
+    stderr: bool,
+}
+
+impl StyleContext {
+    /// Resolve `choice` against the environment and the attached terminals
+    pub fn new(choice: ColorChoice) -> Self {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        Self::resolve(
+            choice,
+            no_color,
+            console::colors_enabled(),
+            console::colors_enabled_stderr(),
+        )
+    }
+
+    fn resolve(choice: ColorChoice, no_color: bool, stdout_term: bool, stderr_term: bool) -> Self {
+        match choice {
+            ColorChoice::Always => Self {
+                stdout: true,
+                stderr: true,
+            },
+            ColorChoice::Never => Self {
+                stdout: false,
+                stderr: false,
+            },
+            ColorChoice::Auto => Self {
+                stdout: stdout_term && !no_color,
+                stderr: stderr_term && !no_color,
+            },
+        }
+    }
+
+    /// Whether stdout is styled
+    pub fn enabled(&self) -> bool {
+        self.stdout
+    }
+
+    /// Apply `style` to `val`, or nothing when color is off
+    pub fn apply_to<D>(&self, style: &Style, val: D) -> StyledObject<D> {
+        style.clone().force_styling(self.stdout).apply_to(val)
+    }
+}
+
+/// Install the color setting used for all output
+///
+/// Progress bars and other output drawn by `console` follow it too. Only the
+/// first call has any effect.
+pub fn init_style(context: StyleContext) {
+    console::set_colors_enabled(context.stdout);
+    console::set_colors_enabled_stderr(context.stderr);
+    let _ = STYLE_CONTEXT.set(context);
+}
+
+/// The active color setting, resolved for `auto` if none was installed
+pub fn style_context() -> &'static StyleContext {
+    STYLE_CONTEXT.get_or_init(|| StyleContext::new(ColorChoice::Auto))
+}
+
+/// Unstyled text that bold and similar modifiers can be added to, colored
+/// only when the active [`StyleContext`] allows
+pub fn style<D>(val: D) -> StyledObject<D> {
+    style_context().apply_to(&Style::new(), val)
+}
+
+/// Styles for the semantic roles used in command output
+#[derive(Debug, Clone)]
+pub struct Theme {
+    name: &'static str,
+    heading: Style,
+    success: Style,
+    error: Style,
+    warning: Style,
+    id: Style,
+    dim: Style,
+}
+
+impl Theme {
+    /// Look up a theme by name
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "default" => Ok(Self::default_theme()),
+            "light" => Ok(Self::light()),
+            "high-contrast" => Ok(Self::high_contrast()),
+            "none" => Ok(Self::none()),
+            _ => anyhow::bail!(
+                "Unknown theme '{}'. Available themes: {}",
+                name,
+                THEME_NAMES.join(", ")
+            ),
+        }
+    }
+
+    /// The original red/green/yellow/cyan palette
+    fn default_theme() -> Self {
+        Self {
+            name: "default",
+            heading: Style::new().bold().cyan(),
+            success: Style::new().green(),
+            error: Style::new().red(),
+            warning: Style::new().yellow(),
+            id: Style::new().cyan(),
+            dim: Style::new().dim(),
+        }
+    }
+
+    /// Darker colors that stay readable on light terminal backgrounds
+    fn light() -> Self {
+        Self {
+            name: "light",
+            heading: Style::new().bold().blue(),
+            success: Style::new().color256(28),
+            error: Style::new().color256(124),
+            warning: Style::new().color256(130),
+            id: Style::new().blue(),
+            dim: Style::new().color256(242),
+        }
+    }
+
+    /// Blue/orange instead of green/red, with weight to tell roles apart
+    fn high_contrast() -> Self {
+        Self {
+            name: "high-contrast",
+            heading: Style::new().bold().underlined(),
+            success: Style::new().bold().blue().bright(),
+            error: Style::new().bold().underlined().color256(208),
+            warning: Style::new().bold().yellow().bright(),
+            id: Style::new().bold().cyan().bright(),
+            dim: Style::new(),
+        }
+    }
+
+    /// No styling at all
+    fn none() -> Self {
+        Self {
+            name: "none",
+            heading: Style::new(),
+            success: Style::new(),
+            error: Style::new(),
+            warning: Style::new(),
+            id: Style::new(),
+            dim: Style::new(),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Section titles
+    pub fn heading<D>(&self, val: D) -> StyledObject<D> {
+        style_context().apply_to(&self.heading, val)
+    }
+
+    pub fn success<D>(&self, val: D) -> StyledObject<D> {
+        style_context().apply_to(&self.success, val)
+    }
+
+    pub fn error<D>(&self, val: D) -> StyledObject<D> {
+        style_context().apply_to(&self.error, val)
+    }
+
+    pub fn warning<D>(&self, val: D) -> StyledObject<D> {
+        style_context().apply_to(&self.warning, val)
+    }
+
+    /// Capsule IDs, digests, CIDs and other values worth picking out
+    pub fn id<D>(&self, val: D) -> StyledObject<D> {
+        style_context().apply_to(&self.id, val)
+    }
+
+    /// Secondary text
+    pub fn dim<D>(&self, val: D) -> StyledObject<D> {
+        style_context().apply_to(&self.dim, val)
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::default_theme()
+    }
+}
+
+/// Install the theme used for all output
+///
+/// The `none` theme also turns off color globally, so progress bars and
+/// bold labels are plain too. Only the first call has any effect.
+pub fn init_theme(theme: Theme) {
+    if theme.name == "none" {
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+    }
+    let _ = THEME.set(theme);
+}
+
+/// The active theme, or the default one if none was installed
+pub fn theme() -> &'static Theme {
+    THEME.get_or_init(Theme::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(style: &Style, text: &str) -> String {
+        style.clone().force_styling(true).apply_to(text).to_string()
+    }
+
+    #[test]
+    fn test_none_theme_is_unstyled() {
+        let theme = Theme::from_name("none").unwrap();
+        for style in [
+            &theme.heading,
+            &theme.success,
+            &theme.error,
+            &theme.warning,
+            &theme.id,
+            &theme.dim,
+        ] {
+            assert_eq!(render(style, "0xabc"), "0xabc");
+        }
+
+        let default = Theme::default();
+        assert_ne!(render(&default.success, "0xabc"), "0xabc");
+    }
+
+    #[test]
+    fn test_unknown_theme_rejected() {
+        for name in THEME_NAMES {
+            assert_eq!(Theme::from_name(name).unwrap().name(), *name);
+        }
+        assert!(Theme::from_name("solarized").is_err());
+    }
+
+    #[test]
+    fn test_color_choice() {
+        let render = |context: &StyleContext| {
+            let theme = Theme::default();
+            [
+                context.apply_to(&theme.heading, "Heading").to_string(),
+                context.apply_to(&theme.id, "0xabc").to_string(),
+                context.apply_to(&Style::new().bold(), "label").to_string(),
+            ]
+        };
+        let has_ansi = |rendered: [String; 3]| rendered.iter().all(|s| s.contains('\x1b'));
+        let no_ansi = |rendered: [String; 3]| rendered.iter().all(|s| !s.contains('\x1b'));
+
+        let never = StyleContext::resolve(ColorChoice::Never, false, true, true);
+        assert!(no_ansi(render(&never)));
+        let always = StyleContext::resolve(ColorChoice::Always, true, false, false);
+        assert!(has_ansi(render(&always)));
+
+        // `auto` colors terminals only, and never when NO_COLOR is set
+        let auto = StyleContext::resolve(ColorChoice::Auto, false, true, false);
+        assert!(auto.enabled() && !auto.stderr);
+        assert!(has_ansi(render(&auto)));
+        assert!(!StyleContext::resolve(ColorChoice::Auto, false, false, true).enabled());
+        let no_color = StyleContext::resolve(ColorChoice::Auto, true, true, true);
+        assert!(no_ansi(render(&no_color)));
+    }
+}
@@ -0,0 +1,336 @@
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+/// Multicodec of blocks holding bytes as they are
+const RAW: u64 = 0x55;
+/// Multicodec of the protobuf nodes IPFS builds files from
+const DAG_PB: u64 = 0x70;
+const SHA2_256: u64 = 0x12;
+const IDENTITY: u64 = 0x00;
+
+/// Bytes in each leaf, as cut by the IPFS default chunker
+pub const CHUNK_SIZE: usize = 262_144;
+/// Most children a node of the default balanced layout links to
+const MAX_LINKS: usize = 174;
+/// UnixFS `Data.Type` of a file node
+const UNIXFS_FILE: u64 = 2;
+
+/// Alphabet of the lowercase base32 multibase (prefix `b`)
+const BASE32_ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+/// A content identifier, as far as IPFS files need one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentId {
+    version: u64,
+    codec: u64,
+    hash_code: u64,
+    digest: Vec<u8>,
+}
+
+impl ContentId {
+    /// Parse a base58 CIDv0 (`Qm...`) or a base32 CIDv1 (`b...`)
+    pub fn parse(cid: &str) -> Result<Self> {
+        if cid.len() == 46 && cid.starts_with("Qm") {
+            let bytes = bs58::decode(cid).into_vec().context("Invalid base58")?;
+            let mut rest = bytes.as_slice();
+            let (hash_code, digest) = read_multihash(&mut rest)?;
+            if hash_code != SHA2_256 || !rest.is_empty() {
+                anyhow::bail!("CIDv0 must be a bare sha2-256 multihash");
+            }
+            return Ok(Self::v0(digest));
+        }
+
+        let bytes = match cid.strip_prefix('b') {
+            Some(encoded) => decode_base32(encoded)?,
+            None => anyhow::bail!("Only base58 CIDv0 and base32 CIDv1 are supported"),
+        };
+        let mut rest = bytes.as_slice();
+        let version = read_varint(&mut rest)?;
+        if version != 1 {
+            anyhow::bail!("Unsupported CID version {version}");
+        }
+        let codec = read_varint(&mut rest)?;
+        let (hash_code, digest) = read_multihash(&mut rest)?;
+        if !rest.is_empty() {
+            anyhow::bail!("Trailing bytes after multihash");
+        }
+        Ok(Self {
+            version,
+            codec,
+            hash_code,
+            digest,
+        })
+    }
+
+    fn v0(digest: Vec<u8>) -> Self {
+        Self {
+            version: 0,
+            codec: DAG_PB,
+            hash_code: SHA2_256,
+            digest,
+        }
+    }
+
+    fn v1(codec: u64, digest: Vec<u8>) -> Self {
+        Self {
+            version: 1,
+            codec,
+            hash_code: SHA2_256,
+            digest,
+        }
+    }
+
+    /// A CID of `version` naming `block` under `codec`, hashed with sha2-256
+    fn for_block(version: u64, codec: u64, block: &[u8]) -> Self {
+        let digest = Sha256::digest(block).to_vec();
+        match version {
+            0 => Self::v0(digest),
+            _ => Self::v1(codec, digest),
+        }
+    }
+
+    fn multihash(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        put_varint(&mut bytes, self.hash_code);
+        put_varint(&mut bytes, self.digest.len() as u64);
+        bytes.extend_from_slice(&self.digest);
+        bytes
+    }
+
+    /// Binary form, as stored in the links of dag-pb nodes
+    fn to_bytes(&self) -> Vec<u8> {
+        if self.version == 0 {
+            return self.multihash();
+        }
+        let mut bytes = Vec::new();
+        put_varint(&mut bytes, self.version);
+        put_varint(&mut bytes, self.codec);
+        bytes.extend(self.multihash());
+        bytes
+    }
+}
+
+impl fmt::Display for ContentId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.version == 0 {
+            write!(f, "{}", bs58::encode(self.multihash()).into_string())
+        } else {
+            write!(f, "b{}", encode_base32(&self.to_bytes()))
+        }
+    }
+}
+
+/// Check that `content` is the file a CID names
+///
+/// Raw blocks are hashed directly. Files stored as UnixFS nodes are rebuilt
+/// with the IPFS default chunker and balanced layout, with and without raw
+/// leaves, so content added with other import settings cannot be checked.
+pub fn verify_content(cid: &str, content: &[u8]) -> Result<()> {
+    let expected = ContentId::parse(cid).with_context(|| format!("Invalid CID: {cid}"))?;
+    let matches = match (expected.codec, expected.hash_code) {
+        (RAW, IDENTITY) => expected.digest == content,
+        (RAW, SHA2_256) => Sha256::digest(content).as_slice() == expected.digest,
+        (DAG_PB, SHA2_256) => [false, true]
+            .into_iter()
+            .any(|raw_leaves| file_cid(content, expected.version, raw_leaves) == expected),
+        (codec, code) => anyhow::bail!(
+            "Cannot check content against {cid}: unsupported codec 0x{codec:x} or hash 0x{code:x}"
+        ),
+    };
+    if !matches {
+        anyhow::bail!("Content does not match CID {cid}");
+    }
+    Ok(())
+}
+
+/// The CID IPFS gives a file added with its default chunker and layout
+///
+/// Raw leaves are always CIDv1; the other nodes use `version` (0 or 1).
+pub fn file_cid(content: &[u8], version: u64, raw_leaves: bool) -> ContentId {
+    let mut nodes: Vec<DagNode> = if content.is_empty() {
+        vec![DagNode::file(version, &[], &[])]
+    } else {
+        content
+            .chunks(CHUNK_SIZE)
+            .map(|chunk| DagNode::leaf(version, chunk, raw_leaves))
+            .collect()
+    };
+    while nodes.len() > 1 {
+        nodes = nodes
+            .chunks(MAX_LINKS)
+            .map(|children| DagNode::file(version, &[], children))
+            .collect();
+    }
+    nodes.remove(0).cid
+}
+
+/// A node of a file's DAG, with the sizes its parent records for it
+struct DagNode {
+    cid: ContentId,
+    /// Bytes of file content under this node
+    file_size: u64,
+    /// Bytes of every block under this node, this one included
+    tree_size: u64,
+}
+
+impl DagNode {
+    fn leaf(version: u64, chunk: &[u8], raw_leaves: bool) -> Self {
+        if raw_leaves {
+            Self {
+                cid: ContentId::for_block(1, RAW, chunk),
+                file_size: chunk.len() as u64,
+                tree_size: chunk.len() as u64,
+            }
+        } else {
+            Self::file(version, chunk, &[])
+        }
+    }
+
+    /// A UnixFS file node holding `data` itself or linking to `children`
+    fn file(version: u64, data: &[u8], children: &[DagNode]) -> Self {
+        let file_size = data.len() as u64 + children.iter().map(|c| c.file_size).sum::<u64>();
+
+        let mut unixfs = Vec::new();
+        put_varint_field(&mut unixfs, 1, UNIXFS_FILE);
+        if !data.is_empty() {
+            put_bytes_field(&mut unixfs, 2, data);
+        }
+        put_varint_field(&mut unixfs, 3, file_size);
+        for child in children {
+            put_varint_field(&mut unixfs, 4, child.file_size);
+        }
+
+        // dag-pb puts the links before the data
+        let mut block = Vec::new();
+        for child in children {
+            let mut link = Vec::new();
+            put_bytes_field(&mut link, 1, &child.cid.to_bytes());
+            put_bytes_field(&mut link, 2, b"");
+            put_varint_field(&mut link, 3, child.tree_size);
+            put_bytes_field(&mut block, 2, &link);
+        }
+        put_bytes_field(&mut block, 1, &unixfs);
+
+        Self {
+            cid: ContentId::for_block(version, DAG_PB, &block),
+            file_size,
+            tree_size: block.len() as u64 + children.iter().map(|c| c.tree_size).sum::<u64>(),
+        }
+    }
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes
+            .split_first()
+            .ok_or_else(|| anyhow::anyhow!("Truncated varint"))?;
+        *bytes = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    anyhow::bail!("Varint too long")
+}
+
+fn read_multihash(bytes: &mut &[u8]) -> Result<(u64, Vec<u8>)> {
+    let code = read_varint(bytes)?;
+    let len = read_varint(bytes)? as usize;
+    if bytes.len() < len {
+        anyhow::bail!("Truncated multihash");
+    }
+    let (digest, rest) = bytes.split_at(len);
+    *bytes = rest;
+    Ok((code, digest.to_vec()))
+}
+
+fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn put_varint_field(out: &mut Vec<u8>, field: u64, value: u64) {
+    put_varint(out, field << 3);
+    put_varint(out, value);
+}
+
+fn put_bytes_field(out: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    put_varint(out, (field << 3) | 2);
+    put_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+fn encode_base32(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    let (mut buffer, mut bits) = (0u32, 0);
+    for &byte in bytes {
+        buffer = (buffer << 8) | u32::from(byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32_ALPHABET[(buffer >> bits) as usize & 31] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32_ALPHABET[(buffer << (5 - bits)) as usize & 31] as char);
+    }
+    out
+}
+
+fn decode_base32(encoded: &str) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let (mut buffer, mut bits) = (0u32, 0);
+    for c in encoded.bytes() {
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|&a| a == c)
+            .ok_or_else(|| anyhow::anyhow!("Invalid base32 character {:?}", c as char))?;
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_cid_matches_ipfs_add() {
+        // `ipfs add` of an empty file and of "hello world\n"
+        assert_eq!(
+            file_cid(b"", 0, false).to_string(),
+            "QmbFMke1KXqnYyBBWxB74N4c5SBnJMVAiMNRcGu6x1AwQH"
+        );
+        assert_eq!(
+            file_cid(b"hello world\n", 0, false).to_string(),
+            "QmT78zSuBmuS4z925WZfrqQ1qHaJ56DQaTfyMUF7F8ff5o"
+        );
+    }
+
+    #[test]
+    fn test_verify_content() {
+        let content = vec![7u8; CHUNK_SIZE * 2 + 1];
+        for (version, raw_leaves) in [(0, false), (1, false), (1, true)] {
+            let cid = file_cid(&content, version, raw_leaves).to_string();
+            assert_eq!(ContentId::parse(&cid).unwrap().to_string(), cid);
+            verify_content(&cid, &content).unwrap();
+            assert!(verify_content(&cid, &content[1..]).is_err());
+        }
+
+        let raw = ContentId::for_block(1, RAW, b"block").to_string();
+        assert!(raw.starts_with("bafkrei"), "{raw}");
+        verify_content(&raw, b"block").unwrap();
+        assert!(verify_content(&raw, b"other").is_err());
+        assert!(verify_content("QmNotACid", b"block").is_err());
+    }
+}
@@ -1,8 +1,14 @@
 use crate::config::Config;
+use crate::keystore::{keystore_passphrase, read_keystore};
 use crate::sdk::CapsuleSDK;
 use anyhow::{Context, Result};
-use std::fs;
-use std::path::Path;
+use log::warn;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+
+/// Local record of which encryption keys were used, inside the config dir
+pub const KEY_FINGERPRINTS_FILE_NAME: &str = "key_fingerprints";
 
 /// Format output based on format type
 pub fn format_output(data: &serde_json::Value, format: &str) -> Result<String> {
@@ -14,15 +20,24 @@ pub fn format_output(data: &serde_json::Value, format: &str) -> Result<String> {
 }
 
 /// Load private key from configuration
+///
+/// A key file or inline key takes precedence over the encrypted keystore,
+/// whose passphrase is read from `CAPSULE_KEY_PASSPHRASE` or prompted for.
 pub fn load_private_key(config: &Config) -> Result<String> {
+    let keystore_path = config.keystore_path();
     if let Some(key_path) = &config.private_key_path {
         fs::read_to_string(key_path)
             .with_context(|| format!("Failed to read private key from: {}", key_path.display()))
             .map(|s| s.trim().to_string())
     } else if let Some(key) = &config.private_key {
         Ok(key.clone())
+    } else if keystore_path.exists() {
+        let passphrase = keystore_passphrase(false)?;
+        Ok(read_keystore(&keystore_path, &passphrase)?.to_string())
     } else {
-        anyhow::bail!("No private key configured. Set PRIVATE_KEY or PRIVATE_KEY_PATH");
+        anyhow::bail!(
+            "No private key configured. Set PRIVATE_KEY or PRIVATE_KEY_PATH, or run `capsule key import`"
+        );
     }
 }
 
@@ -47,6 +62,40 @@ pub fn write_file_content(path: &Path, content: &[u8]) -> Result<()> {
     fs::write(path, content).with_context(|| format!("Failed to write file: {}", path.display()))
 }
 
+/// Resolve an untrusted relative path (a bundle entry name, say) under `base`
+///
+/// Absolute paths and `..` components are rejected outright. When part of the
+/// result already exists on disk it is canonicalized too, so a symlink inside
+/// `base` cannot be used to escape it.
+pub fn sanitize_relative_path(base: &Path, candidate: &str) -> Result<PathBuf> {
+    let mut relative = PathBuf::new();
+    for component in Path::new(candidate).components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                anyhow::bail!("Path escapes the output directory: {candidate}")
+            }
+        }
+    }
+    if relative.as_os_str().is_empty() {
+        anyhow::bail!("Invalid relative path: {candidate:?}");
+    }
+
+    let joined = base.join(&relative);
+    if let Ok(canonical_base) = base.canonicalize() {
+        let existing = joined.ancestors().find(|p| p.exists()).unwrap_or(base);
+        let canonical = existing
+            .canonicalize()
+            .with_context(|| format!("Failed to resolve path: {}", existing.display()))?;
+        if !canonical.starts_with(&canonical_base) {
+            anyhow::bail!("Path escapes the output directory: {candidate}");
+        }
+    }
+
+    Ok(joined)
+}
+
 /// Format file size in human readable format
 pub fn format_file_size(size: u64) -> String {
     const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
@@ -75,49 +124,147 @@ pub fn format_timestamp(timestamp: u64) -> String {
     dt.format("%Y-%m-%d %H:%M:%S UTC").to_string()
 }
 
-/// Validate Sui address format
-pub fn validate_sui_address(address: &str) -> Result<()> {
-    if !address.starts_with("0x") {
-        anyhow::bail!("Sui address must start with '0x'");
+/// Format timestamp in the local timezone, with its UTC offset
+pub fn format_timestamp_local(timestamp: u64) -> String {
+    use chrono::{Local, TimeZone};
+    let dt = Local
+        .timestamp_millis_opt(timestamp as i64)
+        .single()
+        .unwrap_or_else(Local::now);
+    dt.format("%Y-%m-%d %H:%M:%S %:z").to_string()
+}
+
+/// Format timestamp in the local timezone when `local` is set, otherwise in UTC
+pub fn format_timestamp_in(timestamp: u64, local: bool) -> String {
+    if local {
+        format_timestamp_local(timestamp)
+    } else {
+        format_timestamp(timestamp)
     }
-    let hex_part = &address[2..];
-    if hex_part.len() != 64 {
-        anyhow::bail!("Sui address must be 64 hex characters after '0x'");
+}
+
+/// Validate Sui address format, returning its canonical form
+///
+/// Short forms with leading zeros omitted, such as `0x2`, are accepted and
+/// padded out to 64 hex characters.
+pub fn validate_sui_address(address: &str) -> Result<String> {
+    normalize_sui_address(address)
+}
+
+/// Normalize a Sui address to its canonical form: lowercase, `0x`-prefixed, 64 hex chars
+pub fn normalize_sui_address(address: &str) -> Result<String> {
+    let trimmed = address.trim();
+    let hex_part = trimmed
+        .strip_prefix("0x")
+        .or_else(|| trimmed.strip_prefix("0X"))
+        .ok_or_else(|| anyhow::anyhow!("Sui address must start with '0x'"))?;
+    if hex_part.is_empty() {
+        anyhow::bail!("Sui address must contain hex characters after '0x'");
+    }
+    if hex_part.len() > 64 {
+        anyhow::bail!("Sui address must be at most 64 hex characters after '0x'");
     }
     if !hex_part.chars().all(|c| c.is_ascii_hexdigit()) {
         anyhow::bail!("Sui address contains invalid hex characters");
     }
-    Ok(())
+    Ok(format!("0x{:0>64}", hex_part.to_ascii_lowercase()))
+}
+
+/// Read approver addresses from a file
+///
+/// Addresses may be separated by newlines or commas; blank entries and
+/// anything after a `#` on a line are ignored.
+pub fn read_approvers_file(path: &Path) -> Result<Vec<String>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read approvers file: {}", path.display()))?;
+
+    Ok(content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .flat_map(|line| line.split(','))
+        .map(str::trim)
+        .filter(|address| !address.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Merge inline and file approvers into one validated, canonical list
+///
+/// Duplicates (after normalization) are dropped with a warning; the first
+/// occurrence keeps its position.
+pub fn merge_approvers(inline: &[String], from_file: &[String]) -> Result<Vec<String>> {
+    let mut merged: Vec<String> = Vec::with_capacity(inline.len() + from_file.len());
+    for approver in inline.iter().chain(from_file) {
+        let normalized = normalize_sui_address(approver)
+            .with_context(|| format!("Invalid approver address: {approver}"))?;
+        if merged.contains(&normalized) {
+            warn!("Ignoring duplicate approver: {approver}");
+            continue;
+        }
+        merged.push(normalized);
+    }
+    Ok(merged)
 }
 
-/// Parse time duration from string (e.g., "1h", "30m", "2d")
+/// Parse time duration from string (e.g., "1h", "30m", "2d", "1h30m")
+///
+/// Several number+unit segments are summed; units must go from largest to
+/// smallest and each may appear once.
 pub fn parse_duration(duration_str: &str) -> Result<u64> {
     let duration_str = duration_str.trim().to_lowercase();
     if duration_str.is_empty() {
         anyhow::bail!("Duration cannot be empty");
     }
 
-    let (number_part, unit_part) = if let Some(pos) = duration_str.find(|c: char| c.is_alphabetic())
-    {
-        (&duration_str[..pos], &duration_str[pos..])
-    } else {
-        anyhow::bail!("Duration must include a unit (s, m, h, d)");
-    };
+    let mut rest = duration_str.as_str();
+    let mut total_secs: u64 = 0;
+    let mut previous_unit: Option<(&str, u64)> = None;
 
-    let number: u64 = number_part
-        .parse()
-        .with_context(|| format!("Invalid number in duration: {number_part}"))?;
-
-    let multiplier = match unit_part {
-        "s" | "sec" | "second" | "seconds" => 1,
-        "m" | "min" | "minute" | "minutes" => 60,
-        "h" | "hr" | "hour" | "hours" => 60 * 60,
-        "d" | "day" | "days" => 60 * 60 * 24,
-        "w" | "week" | "weeks" => 60 * 60 * 24 * 7,
-        _ => anyhow::bail!("Invalid duration unit: {}. Use s, m, h, d, or w", unit_part),
-    };
+    while !rest.is_empty() {
+        let unit_start = rest
+            .find(|c: char| c.is_alphabetic())
+            .ok_or_else(|| anyhow::anyhow!("Duration must include a unit (s, m, h, d)"))?;
+        let unit_end = rest[unit_start..]
+            .find(|c: char| !c.is_alphabetic())
+            .map_or(rest.len(), |end| unit_start + end);
+        let (number_part, unit_part) = (&rest[..unit_start], &rest[unit_start..unit_end]);
+        rest = &rest[unit_end..];
+
+        let number: u64 = number_part
+            .parse()
+            .with_context(|| format!("Invalid number in duration: {number_part}"))?;
 
-    Ok(number * multiplier * 1000) // Convert to milliseconds
+        let multiplier = match unit_part {
+            "s" | "sec" | "second" | "seconds" => 1,
+            "m" | "min" | "minute" | "minutes" => 60,
+            "h" | "hr" | "hour" | "hours" => 60 * 60,
+            "d" | "day" | "days" => 60 * 60 * 24,
+            "w" | "week" | "weeks" => 60 * 60 * 24 * 7,
+            _ => anyhow::bail!("Invalid duration unit: {}. Use s, m, h, d, or w", unit_part),
+        };
+
+        if let Some((previous, previous_multiplier)) = previous_unit {
+            if multiplier == previous_multiplier {
+                anyhow::bail!("Duration unit repeated: {previous} and {unit_part}");
+            }
+            if multiplier > previous_multiplier {
+                anyhow::bail!(
+                    "Duration units out of order: {unit_part} after {previous}. \
+                     Write larger units first, e.g. 1h30m"
+                );
+            }
+        }
+        previous_unit = Some((unit_part, multiplier));
+
+        total_secs = number
+            .checked_mul(multiplier)
+            .and_then(|secs| total_secs.checked_add(secs))
+            .ok_or_else(|| anyhow::anyhow!("Duration is too long: {duration_str}"))?;
+    }
+
+    total_secs
+        .checked_mul(1000) // Convert to milliseconds
+        .ok_or_else(|| anyhow::anyhow!("Duration is too long: {duration_str}"))
 }
 
 /// Get current timestamp in milliseconds
@@ -128,6 +275,24 @@ pub fn current_timestamp_ms() -> u64 {
         .as_millis() as u64
 }
 
+/// Parse an unlock time: an RFC 3339 datetime or a duration from now
+///
+/// Returns the absolute timestamp in milliseconds. Datetimes must be in the
+/// future, e.g. "2030-12-31T23:59:59Z".
+pub fn parse_unlock_time(input: &str) -> Result<u64> {
+    let input = input.trim();
+    match chrono::DateTime::parse_from_rfc3339(input) {
+        Ok(datetime) => {
+            let timestamp = u64::try_from(datetime.timestamp_millis()).unwrap_or(0);
+            if timestamp <= current_timestamp_ms() {
+                anyhow::bail!("Unlock time {} is in the past", format_timestamp(timestamp));
+            }
+            Ok(timestamp)
+        }
+        Err(_) => Ok(future_timestamp(parse_duration(input)?)),
+    }
+}
+
 /// Calculate future timestamp from duration
 pub fn future_timestamp(duration_ms: u64) -> u64 {
     current_timestamp_ms() + duration_ms
@@ -139,14 +304,70 @@ pub fn is_timestamp_past(timestamp: u64) -> bool {
 }
 
 /// Truncate string to specified length with ellipsis
+///
+/// Lengths count characters, not bytes, so the cut never splits a multi-byte
+/// character.
 pub fn truncate_string(s: &str, max_len: usize) -> String {
-    if s.len() <= max_len {
-        s.to_string()
-    } else {
-        format!("{}...", &s[..max_len.saturating_sub(3)])
+    match s.char_indices().nth(max_len) {
+        None => s.to_string(),
+        Some(_) => {
+            let keep = max_len.saturating_sub(3);
+            let end = s.char_indices().nth(keep).map_or(s.len(), |(i, _)| i);
+            format!("{}...", &s[..end])
+        }
     }
 }
 
+/// Short, non-reversible identifier for a base64 encryption key
+///
+/// The first 16 hex characters of the BLAKE3 hash of the decoded key: enough
+/// to tell keys apart when matching them to capsules, without logging the key.
+pub fn key_fingerprint(encryption_key: &str) -> Result<String> {
+    use base64::Engine;
+
+    let key = base64::engine::general_purpose::STANDARD
+        .decode(encryption_key.trim())
+        .context("Encryption key is not valid base64")?;
+    let mut fingerprint = encryptor_wasi::hash_to_hex(&encryptor_wasi::hash_content_bytes(&key));
+    fingerprint.truncate(16);
+    Ok(fingerprint)
+}
+
+/// Remember that an encryption key was used for a capsule
+///
+/// Only the fingerprint is stored, one `<fingerprint> <capsule_id>` line per use.
+pub fn record_key_fingerprint(store: &Path, encryption_key: &str, capsule_id: &str) -> Result<()> {
+    let fingerprint = key_fingerprint(encryption_key)?;
+    if let Some(parent) = store.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(store)
+        .with_context(|| format!("Failed to open key fingerprint store: {}", store.display()))?;
+    writeln!(file, "{fingerprint} {capsule_id}")
+        .with_context(|| format!("Failed to write key fingerprint store: {}", store.display()))
+}
+
+/// Whether an encryption key was already recorded for a capsule in this environment
+pub fn is_key_reused(store: &Path, encryption_key: &str) -> Result<bool> {
+    let fingerprint = key_fingerprint(encryption_key)?;
+    let content = match fs::read_to_string(store) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => {
+            return Err(e).with_context(|| {
+                format!("Failed to read key fingerprint store: {}", store.display())
+            })
+        }
+    };
+    Ok(content
+        .lines()
+        .any(|line| line.split_whitespace().next() == Some(fingerprint.as_str())))
+}
+
 /// Validate IPFS CID format
 pub fn validate_ipfs_cid(cid: &str) -> Result<()> {
     if cid.is_empty() {
@@ -176,6 +397,64 @@ mod tests {
         assert!(parse_duration("30x").is_err());
     }
 
+    #[test]
+    fn test_parse_compound_duration() {
+        assert_eq!(parse_duration("1h30m").unwrap(), 5_400_000);
+        assert_eq!(parse_duration("2d12h").unwrap(), 216_000_000);
+        assert_eq!(parse_duration("1h30m15s").unwrap(), 5_415_000);
+        assert_eq!(
+            parse_duration("1week2days").unwrap(),
+            parse_duration("9d").unwrap()
+        );
+
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("1h1h").is_err());
+        assert!(parse_duration("1h60min").is_ok());
+        assert!(parse_duration("1m1min").is_err());
+        assert!(parse_duration("30m1h").is_err());
+        assert!(parse_duration("1h30").is_err());
+        assert!(parse_duration("h30m").is_err());
+        assert!(parse_duration("99999999999999999w").is_err());
+    }
+
+    #[test]
+    fn test_local_timestamp_is_same_instant() {
+        let timestamp = 1_700_000_000_123;
+        assert_eq!(format_timestamp(timestamp), "2023-11-14 22:13:20 UTC");
+        assert_eq!(
+            format_timestamp_in(timestamp, false),
+            format_timestamp(timestamp)
+        );
+
+        // Whatever the machine's timezone, the rendering carries its offset and
+        // reads back as the same instant
+        let local = format_timestamp_in(timestamp, true);
+        assert!(!local.ends_with("UTC"), "{local}");
+        let parsed = chrono::DateTime::parse_from_str(&local, "%Y-%m-%d %H:%M:%S %:z").unwrap();
+        assert_eq!(parsed.timestamp(), 1_700_000_000);
+    }
+
+    #[test]
+    fn test_parse_unlock_time() {
+        let now = current_timestamp_ms();
+
+        let absolute = parse_unlock_time("2999-12-31T23:59:59Z").unwrap();
+        assert_eq!(absolute, 32_503_679_999_000);
+        assert_eq!(
+            parse_unlock_time("2999-12-31T23:59:59+01:00").unwrap(),
+            absolute - 3_600_000
+        );
+
+        let relative = parse_unlock_time("2d").unwrap();
+        assert!(relative >= now + 2 * 86_400_000);
+        assert!(relative < now + 2 * 86_400_000 + 60_000);
+
+        let past = parse_unlock_time("2020-01-01T00:00:00Z").unwrap_err();
+        assert!(past.to_string().contains("in the past"), "{past}");
+        assert!(parse_unlock_time("1969-12-31T23:59:59Z").is_err());
+        assert!(parse_unlock_time("2999-13-01T00:00:00Z").is_err());
+    }
+
     #[test]
     fn test_validate_sui_address() {
         assert!(validate_sui_address(
@@ -186,13 +465,91 @@ mod tests {
             "1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef"
         )
         .is_err());
-        assert!(validate_sui_address("0x123").is_err());
         assert!(validate_sui_address(
             "0x1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdeg"
         )
         .is_err());
     }
 
+    #[test]
+    fn test_validate_short_sui_address() {
+        assert_eq!(
+            validate_sui_address("0x2").unwrap(),
+            format!("0x{}2", "0".repeat(63))
+        );
+        assert_eq!(
+            validate_sui_address("0x123").unwrap(),
+            format!("0x{}123", "0".repeat(61))
+        );
+
+        let full = format!("0x{}", "ab".repeat(32));
+        assert_eq!(validate_sui_address(&full).unwrap(), full);
+
+        assert!(validate_sui_address("0x").is_err());
+        assert!(validate_sui_address(&format!("0x{}", "1".repeat(65))).is_err());
+        assert!(validate_sui_address("0xzz").is_err());
+    }
+
+    #[test]
+    fn test_normalize_sui_address() {
+        assert_eq!(
+            normalize_sui_address("0x2").unwrap(),
+            format!("0x{}2", "0".repeat(63))
+        );
+        assert_eq!(
+            normalize_sui_address("0xABCDEF").unwrap(),
+            normalize_sui_address("0xabcdef").unwrap()
+        );
+        assert!(normalize_sui_address("abc").is_err());
+        assert!(normalize_sui_address("0x").is_err());
+        assert!(normalize_sui_address(&format!("0x{}", "1".repeat(65))).is_err());
+    }
+
+    #[test]
+    fn test_read_approvers_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("approvers.txt");
+        fs::write(&path, "# council\n0x1\n0x2, 0x3\n\n0x4 # treasurer\n0X5\n").unwrap();
+
+        let from_file = read_approvers_file(&path).unwrap();
+        assert_eq!(from_file, vec!["0x1", "0x2", "0x3", "0x4", "0X5"]);
+
+        // The inline copy of 0x2 is kept once, in its inline position
+        let merged = merge_approvers(&["0x02".to_string()], &from_file).unwrap();
+        assert_eq!(merged.len(), 5);
+        assert_eq!(merged[0], normalize_sui_address("0x2").unwrap());
+        assert_eq!(merged[4], normalize_sui_address("0x5").unwrap());
+
+        assert!(merge_approvers(&[], &["not-an-address".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_sanitize_relative_path() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let base = temp_dir.path();
+
+        assert_eq!(
+            sanitize_relative_path(base, "notes/./today.txt").unwrap(),
+            base.join("notes").join("today.txt")
+        );
+        assert!(sanitize_relative_path(base, "../../etc/passwd").is_err());
+        assert!(sanitize_relative_path(base, "notes/../../secret").is_err());
+        assert!(sanitize_relative_path(base, "/etc/passwd").is_err());
+        assert!(sanitize_relative_path(base, "").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_sanitize_relative_path_rejects_symlink_escape() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let outside = tempfile::TempDir::new().unwrap();
+        let base = temp_dir.path().join("out");
+        fs::create_dir(&base).unwrap();
+        std::os::unix::fs::symlink(outside.path(), base.join("link")).unwrap();
+
+        assert!(sanitize_relative_path(&base, "link/passwd").is_err());
+    }
+
     #[test]
     fn test_format_file_size() {
         assert_eq!(format_file_size(512), "512 B");
@@ -208,6 +565,25 @@ mod tests {
         assert_eq!(truncate_string("hi", 8), "hi");
     }
 
+    #[test]
+    fn test_truncate_string_multibyte() {
+        // Each of these is several bytes, so byte slicing would cut mid-character
+        let emoji = "🔒🔑⏳📦🎁";
+        assert_eq!(truncate_string(emoji, 5), emoji);
+        assert_eq!(truncate_string(emoji, 4), "🔒...");
+        assert_eq!(truncate_string(emoji, 3), "...");
+
+        let cjk = "时间胶囊测试";
+        assert_eq!(truncate_string(cjk, 6), cjk);
+        assert_eq!(truncate_string(cjk, 5), "时间...");
+        assert_eq!(truncate_string("creator-时间胶囊", 10), "creator...");
+        assert_eq!(truncate_string("ab时间胶囊测", 6), "ab时...");
+
+        for max_len in 0..12 {
+            truncate_string("a🔒b时间c🎁", max_len);
+        }
+    }
+
     #[test]
     fn test_validate_ipfs_cid() {
         assert!(validate_ipfs_cid("QmTest123456789").is_ok());
@@ -219,4 +595,16 @@ mod tests {
         assert!(validate_ipfs_cid("invalid").is_err());
         assert!(validate_ipfs_cid("Qm").is_err());
     }
+
+    #[test]
+    fn test_key_fingerprint() {
+        let fingerprint = key_fingerprint("AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=").unwrap();
+        assert_eq!(fingerprint.len(), 16);
+        assert!(fingerprint.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(
+            fingerprint,
+            key_fingerprint("HxwdHhscGRoXGBUWExQREg8QDQ4LDAkKBwgFBgMEAQI=").unwrap()
+        );
+        assert!(key_fingerprint("not base64!").is_err());
+    }
 }
@@ -1,9 +1,96 @@
 ﻿use anyhow::Result;
-use capsule_cli::batch::{BatchExecutor, BatchOperationBuilder};
+use capsule_cli::batch::{BatchConfig, BatchExecutor, BatchOperationBuilder};
+use capsule_cli::chain::{CapsuleChainState, ChainClient};
 use capsule_cli::config::Config;
+use capsule_cli::manifest::{BatchManifest, ManifestEntry};
+use capsule_cli::sdk::{CapsuleSDK, CreateCapsuleResult};
+use capsule_cli::storage::{ContentStore, MemoryStore};
 use std::fs;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use tempfile::TempDir;
 
+/// Executor whose uploads stay in memory instead of going to an IPFS node
+async fn memory_executor(batch_config: BatchConfig, config: &Config) -> Result<BatchExecutor> {
+    let sdk = CapsuleSDK::new(config.clone())
+        .await?
+        .with_store(Arc::new(MemoryStore::new()));
+    Ok(BatchExecutor::new(batch_config, config).await?.with_sdk(sdk))
+}
+
+/// Chain holding capsules created earlier; IDs it does not know refer to the first
+struct CreatedChain(Vec<CreateCapsuleResult>);
+
+#[async_trait::async_trait]
+impl ChainClient for CreatedChain {
+    async fn estimate_create_gas(&self, _capsule_type: &str, _storage_bytes: u64) -> Result<u64> {
+        anyhow::bail!("no dry run")
+    }
+
+    async fn latest_timestamp_ms(&self) -> Result<u64> {
+        anyhow::bail!("no clock")
+    }
+
+    async fn capsule_state(&self, capsule_id: &str) -> Result<CapsuleChainState> {
+        let created = self
+            .0
+            .iter()
+            .find(|created| created.capsule_id == capsule_id)
+            .unwrap_or(&self.0[0]);
+        Ok(CapsuleChainState {
+            owner: "0xowner".to_string(),
+            revoked: false,
+            cid: Some(created.cid.clone()),
+            content_hash: Some(created.content_hash.clone()),
+            unlock_time_ms: Some(created.condition.recorded_unlock_time()),
+            unlocked: false,
+            approvals: Vec::new(),
+            required_approvals: None,
+        })
+    }
+
+    async fn revoke_capsule(&self, _capsule_id: &str, _sender: &str) -> Result<String> {
+        anyhow::bail!("no transactions")
+    }
+
+    async fn destroy_capsule(&self, _capsule_id: &str, _sender: &str) -> Result<String> {
+        anyhow::bail!("no transactions")
+    }
+
+    async fn revoke_approval(&self, _capsule_id: &str, _sender: &str) -> Result<String> {
+        anyhow::bail!("no transactions")
+    }
+}
+
+/// Store whose first upload fails, counting every upload attempt
+#[derive(Default)]
+struct FailFirstStore {
+    uploads: AtomicUsize,
+    inner: MemoryStore,
+}
+
+#[async_trait::async_trait]
+impl ContentStore for FailFirstStore {
+    async fn put(&self, data: &[u8]) -> Result<String> {
+        if self.uploads.fetch_add(1, Ordering::SeqCst) == 0 {
+            anyhow::bail!("upload rejected");
+        }
+        self.inner.put(data).await
+    }
+
+    async fn get(&self, cid: &str) -> Result<Vec<u8>> {
+        self.inner.get(cid).await
+    }
+
+    async fn contains(&self, cid: &str) -> Result<bool> {
+        self.inner.contains(cid).await
+    }
+
+    async fn unpin(&self, cid: &str) -> Result<()> {
+        self.inner.unpin(cid).await
+    }
+}
+
 /// Integration tests for CLI batch operations
 #[tokio::test]
 async fn test_batch_create_time_capsules() -> Result<()> {
@@ -25,7 +112,7 @@ async fn test_batch_create_time_capsules() -> Result<()> {
         .build()?;
 
     // Create batch executor
-    let executor = BatchExecutor::new(batch_config, &config).await?;
+    let executor = memory_executor(batch_config, &config).await?;
 
     // Execute batch operation
     let result = executor.execute_batch(vec![file1_path, file2_path]).await?;
@@ -54,7 +141,7 @@ async fn test_batch_create_multisig_capsules() -> Result<()> {
         ])
         .build()?;
 
-    let executor = BatchExecutor::new(batch_config, &config).await?;
+    let executor = memory_executor(batch_config, &config).await?;
     let result = executor.execute_batch(vec![file_path]).await?;
 
     assert_eq!(result.total_processed, 1);
@@ -73,7 +160,7 @@ async fn test_batch_create_payment_capsules() -> Result<()> {
         .create_payment_capsules(1000000000) // 1 SUI in MIST
         .build()?;
 
-    let executor = BatchExecutor::new(batch_config, &config).await?;
+    let executor = memory_executor(batch_config, &config).await?;
     let result = executor.execute_batch(vec![file_path]).await?;
 
     assert_eq!(result.total_processed, 1);
@@ -145,7 +232,7 @@ async fn test_batch_error_handling() -> Result<()> {
         .continue_on_error(true)
         .build()?;
 
-    let executor = BatchExecutor::new(batch_config, &config).await?;
+    let executor = memory_executor(batch_config, &config).await?;
     let result = executor.execute_batch(vec![empty_file, valid_file]).await?;
 
     // Should process both files, but some might fail
@@ -174,7 +261,7 @@ async fn test_batch_directory_processing() -> Result<()> {
         .create_time_capsules("30m")?
         .build()?;
 
-    let executor = BatchExecutor::new(batch_config, &config).await?;
+    let executor = memory_executor(batch_config, &config).await?;
     
     // Process the directory
     let result = executor.execute_batch(vec![sub_dir]).await?;
@@ -194,6 +281,7 @@ fn test_batch_operation_result_display() {
             "file1.txt -> 0x123abc".to_string(),
             "file2.txt -> 0x456def".to_string(),
         ],
+        outcomes: Vec::new(),
         failed: vec![
             ("file3.txt".to_string(), "Permission denied".to_string()),
         ],
@@ -231,7 +319,7 @@ async fn test_batch_concurrent_limit() -> Result<()> {
 
         assert_eq!(batch_config.max_concurrent, max_concurrent);
 
-        let executor = BatchExecutor::new(batch_config, &config).await?;
+        let executor = memory_executor(batch_config, &config).await?;
         let result = executor.execute_batch(vec![temp_dir.path().to_path_buf()]).await?;
 
         // Should process all files regardless of concurrency limit
@@ -240,3 +328,251 @@ async fn test_batch_concurrent_limit() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_batch_partial_failure_still_returns_result() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+
+    let valid_file = temp_dir.path().join("valid.txt");
+    fs::write(&valid_file, b"Valid content")?;
+    let missing_file = temp_dir.path().join("missing.txt");
+
+    let config = Config::default();
+
+    let batch_config = BatchOperationBuilder::new()
+        .create_time_capsules("1h")?
+        .continue_on_error(true)
+        .build()?;
+
+    let executor = memory_executor(batch_config, &config).await?;
+    let result = executor
+        .execute_batch(vec![valid_file, missing_file.clone()])
+        .await?;
+
+    // The bad input is reported, the good one still ran
+    assert_eq!(result.total_processed, 2);
+    assert_eq!(result.successful.len(), 1);
+    assert_eq!(result.failed.len(), 1);
+    assert_eq!(result.failed[0].0, missing_file.display().to_string());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_batch_unlock_key_mismatch() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+
+    let first = temp_dir.path().join("a.txt");
+    let second = temp_dir.path().join("b.txt");
+    fs::write(&first, b"0x123")?;
+    fs::write(&second, b"0x456")?;
+
+    let config = Config::default();
+    let sdk = CapsuleSDK::new(config.clone())
+        .await?
+        .with_store(Arc::new(MemoryStore::new()));
+    let created = sdk.create_time_capsule(b"batch unlock".to_vec(), 0, None).await?;
+    let keys = vec![created.encryption_key.clone()];
+
+    // Stopping on errors keeps the up-front check
+    let strict_config = BatchOperationBuilder::new()
+        .unlock_capsules(keys.clone())
+        .continue_on_error(false)
+        .build()?;
+    let executor = memory_executor(strict_config, &config).await?;
+    assert!(executor
+        .execute_batch(vec![first.clone(), second.clone()])
+        .await
+        .is_err());
+
+    // Otherwise the matched pair is unlocked and the extra file fails
+    let lenient_config = BatchOperationBuilder::new()
+        .unlock_capsules(keys)
+        .continue_on_error(true)
+        .build()?;
+    let sdk = sdk.with_chain(Arc::new(CreatedChain(vec![created])));
+    let executor = BatchExecutor::new(lenient_config, &config)
+        .await?
+        .with_sdk(sdk);
+    let result = executor.execute_batch(vec![first, second]).await?;
+
+    assert_eq!(result.total_processed, 2);
+    assert_eq!(result.successful.len(), 1);
+    assert_eq!(result.failed.len(), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_batch_unlock_from_manifest_writes_outputs() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let config = Config::default();
+    let sdk = CapsuleSDK::new(config.clone())
+        .await?
+        .with_store(Arc::new(MemoryStore::new()));
+
+    // Each input file holds a capsule ID; the manifest maps IDs to keys
+    let manifest = BatchManifest::new(temp_dir.path().join("manifest.jsonl"));
+    let plaintexts: [&[u8]; 2] = [b"first capsule", b"second capsule"];
+    let mut created = Vec::new();
+    let mut inputs = Vec::new();
+    for (index, plaintext) in plaintexts.iter().enumerate() {
+        let result = sdk.create_time_capsule(plaintext.to_vec(), 0, None).await?;
+        let input = temp_dir.path().join(format!("capsule-{index}.id"));
+        fs::write(&input, &result.capsule_id)?;
+        manifest.append(&ManifestEntry::new(
+            &input,
+            &result.capsule_id,
+            &result.cid,
+            &result.encryption_key,
+        ))?;
+        inputs.push(input);
+        created.push(result);
+    }
+    // Keys are matched by capsule ID, not by position
+    inputs.reverse();
+
+    let output_dir = temp_dir.path().join("unlocked");
+    let batch_config = BatchOperationBuilder::new()
+        .unlock_capsules_by_id(manifest.keys()?)
+        .output_dir(&output_dir)
+        .continue_on_error(false)
+        .build()?;
+    let sdk = sdk.with_chain(Arc::new(CreatedChain(created.clone())));
+    let executor = BatchExecutor::new(batch_config.clone(), &config)
+        .await?
+        .with_sdk(sdk);
+    let result = executor.execute_batch(inputs).await?;
+    assert_eq!(result.successful.len(), 2, "{:?}", result.failed);
+
+    // Each output is named by its capsule ID and holds that capsule's plaintext
+    assert_eq!(fs::read_dir(&output_dir)?.count(), 2);
+    for (capsule, plaintext) in created.iter().zip(plaintexts) {
+        let output = fs::read_dir(&output_dir)?
+            .map(|entry| entry.unwrap().path())
+            .find(|path| path.file_stem().unwrap().to_string_lossy() == capsule.capsule_id)
+            .unwrap();
+        assert_eq!(fs::read(output)?, plaintext);
+    }
+
+    // A capsule the manifest has no key for is a clear error
+    let stray = temp_dir.path().join("stray.id");
+    fs::write(&stray, "0xdead")?;
+    let executor = memory_executor(batch_config, &config).await?;
+    let err = executor.execute_batch(vec![stray]).await.unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("No encryption key for capsule 0xdead"),
+        "{err:#}"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_batch_estimate_mixed_directory() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+
+    fs::write(temp_dir.path().join("a.txt"), b"12345")?;
+    fs::write(temp_dir.path().join("b.txt"), b"1234567890")?;
+    fs::write(temp_dir.path().join("data.json"), b"{\"k\":1}")?;
+    fs::write(temp_dir.path().join("image.png"), [0u8; 100])?;
+
+    let config = Config::default();
+
+    let batch_config = BatchOperationBuilder::new()
+        .create_time_capsules("1h")?
+        .build()?;
+
+    let executor = memory_executor(batch_config, &config).await?;
+    let estimate = executor.estimate(vec![temp_dir.path().to_path_buf()])?;
+
+    assert_eq!(estimate.file_count, 4);
+    assert_eq!(estimate.total_size, 5 + 10 + 7 + 100);
+    assert_eq!(estimate.by_mime_type["text/plain"].count, 2);
+    assert_eq!(estimate.by_mime_type["text/plain"].total_size, 15);
+    assert_eq!(estimate.by_mime_type["application/json"].total_size, 7);
+    assert_eq!(estimate.by_mime_type["image/png"].total_size, 100);
+    assert!(estimate.skipped.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_batch_stops_at_first_failure() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let mut files = Vec::new();
+    for i in 0..4 {
+        let path = temp_dir.path().join(format!("file{i}.txt"));
+        fs::write(&path, format!("content {i}"))?;
+        files.push(path);
+    }
+
+    let config = Config::default();
+    let batch_config = BatchOperationBuilder::new()
+        .create_time_capsules("1h")?
+        .max_concurrent(1)
+        .continue_on_error(false)
+        .build()?;
+    let store = Arc::new(FailFirstStore::default());
+    let sdk = CapsuleSDK::new(config.clone()).await?.with_store(store.clone());
+    let executor = BatchExecutor::new(batch_config, &config).await?.with_sdk(sdk);
+
+    let result = executor.execute_batch(files).await?;
+
+    // The first file failed and none of the others were started
+    assert!(result.successful.is_empty());
+    assert_eq!(result.failed.len(), 1);
+    assert!(result.failed[0].0.ends_with("file0.txt"));
+    assert_eq!(store.uploads.load(Ordering::SeqCst), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_batch_manifest_resumes() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let inputs = temp_dir.path().join("inputs");
+    fs::create_dir(&inputs)?;
+    for name in ["a.txt", "b.txt", "c.txt"] {
+        fs::write(inputs.join(name), format!("content of {name}"))?;
+    }
+    let manifest_path = temp_dir.path().join("manifest.jsonl");
+
+    let config = Config::default();
+    let batch_config = BatchOperationBuilder::new()
+        .create_time_capsules("1h")?
+        .build()?;
+    let executor = memory_executor(batch_config.clone(), &config)
+        .await?
+        .with_manifest(BatchManifest::new(&manifest_path));
+    let result = executor.execute_batch(vec![inputs.clone()]).await?;
+    assert_eq!(result.outcomes.len(), 3);
+
+    let entries: Vec<ManifestEntry> = fs::read_to_string(&manifest_path)?
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+    assert_eq!(entries.len(), 3);
+    assert!(entries.iter().all(|entry| !entry.cid.is_empty()));
+
+    // Forget b.txt, as if the run had been interrupted before it finished
+    let kept: Vec<String> = entries
+        .iter()
+        .filter(|entry| !entry.path.ends_with("b.txt"))
+        .map(serde_json::to_string)
+        .collect::<Result<_, _>>()?;
+    fs::write(&manifest_path, kept.join("\n") + "\n")?;
+
+    let executor = memory_executor(batch_config, &config)
+        .await?
+        .with_manifest(BatchManifest::new(&manifest_path));
+    let result = executor.execute_batch(vec![inputs]).await?;
+
+    assert_eq!(result.outcomes.len(), 1);
+    assert!(result.outcomes[0].file.ends_with("b.txt"));
+    assert!(result.failed.is_empty());
+    assert_eq!(BatchManifest::new(&manifest_path).completed()?.len(), 3);
+
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use anyhow::Result;
+use capsule_cli::config::Config;
+use capsule_cli::sdk::CapsuleSDK;
+use capsule_cli::storage::{ContentStore, IpfsStore};
+use capsule_cli::utils::validate_ipfs_cid;
+
+/// Set to run these tests against a real IPFS node
+const IPFS_TEST_ENV: &str = "CAPSULE_IPFS_TEST";
+
+/// API URL of the node under test: `CAPSULE_IPFS_URL`, else a local daemon
+///
+/// `None` when the tests are not enabled.
+fn ipfs_test_url() -> Option<String> {
+    std::env::var_os(IPFS_TEST_ENV)?;
+    Some(std::env::var("CAPSULE_IPFS_URL").unwrap_or_else(|_| "http://127.0.0.1:5001".to_string()))
+}
+
+#[tokio::test]
+async fn test_upload_returns_valid_cid() -> Result<()> {
+    let Some(ipfs_url) = ipfs_test_url() else {
+        eprintln!("Skipping: set {IPFS_TEST_ENV}=1 to run against a local IPFS node");
+        return Ok(());
+    };
+
+    let config = Config {
+        ipfs_url: ipfs_url.clone(),
+        ..Config::default()
+    };
+    let sdk = CapsuleSDK::new(config).await?;
+    let result = sdk
+        .create_time_capsule(b"IPFS integration test".to_vec(), 0, None)
+        .await?;
+
+    assert!(!result.cid.is_empty());
+    validate_ipfs_cid(&result.cid)?;
+
+    // The node now serves the encrypted payload under that CID
+    let store = IpfsStore::new(&ipfs_url)?;
+    assert!(store.contains(&result.cid).await?);
+    assert!(!store.get(&result.cid).await?.is_empty());
+
+    Ok(())
+}
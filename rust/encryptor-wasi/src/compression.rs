@@ -0,0 +1,126 @@
+/// Number of leading bytes inspected by the compression heuristic
+pub const COMPRESSION_SAMPLE_SIZE: usize = 4096;
+
+/// Samples with an estimated entropy above this (bits per byte) are treated as incompressible
+pub const ENTROPY_SKIP_THRESHOLD: f64 = 7.5;
+
+/// Inputs smaller than this are never worth compressing
+pub const MIN_COMPRESSIBLE_SIZE: usize = 64;
+
+/// Magic prefixes of formats that are already compressed (images, archives, media)
+const COMPRESSED_SIGNATURES: &[&[u8]] = &[
+    b"\xFF\xD8\xFF",       // JPEG
+    b"\x89PNG\r\n\x1a\n",  // PNG
+    b"GIF8",               // GIF
+    b"PK\x03\x04",         // ZIP / DOCX / JAR
+    b"\x1F\x8B",           // gzip
+    b"\x28\xB5\x2F\xFD",   // zstd
+    b"BZh",                // bzip2
+    b"\xFD7zXZ\x00",       // xz
+    b"7z\xBC\xAF\x27\x1C", // 7z
+    b"Rar!\x1A\x07",       // RAR
+    b"OggS",               // Ogg
+    b"fLaC",               // FLAC
+    b"ID3",                // MP3
+];
+
+/// Estimate the Shannon entropy of a sample in bits per byte (0.0 - 8.0)
+pub fn estimate_entropy(sample: &[u8]) -> f64 {
+    if sample.is_empty() {
+        return 0.0;
+    }
+
+    let mut counts = [0usize; 256];
+    for &byte in sample {
+        counts[byte as usize] += 1;
+    }
+
+    let len = sample.len() as f64;
+    counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Detect well-known compressed container formats from their magic bytes
+pub fn is_compressed_format(sample: &[u8]) -> bool {
+    if COMPRESSED_SIGNATURES
+        .iter()
+        .any(|signature| sample.starts_with(signature))
+    {
+        return true;
+    }
+
+    // ISO base media (mp4, mov, heic) and WebP carry their tag at an offset
+    (sample.len() >= 12 && &sample[4..8] == b"ftyp")
+        || (sample.len() >= 12 && sample.starts_with(b"RIFF") && &sample[8..12] == b"WEBP")
+}
+
+/// Decide whether content is worth compressing, based on its first bytes
+///
+/// Already-compressed formats and high-entropy data (ciphertext, random bytes)
+/// are skipped, since compressing them wastes CPU and can grow the output.
+pub fn should_compress(sample: &[u8]) -> bool {
+    let sample = &sample[..sample.len().min(COMPRESSION_SAMPLE_SIZE)];
+
+    if sample.len() < MIN_COMPRESSIBLE_SIZE {
+        return false;
+    }
+
+    if is_compressed_format(sample) {
+        return false;
+    }
+
+    estimate_entropy(sample) < ENTROPY_SKIP_THRESHOLD
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chacha20poly1305::aead::{rand_core::RngCore, OsRng};
+
+    #[test]
+    fn test_estimate_entropy_bounds() {
+        assert_eq!(estimate_entropy(b""), 0.0);
+        assert_eq!(estimate_entropy(&[0u8; 1024]), 0.0);
+
+        let all_bytes: Vec<u8> = (0..=255u8).collect();
+        assert!((estimate_entropy(&all_bytes) - 8.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_random_data_is_skipped() {
+        let mut random = vec![0u8; COMPRESSION_SAMPLE_SIZE];
+        OsRng.fill_bytes(&mut random);
+
+        assert!(!should_compress(&random));
+    }
+
+    #[test]
+    fn test_text_is_compressed() {
+        let text = "The quick brown fox jumps over the lazy dog. ".repeat(200);
+
+        assert!(should_compress(text.as_bytes()));
+    }
+
+    #[test]
+    fn test_compressed_formats_are_skipped() {
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE0];
+        jpeg.extend(std::iter::repeat_n(b'a', 1024));
+        assert!(!should_compress(&jpeg));
+
+        let mut mp4 = vec![0, 0, 0, 0x18];
+        mp4.extend_from_slice(b"ftypisom");
+        mp4.extend(std::iter::repeat_n(b'a', 1024));
+        assert!(!should_compress(&mp4));
+    }
+
+    #[test]
+    fn test_tiny_input_is_skipped() {
+        assert!(!should_compress(b"short"));
+    }
+}
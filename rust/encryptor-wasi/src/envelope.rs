@@ -0,0 +1,269 @@
+use serde::{Deserialize, Serialize};
+
+use crate::password::PasswordKdf;
+use crate::{EncryptionError, EncryptionResult};
+
+/// Envelope version written for payloads without associated data
+pub const ENVELOPE_VERSION: u8 = 1;
+
+/// Envelope version written for payloads bound to associated data
+///
+/// Older builds would decrypt these without the associated data and fail
+/// with an opaque authentication error, so they get a version of their own.
+pub const ENVELOPE_VERSION_AAD: u8 = 2;
+
+/// Envelope versions this build can read
+pub const SUPPORTED_ENVELOPE_VERSIONS: &[u8] = &[1, 2];
+
+/// Identifier of the XChaCha20-Poly1305 AEAD
+pub const ALGORITHM_XCHACHA20POLY1305: &str = "xchacha20poly1305";
+
+/// Content encryption algorithms this build can decrypt
+pub const SUPPORTED_ALGORITHMS: &[&str] = &[ALGORITHM_XCHACHA20POLY1305];
+
+/// Format metadata stored alongside encrypted content
+///
+/// Payloads written before the envelope existed carry no metadata and are
+/// read as version 1 with XChaCha20-Poly1305, which is what they used.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EnvelopeInfo {
+    pub version: u8,
+    pub algorithm: String,
+    /// How the content key was derived, when it came from a passphrase
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kdf: Option<PasswordKdf>,
+    /// How the plaintext was prepared before it was encrypted
+    #[serde(default, skip_serializing_if = "ContentEncoding::is_plain")]
+    pub encoding: ContentEncoding,
+}
+
+/// How plaintext was prepared before it was encrypted
+///
+/// Recorded in the envelope rather than as a marker at the start of the
+/// plaintext, so content that happens to begin with such a marker is never
+/// mistaken for an encoding.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ContentEncoding {
+    /// Compression algorithm applied; `None` when the content is stored as is
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression: Option<String>,
+    /// Whether the plaintext lists separately stored chunks of the content
+    /// rather than holding the content itself
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub chunked: bool,
+    /// Whether the content starts with the original file's metadata
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub file_metadata: bool,
+    /// Whether the content is a bundle of several files
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub bundle: bool,
+}
+
+impl ContentEncoding {
+    /// Whether the plaintext is the content itself, with nothing to undo
+    pub fn is_plain(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl Default for EnvelopeInfo {
+    fn default() -> Self {
+        Self {
+            version: ENVELOPE_VERSION,
+            algorithm: ALGORITHM_XCHACHA20POLY1305.to_string(),
+            kdf: None,
+            encoding: ContentEncoding::default(),
+        }
+    }
+}
+
+/// Magic prefix of the binary `EncryptionResult` layout
+pub const BINARY_FORMAT_MAGIC: &[u8; 4] = b"TCEN";
+
+/// Length of the binary header: magic, version, nonce and content hash
+pub const BINARY_HEADER_LEN: usize = 4 + 1 + 24 + 32;
+
+impl EncryptionResult {
+    /// Encode as magic, envelope version, nonce, content hash, then the ciphertext
+    ///
+    /// Much smaller than JSON for large payloads. The algorithm is implied by
+    /// the version, and neither key derivation details nor the content
+    /// encoding are carried, so payloads with a passphrase-derived key or an
+    /// encoded plaintext should stay in JSON.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(BINARY_HEADER_LEN + self.ciphertext.len());
+        out.extend_from_slice(BINARY_FORMAT_MAGIC);
+        out.push(self.envelope.version);
+        out.extend_from_slice(&self.nonce);
+        out.extend_from_slice(&self.content_hash);
+        out.extend_from_slice(&self.ciphertext);
+        out
+    }
+
+    /// Decode the layout written by `to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, EncryptionError> {
+        if bytes.len() < BINARY_HEADER_LEN {
+            return Err(EncryptionError::UnsupportedFormat(format!(
+                "payload is {} bytes, shorter than the {BINARY_HEADER_LEN}-byte header",
+                bytes.len()
+            )));
+        }
+        let (magic, rest) = bytes.split_at(BINARY_FORMAT_MAGIC.len());
+        if magic != BINARY_FORMAT_MAGIC {
+            return Err(EncryptionError::UnsupportedFormat(
+                "payload does not start with the encryption result magic".to_string(),
+            ));
+        }
+        let version = rest[0];
+        if !SUPPORTED_ENVELOPE_VERSIONS.contains(&version) {
+            return Err(EncryptionError::UnsupportedFormat(format!(
+                "payload uses format v{version}, which this build cannot read"
+            )));
+        }
+
+        let mut nonce = [0u8; 24];
+        let mut content_hash = [0u8; 32];
+        nonce.copy_from_slice(&rest[1..25]);
+        content_hash.copy_from_slice(&rest[25..57]);
+
+        Ok(Self {
+            ciphertext: rest[57..].to_vec(),
+            nonce,
+            content_hash,
+            envelope: EnvelopeInfo {
+                version,
+                ..EnvelopeInfo::default()
+            },
+        })
+    }
+}
+
+/// Check that this build can read an envelope before trying to decrypt it
+pub fn check_compatibility(info: &EnvelopeInfo) -> Result<(), EncryptionError> {
+    if !SUPPORTED_ENVELOPE_VERSIONS.contains(&info.version) {
+        let newest = SUPPORTED_ENVELOPE_VERSIONS
+            .iter()
+            .max()
+            .copied()
+            .unwrap_or(0);
+        let message = if info.version > newest {
+            format!(
+                "capsule uses envelope v{} but this build supports up to v{}; please upgrade",
+                info.version, newest
+            )
+        } else {
+            format!(
+                "capsule uses envelope v{}, which this build no longer supports",
+                info.version
+            )
+        };
+        return Err(EncryptionError::UnsupportedEnvelope(message));
+    }
+
+    if !SUPPORTED_ALGORITHMS.contains(&info.algorithm.as_str()) {
+        return Err(EncryptionError::UnsupportedEnvelope(format!(
+            "capsule uses algorithm '{}' but this build supports {}; please upgrade",
+            info.algorithm,
+            SUPPORTED_ALGORITHMS.join(", ")
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{encrypt_content, encrypt_content_with_aad, generate_key};
+
+    #[test]
+    fn test_current_envelope_is_supported() {
+        assert!(check_compatibility(&EnvelopeInfo::default()).is_ok());
+    }
+
+    #[test]
+    fn test_newer_version_rejected() {
+        let info = EnvelopeInfo {
+            version: 3,
+            ..Default::default()
+        };
+
+        let message = check_compatibility(&info).unwrap_err().to_string();
+        assert!(message.contains("envelope v3"));
+        assert!(message.contains("up to v2"));
+        assert!(message.contains("please upgrade"));
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let key = generate_key().unwrap();
+        for encrypted in [
+            encrypt_content(b"compact", &key).unwrap(),
+            encrypt_content_with_aad(b"compact", &key, b"aad").unwrap(),
+        ] {
+            let bytes = encrypted.to_bytes();
+            assert_eq!(bytes.len(), BINARY_HEADER_LEN + encrypted.ciphertext.len());
+
+            let decoded = EncryptionResult::from_bytes(&bytes).unwrap();
+            assert_eq!(decoded.ciphertext, encrypted.ciphertext);
+            assert_eq!(decoded.nonce, encrypted.nonce);
+            assert_eq!(decoded.content_hash, encrypted.content_hash);
+            assert_eq!(decoded.envelope, encrypted.envelope);
+        }
+    }
+
+    #[test]
+    fn test_bad_binary_payload_rejected() {
+        let key = generate_key().unwrap();
+        let bytes = encrypt_content(b"compact", &key).unwrap().to_bytes();
+
+        for len in 0..BINARY_HEADER_LEN {
+            assert!(matches!(
+                EncryptionResult::from_bytes(&bytes[..len]),
+                Err(EncryptionError::UnsupportedFormat(_))
+            ));
+        }
+
+        let mut wrong_magic = bytes.clone();
+        wrong_magic[0] = b'X';
+        assert!(matches!(
+            EncryptionResult::from_bytes(&wrong_magic),
+            Err(EncryptionError::UnsupportedFormat(_))
+        ));
+
+        let mut wrong_version = bytes;
+        wrong_version[4] = 9;
+        let message = EncryptionResult::from_bytes(&wrong_version)
+            .unwrap_err()
+            .to_string();
+        assert!(message.contains("format v9"), "{message}");
+    }
+
+    #[test]
+    fn test_plain_encoding_left_out_of_json() {
+        let json = serde_json::to_value(EnvelopeInfo::default()).unwrap();
+        assert!(json.get("encoding").is_none());
+
+        let info = EnvelopeInfo {
+            encoding: ContentEncoding {
+                compression: Some("zstd".to_string()),
+                chunked: true,
+                file_metadata: true,
+                bundle: false,
+            },
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&info).unwrap();
+        assert_eq!(serde_json::from_str::<EnvelopeInfo>(&json).unwrap(), info);
+    }
+
+    #[test]
+    fn test_unknown_algorithm_rejected() {
+        let info = EnvelopeInfo {
+            algorithm: "aes-256-gcm".to_string(),
+            ..Default::default()
+        };
+
+        assert!(check_compatibility(&info).is_err());
+    }
+}
@@ -1,5 +1,7 @@
 use blake3::Hasher;
 use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+use subtle::ConstantTimeEq;
 use thiserror::Error;
 
 /// Hash computation errors
@@ -19,6 +21,69 @@ pub struct HashResult {
     pub input_size: usize,
 }
 
+/// Hash functions content can be hashed with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HashAlgorithm {
+    #[default]
+    Blake3,
+    /// For verifiers, such as on-chain ones, that only support SHA3
+    Sha3_256,
+}
+
+impl HashAlgorithm {
+    /// Name recorded in `HashResult::algorithm`
+    pub fn name(self) -> &'static str {
+        match self {
+            HashAlgorithm::Blake3 => "BLAKE3",
+            HashAlgorithm::Sha3_256 => "SHA3-256",
+        }
+    }
+
+    /// Look up an algorithm by the name recorded in a `HashResult`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "BLAKE3" => Some(HashAlgorithm::Blake3),
+            "SHA3-256" => Some(HashAlgorithm::Sha3_256),
+            _ => None,
+        }
+    }
+}
+
+/// Incremental BLAKE3 hasher for content that arrives in pieces
+///
+/// Feeding the same bytes through any number of `update` calls gives the
+/// same result as `hash_content` over their concatenation.
+#[derive(Debug, Clone, Default)]
+pub struct ContentHasher {
+    hasher: Hasher,
+    input_size: usize,
+}
+
+impl ContentHasher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the next piece of content
+    pub fn update(&mut self, content: &[u8]) {
+        self.hasher.update(content);
+        self.input_size += content.len();
+    }
+
+    /// Number of bytes hashed so far
+    pub fn input_size(&self) -> usize {
+        self.input_size
+    }
+
+    pub fn finalize(self) -> HashResult {
+        HashResult {
+            hash: self.hasher.finalize().into(),
+            algorithm: "BLAKE3".to_string(),
+            input_size: self.input_size,
+        }
+    }
+}
+
 /// Compute BLAKE3 hash of content
 pub fn hash_content(content: &[u8]) -> HashResult {
     let mut hasher = Hasher::new();
@@ -32,6 +97,20 @@ pub fn hash_content(content: &[u8]) -> HashResult {
     }
 }
 
+/// Compute a 32-byte hash of content with the chosen algorithm
+pub fn hash_content_with(content: &[u8], algorithm: HashAlgorithm) -> HashResult {
+    let hash = match algorithm {
+        HashAlgorithm::Blake3 => hash_content_bytes(content),
+        HashAlgorithm::Sha3_256 => Sha3_256::digest(content).into(),
+    };
+
+    HashResult {
+        hash,
+        algorithm: algorithm.name().to_string(),
+        input_size: content.len(),
+    }
+}
+
 /// Compute BLAKE3 hash and return only the hash bytes
 pub fn hash_content_bytes(content: &[u8]) -> [u8; 32] {
     let mut hasher = Hasher::new();
@@ -40,34 +119,79 @@ pub fn hash_content_bytes(content: &[u8]) -> [u8; 32] {
 }
 
 /// Verify that content matches the expected hash
+///
+/// The comparison is constant-time: it takes as long whichever byte differs.
 pub fn verify_content_hash(content: &[u8], expected_hash: &[u8; 32]) -> bool {
     let computed_hash = hash_content_bytes(content);
-    computed_hash == *expected_hash
+    computed_hash.ct_eq(expected_hash).into()
+}
+
+/// Compute a keyed BLAKE3 tag (MAC) of content
+///
+/// Only holders of `key` can produce or check the tag, so it detects
+/// tampering with stored ciphertext without handing out the encryption key.
+pub fn hash_content_keyed(content: &[u8], key: &[u8; 32]) -> HashResult {
+    let mut hasher = Hasher::new_keyed(key);
+    hasher.update(content);
+    let hash = hasher.finalize().into();
+
+    HashResult {
+        hash,
+        algorithm: "BLAKE3-keyed".to_string(),
+        input_size: content.len(),
+    }
+}
+
+/// Verify a keyed BLAKE3 tag in constant time
+pub fn verify_content_hash_keyed(content: &[u8], key: &[u8; 32], expected_tag: &[u8; 32]) -> bool {
+    let computed = hash_content_keyed(content, key);
+    computed.hash.ct_eq(expected_tag).into()
+}
+
+/// Compute a BLAKE3 digest of arbitrary length using its extendable output
+///
+/// The first 32 bytes are always the standard hash, so shorter or longer
+/// digests used by other content-addressing schemes stay comparable.
+pub fn hash_content_xof(content: &[u8], out_len: usize) -> Vec<u8> {
+    let mut hasher = Hasher::new();
+    hasher.update(content);
+    let mut output = vec![0u8; out_len];
+    hasher.finalize_xof().fill(&mut output);
+    output
+}
+
+/// Verify content against a digest of any length produced by `hash_content_xof`
+///
+/// The digest bytes are compared in constant time; only its length is not secret.
+pub fn verify_content_hash_xof(content: &[u8], expected_hash: &[u8]) -> bool {
+    !expected_hash.is_empty()
+        && bool::from(
+            hash_content_xof(content, expected_hash.len())
+                .as_slice()
+                .ct_eq(expected_hash),
+        )
 }
 
 /// Verify that content matches the expected hash result
+///
+/// The hash is recomputed with the algorithm `expected` records, and an
+/// unrecognised algorithm never verifies. The hashes are compared in
+/// constant time.
 pub fn verify_content_hash_result(content: &[u8], expected: &HashResult) -> bool {
-    let computed = hash_content(content);
-    computed.hash == expected.hash && computed.input_size == content.len()
+    let Some(algorithm) = HashAlgorithm::from_name(&expected.algorithm) else {
+        return false;
+    };
+    let computed = hash_content_with(content, algorithm);
+    bool::from(computed.hash.ct_eq(&expected.hash)) && computed.input_size == expected.input_size
 }
 
 /// Compute hash of multiple content pieces concatenated
 pub fn hash_multiple_contents(contents: &[&[u8]]) -> HashResult {
-    let mut hasher = Hasher::new();
-    let mut total_size = 0;
-
+    let mut hasher = ContentHasher::new();
     for content in contents {
         hasher.update(content);
-        total_size += content.len();
-    }
-
-    let hash = hasher.finalize().into();
-
-    HashResult {
-        hash,
-        algorithm: "BLAKE3".to_string(),
-        input_size: total_size,
     }
+    hasher.finalize()
 }
 
 /// Create a hash from hex string
@@ -126,6 +250,13 @@ mod tests {
 
         assert!(verify_content_hash(content, &hash));
         assert!(!verify_content_hash(b"Different content", &hash));
+
+        // Differences at either end are caught, not just early ones
+        for position in [0, 15, 31] {
+            let mut altered = hash;
+            altered[position] ^= 0x80;
+            assert!(!verify_content_hash(content, &altered));
+        }
     }
 
     #[test]
@@ -135,6 +266,42 @@ mod tests {
 
         assert!(verify_content_hash_result(content, &result));
         assert!(!verify_content_hash_result(b"Different", &result));
+
+        let mut altered = result.clone();
+        altered.hash[31] ^= 0x01;
+        assert!(!verify_content_hash_result(content, &altered));
+    }
+
+    #[test]
+    fn test_hash_algorithms_cross_verify() {
+        let content = b"Algorithm selection test";
+        let blake3 = hash_content_with(content, HashAlgorithm::Blake3);
+        let sha3 = hash_content_with(content, HashAlgorithm::Sha3_256);
+
+        assert_eq!(blake3, hash_content(content));
+        assert_eq!(sha3.algorithm, "SHA3-256");
+        assert_eq!(sha3.hash.len(), 32);
+        assert_ne!(sha3.hash, blake3.hash);
+        assert_eq!(
+            hash_to_hex(&hash_content_with(b"", HashAlgorithm::Sha3_256).hash),
+            "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a"
+        );
+
+        assert!(verify_content_hash_result(content, &blake3));
+        assert!(verify_content_hash_result(content, &sha3));
+        assert!(!verify_content_hash_result(b"Different", &sha3));
+
+        // A hash only verifies under the algorithm it was made with
+        let mislabelled = HashResult {
+            algorithm: "BLAKE3".to_string(),
+            ..sha3.clone()
+        };
+        assert!(!verify_content_hash_result(content, &mislabelled));
+        let unknown = HashResult {
+            algorithm: "MD5".to_string(),
+            ..sha3
+        };
+        assert!(!verify_content_hash_result(content, &unknown));
     }
 
     #[test]
@@ -154,6 +321,71 @@ mod tests {
         assert_eq!(result.hash, single_result.hash);
     }
 
+    #[test]
+    fn test_content_hasher_matches_one_shot() {
+        let content: Vec<u8> = (0..10_000u32).map(|i| i as u8).collect();
+
+        let mut hasher = ContentHasher::new();
+        for piece in content.chunks(777) {
+            hasher.update(piece);
+        }
+        hasher.update(&[]);
+        assert_eq!(hasher.input_size(), content.len());
+        assert_eq!(hasher.finalize(), hash_content(&content));
+
+        assert_eq!(ContentHasher::new().finalize(), hash_content(b""));
+    }
+
+    #[test]
+    fn test_hash_content_xof() {
+        let content = b"XOF interop test";
+        let extended = hash_content_xof(content, 64);
+
+        assert_eq!(extended.len(), 64);
+        assert_eq!(extended[..32], hash_content_bytes(content));
+        assert_eq!(hash_content_xof(content, 16), extended[..16]);
+    }
+
+    #[test]
+    fn test_verify_content_hash_xof() {
+        let content = b"XOF verification test";
+        let digest = hash_content_xof(content, 48);
+
+        assert!(verify_content_hash_xof(content, &digest));
+        assert!(verify_content_hash_xof(content, &digest[..20]));
+        assert!(!verify_content_hash_xof(b"Different content", &digest));
+        assert!(!verify_content_hash_xof(content, &[]));
+    }
+
+    #[test]
+    fn test_keyed_hash_depends_on_key() {
+        let content = b"Keyed tag test";
+        let tag = hash_content_keyed(content, &[1; 32]);
+
+        assert_eq!(tag.algorithm, "BLAKE3-keyed");
+        assert_eq!(tag.input_size, content.len());
+        assert_ne!(tag.hash, hash_content_keyed(content, &[2; 32]).hash);
+        assert_ne!(tag.hash, hash_content_bytes(content));
+    }
+
+    #[test]
+    fn test_verify_content_hash_keyed() {
+        let content = b"Keyed verification test";
+        let key = [7; 32];
+        let tag = hash_content_keyed(content, &key).hash;
+
+        assert!(verify_content_hash_keyed(content, &key, &tag));
+        assert!(!verify_content_hash_keyed(content, &[8; 32], &tag));
+        assert!(!verify_content_hash_keyed(b"Tampered", &key, &tag));
+
+        // A difference anywhere in the tag is rejected, first byte or last
+        for position in [0, 31] {
+            let mut altered = tag;
+            altered[position] ^= 0x01;
+            assert!(!verify_content_hash_keyed(content, &key, &altered));
+        }
+    }
+
     #[test]
     fn test_hash_hex_conversion() {
         let content = b"Hex conversion test";
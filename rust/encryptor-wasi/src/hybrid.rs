@@ -0,0 +1,168 @@
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, OsRng},
+    XChaCha20Poly1305, XNonce,
+};
+use hkdf::Hkdf;
+use sha2::Sha256;
+use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
+
+use crate::{generate_nonce, EncryptionError};
+
+/// HKDF info string for keys derived from an X25519 exchange
+const HYBRID_KEY_INFO: &[u8] = b"time-capsule-hybrid-key";
+
+/// Length of the ephemeral public key and nonce preceding the ciphertext
+const HYBRID_HEADER_LEN: usize = 32 + 24;
+
+/// Data sealed to an X25519 public key
+///
+/// A fresh ephemeral key is agreed with the recipient's key, HKDF-SHA256
+/// turns the shared secret into an XChaCha20-Poly1305 key, and only the
+/// holder of the recipient's secret key can derive it again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HybridCiphertext {
+    pub ephemeral_public: [u8; 32],
+    pub nonce: [u8; 24],
+    pub ciphertext: Vec<u8>,
+}
+
+impl HybridCiphertext {
+    /// Encode as ephemeral public key, nonce, then ciphertext
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HYBRID_HEADER_LEN + self.ciphertext.len());
+        out.extend_from_slice(&self.ephemeral_public);
+        out.extend_from_slice(&self.nonce);
+        out.extend_from_slice(&self.ciphertext);
+        out
+    }
+
+    /// Decode the layout written by `to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, EncryptionError> {
+        if bytes.len() < HYBRID_HEADER_LEN {
+            return Err(EncryptionError::DecryptionFailed(
+                "hybrid ciphertext is truncated".to_string(),
+            ));
+        }
+        let mut ephemeral_public = [0u8; 32];
+        let mut nonce = [0u8; 24];
+        ephemeral_public.copy_from_slice(&bytes[..32]);
+        nonce.copy_from_slice(&bytes[32..HYBRID_HEADER_LEN]);
+
+        Ok(Self {
+            ephemeral_public,
+            nonce,
+            ciphertext: bytes[HYBRID_HEADER_LEN..].to_vec(),
+        })
+    }
+}
+
+/// Generate an X25519 key pair, returned as (secret, public)
+pub fn generate_hybrid_keypair() -> ([u8; 32], [u8; 32]) {
+    let secret = StaticSecret::random_from_rng(OsRng);
+    let public = PublicKey::from(&secret);
+    (secret.to_bytes(), public.to_bytes())
+}
+
+/// Encrypt `plaintext` so only the holder of `recipient_public`'s secret key can read it
+pub fn hybrid_encrypt(
+    recipient_public: &[u8; 32],
+    plaintext: &[u8],
+) -> Result<HybridCiphertext, EncryptionError> {
+    let recipient = PublicKey::from(*recipient_public);
+    let ephemeral = EphemeralSecret::random_from_rng(OsRng);
+    let ephemeral_public = PublicKey::from(&ephemeral).to_bytes();
+
+    let shared = ephemeral.diffie_hellman(&recipient);
+    if !shared.was_contributory() {
+        return Err(EncryptionError::EncryptionFailed(
+            "recipient public key is a low-order point".to_string(),
+        ));
+    }
+    let key = derive_hybrid_key(shared.as_bytes(), &ephemeral_public, recipient_public)?;
+
+    let nonce = generate_nonce()?;
+    let cipher = XChaCha20Poly1305::new_from_slice(&key)
+        .map_err(|e| EncryptionError::EncryptionFailed(e.to_string()))?;
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&nonce), plaintext)
+        .map_err(|e| EncryptionError::EncryptionFailed(e.to_string()))?;
+
+    Ok(HybridCiphertext {
+        ephemeral_public,
+        nonce,
+        ciphertext,
+    })
+}
+
+/// Decrypt data sealed with `hybrid_encrypt` using the recipient's secret key
+pub fn hybrid_decrypt(
+    recipient_secret: &[u8; 32],
+    sealed: &HybridCiphertext,
+) -> Result<Vec<u8>, EncryptionError> {
+    let secret = StaticSecret::from(*recipient_secret);
+    let recipient_public = PublicKey::from(&secret).to_bytes();
+
+    let shared = secret.diffie_hellman(&PublicKey::from(sealed.ephemeral_public));
+    if !shared.was_contributory() {
+        return Err(EncryptionError::DecryptionFailed(
+            "ephemeral public key is a low-order point".to_string(),
+        ));
+    }
+    let key = derive_hybrid_key(
+        shared.as_bytes(),
+        &sealed.ephemeral_public,
+        &recipient_public,
+    )?;
+
+    let cipher = XChaCha20Poly1305::new_from_slice(&key)
+        .map_err(|e| EncryptionError::DecryptionFailed(e.to_string()))?;
+    cipher
+        .decrypt(
+            XNonce::from_slice(&sealed.nonce),
+            sealed.ciphertext.as_slice(),
+        )
+        .map_err(|e| EncryptionError::DecryptionFailed(e.to_string()))
+}
+
+/// Bind the derived key to both public keys so a ciphertext cannot be re-targeted
+fn derive_hybrid_key(
+    shared_secret: &[u8; 32],
+    ephemeral_public: &[u8; 32],
+    recipient_public: &[u8; 32],
+) -> Result<[u8; 32], EncryptionError> {
+    let mut salt = [0u8; 64];
+    salt[..32].copy_from_slice(ephemeral_public);
+    salt[32..].copy_from_slice(recipient_public);
+
+    let hk = Hkdf::<Sha256>::new(Some(&salt), shared_secret);
+    let mut key = [0u8; 32];
+    hk.expand(HYBRID_KEY_INFO, &mut key)
+        .map_err(|e| EncryptionError::KeyDerivationFailed(e.to_string()))?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hybrid_round_trip() {
+        let (secret, public) = generate_hybrid_keypair();
+        let sealed = hybrid_encrypt(&public, b"content key").unwrap();
+
+        let decoded = HybridCiphertext::from_bytes(&sealed.to_bytes()).unwrap();
+        assert_eq!(decoded, sealed);
+        assert_eq!(hybrid_decrypt(&secret, &decoded).unwrap(), b"content key");
+    }
+
+    #[test]
+    fn test_wrong_secret_rejected() {
+        let (_, public) = generate_hybrid_keypair();
+        let (other_secret, _) = generate_hybrid_keypair();
+        let sealed = hybrid_encrypt(&public, b"content key").unwrap();
+
+        assert!(hybrid_decrypt(&other_secret, &sealed).is_err());
+        assert!(hybrid_encrypt(&[0u8; 32], b"content key").is_err());
+        assert!(HybridCiphertext::from_bytes(&[0u8; 10]).is_err());
+    }
+}
@@ -0,0 +1,219 @@
+use chacha20poly1305::aead::{rand_core::RngCore, OsRng};
+use std::collections::HashSet;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::{EncryptionError, SecretKey};
+
+/// Length of an encoded share: the index, then one byte per key byte
+pub const KEY_SHARE_LEN: usize = 1 + 32;
+
+/// One Shamir share of a 32-byte key
+///
+/// Any `threshold` shares from the same split reconstruct the key; fewer
+/// reveal nothing about it.
+#[derive(Clone, PartialEq, Eq, Zeroize, ZeroizeOnDrop)]
+pub struct KeyShare {
+    index: u8,
+    value: [u8; 32],
+}
+
+impl KeyShare {
+    /// Nonzero x coordinate of this share; shares with equal indexes are duplicates
+    pub fn index(&self) -> u8 {
+        self.index
+    }
+
+    /// Encode as the index followed by the share value
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(KEY_SHARE_LEN);
+        out.push(self.index);
+        out.extend_from_slice(&self.value);
+        out
+    }
+
+    /// Decode the layout written by `to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, EncryptionError> {
+        if bytes.len() != KEY_SHARE_LEN {
+            return Err(EncryptionError::InvalidShares(format!(
+                "share is {} bytes, expected {KEY_SHARE_LEN}",
+                bytes.len()
+            )));
+        }
+        if bytes[0] == 0 {
+            return Err(EncryptionError::InvalidShares(
+                "share index must be nonzero".to_string(),
+            ));
+        }
+        let mut value = [0u8; 32];
+        value.copy_from_slice(&bytes[1..]);
+        Ok(Self {
+            index: bytes[0],
+            value,
+        })
+    }
+}
+
+impl std::fmt::Debug for KeyShare {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "KeyShare({}, ..)", self.index)
+    }
+}
+
+/// Split `key` into `shares` Shamir shares over GF(256), any `threshold` of which recover it
+pub fn split_key(
+    key: &[u8; 32],
+    threshold: u8,
+    shares: u8,
+) -> Result<Vec<KeyShare>, EncryptionError> {
+    if threshold == 0 {
+        return Err(EncryptionError::InvalidShares(
+            "threshold must be greater than 0".to_string(),
+        ));
+    }
+    if shares < threshold {
+        return Err(EncryptionError::InvalidShares(format!(
+            "cannot split into {shares} shares with a threshold of {threshold}"
+        )));
+    }
+
+    // One polynomial per key byte, with that byte as the constant term
+    let mut coefficients = vec![[0u8; 32]; threshold as usize];
+    coefficients[0] = *key;
+    for row in &mut coefficients[1..] {
+        OsRng
+            .try_fill_bytes(row)
+            .map_err(|_| EncryptionError::RandomGenerationFailed)?;
+    }
+
+    let split = (1..=shares)
+        .map(|index| {
+            let mut value = [0u8; 32];
+            for (byte, out) in value.iter_mut().enumerate() {
+                // Horner's rule, from the highest coefficient down
+                *out = coefficients
+                    .iter()
+                    .rev()
+                    .fold(0, |acc, row| gf_mul(acc, index) ^ row[byte]);
+            }
+            KeyShare { index, value }
+        })
+        .collect();
+    coefficients.zeroize();
+    Ok(split)
+}
+
+/// Recover a key from shares made by `split_key`
+///
+/// The shares must be distinct. Fewer than the split's threshold produce a
+/// different key rather than an error, since shares do not record it.
+pub fn combine_shares(shares: &[KeyShare]) -> Result<SecretKey, EncryptionError> {
+    if shares.is_empty() {
+        return Err(EncryptionError::InvalidShares(
+            "no shares given".to_string(),
+        ));
+    }
+    let distinct: HashSet<u8> = shares.iter().map(KeyShare::index).collect();
+    if distinct.len() != shares.len() {
+        return Err(EncryptionError::InvalidShares(
+            "the same share was given more than once".to_string(),
+        ));
+    }
+    if distinct.contains(&0) {
+        return Err(EncryptionError::InvalidShares(
+            "share index must be nonzero".to_string(),
+        ));
+    }
+
+    // Lagrange interpolation at x = 0; subtraction in GF(256) is XOR
+    let mut key = SecretKey::default();
+    for share in shares {
+        let mut basis = 1u8;
+        for other in shares.iter().filter(|o| o.index != share.index) {
+            basis = gf_mul(basis, gf_div(other.index, other.index ^ share.index));
+        }
+        for (out, y) in key.as_mut_bytes().iter_mut().zip(share.value) {
+            *out ^= gf_mul(y, basis);
+        }
+    }
+    Ok(key)
+}
+
+/// Multiply in GF(2^8) modulo x^8 + x^4 + x^3 + x^2 + 1
+///
+/// This is the field the `sharks` crate uses, so shares it produced still
+/// combine. Runs the same steps for every input, so timing does not depend on
+/// key bytes.
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0u8;
+    for _ in 0..8 {
+        product ^= a & 0u8.wrapping_sub(b & 1);
+        let carry = 0u8.wrapping_sub(a >> 7);
+        a = (a << 1) ^ (0x1d & carry);
+        b >>= 1;
+    }
+    product
+}
+
+/// Divide in GF(2^8); `b` must be nonzero
+fn gf_div(a: u8, b: u8) -> u8 {
+    // b^254 is b's inverse, since the multiplicative group has order 255
+    let mut inverse = 1u8;
+    let mut power = b;
+    let mut exponent = 254u8;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            inverse = gf_mul(inverse, power);
+        }
+        power = gf_mul(power, power);
+        exponent >>= 1;
+    }
+    gf_mul(a, inverse)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate_key;
+
+    #[test]
+    fn test_every_threshold_subset_recovers_key() {
+        let key = generate_key().unwrap();
+        let shares = split_key(&key, 3, 5).unwrap();
+        assert_eq!(shares.len(), 5);
+
+        for a in 0..5 {
+            for b in a + 1..5 {
+                for c in b + 1..5 {
+                    let subset = [shares[a].clone(), shares[b].clone(), shares[c].clone()];
+                    assert_eq!(*combine_shares(&subset).unwrap(), *key);
+                }
+            }
+        }
+
+        let decoded: Vec<KeyShare> = shares
+            .iter()
+            .map(|s| KeyShare::from_bytes(&s.to_bytes()).unwrap())
+            .collect();
+        assert_eq!(*combine_shares(&decoded[2..]).unwrap(), *key);
+    }
+
+    #[test]
+    fn test_below_threshold_gives_wrong_key() {
+        let key = generate_key().unwrap();
+        let shares = split_key(&key, 3, 5).unwrap();
+
+        assert_ne!(*combine_shares(&shares[..2]).unwrap(), *key);
+        assert!(combine_shares(&[shares[0].clone(), shares[0].clone()]).is_err());
+        assert!(combine_shares(&[]).is_err());
+        assert!(split_key(&key, 4, 3).is_err());
+        assert!(split_key(&key, 0, 3).is_err());
+        assert!(KeyShare::from_bytes(&[0u8; KEY_SHARE_LEN]).is_err());
+    }
+
+    #[test]
+    fn test_field_inverse() {
+        for a in 1..=255u8 {
+            assert_eq!(gf_mul(a, gf_div(1, a)), 1);
+        }
+    }
+}
@@ -1,28 +1,74 @@
 use chacha20poly1305::{
-    aead::{rand_core::RngCore, Aead, KeyInit, OsRng},
+    aead::{rand_core::RngCore, Aead, KeyInit, OsRng, Payload},
     XChaCha20Poly1305, XNonce,
 };
 use hkdf::Hkdf;
 use serde::{Deserialize, Serialize};
 use sha2::Sha256;
-// SHA3 imports removed as they're not currently used
 use thiserror::Error;
+use zeroize::Zeroize;
 
+pub mod compression;
+pub mod envelope;
 pub mod hash;
+pub mod hybrid;
+pub mod key_sharing;
+pub mod merkle;
+pub mod password;
+pub mod secret;
+pub mod selftest;
+pub mod stream;
 pub mod wasm_bindings;
 
 // Re-export hash functionality
 pub use hash::{
-    hash_content_bytes, hash_from_hex, hash_multiple_contents, hash_to_hex,
-    verify_content_hash_result, HashError, HashResult,
+    hash_content_bytes, hash_content_keyed, hash_content_with, hash_content_xof, hash_from_hex,
+    hash_multiple_contents, hash_to_hex, verify_content_hash_keyed, verify_content_hash_result,
+    verify_content_hash_xof, ContentHasher, HashAlgorithm, HashError, HashResult,
 };
 
+// Re-export X25519 hybrid encryption
+pub use hybrid::{generate_hybrid_keypair, hybrid_decrypt, hybrid_encrypt, HybridCiphertext};
+
+// Re-export Shamir sharing of keys
+pub use key_sharing::{combine_shares, split_key, KeyShare, KEY_SHARE_LEN};
+
+// Re-export Merkle proofs for verifying single chunks
+pub use merkle::{build_merkle_root, merkle_proof, verify_merkle_proof};
+
+// Re-export Argon2id passphrase-based encryption
+pub use password::{
+    decrypt_content_with_password, derive_key_from_password, encrypt_content_with_password,
+    Argon2Params, PasswordEncryptionResult, PasswordKdf,
+};
+
+// Re-export compression heuristics
+pub use compression::{estimate_entropy, should_compress};
+
+// Re-export envelope compatibility checks
+pub use envelope::{
+    check_compatibility, ContentEncoding, EnvelopeInfo, BINARY_FORMAT_MAGIC, BINARY_HEADER_LEN,
+    ENVELOPE_VERSION, ENVELOPE_VERSION_AAD, SUPPORTED_ALGORITHMS, SUPPORTED_ENVELOPE_VERSIONS,
+};
+
+// Re-export key material that is wiped on drop
+pub use secret::SecretKey;
+pub use zeroize::Zeroizing;
+
+// Re-export known-answer self-test
+pub use selftest::{run_selftest, SelfTestCheck};
+
+// Re-export single-pass streaming encryption
+pub use stream::{decrypt_stream, hash_and_encrypt_stream, StreamHeader, STREAM_CHUNK_SIZE};
+
 /// Encryption result containing ciphertext, nonce, and content hash
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EncryptionResult {
     pub ciphertext: Vec<u8>,
     pub nonce: [u8; 24],
     pub content_hash: [u8; 32],
+    #[serde(default)]
+    pub envelope: EnvelopeInfo,
 }
 
 /// Wallet-based encryption result with key derivation salt
@@ -37,7 +83,8 @@ pub struct WalletEncryptionResult {
 /// Decryption result containing the original content
 #[derive(Debug, Clone)]
 pub struct DecryptionResult {
-    pub content: Vec<u8>,
+    /// Plaintext, wiped from memory when the result is dropped
+    pub content: Zeroizing<Vec<u8>>,
 }
 
 /// Encryption errors
@@ -55,13 +102,19 @@ pub enum EncryptionError {
     KeyDerivationFailed(String),
     #[error("Invalid address format")]
     InvalidAddress,
+    #[error("Unsupported envelope: {0}")]
+    UnsupportedEnvelope(String),
+    #[error("Invalid key shares: {0}")]
+    InvalidShares(String),
+    #[error("Unsupported format: {0}")]
+    UnsupportedFormat(String),
 }
 
 /// Generate a new 32-byte encryption key
-pub fn generate_key() -> Result<[u8; 32], EncryptionError> {
-    let mut key = [0u8; 32];
+pub fn generate_key() -> Result<SecretKey, EncryptionError> {
+    let mut key = SecretKey::default();
     OsRng
-        .try_fill_bytes(&mut key)
+        .try_fill_bytes(key.as_mut_bytes())
         .map_err(|_| EncryptionError::RandomGenerationFailed)?;
     Ok(key)
 }
@@ -90,9 +143,9 @@ pub fn derive_key_from_wallet(
     capsule_id: &str,
     unlock_time: u64,
     salt: &[u8; 32],
-) -> Result<[u8; 32], EncryptionError> {
+) -> Result<SecretKey, EncryptionError> {
     // Create key material by combining all inputs
-    let mut key_material = Vec::new();
+    let mut key_material = Zeroizing::new(Vec::new());
 
     // Add wallet address (remove 0x prefix if present)
     let addr = if wallet_address.starts_with("0x") {
@@ -116,14 +169,17 @@ pub fn derive_key_from_wallet(
 
     // Use HKDF to derive a proper encryption key
     let hk = Hkdf::<Sha256>::new(Some(salt), &key_material);
-    let mut key = [0u8; 32];
-    hk.expand(b"time-capsule-encryption-key", &mut key)
+    let mut key = SecretKey::default();
+    hk.expand(b"time-capsule-encryption-key", key.as_mut_bytes())
         .map_err(|e| EncryptionError::KeyDerivationFailed(e.to_string()))?;
 
     Ok(key)
 }
 
 /// Encrypt content using wallet-based key derivation
+///
+/// The capsule ID is also bound as associated data, so the ciphertext cannot
+/// be moved onto another capsule.
 pub fn encrypt_content_with_wallet(
     content: &[u8],
     wallet_address: &str,
@@ -136,31 +192,20 @@ pub fn encrypt_content_with_wallet(
     // Derive key from wallet and capsule metadata
     let key = derive_key_from_wallet(wallet_address, capsule_id, unlock_time, &salt)?;
 
-    // Generate nonce
-    let nonce_bytes = generate_nonce()?;
-    let nonce = XNonce::from_slice(&nonce_bytes);
-
-    // Create cipher instance
-    let cipher = XChaCha20Poly1305::new_from_slice(&key)
-        .map_err(|e| EncryptionError::EncryptionFailed(e.to_string()))?;
-
-    // Encrypt content
-    let ciphertext = cipher
-        .encrypt(nonce, content)
-        .map_err(|e| EncryptionError::EncryptionFailed(e.to_string()))?;
-
-    // Compute content hash
-    let content_hash = hash_content(content);
+    // Encrypt content, authenticating the capsule ID
+    let encrypted = encrypt_content_with_aad(content, &key, capsule_id.as_bytes())?;
 
     Ok(WalletEncryptionResult {
-        ciphertext,
-        nonce: nonce_bytes,
-        content_hash,
+        ciphertext: encrypted.ciphertext,
+        nonce: encrypted.nonce,
+        content_hash: encrypted.content_hash,
         key_derivation_salt: salt,
     })
 }
 
 /// Decrypt content using wallet-based key derivation
+///
+/// Fails unless `capsule_id` is the one the content was encrypted for.
 pub fn decrypt_content_with_wallet(
     ciphertext: &[u8],
     nonce: &[u8; 24],
@@ -172,19 +217,8 @@ pub fn decrypt_content_with_wallet(
     // Derive the same key used for encryption
     let key = derive_key_from_wallet(wallet_address, capsule_id, unlock_time, salt)?;
 
-    // Create cipher instance
-    let cipher = XChaCha20Poly1305::new_from_slice(&key)
-        .map_err(|e| EncryptionError::DecryptionFailed(e.to_string()))?;
-
-    // Create nonce
-    let nonce = XNonce::from_slice(nonce);
-
-    // Decrypt content
-    let content = cipher
-        .decrypt(nonce, ciphertext)
-        .map_err(|e| EncryptionError::DecryptionFailed(e.to_string()))?;
-
-    Ok(DecryptionResult { content })
+    // Decrypt content, checking the capsule ID it was bound to
+    decrypt_content_with_aad(ciphertext, nonce, &key, capsule_id.as_bytes())
 }
 
 /// Compute BLAKE3 hash of content (legacy function, use hash::hash_content_bytes instead)
@@ -217,6 +251,64 @@ pub fn encrypt_content(
         ciphertext,
         nonce: nonce_bytes,
         content_hash,
+        envelope: EnvelopeInfo::default(),
+    })
+}
+
+/// Encrypt content using XChaCha20-Poly1305, binding `aad` into the authentication tag
+///
+/// The associated data is not stored; decryption must supply the same bytes.
+pub fn encrypt_content_with_aad(
+    content: &[u8],
+    key: &[u8; 32],
+    aad: &[u8],
+) -> Result<EncryptionResult, EncryptionError> {
+    // Create cipher instance
+    let cipher = XChaCha20Poly1305::new_from_slice(key)
+        .map_err(|e| EncryptionError::EncryptionFailed(e.to_string()))?;
+
+    // Generate nonce
+    let nonce_bytes = generate_nonce()?;
+    let nonce = XNonce::from_slice(&nonce_bytes);
+
+    // Encrypt content
+    let ciphertext = cipher
+        .encrypt(nonce, Payload { msg: content, aad })
+        .map_err(|e| EncryptionError::EncryptionFailed(e.to_string()))?;
+
+    Ok(EncryptionResult {
+        ciphertext,
+        nonce: nonce_bytes,
+        content_hash: hash_content(content),
+        envelope: EnvelopeInfo {
+            version: ENVELOPE_VERSION_AAD,
+            ..EnvelopeInfo::default()
+        },
+    })
+}
+
+/// Decrypt content encrypted with `encrypt_content_with_aad`
+pub fn decrypt_content_with_aad(
+    ciphertext: &[u8],
+    nonce: &[u8; 24],
+    key: &[u8; 32],
+    aad: &[u8],
+) -> Result<DecryptionResult, EncryptionError> {
+    let cipher = XChaCha20Poly1305::new_from_slice(key)
+        .map_err(|e| EncryptionError::DecryptionFailed(e.to_string()))?;
+
+    let content = cipher
+        .decrypt(
+            XNonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
+        .map_err(|e| EncryptionError::DecryptionFailed(e.to_string()))?;
+
+    Ok(DecryptionResult {
+        content: Zeroizing::new(content),
     })
 }
 
@@ -238,7 +330,32 @@ pub fn decrypt_content(
         .decrypt(nonce, ciphertext)
         .map_err(|e| EncryptionError::DecryptionFailed(e.to_string()))?;
 
-    Ok(DecryptionResult { content })
+    Ok(DecryptionResult {
+        content: Zeroizing::new(content),
+    })
+}
+
+/// Decrypt an encryption result after checking its envelope is supported
+pub fn decrypt_result(
+    result: &EncryptionResult,
+    key: &[u8; 32],
+) -> Result<DecryptionResult, EncryptionError> {
+    decrypt_result_with_aad(result, key, &[])
+}
+
+/// Decrypt an encryption result, supplying the associated data it was bound to
+///
+/// Payloads from before associated data was supported ignore `aad`.
+pub fn decrypt_result_with_aad(
+    result: &EncryptionResult,
+    key: &[u8; 32],
+    aad: &[u8],
+) -> Result<DecryptionResult, EncryptionError> {
+    check_compatibility(&result.envelope)?;
+    if result.envelope.version < ENVELOPE_VERSION_AAD {
+        return decrypt_content(&result.ciphertext, &result.nonce, key);
+    }
+    decrypt_content_with_aad(&result.ciphertext, &result.nonce, key, aad)
 }
 
 /// Verify content hash matches the original (legacy function, use hash::verify_content_hash instead)
@@ -252,6 +369,7 @@ pub fn verify_content_hash(content: &[u8], expected_hash: &[u8; 32]) -> bool {
 use std::ptr;
 
 /// WASI-compatible encryption function
+/// Writes the result in the binary layout of `EncryptionResult::to_bytes`
 /// Returns 0 on success, non-zero on error
 #[no_mangle]
 pub extern "C" fn wasi_encrypt(
@@ -271,17 +389,13 @@ pub extern "C" fn wasi_encrypt(
 
     unsafe {
         let content = std::slice::from_raw_parts(content_ptr, content_len);
-        let key_slice = std::slice::from_raw_parts(key_ptr, 32);
-
-        let mut key = [0u8; 32];
-        key.copy_from_slice(key_slice);
+        let Ok(key) = SecretKey::from_slice(std::slice::from_raw_parts(key_ptr, 32)) else {
+            return -1;
+        };
 
         match encrypt_content(content, &key) {
             Ok(encrypted) => {
-                let serialized = match serde_json::to_vec(&encrypted) {
-                    Ok(data) => data,
-                    Err(_) => return -2, // Serialization error
-                };
+                let serialized = encrypted.to_bytes();
 
                 if serialized.len() > *result_len_ptr {
                     *result_len_ptr = serialized.len();
@@ -320,14 +434,16 @@ pub extern "C" fn wasi_decrypt(
     unsafe {
         let ciphertext = std::slice::from_raw_parts(ciphertext_ptr, ciphertext_len);
         let nonce_slice = std::slice::from_raw_parts(nonce_ptr, 24);
-        let key_slice = std::slice::from_raw_parts(key_ptr, 32);
+        let Ok(key) = SecretKey::from_slice(std::slice::from_raw_parts(key_ptr, 32)) else {
+            return -1;
+        };
 
         let mut nonce = [0u8; 24];
-        let mut key = [0u8; 32];
         nonce.copy_from_slice(nonce_slice);
-        key.copy_from_slice(key_slice);
+        let decrypted = decrypt_content(ciphertext, &nonce, &key);
+        nonce.zeroize();
 
-        match decrypt_content(ciphertext, &nonce, &key) {
+        match decrypted {
             Ok(decrypted) => {
                 if decrypted.content.len() > *result_len_ptr {
                     *result_len_ptr = decrypted.content.len();
@@ -412,7 +528,7 @@ mod tests {
 
         assert_eq!(key1.len(), 32);
         assert_eq!(key2.len(), 32);
-        assert_ne!(key1, key2); // Keys should be different
+        assert_ne!(*key1, *key2); // Keys should be different
     }
 
     #[test]
@@ -425,6 +541,83 @@ mod tests {
         assert_ne!(nonce1, nonce2); // Nonces should be different
     }
 
+    #[test]
+    fn test_decrypt_result_checks_envelope() {
+        let key = generate_key().unwrap();
+        let mut result = encrypt_content(b"versioned", &key).unwrap();
+        assert_eq!(result.envelope.version, ENVELOPE_VERSION);
+        assert_eq!(
+            *decrypt_result(&result, &key).unwrap().content,
+            b"versioned"
+        );
+
+        result.envelope.version = ENVELOPE_VERSION + 2;
+        assert!(matches!(
+            decrypt_result(&result, &key),
+            Err(EncryptionError::UnsupportedEnvelope(_))
+        ));
+    }
+
+    #[test]
+    fn test_aad_mismatch_fails() {
+        let key = generate_key().unwrap();
+        let result = encrypt_content_with_aad(b"bound", &key, b"unlock_time=100").unwrap();
+        assert_eq!(result.envelope.version, ENVELOPE_VERSION_AAD);
+
+        let decrypted = decrypt_result_with_aad(&result, &key, b"unlock_time=100").unwrap();
+        assert_eq!(*decrypted.content, b"bound");
+        assert!(decrypt_result_with_aad(&result, &key, b"unlock_time=0").is_err());
+        assert!(decrypt_result(&result, &key).is_err());
+
+        // Payloads without associated data still decrypt whatever is passed
+        let plain = encrypt_content(b"plain", &key).unwrap();
+        let decrypted = decrypt_result_with_aad(&plain, &key, b"unlock_time=100").unwrap();
+        assert_eq!(*decrypted.content, b"plain");
+    }
+
+    #[test]
+    fn test_wallet_content_bound_to_capsule_id() {
+        let address = "0x1234567890abcdef";
+        let encrypted = encrypt_content_with_wallet(b"bound", address, "0xcapsule1", 100).unwrap();
+
+        let decrypted = decrypt_content_with_wallet(
+            &encrypted.ciphertext,
+            &encrypted.nonce,
+            address,
+            "0xcapsule1",
+            100,
+            &encrypted.key_derivation_salt,
+        )
+        .unwrap();
+        assert_eq!(*decrypted.content, b"bound");
+
+        // The right key with another capsule's ID as associated data is rejected
+        let key =
+            derive_key_from_wallet(address, "0xcapsule1", 100, &encrypted.key_derivation_salt)
+                .unwrap();
+        assert!(matches!(
+            decrypt_content_with_aad(&encrypted.ciphertext, &encrypted.nonce, &key, b"0xcapsule2"),
+            Err(EncryptionError::DecryptionFailed(_))
+        ));
+        assert!(matches!(
+            decrypt_content(&encrypted.ciphertext, &encrypted.nonce, &key),
+            Err(EncryptionError::DecryptionFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_legacy_result_defaults_envelope() {
+        let key = generate_key().unwrap();
+        let result = encrypt_content(b"legacy", &key).unwrap();
+
+        let mut json = serde_json::to_value(&result).unwrap();
+        json.as_object_mut().unwrap().remove("envelope");
+        let legacy: EncryptionResult = serde_json::from_value(json).unwrap();
+
+        assert_eq!(legacy.envelope, EnvelopeInfo::default());
+        assert_eq!(*decrypt_result(&legacy, &key).unwrap().content, b"legacy");
+    }
+
     #[test]
     fn test_content_hashing() {
         let content = b"Hello, World!";
@@ -449,7 +642,7 @@ mod tests {
         // Decrypt
         let decrypted = decrypt_content(&encrypted.ciphertext, &encrypted.nonce, &key).unwrap();
 
-        assert_eq!(decrypted.content, content.to_vec());
+        assert_eq!(*decrypted.content, content.to_vec());
 
         // Verify hash
         assert!(verify_content_hash(
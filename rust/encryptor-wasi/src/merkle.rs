@@ -0,0 +1,143 @@
+use blake3::Hasher;
+
+/// Prefix for hashing a chunk into a leaf
+const LEAF_PREFIX: u8 = 0x00;
+
+/// Prefix for hashing two children into an internal node
+const NODE_PREFIX: u8 = 0x01;
+
+/// Partner for the last node of a level with an odd number of nodes
+///
+/// Not a possible BLAKE3 output in practice, so a chunk can never be proven at
+/// a padding position.
+const EMPTY_NODE: [u8; 32] = [0; 32];
+
+/// Hash one chunk into a leaf
+///
+/// The prefix keeps a leaf from ever equalling an internal node, so a subtree
+/// cannot be passed off as a chunk.
+pub fn hash_leaf(chunk: &[u8]) -> [u8; 32] {
+    let mut hasher = Hasher::new();
+    hasher.update(&[LEAF_PREFIX]);
+    hasher.update(chunk);
+    hasher.finalize().into()
+}
+
+fn hash_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Hasher::new();
+    hasher.update(&[NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Hash one level of the tree into the level above it
+fn next_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| hash_node(&pair[0], pair.get(1).unwrap_or(&EMPTY_NODE)))
+        .collect()
+}
+
+/// Root of the BLAKE3 Merkle tree over `chunks`, in order
+///
+/// An empty chunk list has the all-zero root.
+pub fn build_merkle_root(chunks: &[&[u8]]) -> [u8; 32] {
+    let mut level: Vec<[u8; 32]> = chunks.iter().map(|chunk| hash_leaf(chunk)).collect();
+    if level.is_empty() {
+        return EMPTY_NODE;
+    }
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+/// Sibling hashes from the leaf at `index` up to the root
+///
+/// Panics if `index` is out of range.
+pub fn merkle_proof(chunks: &[&[u8]], index: usize) -> Vec<[u8; 32]> {
+    assert!(
+        index < chunks.len(),
+        "chunk index {index} out of range for {} chunks",
+        chunks.len()
+    );
+
+    let mut level: Vec<[u8; 32]> = chunks.iter().map(|chunk| hash_leaf(chunk)).collect();
+    let mut position = index;
+    let mut proof = Vec::new();
+    while level.len() > 1 {
+        proof.push(*level.get(position ^ 1).unwrap_or(&EMPTY_NODE));
+        level = next_level(&level);
+        position /= 2;
+    }
+    proof
+}
+
+/// Check that `leaf` is the chunk at `index` of the tree with `root`
+///
+/// Only the chunk and its proof are needed, not the rest of the file.
+pub fn verify_merkle_proof(leaf: &[u8], proof: &[[u8; 32]], index: usize, root: &[u8; 32]) -> bool {
+    // Every bit of the index picks a side at one level, so none may be left over
+    if proof.len() < usize::BITS as usize && index >> proof.len() != 0 {
+        return false;
+    }
+
+    let mut node = hash_leaf(leaf);
+    for (level, sibling) in proof.iter().enumerate() {
+        node = if (index >> level) & 1 == 0 {
+            hash_node(&node, sibling)
+        } else {
+            hash_node(sibling, &node)
+        };
+    }
+    node == *root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_chunks() -> Vec<Vec<u8>> {
+        (0..5u8).map(|i| vec![i; 100 + i as usize]).collect()
+    }
+
+    #[test]
+    fn test_every_chunk_proof_verifies() {
+        let chunks = sample_chunks();
+        for count in 1..=chunks.len() {
+            let refs: Vec<&[u8]> = chunks[..count].iter().map(Vec::as_slice).collect();
+            let root = build_merkle_root(&refs);
+
+            for (index, chunk) in refs.iter().enumerate() {
+                let proof = merkle_proof(&refs, index);
+                assert!(verify_merkle_proof(chunk, &proof, index, &root));
+            }
+        }
+    }
+
+    #[test]
+    fn test_tampered_leaf_or_wrong_index_fails() {
+        let chunks = sample_chunks();
+        let refs: Vec<&[u8]> = chunks.iter().map(Vec::as_slice).collect();
+        let root = build_merkle_root(&refs);
+        let proof = merkle_proof(&refs, 2);
+
+        let mut tampered = chunks[2].clone();
+        tampered[0] ^= 1;
+        assert!(!verify_merkle_proof(&tampered, &proof, 2, &root));
+        assert!(!verify_merkle_proof(&chunks[2], &proof, 3, &root));
+        assert!(!verify_merkle_proof(&chunks[2], &proof, 2 + 8, &root));
+        assert!(!verify_merkle_proof(&chunks[2], &proof[1..], 2, &root));
+
+        // The last chunk cannot be claimed at the padding slot beside it
+        let last = merkle_proof(&refs, 4);
+        assert!(verify_merkle_proof(&chunks[4], &last, 4, &root));
+        assert!(!verify_merkle_proof(&chunks[4], &last, 5, &root));
+
+        // Reordering chunks changes the root
+        let mut swapped = refs.clone();
+        swapped.swap(0, 1);
+        assert_ne!(build_merkle_root(&swapped), root);
+    }
+}
@@ -0,0 +1,156 @@
+use argon2::{Algorithm, Argon2, Params, Version};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    decrypt_content, encrypt_content, generate_salt, DecryptionResult, EncryptionError, SecretKey,
+};
+
+/// Argon2id cost parameters
+///
+/// Stored next to the ciphertext, since the same parameters are needed to
+/// derive the key again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Argon2Params {
+    /// Memory cost in KiB
+    pub memory_kib: u32,
+    /// Number of passes over memory
+    pub iterations: u32,
+    /// Degree of parallelism
+    pub parallelism: u32,
+}
+
+impl Default for Argon2Params {
+    /// The `argon2` crate's defaults: 19 MiB, two passes, one lane
+    fn default() -> Self {
+        Self {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+/// Salt and parameters a passphrase-derived key was made with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PasswordKdf {
+    pub salt: [u8; 32],
+    pub params: Argon2Params,
+}
+
+impl PasswordKdf {
+    /// Fresh random salt with the given parameters
+    pub fn generate(params: Argon2Params) -> Result<Self, EncryptionError> {
+        Ok(Self {
+            salt: generate_salt()?,
+            params,
+        })
+    }
+
+    /// Derive the key for `password` with this salt and these parameters
+    pub fn derive_key(&self, password: &str) -> Result<SecretKey, EncryptionError> {
+        derive_key_from_password(password, &self.salt, self.params)
+    }
+}
+
+/// Passphrase-based encryption result with what is needed to derive the key again
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PasswordEncryptionResult {
+    pub ciphertext: Vec<u8>,
+    pub nonce: [u8; 24],
+    pub content_hash: [u8; 32],
+    pub kdf: PasswordKdf,
+}
+
+/// Derive an encryption key from a passphrase with Argon2id
+pub fn derive_key_from_password(
+    password: &str,
+    salt: &[u8; 32],
+    params: Argon2Params,
+) -> Result<SecretKey, EncryptionError> {
+    let params = Params::new(
+        params.memory_kib,
+        params.iterations,
+        params.parallelism,
+        Some(32),
+    )
+    .map_err(|e| EncryptionError::KeyDerivationFailed(e.to_string()))?;
+
+    let mut key = SecretKey::default();
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password.as_bytes(), salt, key.as_mut_bytes())
+        .map_err(|e| EncryptionError::KeyDerivationFailed(e.to_string()))?;
+    Ok(key)
+}
+
+/// Encrypt content with a key derived from a passphrase
+pub fn encrypt_content_with_password(
+    content: &[u8],
+    password: &str,
+    params: Argon2Params,
+) -> Result<PasswordEncryptionResult, EncryptionError> {
+    let kdf = PasswordKdf::generate(params)?;
+    let key = kdf.derive_key(password)?;
+    let encrypted = encrypt_content(content, &key)?;
+
+    Ok(PasswordEncryptionResult {
+        ciphertext: encrypted.ciphertext,
+        nonce: encrypted.nonce,
+        content_hash: encrypted.content_hash,
+        kdf,
+    })
+}
+
+/// Decrypt content encrypted with `encrypt_content_with_password`
+pub fn decrypt_content_with_password(
+    ciphertext: &[u8],
+    nonce: &[u8; 24],
+    password: &str,
+    kdf: &PasswordKdf,
+) -> Result<DecryptionResult, EncryptionError> {
+    let key = kdf.derive_key(password)?;
+    decrypt_content(ciphertext, nonce, &key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cheap parameters so the tests stay fast
+    const TEST_PARAMS: Argon2Params = Argon2Params {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    #[test]
+    fn test_password_round_trip() {
+        let encrypted =
+            encrypt_content_with_password(b"open sesame", "correct horse", TEST_PARAMS).unwrap();
+        assert_eq!(encrypted.kdf.params, TEST_PARAMS);
+
+        let decrypted = decrypt_content_with_password(
+            &encrypted.ciphertext,
+            &encrypted.nonce,
+            "correct horse",
+            &encrypted.kdf,
+        )
+        .unwrap();
+        assert_eq!(*decrypted.content, b"open sesame");
+    }
+
+    #[test]
+    fn test_wrong_password_fails() {
+        let encrypted =
+            encrypt_content_with_password(b"open sesame", "correct horse", TEST_PARAMS).unwrap();
+
+        assert!(matches!(
+            decrypt_content_with_password(
+                &encrypted.ciphertext,
+                &encrypted.nonce,
+                "battery staple",
+                &encrypted.kdf,
+            ),
+            Err(EncryptionError::DecryptionFailed(_))
+        ));
+    }
+}
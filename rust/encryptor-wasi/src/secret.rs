@@ -0,0 +1,91 @@
+use crate::EncryptionError;
+use std::fmt;
+use std::ops::Deref;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// A 32-byte encryption key that is wiped from memory when dropped
+///
+/// Derefs to `[u8; 32]`, so `&key` can be passed wherever a key reference is
+/// expected. Debug output never shows the key bytes.
+#[derive(Clone, Default, Zeroize, ZeroizeOnDrop)]
+pub struct SecretKey([u8; 32]);
+
+impl SecretKey {
+    /// Copy a key out of a slice, which must be exactly 32 bytes
+    ///
+    /// Copying straight into the wrapper avoids leaving an unwiped array on
+    /// the stack.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, EncryptionError> {
+        if bytes.len() != 32 {
+            return Err(EncryptionError::InvalidKeyLength(bytes.len()));
+        }
+        let mut key = Self::default();
+        key.0.copy_from_slice(bytes);
+        Ok(key)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    pub(crate) fn as_mut_bytes(&mut self) -> &mut [u8; 32] {
+        &mut self.0
+    }
+}
+
+impl From<[u8; 32]> for SecretKey {
+    fn from(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl Deref for SecretKey {
+    type Target = [u8; 32];
+
+    fn deref(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for SecretKey {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretKey(..)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::ManuallyDrop;
+
+    #[test]
+    fn test_drop_wipes_key() {
+        let mut key = ManuallyDrop::new(SecretKey::from([0xab; 32]));
+        let bytes = key.as_bytes().as_ptr();
+
+        // The storage outlives the drop because it is held in `ManuallyDrop`
+        unsafe {
+            ManuallyDrop::drop(&mut key);
+            assert_eq!(std::slice::from_raw_parts(bytes, 32), &[0u8; 32]);
+        }
+    }
+
+    #[test]
+    fn test_from_slice_checks_length() {
+        assert_eq!(
+            SecretKey::from_slice(&[7; 32]).unwrap().as_bytes(),
+            &[7; 32]
+        );
+        assert!(matches!(
+            SecretKey::from_slice(&[7; 16]),
+            Err(EncryptionError::InvalidKeyLength(16))
+        ));
+        assert_eq!(format!("{:?}", SecretKey::from([7; 32])), "SecretKey(..)");
+    }
+}
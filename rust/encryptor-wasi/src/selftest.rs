@@ -0,0 +1,206 @@
+use serde::Serialize;
+
+use crate::{
+    decrypt_content, decrypt_content_with_wallet, decrypt_result, derive_key_from_wallet,
+    encrypt_content, encrypt_content_with_wallet, generate_key, hash_content_bytes, hash_from_hex,
+    hash_to_hex,
+};
+
+/// XChaCha20-Poly1305 known answer: key 0x80..0x9f, nonce 0x40..0x57
+const XCHACHA_KEY_HEX: &str = "808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9f";
+const XCHACHA_NONCE_HEX: &str = "404142434445464748494a4b4c4d4e4f5051525354555657";
+const XCHACHA_PLAINTEXT: &[u8] = b"time capsule self-test";
+const XCHACHA_CIPHERTEXT_HEX: &str =
+    "85651e917b93952a88671bb6d6ddf839546a060b76303ae032ab67aeae077917c2bc4311a11d";
+
+/// BLAKE3 digests of "" and "abc" from the reference test vectors
+const BLAKE3_VECTORS: &[(&[u8], &str)] = &[
+    (
+        b"",
+        "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262",
+    ),
+    (
+        b"abc",
+        "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85",
+    ),
+];
+
+/// Wallet key derivation inputs and the HKDF-SHA256 output they must produce
+const WALLET_ADDRESS: &str = "0xabababababababababababababababababababababababababababababababab";
+const WALLET_CAPSULE_ID: &str = "0xc0ffee";
+const WALLET_UNLOCK_TIME: u64 = 1_700_000_000_000;
+const WALLET_SALT: [u8; 32] = [7; 32];
+const WALLET_KEY_HEX: &str = "731d31dbaef8c766093895c7458e198f8309f8de1c5567a51ba4655ae982aa21";
+
+/// Outcome of a single self-test check
+#[derive(Debug, Clone, Serialize)]
+pub struct SelfTestCheck {
+    pub name: &'static str,
+    pub passed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+type Check = fn() -> Result<(), String>;
+
+const CHECKS: &[(&str, Check)] = &[
+    ("xchacha20poly1305 known answer", check_xchacha_known_answer),
+    ("encrypt/decrypt round trip", check_round_trip),
+    ("tampered ciphertext rejected", check_tamper_detection),
+    ("wallet encrypt/decrypt round trip", check_wallet_round_trip),
+    ("blake3 known vectors", check_blake3_vectors),
+    ("hkdf wallet key derivation", check_wallet_key_derivation),
+    ("hash hex conversion", check_hash_hex),
+];
+
+/// Run every known-answer check
+///
+/// Nothing here touches the network or the filesystem, so it is safe to run
+/// on any platform the encryptor is built for.
+pub fn run_selftest() -> Vec<SelfTestCheck> {
+    CHECKS
+        .iter()
+        .map(|(name, check)| {
+            let result = check();
+            SelfTestCheck {
+                name,
+                passed: result.is_ok(),
+                error: result.err(),
+            }
+        })
+        .collect()
+}
+
+fn decode_array<const N: usize>(hex_str: &str) -> Result<[u8; N], String> {
+    hex::decode(hex_str)
+        .map_err(|e| e.to_string())?
+        .try_into()
+        .map_err(|bytes: Vec<u8>| format!("expected {} bytes, got {}", N, bytes.len()))
+}
+
+fn check_xchacha_known_answer() -> Result<(), String> {
+    let key = decode_array::<32>(XCHACHA_KEY_HEX)?;
+    let nonce = decode_array::<24>(XCHACHA_NONCE_HEX)?;
+    let ciphertext = hex::decode(XCHACHA_CIPHERTEXT_HEX).map_err(|e| e.to_string())?;
+
+    let decrypted = decrypt_content(&ciphertext, &nonce, &key).map_err(|e| e.to_string())?;
+    if *decrypted.content != XCHACHA_PLAINTEXT {
+        return Err("decrypted plaintext does not match the test vector".to_string());
+    }
+    Ok(())
+}
+
+fn check_round_trip() -> Result<(), String> {
+    let key = generate_key().map_err(|e| e.to_string())?;
+    let encrypted = encrypt_content(XCHACHA_PLAINTEXT, &key).map_err(|e| e.to_string())?;
+    let decrypted = decrypt_result(&encrypted, &key).map_err(|e| e.to_string())?;
+
+    if *decrypted.content != XCHACHA_PLAINTEXT {
+        return Err("round trip changed the content".to_string());
+    }
+    if encrypted.content_hash != hash_content_bytes(XCHACHA_PLAINTEXT) {
+        return Err("content hash does not match the plaintext".to_string());
+    }
+    Ok(())
+}
+
+fn check_tamper_detection() -> Result<(), String> {
+    let key = decode_array::<32>(XCHACHA_KEY_HEX)?;
+    let nonce = decode_array::<24>(XCHACHA_NONCE_HEX)?;
+    let mut ciphertext = hex::decode(XCHACHA_CIPHERTEXT_HEX).map_err(|e| e.to_string())?;
+    ciphertext[0] ^= 0x01;
+
+    match decrypt_content(&ciphertext, &nonce, &key) {
+        Ok(_) => Err("modified ciphertext was accepted".to_string()),
+        Err(_) => Ok(()),
+    }
+}
+
+fn check_wallet_round_trip() -> Result<(), String> {
+    let encrypted = encrypt_content_with_wallet(
+        XCHACHA_PLAINTEXT,
+        WALLET_ADDRESS,
+        WALLET_CAPSULE_ID,
+        WALLET_UNLOCK_TIME,
+    )
+    .map_err(|e| e.to_string())?;
+
+    let decrypted = decrypt_content_with_wallet(
+        &encrypted.ciphertext,
+        &encrypted.nonce,
+        WALLET_ADDRESS,
+        WALLET_CAPSULE_ID,
+        WALLET_UNLOCK_TIME,
+        &encrypted.key_derivation_salt,
+    )
+    .map_err(|e| e.to_string())?;
+
+    if *decrypted.content != XCHACHA_PLAINTEXT {
+        return Err("wallet round trip changed the content".to_string());
+    }
+    Ok(())
+}
+
+fn check_blake3_vectors() -> Result<(), String> {
+    for (input, expected) in BLAKE3_VECTORS {
+        let actual = hex::encode(hash_content_bytes(input));
+        if actual != *expected {
+            return Err(format!(
+                "BLAKE3({:?}) = {}, expected {}",
+                String::from_utf8_lossy(input),
+                actual,
+                expected
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn check_wallet_key_derivation() -> Result<(), String> {
+    let key = derive_key_from_wallet(
+        WALLET_ADDRESS,
+        WALLET_CAPSULE_ID,
+        WALLET_UNLOCK_TIME,
+        &WALLET_SALT,
+    )
+    .map_err(|e| e.to_string())?;
+
+    let actual = hex::encode(key);
+    if actual != WALLET_KEY_HEX {
+        return Err(format!(
+            "derived key {}, expected {}",
+            actual, WALLET_KEY_HEX
+        ));
+    }
+    Ok(())
+}
+
+fn check_hash_hex() -> Result<(), String> {
+    let (input, expected) = BLAKE3_VECTORS[1];
+    let hash = hash_content_bytes(input);
+
+    if hash_to_hex(&hash) != expected {
+        return Err("hash_to_hex produced the wrong string".to_string());
+    }
+    if hash_from_hex(expected).map_err(|e| e.to_string())? != hash {
+        return Err("hash_from_hex did not round trip".to_string());
+    }
+    if hash_from_hex("not hex").is_ok() {
+        return Err("hash_from_hex accepted invalid input".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selftest_passes() {
+        let checks = run_selftest();
+        assert_eq!(checks.len(), CHECKS.len());
+        for check in &checks {
+            assert!(check.passed, "{} failed: {:?}", check.name, check.error);
+        }
+    }
+}
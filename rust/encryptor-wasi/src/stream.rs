@@ -0,0 +1,252 @@
+use std::io::{Read, Write};
+
+use chacha20poly1305::{
+    aead::{
+        rand_core::RngCore,
+        stream::{DecryptorBE32, EncryptorBE32},
+        KeyInit, OsRng,
+    },
+    XChaCha20Poly1305,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{ContentHasher, EncryptionError, HashResult};
+
+/// Plaintext bytes sealed per chunk
+pub const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Poly1305 tag appended to every sealed chunk
+const TAG_SIZE: usize = 16;
+
+/// Everything besides the key needed to decrypt a stream
+///
+/// The nonce is the 19-byte prefix of the STREAM construction; the remaining
+/// five bytes of the XChaCha20 nonce hold the chunk counter and last-chunk flag.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StreamHeader {
+    pub nonce: [u8; 19],
+    pub chunk_size: u32,
+}
+
+/// Encrypt everything `reader` yields into `writer`, hashing the plaintext on the way
+///
+/// The content is read once: each chunk feeds the BLAKE3 hasher and is then
+/// sealed, so the returned hash equals `hash_content` over the same bytes.
+pub fn hash_and_encrypt_stream<R: Read, W: Write>(
+    mut reader: R,
+    mut writer: W,
+    key: &[u8; 32],
+) -> Result<(StreamHeader, HashResult), EncryptionError> {
+    let cipher = XChaCha20Poly1305::new_from_slice(key)
+        .map_err(|e| EncryptionError::EncryptionFailed(e.to_string()))?;
+
+    let mut nonce = [0u8; 19];
+    OsRng
+        .try_fill_bytes(&mut nonce)
+        .map_err(|_| EncryptionError::RandomGenerationFailed)?;
+    let mut encryptor = EncryptorBE32::from_aead(cipher, nonce.as_ref().into());
+
+    let mut hasher = ContentHasher::new();
+
+    // The last chunk is sealed differently, so always read one chunk ahead
+    let mut current = vec![0u8; STREAM_CHUNK_SIZE];
+    let mut next = vec![0u8; STREAM_CHUNK_SIZE];
+    let mut current_len = read_chunk(&mut reader, &mut current, EncryptionError::EncryptionFailed)?;
+
+    loop {
+        let chunk = &current[..current_len];
+        hasher.update(chunk);
+
+        let next_len = if current_len == STREAM_CHUNK_SIZE {
+            read_chunk(&mut reader, &mut next, EncryptionError::EncryptionFailed)?
+        } else {
+            0
+        };
+
+        if next_len == 0 {
+            let sealed = encryptor
+                .encrypt_last(chunk)
+                .map_err(|e| EncryptionError::EncryptionFailed(e.to_string()))?;
+            write_chunk(&mut writer, &sealed, EncryptionError::EncryptionFailed)?;
+            break;
+        }
+
+        let sealed = encryptor
+            .encrypt_next(chunk)
+            .map_err(|e| EncryptionError::EncryptionFailed(e.to_string()))?;
+        write_chunk(&mut writer, &sealed, EncryptionError::EncryptionFailed)?;
+
+        std::mem::swap(&mut current, &mut next);
+        current_len = next_len;
+    }
+
+    writer
+        .flush()
+        .map_err(|e| EncryptionError::EncryptionFailed(e.to_string()))?;
+
+    let header = StreamHeader {
+        nonce,
+        chunk_size: STREAM_CHUNK_SIZE as u32,
+    };
+    Ok((header, hasher.finalize()))
+}
+
+/// Decrypt a stream written by `hash_and_encrypt_stream`
+///
+/// Fails if any chunk was modified, reordered, dropped, or if the stream was
+/// cut short before its final chunk. Headers with a chunk size other than
+/// `STREAM_CHUNK_SIZE` are rejected before anything is allocated for them.
+pub fn decrypt_stream<R: Read, W: Write>(
+    mut reader: R,
+    mut writer: W,
+    header: &StreamHeader,
+    key: &[u8; 32],
+) -> Result<(), EncryptionError> {
+    if header.chunk_size != STREAM_CHUNK_SIZE as u32 {
+        return Err(EncryptionError::UnsupportedFormat(format!(
+            "stream chunk size is {} bytes, expected {STREAM_CHUNK_SIZE}",
+            header.chunk_size
+        )));
+    }
+
+    let cipher = XChaCha20Poly1305::new_from_slice(key)
+        .map_err(|e| EncryptionError::DecryptionFailed(e.to_string()))?;
+    let mut decryptor = DecryptorBE32::from_aead(cipher, header.nonce.as_ref().into());
+
+    let sealed_size = header.chunk_size as usize + TAG_SIZE;
+    let mut current = vec![0u8; sealed_size];
+    let mut next = vec![0u8; sealed_size];
+    let mut current_len = read_chunk(&mut reader, &mut current, EncryptionError::DecryptionFailed)?;
+
+    loop {
+        let next_len = if current_len == sealed_size {
+            read_chunk(&mut reader, &mut next, EncryptionError::DecryptionFailed)?
+        } else {
+            0
+        };
+
+        let chunk = &current[..current_len];
+        if next_len == 0 {
+            let content = decryptor
+                .decrypt_last(chunk)
+                .map_err(|e| EncryptionError::DecryptionFailed(e.to_string()))?;
+            write_chunk(&mut writer, &content, EncryptionError::DecryptionFailed)?;
+            break;
+        }
+
+        let content = decryptor
+            .decrypt_next(chunk)
+            .map_err(|e| EncryptionError::DecryptionFailed(e.to_string()))?;
+        write_chunk(&mut writer, &content, EncryptionError::DecryptionFailed)?;
+
+        std::mem::swap(&mut current, &mut next);
+        current_len = next_len;
+    }
+
+    writer
+        .flush()
+        .map_err(|e| EncryptionError::DecryptionFailed(e.to_string()))
+}
+
+/// Fill `buf` from `reader`, returning fewer bytes only at end of input
+fn read_chunk<R: Read>(
+    reader: &mut R,
+    buf: &mut [u8],
+    to_error: fn(String) -> EncryptionError,
+) -> Result<usize, EncryptionError> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(to_error(e.to_string())),
+        }
+    }
+    Ok(filled)
+}
+
+fn write_chunk<W: Write>(
+    writer: &mut W,
+    data: &[u8],
+    to_error: fn(String) -> EncryptionError,
+) -> Result<(), EncryptionError> {
+    writer.write_all(data).map_err(|e| to_error(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate_key, hash::hash_content};
+
+    fn sample(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 31 % 251) as u8).collect()
+    }
+
+    #[test]
+    fn test_single_pass_hash_matches_two_pass() {
+        let key = generate_key().unwrap();
+        // A few MB, ending in a partial chunk
+        let content = sample(5 * 1024 * 1024 + 12_345);
+
+        let mut sealed = Vec::new();
+        let (header, hash) =
+            hash_and_encrypt_stream(content.as_slice(), &mut sealed, &key).unwrap();
+
+        assert_eq!(hash, hash_content(&content));
+        let chunks = content.len().div_ceil(STREAM_CHUNK_SIZE);
+        assert_eq!(sealed.len(), content.len() + chunks * TAG_SIZE);
+
+        let mut decrypted = Vec::new();
+        decrypt_stream(sealed.as_slice(), &mut decrypted, &header, &key).unwrap();
+        assert_eq!(decrypted, content);
+    }
+
+    #[test]
+    fn test_empty_and_exact_chunk_inputs() {
+        let key = generate_key().unwrap();
+        for len in [0, STREAM_CHUNK_SIZE, 2 * STREAM_CHUNK_SIZE] {
+            let content = sample(len);
+            let mut sealed = Vec::new();
+            let (header, hash) =
+                hash_and_encrypt_stream(content.as_slice(), &mut sealed, &key).unwrap();
+            assert_eq!(hash, hash_content(&content));
+
+            let mut decrypted = Vec::new();
+            decrypt_stream(sealed.as_slice(), &mut decrypted, &header, &key).unwrap();
+            assert_eq!(decrypted, content);
+        }
+    }
+
+    #[test]
+    fn test_truncated_stream_rejected() {
+        let key = generate_key().unwrap();
+        let content = sample(2 * STREAM_CHUNK_SIZE + 10);
+        let mut sealed = Vec::new();
+        let (header, _) = hash_and_encrypt_stream(content.as_slice(), &mut sealed, &key).unwrap();
+
+        // Dropping the final chunk leaves a stream that ends on a non-final chunk
+        let truncated = &sealed[..2 * (STREAM_CHUNK_SIZE + TAG_SIZE)];
+        let mut decrypted = Vec::new();
+        assert!(decrypt_stream(truncated, &mut decrypted, &header, &key).is_err());
+    }
+
+    #[test]
+    fn test_unexpected_chunk_size_rejected() {
+        let key = generate_key().unwrap();
+        let mut sealed = Vec::new();
+        let (header, _) =
+            hash_and_encrypt_stream(sample(10).as_slice(), &mut sealed, &key).unwrap();
+
+        let header = StreamHeader {
+            chunk_size: u32::MAX,
+            ..header
+        };
+        let mut decrypted = Vec::new();
+        assert!(matches!(
+            decrypt_stream(sealed.as_slice(), &mut decrypted, &header, &key),
+            Err(EncryptionError::UnsupportedFormat(_))
+        ));
+        assert!(decrypted.is_empty());
+    }
+}
@@ -1,8 +1,9 @@
 use crate::{
     decrypt_content, decrypt_content_with_wallet, encrypt_content, encrypt_content_with_wallet,
-    generate_key, generate_nonce, generate_salt, hash_content,
+    generate_key, generate_nonce, generate_salt, hash_content, SecretKey,
 };
 use wasm_bindgen::prelude::*;
+use zeroize::Zeroize;
 
 // JavaScript 接口
 #[wasm_bindgen]
@@ -88,8 +89,7 @@ pub fn wasm_encrypt_content(content: &[u8], key: &[u8]) -> Result<WasmEncryption
         return Err(JsValue::from_str("Key must be 32 bytes"));
     }
 
-    let mut key_array = [0u8; 32];
-    key_array.copy_from_slice(key);
+    let key_array = SecretKey::from_slice(key).map_err(|e| JsValue::from_str(&e.to_string()))?;
 
     encrypt_content(content, &key_array)
         .map(|result| WasmEncryptionResult {
@@ -113,13 +113,16 @@ pub fn wasm_decrypt_content(
         return Err(JsValue::from_str("Nonce must be 24 bytes"));
     }
 
-    let mut key_array = [0u8; 32];
+    let key_array = SecretKey::from_slice(key).map_err(|e| JsValue::from_str(&e.to_string()))?;
     let mut nonce_array = [0u8; 24];
-    key_array.copy_from_slice(key);
     nonce_array.copy_from_slice(nonce);
 
-    decrypt_content(ciphertext, &nonce_array, &key_array)
-        .map(|result| result.content)
+    let result = decrypt_content(ciphertext, &nonce_array, &key_array);
+    nonce_array.zeroize();
+
+    // The plaintext is handed to JavaScript, so it leaves the wiped wrapper here
+    result
+        .map(|mut result| std::mem::take(&mut *result.content))
         .map_err(|e| JsValue::from_str(&e.to_string()))
 }
 
@@ -165,16 +168,20 @@ pub fn wasm_decrypt_content_with_wallet(
 
     let unlock_time_u64 = unlock_time as u64;
 
-    decrypt_content_with_wallet(
+    let result = decrypt_content_with_wallet(
         ciphertext,
         &nonce_array,
         wallet_address,
         capsule_id,
         unlock_time_u64,
         &salt_array,
-    )
-    .map(|result| result.content)
-    .map_err(|e| JsValue::from_str(&e.to_string()))
+    );
+    nonce_array.zeroize();
+    salt_array.zeroize();
+
+    result
+        .map(|mut result| std::mem::take(&mut *result.content))
+        .map_err(|e| JsValue::from_str(&e.to_string()))
 }
 
 #[wasm_bindgen]
@@ -182,6 +189,11 @@ pub fn wasm_hash_content(content: &[u8]) -> Vec<u8> {
     hash_content(content).to_vec()
 }
 
+#[wasm_bindgen]
+pub fn wasm_hash_content_xof(content: &[u8], out_len: usize) -> Vec<u8> {
+    crate::hash_content_xof(content, out_len)
+}
+
 #[wasm_bindgen]
 pub fn wasm_verify_content_hash(content: &[u8], expected_hash: &[u8]) -> bool {
     if expected_hash.len() != 32 {